@@ -54,6 +54,27 @@ pub(crate) fn iter_globals(llmod: &llvm::Module) -> ValueIter<'_> {
     unsafe { ValueIter { cur: llvm::LLVMGetFirstGlobal(llmod), step: llvm::LLVMGetNextGlobal } }
 }
 
+fn iter_functions(llmod: &llvm::Module) -> ValueIter<'_> {
+    unsafe { ValueIter { cur: llvm::LLVMGetFirstFunction(llmod), step: llvm::LLVMGetNextFunction } }
+}
+
+/// Counts the instructions of each function defined in `llmod`, by symbol name.
+pub(crate) fn count_instructions(llmod: &llvm::Module) -> Vec<(String, usize)> {
+    iter_functions(llmod)
+        .filter(|&llfn| !llvm::is_declaration(llfn))
+        .map(|llfn| {
+            let mut count = 0;
+            let mut llbb = Some(unsafe { llvm::LLVMGetFirstBasicBlock(llfn) });
+            while let Some(bb) = llbb {
+                let first = unsafe { llvm::LLVMGetFirstInstruction(bb) };
+                count += ValueIter { cur: first, step: llvm::LLVMGetNextInstruction }.count();
+                llbb = unsafe { llvm::LLVMGetNextBasicBlock(bb) };
+            }
+            (String::from_utf8_lossy(&llvm::get_value_name(llfn)).into_owned(), count)
+        })
+        .collect()
+}
+
 pub(crate) fn compile_codegen_unit(
     tcx: TyCtxt<'_>,
     cgu_name: Symbol,
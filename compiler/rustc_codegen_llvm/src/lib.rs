@@ -126,6 +126,9 @@ fn compile_codegen_unit(
     ) -> (ModuleCodegen<ModuleLlvm>, u64) {
         base::compile_codegen_unit(tcx, cgu_name)
     }
+    fn count_instructions(&self, module: &ModuleCodegen<ModuleLlvm>) -> Vec<(String, usize)> {
+        base::count_instructions(module.module_llvm.llmod())
+    }
     fn target_machine_factory(
         &self,
         sess: &Session,
@@ -1145,6 +1145,8 @@ pub(crate) fn LLVMGetIntrinsicDeclaration<'a>(
 
     // Operations on basic blocks
     pub(crate) fn LLVMGetBasicBlockParent(BB: &BasicBlock) -> &Value;
+    pub(crate) fn LLVMGetNextBasicBlock(BB: &BasicBlock) -> Option<&BasicBlock>;
+    pub(crate) fn LLVMGetFirstInstruction(BB: &BasicBlock) -> Option<&Value>;
     pub(crate) fn LLVMAppendBasicBlockInContext<'a>(
         C: &'a Context,
         Fn: &'a Value,
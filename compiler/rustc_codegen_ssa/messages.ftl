@@ -264,6 +264,11 @@ codegen_ssa_static_library_native_artifacts = link against the following native
 
 codegen_ssa_static_library_native_artifacts_to_file = native artifacts to link against have been written to {$path}. The order and any duplication can be significant on some platforms.
 
+codegen_ssa_stack_usage_no_stack_sizes = no stack sizes were found for `--print stack-usage`
+    .note = LLVM only emits stack sizes for ELF targets
+
+codegen_ssa_stack_usage_read_object = failed to read the stack sizes of `{$path}`: {$error}
+
 codegen_ssa_stripping_debug_info_failed = stripping debug info with `{$util}` failed: {$status}
     .note = {$output}
 
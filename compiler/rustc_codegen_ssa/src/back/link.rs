@@ -57,7 +57,7 @@
 use super::linker::{self, Linker};
 use super::metadata::{MetadataPosition, create_wrapper_file};
 use super::rpath::{self, RPathConfig};
-use super::{apple, versioned_llvm_target};
+use super::{apple, stack_usage, versioned_llvm_target};
 use crate::base::needs_allocator_shim_for_linking;
 use crate::{
     CodegenResults, CompiledModule, CrateInfo, NativeLib, errors, looks_like_rust_object_file,
@@ -195,6 +195,13 @@ pub fn link_binary(
         }
     }
 
+    // The stack sizes are read from the object files, so print them before they are removed.
+    for print in &sess.opts.prints {
+        if print.kind == PrintKind::StackUsage {
+            stack_usage::print_stack_usage(sess, &print.out, &codegen_results);
+        }
+    }
+
     // Remove the temporary object file and metadata if we aren't saving temps.
     sess.time("link_binary_remove_temps", || {
         // If the user requests that temporaries are saved, don't delete any.
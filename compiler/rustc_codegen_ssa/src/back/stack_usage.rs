@@ -0,0 +1,632 @@
+//! `--print stack-usage`: the worst-case stack usage of each entry point of the crate.
+//!
+//! The call graph of the functions codegened in the crate is built from their MIR when the crate
+//! is codegened, and stored in the [`CrateInfo`]. LLVM records the stack frame size of each
+//! function in the `.stack_sizes` section of the object files, which are read when linking. The
+//! worst case of a function is its own frame plus the worst case of the functions that it calls.
+//!
+//! The report is conservative: calls through `dyn` vtables and function pointers may call any
+//! function of the crate that is put in a matching vtable or turned into a function pointer of
+//! the same type. Only the functions codegened in the crate are part of the call graph, so these
+//! candidates are only complete for `dyn` calls of traits that other crates can't implement or
+//! use. Recursion and calls of functions that are not codegened in the crate, such as foreign
+//! functions or the functions of other crates, make the stack usage unbounded or incomplete, so
+//! they are flagged. So are the calls that the codegen backend inserts, like calls of `memcpy`,
+//! which are read from the relocations of the call instructions in the object files.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+
+use object::{
+    Architecture, Endian, Endianness, Object, ObjectSection, ObjectSymbol, RelocationFlags,
+    RelocationTarget, SectionKind, SymbolKind, elf,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexSet};
+use rustc_data_structures::graph::scc::Sccs;
+use rustc_data_structures::graph::vec_graph::VecGraph;
+use rustc_hir::def_id::DefId;
+use rustc_hir::lang_items::LangItem;
+use rustc_macros::{Decodable, Encodable};
+use rustc_middle::mir::mono::{MonoItem, MonoItemUsage};
+use rustc_middle::mir::{AssertKind, TerminatorKind};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{self, Instance, InstanceKind, TyCtxt};
+use rustc_session::Session;
+use rustc_session::config::{CrateType, OutFileName};
+use rustc_span::Span;
+
+use crate::{CodegenResults, CrateInfo, errors};
+
+/// The functions codegened in the crate, and the functions that they call.
+#[derive(Debug, Encodable, Decodable)]
+pub struct CallGraph {
+    pub functions: Vec<CallGraphFunction>,
+}
+
+#[derive(Debug, Encodable, Decodable)]
+pub struct CallGraphFunction {
+    pub symbol_name: String,
+    pub name: String,
+    pub calls: Vec<Call>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Encodable, Decodable)]
+pub enum Call {
+    /// A direct call of a function of the call graph, by index.
+    Local(usize),
+    /// A direct call of a function that isn't codegened in the crate.
+    Unknown { name: String, symbol_name: String, foreign: bool },
+    /// A call through a vtable or a function pointer, which may call any of the `candidates`.
+    /// Unless the candidates are `complete`, it may also call functions of other crates.
+    Indirect { description: String, candidates: Vec<usize>, complete: bool },
+}
+
+/// Builds the call graph of the functions in the codegen units of the crate.
+pub(crate) fn call_graph(tcx: TyCtxt<'_>) -> CallGraph {
+    let codegen_units = tcx.collect_and_partition_mono_items(()).codegen_units;
+    let mono_item_graph = tcx.mono_item_graph(());
+    let items: FxIndexSet<MonoItem<'_>> = codegen_units
+        .iter()
+        .flat_map(|cgu| cgu.items_in_deterministic_order(tcx))
+        .map(|(item, _)| item)
+        .collect();
+    let instances: FxIndexSet<Instance<'_>> = items
+        .iter()
+        .filter_map(|item| match *item {
+            MonoItem::Fn(instance) => Some(instance),
+            _ => None,
+        })
+        .collect();
+
+    // The functions that may be called indirectly.
+    let mut vtable_methods = FxIndexSet::default();
+    let mut fn_ptrs = FxIndexSet::default();
+    for item in &items {
+        for &(used, usages) in mono_item_graph.used_items.get(item).into_iter().flatten() {
+            let MonoItem::Fn(used) = used else { continue };
+            let Some(index) = instances.get_index_of(&used) else { continue };
+            if usages.contains(MonoItemUsage::VTable) {
+                vtable_methods.insert(index);
+            }
+            if usages.contains(MonoItemUsage::FnPtr) {
+                fn_ptrs.insert(index);
+            }
+        }
+    }
+
+    // Other crates can't create the vtables of a local trait that they can't name, and executables
+    // have no downstream crates. Function pointers, however, may come from any crate.
+    let only_local_vtables = |trait_def_id: Option<DefId>| {
+        trait_def_id.and_then(DefId::as_local).is_some_and(|trait_def_id| {
+            tcx.crate_types().iter().all(|&crate_type| crate_type == CrateType::Executable)
+                || !tcx.effective_visibilities(()).is_reachable(trait_def_id)
+        })
+    };
+
+    let typing_env = ty::TypingEnv::fully_monomorphized();
+    let call_instance = |callee: Instance<'_>| -> Option<Call> {
+        match callee.def {
+            InstanceKind::Virtual(def_id, _) => {
+                let candidates = vtable_methods
+                    .iter()
+                    .copied()
+                    .filter(|&candidate| {
+                        let candidate = instances[candidate].def_id();
+                        candidate == def_id || tcx.trait_item_of(candidate) == Some(def_id)
+                    })
+                    .collect();
+                let method = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+                Some(Call::Indirect {
+                    description: format!("`dyn` call of `{method}`"),
+                    candidates,
+                    complete: only_local_vtables(tcx.trait_of_assoc(def_id)),
+                })
+            }
+            // Intrinsics are lowered by the codegen backend, and there is nothing to drop.
+            InstanceKind::Intrinsic(_) | InstanceKind::DropGlue(_, None) => None,
+            _ => Some(match instances.get_index_of(&callee) {
+                Some(index) => Call::Local(index),
+                None => Call::Unknown {
+                    name: with_no_trimmed_paths!(callee.to_string()),
+                    symbol_name: callee.symbol_name(tcx).name.to_owned(),
+                    foreign: tcx.is_foreign_item(callee.def_id()),
+                },
+            }),
+        }
+    };
+    let call_lang_item = |lang_item: LangItem, span: Span| {
+        call_instance(Instance::mono(tcx, tcx.require_lang_item(lang_item, span)))
+    };
+
+    let functions = instances
+        .iter()
+        .map(|&instance| {
+            let body = tcx.mono_instance_mir(instance);
+            let monomorphize = |ty| {
+                instance.instantiate_mir_and_normalize_erasing_regions(
+                    tcx,
+                    typing_env,
+                    ty::EarlyBinder::bind(ty),
+                )
+            };
+            let mut calls = FxIndexSet::default();
+            for block in body.basic_blocks.iter() {
+                let terminator = block.terminator();
+                let span = terminator.source_info.span;
+                let call = match terminator.kind {
+                    TerminatorKind::Call { ref func, .. }
+                    | TerminatorKind::TailCall { ref func, .. } => {
+                        let callee_ty = monomorphize(func.ty(body, tcx));
+                        match *callee_ty.kind() {
+                            ty::FnDef(def_id, args) => call_instance(Instance::expect_resolve(
+                                tcx, typing_env, def_id, args, span,
+                            )),
+                            ty::FnPtr(..) => {
+                                let sig = tcx
+                                    .instantiate_bound_regions_with_erased(callee_ty.fn_sig(tcx));
+                                // Functions that aren't items, like closures, are kept as
+                                // candidates without comparing their signatures.
+                                let candidates = fn_ptrs
+                                    .iter()
+                                    .copied()
+                                    .filter(|&candidate| {
+                                        let ty = instances[candidate].ty(tcx, typing_env);
+                                        !ty.is_fn()
+                                            || tcx
+                                                .instantiate_bound_regions_with_erased(
+                                                    ty.fn_sig(tcx),
+                                                )
+                                                .inputs_and_output
+                                                == sig.inputs_and_output
+                                    })
+                                    .collect();
+                                Some(Call::Indirect {
+                                    description: format!("call through a `{callee_ty}` pointer"),
+                                    candidates,
+                                    complete: false,
+                                })
+                            }
+                            _ => None,
+                        }
+                    }
+                    TerminatorKind::Drop { ref place, .. } => {
+                        let ty = monomorphize(place.ty(body, tcx).ty);
+                        if let ty::Dynamic(predicates, ..) = ty.kind() {
+                            let candidates = vtable_methods
+                                .iter()
+                                .copied()
+                                .filter(|&candidate| {
+                                    matches!(instances[candidate].def, InstanceKind::DropGlue(..))
+                                })
+                                .collect();
+                            Some(Call::Indirect {
+                                description: format!("drop of `{ty}`"),
+                                candidates,
+                                complete: only_local_vtables(predicates.principal_def_id()),
+                            })
+                        } else {
+                            call_instance(Instance::resolve_drop_in_place(tcx, ty))
+                        }
+                    }
+                    // These call the same panic functions as the collector.
+                    TerminatorKind::Assert { ref msg, .. } => {
+                        let lang_item = match &**msg {
+                            AssertKind::BoundsCheck { .. } => LangItem::PanicBoundsCheck,
+                            AssertKind::MisalignedPointerDereference { .. } => {
+                                LangItem::PanicMisalignedPointerDereference
+                            }
+                            AssertKind::NullPointerDereference => {
+                                LangItem::PanicNullPointerDereference
+                            }
+                            AssertKind::InvalidEnumConstruction(_) => {
+                                LangItem::PanicInvalidEnumConstruction
+                            }
+                            _ => msg.panic_function(),
+                        };
+                        call_lang_item(lang_item, span)
+                    }
+                    TerminatorKind::UnwindTerminate(reason) => {
+                        call_lang_item(reason.lang_item(), span)
+                    }
+                    _ => None,
+                };
+                calls.extend(call);
+            }
+            CallGraphFunction {
+                symbol_name: instance.symbol_name(tcx).name.to_owned(),
+                name: with_no_trimmed_paths!(instance.to_string()),
+                calls: calls.into_iter().collect(),
+            }
+        })
+        .collect();
+
+    CallGraph { functions }
+}
+
+/// The stack frame of a function, as recorded by LLVM.
+#[derive(Copy, Clone, PartialEq)]
+enum Frame {
+    Size(u64),
+    /// The function is defined in an object file, but its stack size isn't known, e.g. because
+    /// the object file was reused by incremental compilation.
+    Unknown,
+    /// The function isn't defined in any object file, e.g. because it has been inlined into all
+    /// of its callers.
+    Missing,
+}
+
+impl Frame {
+    fn size(self) -> u64 {
+        match self {
+            Frame::Size(size) => size,
+            Frame::Unknown | Frame::Missing => 0,
+        }
+    }
+}
+
+/// What the object files of the crate say about the functions that they define.
+#[derive(Default)]
+struct ObjectFunctions {
+    /// The stack size of each function by symbol name, or `None` if the function is defined
+    /// without one.
+    frames: FxHashMap<String, Option<u64>>,
+    /// The symbols called by each function, as found in the relocations of call instructions.
+    calls: FxHashMap<String, FxIndexSet<String>>,
+    /// Whether an object file is for an architecture whose call relocations aren't known, so the
+    /// calls inserted by the codegen backend are unknown.
+    unknown_calls: bool,
+}
+
+/// Prints the worst-case stack usage of each entry point of the crate, i.e. each function that
+/// isn't called by any other function of the crate.
+pub(crate) fn print_stack_usage(
+    sess: &Session,
+    out: &OutFileName,
+    codegen_results: &CodegenResults,
+) {
+    let CrateInfo { call_graph: Some(call_graph), .. } = &codegen_results.crate_info else {
+        return;
+    };
+    let functions = &call_graph.functions;
+
+    let mut object_functions = ObjectFunctions::default();
+    let objects = codegen_results
+        .modules
+        .iter()
+        .chain(&codegen_results.allocator_module)
+        .filter_map(|module| module.object.as_deref());
+    for path in objects {
+        let result = fs::read(path).map_err(|error| error.to_string()).and_then(|data| {
+            read_object_functions(&data, &mut object_functions).map_err(|e| e.to_string())
+        });
+        if let Err(error) = result {
+            sess.dcx().emit_err(errors::StackUsageReadObject { path, error });
+        }
+    }
+    let ObjectFunctions { frames, calls: object_calls, unknown_calls } = object_functions;
+    if !functions.is_empty() && !frames.values().any(Option::is_some) {
+        sess.dcx().emit_warn(errors::StackUsageNoStackSizes);
+    }
+    let frames: Vec<Frame> = functions
+        .iter()
+        .map(|function| match frames.get(&function.symbol_name) {
+            Some(Some(size)) => Frame::Size(*size),
+            Some(None) => Frame::Unknown,
+            None => Frame::Missing,
+        })
+        .collect();
+
+    // The calls in the object files that are not in the call graph were inserted by the codegen
+    // backend.
+    let symbols: FxHashSet<&str> =
+        functions.iter().map(|function| &*function.symbol_name).collect();
+    let inserted_calls: Vec<Vec<&str>> = functions
+        .iter()
+        .map(|function| {
+            let Some(callees) = object_calls.get(&function.symbol_name) else { return vec![] };
+            let unknown_callees: FxHashSet<&str> = function
+                .calls
+                .iter()
+                .filter_map(|call| match call {
+                    Call::Unknown { symbol_name, .. } => Some(&**symbol_name),
+                    _ => None,
+                })
+                .collect();
+            callees
+                .iter()
+                .map(|callee| &**callee)
+                .filter(|callee| !symbols.contains(callee) && !unknown_callees.contains(callee))
+                .collect()
+        })
+        .collect();
+
+    let successors = |caller: usize| {
+        functions[caller].calls.iter().flat_map(|call| match call {
+            Call::Local(callee) => std::slice::from_ref(callee),
+            Call::Indirect { candidates, .. } => &candidates[..],
+            Call::Unknown { .. } => &[][..],
+        })
+    };
+    let edges = (0..functions.len())
+        .flat_map(|caller| successors(caller).map(move |&callee| (caller, callee)))
+        .collect::<Vec<_>>();
+    let sccs: Sccs<usize, usize> = Sccs::new(&VecGraph::<usize>::new(functions.len(), edges));
+
+    let mut is_called = vec![false; sccs.num_sccs()];
+    let mut is_recursive = vec![false; sccs.num_sccs()];
+    for caller in 0..functions.len() {
+        for &callee in successors(caller) {
+            if sccs.scc(caller) == sccs.scc(callee) {
+                is_recursive[sccs.scc(caller)] = true;
+            } else {
+                is_called[sccs.scc(callee)] = true;
+            }
+        }
+    }
+
+    // The SCCs are numbered so that callees come before their callers. Calls within an SCC are
+    // recursive, so they are ignored here and flagged in the report instead.
+    let mut order: Vec<usize> = (0..functions.len()).collect();
+    order.sort_by_key(|&function| sccs.scc(function));
+    let mut worst_case = vec![0; functions.len()];
+    let mut worst_callee = vec![None; functions.len()];
+    for &caller in &order {
+        let callee = successors(caller)
+            .copied()
+            .filter(|&callee| sccs.scc(callee) != sccs.scc(caller))
+            .max_by_key(|&callee| (worst_case[callee], std::cmp::Reverse(callee)));
+        worst_case[caller] = frames[caller].size() + callee.map_or(0, |callee| worst_case[callee]);
+        worst_callee[caller] = callee;
+    }
+
+    let mut entry_points: Vec<usize> =
+        (0..functions.len()).filter(|&function| !is_called[sccs.scc(function)]).collect();
+    entry_points.sort_by(|&a, &b| {
+        worst_case[b].cmp(&worst_case[a]).then_with(|| functions[a].name.cmp(&functions[b].name))
+    });
+
+    let mut content = String::new();
+    for entry_point in entry_points {
+        // Find the functions that can be reached from the entry point, and what they call.
+        let mut reached = FxIndexSet::default();
+        reached.insert(entry_point);
+        let mut i = 0;
+        while let Some(&caller) = reached.get_index(i) {
+            reached.extend(successors(caller).copied());
+            i += 1;
+        }
+
+        let mut notes = BTreeSet::new();
+        let mut recursive_sccs = FxHashSet::default();
+        let mut bounded = true;
+        let mut exact = true;
+        for &function in &reached {
+            let name = &functions[function].name;
+            let scc = sccs.scc(function);
+            if is_recursive[scc] && recursive_sccs.insert(scc) {
+                let cycle = reached
+                    .iter()
+                    .filter(|&&other| sccs.scc(other) == scc)
+                    .map(|&other| format!("`{}`", functions[other].name))
+                    .collect::<Vec<_>>();
+                notes.insert(format!("recursion through {}", cycle.join(", ")));
+            }
+            if is_recursive[scc] {
+                bounded = false;
+            }
+            match frames[function] {
+                Frame::Size(_) => {}
+                Frame::Unknown => {
+                    notes.insert(format!("unknown stack size of `{name}`"));
+                    exact = false;
+                }
+                Frame::Missing => {
+                    notes.insert(format!("`{name}` was not found in the object files"));
+                    exact = false;
+                }
+            }
+            for callee in &inserted_calls[function] {
+                notes.insert(format!(
+                    "call of `{callee}` inserted by the codegen backend in `{name}`"
+                ));
+                exact = false;
+            }
+            for call in &functions[function].calls {
+                match call {
+                    Call::Local(_) => {}
+                    Call::Unknown { name: callee, foreign, .. } => {
+                        let kind = if *foreign { "foreign function" } else { "function" };
+                        notes.insert(format!("call of {kind} `{callee}` in `{name}`"));
+                        exact = false;
+                    }
+                    Call::Indirect { description, candidates, complete } => {
+                        let candidates = candidates
+                            .iter()
+                            .map(|&candidate| format!("`{}`", functions[candidate].name))
+                            .collect::<Vec<_>>();
+                        let candidates = match (*complete, candidates.is_empty()) {
+                            // Nothing creates a vtable that the call could go through.
+                            (true, true) => "no candidates".to_owned(),
+                            (true, false) => format!("candidates: {}", candidates.join(", ")),
+                            (false, true) => "no known candidates".to_owned(),
+                            (false, false) => {
+                                format!("known candidates: {}", candidates.join(", "))
+                            }
+                        };
+                        if !*complete {
+                            exact = false;
+                        }
+                        notes.insert(format!("{description} in `{name}`, {candidates}"));
+                    }
+                }
+            }
+        }
+
+        if unknown_calls {
+            notes.insert("the calls inserted by the codegen backend are unknown".to_owned());
+            exact = false;
+        }
+
+        let worst = worst_case[entry_point];
+        let qualifier = match (bounded, exact) {
+            (false, false) => "unbounded, incomplete, at least ",
+            (false, true) => "unbounded, at least ",
+            (true, false) => "incomplete, at least ",
+            (true, true) => "",
+        };
+        let _ = writeln!(
+            content,
+            "stack-usage: `{}`: {qualifier}{worst} bytes",
+            functions[entry_point].name
+        );
+        let path = std::iter::successors(Some(entry_point), |&caller| worst_callee[caller]);
+        for function in path {
+            let frame = match frames[function] {
+                Frame::Size(size) => format!("{size} bytes"),
+                Frame::Unknown => "unknown".to_owned(),
+                Frame::Missing => "not found".to_owned(),
+            };
+            let _ = writeln!(content, "    `{}`: {frame}", functions[function].name);
+        }
+        for note in notes {
+            let _ = writeln!(content, "    note: {note}");
+        }
+    }
+    out.overwrite(&content, sess);
+}
+
+/// Reads the functions defined in an object file, with the stack sizes recorded in its
+/// `.stack_sizes` sections and the calls found in the relocations of its code.
+fn read_object_functions(
+    data: &[u8],
+    object_functions: &mut ObjectFunctions,
+) -> object::Result<()> {
+    let file = object::File::parse(data)?;
+    let ObjectFunctions { frames, calls, unknown_calls } = object_functions;
+
+    let mut functions = FxHashMap::default();
+    for symbol in file.symbols() {
+        if symbol.kind() == SymbolKind::Text
+            && symbol.is_definition()
+            && let Some(section) = symbol.section_index()
+        {
+            let name = original_symbol_name(symbol.name()?);
+            functions.insert((section, symbol.address()), name);
+            frames.entry(name.to_owned()).or_insert(None);
+        }
+    }
+
+    match call_relocations(file.architecture()) {
+        Some(call_relocations) => {
+            for section in file.sections().filter(|section| section.kind() == SectionKind::Text) {
+                // The calls are attributed to the last function that starts before them.
+                let mut starts: Vec<(u64, &str)> = functions
+                    .iter()
+                    .filter(|&(&(index, _), _)| index == section.index())
+                    .map(|(&(_, address), &name)| (address, name))
+                    .collect();
+                starts.sort_unstable();
+                for (offset, relocation) in section.relocations() {
+                    let RelocationFlags::Elf { r_type } = relocation.flags() else { continue };
+                    let RelocationTarget::Symbol(index) = relocation.target() else { continue };
+                    if !call_relocations.contains(&r_type) {
+                        continue;
+                    }
+                    let callee = file.symbol_by_index(index)?;
+                    if callee.kind() == SymbolKind::Section {
+                        continue;
+                    }
+                    let address = section.address() + offset;
+                    let Some(caller) =
+                        starts.partition_point(|&(start, _)| start <= address).checked_sub(1)
+                    else {
+                        continue;
+                    };
+                    calls
+                        .entry(starts[caller].1.to_owned())
+                        .or_default()
+                        .insert(original_symbol_name(callee.name()?).to_owned());
+                }
+            }
+        }
+        None => *unknown_calls = true,
+    }
+
+    // Each entry is the address of a function, followed by its stack size as a ULEB128. The
+    // address is relocated, either against the symbol of the function or against its section.
+    let address_size = if file.is_64() { 8 } else { 4 };
+    for section in file.sections().filter(|section| section.name() == Ok(".stack_sizes")) {
+        let data = section.data()?;
+        for (offset, relocation) in section.relocations() {
+            let RelocationTarget::Symbol(index) = relocation.target() else { continue };
+            let Ok(start) = usize::try_from(offset) else { continue };
+            let Some(address) = data.get(start..start + address_size) else { continue };
+            let symbol = file.symbol_by_index(index)?;
+            let name = if symbol.kind() == SymbolKind::Section {
+                let offset = if relocation.has_implicit_addend() {
+                    read_address(address, file.endianness())
+                } else {
+                    relocation.addend() as u64
+                };
+                let Some(section) = symbol.section_index() else { continue };
+                let Some(&name) = functions.get(&(section, offset)) else { continue };
+                name
+            } else {
+                original_symbol_name(symbol.name()?)
+            };
+            let Some(size) = read_uleb128(&data[start + address_size..]) else { continue };
+            let frame = frames.entry(name.to_owned()).or_insert(None);
+            *frame = Some(frame.map_or(size, |frame| frame.max(size)));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the suffix that ThinLTO appends to the symbols of the functions that it promotes from
+/// internal to hidden visibility.
+fn original_symbol_name(name: &str) -> &str {
+    name.split_once(".llvm.").map_or(name, |(name, _)| name)
+}
+
+/// The types of the ELF relocations of call instructions, or `None` if they aren't known for the
+/// architecture.
+fn call_relocations(architecture: Architecture) -> Option<&'static [u32]> {
+    let relocations: &[u32] = match architecture {
+        Architecture::X86_64 | Architecture::X86_64_X32 => &[elf::R_X86_64_PLT32],
+        Architecture::I386 => &[elf::R_386_PC32, elf::R_386_PLT32],
+        Architecture::Aarch64 => &[elf::R_AARCH64_CALL26, elf::R_AARCH64_JUMP26],
+        Architecture::Arm => &[
+            elf::R_ARM_PC24,
+            elf::R_ARM_CALL,
+            elf::R_ARM_JUMP24,
+            elf::R_ARM_THM_PC22,
+            elf::R_ARM_THM_JUMP24,
+        ],
+        Architecture::Riscv32 | Architecture::Riscv64 => {
+            &[elf::R_RISCV_CALL, elf::R_RISCV_CALL_PLT]
+        }
+        Architecture::LoongArch64 => &[elf::R_LARCH_B26],
+        _ => return None,
+    };
+    Some(relocations)
+}
+
+fn read_address(bytes: &[u8], endian: Endianness) -> u64 {
+    match *bytes {
+        [a, b, c, d] => endian.read_u32_bytes([a, b, c, d]).into(),
+        [a, b, c, d, e, f, g, h] => endian.read_u64_bytes([a, b, c, d, e, f, g, h]),
+        _ => 0,
+    }
+}
+
+fn read_uleb128(bytes: &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -12,7 +12,8 @@
 use rustc_data_structures::jobserver::{self, Acquired};
 use rustc_data_structures::memmap::Mmap;
 use rustc_data_structures::profiling::{SelfProfilerRef, VerboseTimingGuard};
-use rustc_errors::emitter::Emitter;
+use rustc_errors::emitter::{Emitter, TimingEvent};
+use rustc_errors::timings::{TimingRecord, TimingSection, TimingSectionHandler};
 use rustc_errors::translation::Translator;
 use rustc_errors::{
     Diag, DiagArgMap, DiagCtxt, DiagMessage, ErrCode, FatalError, FatalErrorMarker, Level,
@@ -348,6 +349,8 @@ pub struct CodegenContext<B: WriteBackendMethods> {
 
     /// Emitter to use for diagnostics produced during codegen.
     pub diag_emitter: SharedEmitter,
+    /// Handler for the sections timed on the worker threads, sent through `diag_emitter`.
+    pub timings: Arc<TimingSectionHandler>,
     /// LLVM optimizations for which we want to print remarks.
     pub remark: Passes,
     /// Directory into which should the LLVM optimization remarks be written.
@@ -843,7 +846,11 @@ fn execute_optimize_work_item<B: ExtraBackendMethods>(
         ModuleKind::Allocator => &cgcx.allocator_config,
     };
 
-    B::optimize(cgcx, dcx, &mut module, module_config);
+    {
+        let section = TimingSection::BackendOptimization(module.name.clone());
+        let _timing = cgcx.timings.section_guard(dcx, section);
+        B::optimize(cgcx, dcx, &mut module, module_config);
+    }
 
     // After we've done the initial round of optimizations we need to
     // decide whether to synchronously codegen this module or ship it
@@ -1299,6 +1306,7 @@ fn start_executing_work<B: ExtraBackendMethods>(
         remark_dir,
         incr_comp_session_dir: sess.incr_comp_session_dir_opt().map(|r| r.clone()),
         diag_emitter: shared_emitter.clone(),
+        timings: Arc::new(sess.timings.detached()),
         output_filenames: Arc::clone(tcx.output_filenames(())),
         module_config: regular_config,
         allocator_config,
@@ -1897,6 +1905,7 @@ enum SharedEmitterMessage {
     Diagnostic(Diagnostic),
     InlineAsmError(SpanData, String, Level, Option<(String, Vec<InnerSpan>)>),
     Fatal(String),
+    TimingSection(TimingRecord, TimingEvent),
 }
 
 #[derive(Clone)]
@@ -1960,6 +1969,10 @@ fn emit_diagnostic(
         );
     }
 
+    fn emit_timing_section(&mut self, record: TimingRecord, event: TimingEvent) {
+        drop(self.sender.send(SharedEmitterMessage::TimingSection(record, event)));
+    }
+
     fn source_map(&self) -> Option<&SourceMap> {
         None
     }
@@ -2034,6 +2047,12 @@ fn check(&self, sess: &Session, blocking: bool) {
                 Ok(SharedEmitterMessage::Fatal(msg)) => {
                     sess.dcx().fatal(msg);
                 }
+                Ok(SharedEmitterMessage::TimingSection(record, TimingEvent::Start)) => {
+                    sess.dcx().emit_timing_section_start(record);
+                }
+                Ok(SharedEmitterMessage::TimingSection(record, TimingEvent::End)) => {
+                    sess.dcx().emit_timing_section_end(record);
+                }
                 Err(_) => {
                     break;
                 }
@@ -754,6 +754,10 @@ pub fn codegen_crate<B: ExtraBackendMethods>(
     });
 
     let mut total_codegen_time = Duration::new(0, 0);
+    // The number of LLVM IR instructions of each function, for `-Zdump-generic-bloat`. Functions
+    // of reused codegen units are not counted.
+    let dump_generic_bloat = tcx.sess.opts.unstable_opts.dump_generic_bloat.enabled();
+    let mut llvm_instructions = FxHashMap::default();
     let start_rss = tcx.sess.opts.unstable_opts.time_passes.then(|| get_resident_set_size());
 
     // The non-parallel compiler can only translate codegen units to LLVM IR
@@ -813,6 +817,12 @@ pub fn codegen_crate<B: ExtraBackendMethods>(
                 // compilation hang on post-monomorphization errors.
                 tcx.dcx().abort_if_errors();
 
+                if dump_generic_bloat {
+                    for (symbol_name, count) in backend.count_instructions(&module) {
+                        // Local copies of an item are defined in every codegen unit using them.
+                        *llvm_instructions.entry(symbol_name).or_default() += count;
+                    }
+                }
                 submit_codegened_module_to_llvm(&ongoing_codegen.coordinator, module, cost);
             }
             CguReuse::PreLto => {
@@ -839,6 +849,10 @@ pub fn codegen_crate<B: ExtraBackendMethods>(
 
     ongoing_codegen.codegen_finished(tcx);
 
+    if dump_generic_bloat {
+        tcx.dump_generic_bloat(&llvm_instructions);
+    }
+
     // Since the main thread is sometimes blocked during codegen, we keep track
     // -Ztime-passes output manually.
     if tcx.sess.opts.unstable_opts.time_passes {
@@ -953,6 +967,13 @@ pub fn new(tcx: TyCtxt<'_>, target_cpu: String) -> CrateInfo {
             natvis_debugger_visualizers: Default::default(),
             lint_levels: CodegenLintLevels::from_tcx(tcx),
             metadata_symbol: exported_symbols::metadata_symbol_name(tcx),
+            call_graph: tcx
+                .sess
+                .opts
+                .prints
+                .iter()
+                .any(|print| print.kind == config::PrintKind::StackUsage)
+                .then(|| crate::back::stack_usage::call_graph(tcx)),
         };
 
         info.native_libraries.reserve(n_crates);
@@ -656,6 +656,18 @@ pub(crate) struct StaticLibraryNativeArtifactsToFile<'a> {
     pub path: &'a Path,
 }
 
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_stack_usage_no_stack_sizes)]
+#[note]
+pub(crate) struct StackUsageNoStackSizes;
+
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_stack_usage_read_object)]
+pub(crate) struct StackUsageReadObject<'a> {
+    pub path: &'a Path,
+    pub error: String,
+}
+
 #[derive(Diagnostic)]
 #[diag(codegen_ssa_link_script_unavailable)]
 pub(crate) struct LinkScriptUnavailable;
@@ -233,6 +233,8 @@ pub struct CrateInfo {
     pub natvis_debugger_visualizers: BTreeSet<DebuggerVisualizerFile>,
     pub lint_levels: CodegenLintLevels,
     pub metadata_symbol: String,
+    /// The call graph for `--print stack-usage`, if it was requested.
+    pub call_graph: Option<back::stack_usage::CallGraph>,
 }
 
 /// Target-specific options that get set in `cfg(...)`.
@@ -139,6 +139,12 @@ fn compile_codegen_unit(
         cgu_name: Symbol,
     ) -> (ModuleCodegen<Self::Module>, u64);
 
+    /// Returns the number of instructions of each function defined in the module, by symbol name,
+    /// for `-Zdump-generic-bloat`. Backends that can't count them return nothing.
+    fn count_instructions(&self, _module: &ModuleCodegen<Self::Module>) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
     fn target_machine_factory(
         &self,
         sess: &Session,
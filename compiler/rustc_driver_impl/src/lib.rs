@@ -248,6 +248,7 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
     let has_input = input.is_some();
     let (odir, ofile) = make_output(&matches);
 
+    let early_sarif_results = default_early_dcx.sarif_results();
     drop(default_early_dcx);
 
     let mut config = interface::Config {
@@ -269,6 +270,7 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
         make_codegen_backend: None,
         registry: diagnostics_registry(),
         using_internal_features: &USING_INTERNAL_FEATURES,
+        early_sarif_results,
     };
 
     callbacks.config(&mut config);
@@ -629,9 +631,14 @@ fn print_crate_info(
     #[allow(unused_imports)]
     use {do_not_use_safe_print as safe_print, do_not_use_safe_print as safe_println};
 
-    // NativeStaticLibs and LinkArgs are special - printed during linking
+    // NativeStaticLibs, LinkArgs and StackUsage are special - printed during linking
     // (empty iterator returns true)
-    if sess.opts.prints.iter().all(|p| p.kind == NativeStaticLibs || p.kind == LinkArgs) {
+    if sess
+        .opts
+        .prints
+        .iter()
+        .all(|p| p.kind == NativeStaticLibs || p.kind == LinkArgs || p.kind == StackUsage)
+    {
         return Compilation::Continue;
     }
 
@@ -808,6 +815,7 @@ fn print_crate_info(
             // Any output here interferes with Cargo's parsing of other printed output
             NativeStaticLibs => {}
             LinkArgs => {}
+            StackUsage => {}
             SplitDebuginfo => {
                 use rustc_target::spec::SplitDebuginfo::{Off, Packed, Unpacked};
 
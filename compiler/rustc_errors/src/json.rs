@@ -127,11 +127,19 @@ fn emit_timing_section(&mut self, record: TimingRecord, event: TimingEvent) {
             TimingEvent::Start => "start",
             TimingEvent::End => "end",
         };
-        let name = match record.section {
-            TimingSection::Linking => "link",
-            TimingSection::Codegen => "codegen",
+        let (name, codegen_unit) = match &record.section {
+            TimingSection::Parse => ("parse", None),
+            TimingSection::MacroExpansion => ("expand", None),
+            TimingSection::NameResolution => ("resolve", None),
+            TimingSection::TypeCheck => ("typeck", None),
+            TimingSection::BorrowCheck => ("borrowck", None),
+            TimingSection::MirOptimization => ("mir-opt", None),
+            TimingSection::MonoCollection => ("mono-collect", None),
+            TimingSection::Codegen => ("codegen", None),
+            TimingSection::BackendOptimization(cgu) => ("backend-opt", Some(cgu.as_str())),
+            TimingSection::Linking => ("link", None),
         };
-        let data = SectionTimestamp { name, event, timestamp: record.timestamp };
+        let data = SectionTimestamp { name, event, timestamp: record.timestamp, codegen_unit };
         let result = self.emit(EmitTyped::SectionTiming(data));
         if let Err(e) = result {
             panic!("failed to print timing section: {e:?}");
@@ -278,6 +286,9 @@ struct SectionTimestamp<'a> {
     event: &'a str,
     /// Opaque timestamp.
     timestamp: u128,
+    /// The codegen unit of the sections that are run in parallel for each of them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codegen_unit: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -92,6 +92,7 @@
 mod lock;
 pub mod markdown;
 pub mod registry;
+pub mod sarif;
 mod snippet;
 mod styled_buffer;
 #[cfg(test)]
@@ -1053,7 +1054,7 @@ pub fn print_error_count(&self) {
         // `emit_stashed_diagnostics` by now.
         assert!(inner.stashed_diagnostics.is_empty());
 
-        if inner.treat_err_as_bug() {
+        if inner.treat_err_as_bug() || !inner.emitter.should_show_error_count() {
             return;
         }
 
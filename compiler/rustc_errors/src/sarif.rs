@@ -0,0 +1,520 @@
+//! A SARIF 2.1.0 emitter for errors.
+//!
+//! SARIF logs are a single JSON document rather than a stream of messages, so diagnostics are
+//! collected as results while compiling and the log is written when the emitter is dropped.
+//! Early diagnostics, which are emitted before the session exists, are collected in
+//! [`EarlySarifResults`] that are handed to the emitter of the session, so that a compilation only
+//! ever writes a single log.
+//!
+//! Error codes and lint names become the rules of the run, the primary spans of a diagnostic its
+//! locations, and the secondary spans and sub-diagnostics its related locations. Only
+//! machine-applicable suggestions are turned into fixes.
+
+use std::error::Report;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use derive_setters::Setters;
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_data_structures::sync::IntoDynSyncSend;
+use rustc_error_messages::FluentArgs;
+use rustc_lint_defs::Applicability;
+use rustc_span::Span;
+use rustc_span::source_map::SourceMap;
+use serde::Serialize;
+
+use crate::diagnostic::IsLint;
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::translation::{Translator, to_fluent_args};
+use crate::{CodeSuggestion, DiagInner, Level, MultiSpan, Substitution, Suggestions};
+
+#[cfg(test)]
+mod tests;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The results of early emitters that have not been written to a log yet.
+///
+/// Clones share the same results, so that the early emitters of a compilation and the emitter of
+/// its session can all be given the same results.
+#[derive(Clone, Default)]
+pub struct EarlySarifResults(Arc<Mutex<Vec<(Rule, SarifResult)>>>);
+
+impl EarlySarifResults {
+    fn take(&self) -> Vec<(Rule, SarifResult)> {
+        mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_empty()
+    }
+}
+
+#[derive(Setters)]
+pub struct SarifEmitter {
+    #[setters(skip)]
+    dst: IntoDynSyncSend<Box<dyn Write + Send>>,
+    #[setters(skip)]
+    sm: Option<Arc<SourceMap>>,
+    #[setters(skip)]
+    translator: Translator,
+    /// Write the log even if no diagnostics were emitted, so that a clean build still reports an
+    /// empty run.
+    always_emit: bool,
+    /// Forward the results to `early_results` rather than writing a log, unless the compilation
+    /// aborts before the session exists.
+    early: bool,
+    /// The results of the early emitters, which come first in the log.
+    early_results: EarlySarifResults,
+    #[setters(skip)]
+    rules: FxIndexMap<String, Rule>,
+    #[setters(skip)]
+    results: Vec<SarifResult>,
+}
+
+impl SarifEmitter {
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        sm: Option<Arc<SourceMap>>,
+        translator: Translator,
+    ) -> SarifEmitter {
+        SarifEmitter {
+            dst: IntoDynSyncSend(dst),
+            sm,
+            translator,
+            always_emit: false,
+            early: false,
+            early_results: EarlySarifResults::default(),
+            rules: FxIndexMap::default(),
+            results: Vec::new(),
+        }
+    }
+
+    fn emit_log(&mut self) -> io::Result<()> {
+        let early_results = self.early_results.take();
+        if !early_results.is_empty() {
+            // Early results come first, as they were emitted first.
+            let rules = mem::take(&mut self.rules);
+            let results = mem::take(&mut self.results);
+            let results =
+                results.into_iter().map(|result| (rules[result.rule_index].clone(), result));
+            for (rule, result) in early_results.into_iter().chain(results) {
+                self.push_result(rule, result);
+            }
+        }
+        let log = Log {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: [Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: "rustc",
+                        information_uri: "https://www.rust-lang.org/",
+                        rules: self.rules.values().collect(),
+                    },
+                },
+                // rustc counts columns in chars rather than in UTF-16 code units.
+                column_kind: "unicodeCodePoints",
+                results: &self.results,
+            }],
+        };
+        serde_json::to_writer_pretty(&mut *self.dst, &log)?;
+        self.dst.write_all(b"\n")?;
+        self.dst.flush()
+    }
+
+    /// Adds the result to the log, registering its rule if this is its first result.
+    fn push_result(&mut self, rule: Rule, mut result: SarifResult) {
+        let entry = self.rules.entry(rule.id.clone());
+        result.rule_index = entry.index();
+        entry.or_insert(rule);
+        self.results.push(result);
+    }
+
+    /// Returns the rule the diagnostic belongs to.
+    fn rule(&self, diag: &DiagInner, level: &'static str, registry: &Registry) -> Rule {
+        let (id, help_uri, help) = if let Some(code) = diag.code {
+            (
+                code.to_string(),
+                Some(format!("https://doc.rust-lang.org/error_codes/{code}.html")),
+                registry.try_find_description(code).ok(),
+            )
+        } else if let Some(IsLint { name, .. }) = &diag.is_lint {
+            (name.clone(), None, None)
+        } else {
+            // Diagnostics without a code are grouped by their level.
+            (level.to_owned(), None, None)
+        };
+        Rule {
+            id,
+            help_uri,
+            help: help.map(|explanation| Message {
+                text: explanation.to_owned(),
+                markdown: Some(explanation.to_owned()),
+            }),
+        }
+    }
+
+    /// Splits the spans into the primary locations and the secondary, related, ones.
+    ///
+    /// Locations are described by their label, or by `message` if they don't have one.
+    fn locations(
+        &self,
+        span: &MultiSpan,
+        message: Option<&str>,
+        args: &FluentArgs<'_>,
+    ) -> (Vec<Location>, Vec<Location>) {
+        let mut primary = vec![];
+        let mut secondary = vec![];
+        for span_label in span.span_labels() {
+            let Some(physical_location) = self.physical_location(span_label.span) else {
+                continue;
+            };
+            let label = span_label.label.as_ref().map(|label| {
+                self.translator.translate_message(label, args).map_err(Report::new).unwrap()
+            });
+            let text = label.as_deref().or(message).map(str::to_owned);
+            let location = Location { physical_location, message: text.map(Message::text) };
+            if span_label.is_primary { primary.push(location) } else { secondary.push(location) }
+        }
+        (primary, secondary)
+    }
+
+    fn physical_location(&self, span: Span) -> Option<PhysicalLocation> {
+        let sm = self.sm.as_deref()?;
+        if span.is_dummy() {
+            return None;
+        }
+        let start = sm.lookup_char_pos(span.lo());
+        let end = sm.lookup_char_pos(span.hi());
+        let byte_offset = start.file.original_relative_byte_pos(span.lo()).0;
+        let byte_end = start.file.original_relative_byte_pos(span.hi()).0;
+        Some(PhysicalLocation {
+            artifact_location: ArtifactLocation {
+                uri: path_to_uri(&sm.filename_for_diagnostics(&start.file.name).to_string()),
+            },
+            region: Region {
+                start_line: start.line,
+                start_column: start.col.0 + 1,
+                end_line: end.line,
+                end_column: end.col.0 + 1,
+                byte_offset,
+                byte_length: byte_end - byte_offset,
+            },
+        })
+    }
+
+    fn fixes(&self, suggestions: &Suggestions, args: &FluentArgs<'_>) -> Vec<Fix> {
+        let suggestions: &[CodeSuggestion] = match suggestions {
+            Suggestions::Enabled(suggestions) => suggestions,
+            Suggestions::Sealed(suggestions) => suggestions,
+            Suggestions::Disabled => &[],
+        };
+        let mut fixes = vec![];
+        for sugg in suggestions {
+            if sugg.applicability != Applicability::MachineApplicable {
+                continue;
+            }
+            let description =
+                self.translator.translate_message(&sugg.msg, args).map_err(Report::new).unwrap();
+            fixes.extend(
+                sugg.substitutions
+                    .iter()
+                    .filter_map(|substitution| self.fix(&description, substitution)),
+            );
+        }
+        fixes
+    }
+
+    /// Groups the parts of the substitution by the file they change.
+    fn fix(&self, description: &str, substitution: &Substitution) -> Option<Fix> {
+        let mut artifact_changes: Vec<ArtifactChange> = vec![];
+        for part in &substitution.parts {
+            let PhysicalLocation { artifact_location, region } =
+                self.physical_location(part.span)?;
+            let replacement = Replacement {
+                deleted_region: region,
+                inserted_content: ArtifactContent { text: part.snippet.clone() },
+            };
+            match artifact_changes
+                .iter_mut()
+                .find(|change| change.artifact_location == artifact_location)
+            {
+                Some(change) => change.replacements.push(replacement),
+                None => artifact_changes
+                    .push(ArtifactChange { artifact_location, replacements: vec![replacement] }),
+            }
+        }
+        Some(Fix { description: Message::text(description.to_owned()), artifact_changes })
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner, registry: &Registry) {
+        let Some(level) = result_level(diag.level) else { return };
+        let args = to_fluent_args(diag.args.iter());
+        let mut message = self.translator.translate_messages(&diag.messages, &args).into_owned();
+        let (locations, mut related_locations) = self.locations(&diag.span, None, &args);
+        for child in &diag.children {
+            let child_message = format!(
+                "{}: {}",
+                child.level.to_str(),
+                self.translator.translate_messages(&child.messages, &args)
+            );
+            if child.span.has_primary_spans() {
+                let (primary, secondary) = self.locations(&child.span, Some(&child_message), &args);
+                related_locations.extend(primary.into_iter().chain(secondary));
+            } else {
+                // Sub-diagnostics without a location are rendered below the message, like in the
+                // human-readable output.
+                message.push('\n');
+                message.push_str(&child_message);
+            }
+        }
+        let fixes = self.fixes(&diag.suggestions, &args);
+        let rule = self.rule(&diag, level, registry);
+        let result = SarifResult {
+            rule_id: rule.id.clone(),
+            // Set when the result is added to a log.
+            rule_index: 0,
+            level,
+            message: Message::text(message),
+            locations,
+            related_locations,
+            fixes,
+        };
+        if self.early {
+            self.early_results.0.lock().unwrap().push((rule, result));
+        } else {
+            self.push_result(rule, result);
+        }
+    }
+
+    fn source_map(&self) -> Option<&SourceMap> {
+        self.sm.as_deref()
+    }
+
+    fn should_show_explain(&self) -> bool {
+        // The explanations are part of the rules.
+        false
+    }
+
+    fn should_show_error_count(&self) -> bool {
+        // The summary would be reported as another result.
+        false
+    }
+
+    fn translator(&self) -> &Translator {
+        &self.translator
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        if self.early {
+            // Without a session, nothing else would report the results of an aborted compilation.
+            if !std::thread::panicking() || self.early_results.is_empty() {
+                return;
+            }
+        } else if self.results.is_empty() && !self.always_emit && self.early_results.is_empty() {
+            return;
+        }
+        if let Err(e) = self.emit_log()
+            && !std::thread::panicking()
+        {
+            panic!("failed to print diagnostics: {e:?}");
+        }
+    }
+}
+
+/// The SARIF level of a diagnostic, or `None` if it should not be reported.
+fn result_level(level: Level) -> Option<&'static str> {
+    match level {
+        Level::Bug | Level::Fatal | Level::Error | Level::DelayedBug => Some("error"),
+        Level::ForceWarning | Level::Warning => Some("warning"),
+        Level::Note | Level::OnceNote | Level::Help | Level::OnceHelp => Some("note"),
+        // Failure notes such as "aborting due to 2 previous errors" only summarize the other
+        // results.
+        Level::FailureNote => None,
+        Level::Allow | Level::Expect => None,
+    }
+}
+
+/// Converts a file name to a URI reference: relative paths stay relative, absolute paths become
+/// `file` URIs.
+fn path_to_uri(path: &str) -> String {
+    let mut uri = String::new();
+    if Path::new(path).is_absolute() {
+        uri.push_str("file://");
+        if !path.starts_with(['/', '\\']) {
+            // Windows paths start with a drive letter.
+            uri.push('/');
+        }
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'\\' => uri.push('/'),
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~'
+            | b'/'
+            | b':'
+            | b'@'
+            | b'!'
+            | b'$'
+            | b'&'
+            | b'\''
+            | b'('
+            | b')'
+            | b'*'
+            | b'+'
+            | b','
+            | b';'
+            | b'=' => uri.push(byte as char),
+            _ => write!(uri, "%{byte:02X}").unwrap(),
+        }
+    }
+    uri
+}
+
+// The following data types are provided just for serialisation. They follow the names of the
+// SARIF 2.1.0 specification.
+
+#[derive(Serialize)]
+struct Log<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run<'a> {
+    tool: Tool<'a>,
+    column_kind: &'static str,
+    results: &'a [SarifResult],
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+    driver: ToolComponent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent<'a> {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<&'a Rule>,
+}
+
+/// An error code or a lint.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+    /// The explanation of the error code, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    /// "error", "warning" or "note".
+    level: &'static str,
+    message: Message,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<Fix>,
+}
+
+#[derive(Clone, Serialize)]
+struct Message {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    markdown: Option<String>,
+}
+
+impl Message {
+    fn text(text: String) -> Message {
+        Message { text, markdown: None }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize, PartialEq)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    /// 1-based.
+    start_line: usize,
+    /// 1-based, character offset.
+    start_column: usize,
+    end_line: usize,
+    /// 1-based, exclusive.
+    end_column: usize,
+    /// Offset in the original source file, before normalization.
+    byte_offset: u32,
+    byte_length: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+    description: Message,
+    artifact_changes: Vec<ArtifactChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+    artifact_location: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    deleted_region: Region,
+    inserted_content: ArtifactContent,
+}
+
+#[derive(Serialize)]
+struct ArtifactContent {
+    text: String,
+}
@@ -0,0 +1,208 @@
+use std::str;
+use std::sync::Mutex;
+
+use rustc_span::BytePos;
+use rustc_span::source_map::FilePathMapping;
+use serde_json::{Value, json};
+
+use super::*;
+use crate::DiagCtxt;
+use crate::codes::*;
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+/// Emits diagnostics for `code` and returns the SARIF log, or `None` if nothing was written.
+fn test_log(code: &str, always_emit: bool, f: impl FnOnce(&DiagCtxt)) -> Option<Value> {
+    test_log_with(code, |emitter| emitter.always_emit(always_emit), f)
+}
+
+/// Like `test_log`, but with the emitter returned by `configure`.
+fn test_log_with(
+    code: &str,
+    configure: impl FnOnce(SarifEmitter) -> SarifEmitter,
+    f: impl FnOnce(&DiagCtxt),
+) -> Option<Value> {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+        let translator =
+            Translator::with_fallback_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let emitter = configure(SarifEmitter::new(
+            Box::new(Shared { data: output.clone() }),
+            Some(sm),
+            translator,
+        ));
+        let dcx = DiagCtxt::new(Box::new(emitter)).with_registry(Registry::new(&[(
+            E0308,
+            "Expected type did not match the received type.",
+        )]));
+        f(&dcx);
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        let output = str::from_utf8(&bytes).unwrap();
+        (!output.is_empty()).then(|| serde_json::from_str(output).unwrap())
+    })
+}
+
+#[test]
+fn results() {
+    let log = test_log("let x = 1;\nfoo(x);\n", false, |dcx| {
+        dcx.handle()
+            .struct_span_err(span(15, 16), "mismatched types")
+            .with_code(E0308)
+            .with_span_label(span(15, 16), "expected `u8`")
+            .with_span_label(span(4, 5), "defined here")
+            .with_span_note(span(11, 14), "function defined here")
+            .with_help("consider converting")
+            .with_span_suggestion(
+                span(15, 16),
+                "use a literal",
+                "1u8",
+                Applicability::MachineApplicable,
+            )
+            .with_span_suggestion(span(11, 14), "call `bar`", "bar", Applicability::MaybeIncorrect)
+            .emit();
+        dcx.handle().struct_span_err(span(4, 5), "also mismatched").with_code(E0308).emit();
+        dcx.handle().struct_span_warn(span(0, 3), "unusual").emit();
+    })
+    .unwrap();
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(
+        run["tool"]["driver"]["rules"],
+        json!([
+            {
+                "id": "E0308",
+                "helpUri": "https://doc.rust-lang.org/error_codes/E0308.html",
+                "help": {
+                    "text": "Expected type did not match the received type.",
+                    "markdown": "Expected type did not match the received type.",
+                },
+            },
+            { "id": "warning" },
+        ])
+    );
+
+    let region = |start_line, start_column, end_column, byte_offset, byte_length| {
+        json!({
+            "startLine": start_line,
+            "startColumn": start_column,
+            "endLine": start_line,
+            "endColumn": end_column,
+            "byteOffset": byte_offset,
+            "byteLength": byte_length,
+        })
+    };
+    let location = |region: Value, message: &str| {
+        json!({
+            "physicalLocation": { "artifactLocation": { "uri": "test.rs" }, "region": region },
+            "message": { "text": message },
+        })
+    };
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[0],
+        json!({
+            "ruleId": "E0308",
+            "ruleIndex": 0,
+            "level": "error",
+            "message": { "text": "mismatched types\nhelp: consider converting" },
+            "locations": [location(region(2, 5, 6, 15, 1), "expected `u8`")],
+            "relatedLocations": [
+                location(region(1, 5, 6, 4, 1), "defined here"),
+                location(region(2, 1, 4, 11, 3), "note: function defined here"),
+            ],
+            "fixes": [{
+                "description": { "text": "use a literal" },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": "test.rs" },
+                    "replacements": [{
+                        "deletedRegion": region(2, 5, 6, 15, 1),
+                        "insertedContent": { "text": "1u8" },
+                    }],
+                }],
+            }],
+        })
+    );
+    assert_eq!(results[1]["ruleId"], "E0308");
+    assert_eq!(results[1]["ruleIndex"], 0);
+    assert_eq!(results[2]["ruleId"], "warning");
+    assert_eq!(results[2]["ruleIndex"], 1);
+    assert_eq!(results[2]["level"], "warning");
+}
+
+#[test]
+fn empty_log() {
+    assert_eq!(test_log("", false, |_| {}), None);
+    let log = test_log("", true, |_| {}).unwrap();
+    assert_eq!(log["runs"][0]["results"], json!([]));
+    assert_eq!(log["runs"][0]["tool"]["driver"]["rules"], json!([]));
+}
+
+#[test]
+fn early_results() {
+    let early_results = EarlySarifResults::default();
+    let log = test_log_with(
+        "",
+        |emitter| emitter.early(true).early_results(early_results.clone()),
+        |dcx| dcx.handle().warn("unknown option"),
+    );
+    // Early emitters leave their results to the session's emitter.
+    assert_eq!(log, None);
+
+    let log = test_log_with(
+        "fn main() {}",
+        |emitter| emitter.early_results(early_results.clone()),
+        |dcx| {
+            dcx.handle().struct_span_err(span(3, 7), "wrong name").emit();
+        },
+    )
+    .unwrap();
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"], json!([{ "id": "warning" }, { "id": "error" }]));
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleIndex"], 0);
+    assert_eq!(results[0]["message"]["text"], "unknown option");
+    assert_eq!(results[1]["ruleIndex"], 1);
+    assert_eq!(results[1]["message"]["text"], "wrong name");
+    assert!(early_results.is_empty());
+}
+
+#[test]
+fn levels() {
+    assert_eq!(result_level(Level::Error), Some("error"));
+    assert_eq!(result_level(Level::ForceWarning), Some("warning"));
+    assert_eq!(result_level(Level::Help), Some("note"));
+    assert_eq!(result_level(Level::FailureNote), None);
+}
+
+#[test]
+fn uris() {
+    assert_eq!(path_to_uri("src/lib.rs"), "src/lib.rs");
+    assert_eq!(path_to_uri("src/my file.rs"), "src/my%20file.rs");
+    if cfg!(unix) {
+        assert_eq!(path_to_uri("/home/user/src/lib.rs"), "file:///home/user/src/lib.rs");
+    }
+}
@@ -6,16 +6,39 @@
 use crate::DiagCtxtHandle;
 
 /// A high-level section of the compilation process.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// Sections are nested: for example, monomorphization collection happens during codegen.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TimingSection {
+    /// Time spent parsing the crate.
+    Parse,
+    /// Time spent expanding macros.
+    MacroExpansion,
+    /// Time spent finalizing name resolution, after macro expansion.
+    NameResolution,
+    /// Time spent type checking.
+    TypeCheck,
+    /// Time spent borrow checking and running the other MIR analyses.
+    BorrowCheck,
+    /// Time spent optimizing the MIR that is encoded in the crate metadata.
+    MirOptimization,
+    /// Time spent collecting the items to codegen.
+    ///
+    /// MIR is optimized on demand, so this includes optimizing the MIR of the collected items
+    /// that was not already optimized for the crate metadata.
+    MonoCollection,
     /// Time spent doing codegen.
     Codegen,
+    /// Time spent by the codegen backend optimizing the codegen unit with the given name.
+    ///
+    /// These sections are run in parallel by the backend.
+    BackendOptimization(String),
     /// Time spent linking.
     Linking,
 }
 
 /// Section with attached timestamp
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct TimingRecord {
     pub section: TimingSection,
     /// Microseconds elapsed since some predetermined point in time (~start of the rustc process).
@@ -27,8 +50,8 @@ fn from_origin(origin: Instant, section: TimingSection) -> Self {
         Self { section, timestamp: Instant::now().duration_since(origin).as_micros() }
     }
 
-    pub fn section(&self) -> TimingSection {
-        self.section
+    pub fn section(&self) -> &TimingSection {
+        &self.section
     }
 
     pub fn timestamp(&self) -> u128 {
@@ -51,6 +74,12 @@ pub fn new(enabled: bool) -> Self {
         Self { origin, opened_sections: Lock::new(FxHashSet::default()) }
     }
 
+    /// Returns a handler with the same time origin, for threads that don't have access to the
+    /// session, like the workers of the codegen backend.
+    pub fn detached(&self) -> Self {
+        Self { origin: self.origin, opened_sections: Lock::new(FxHashSet::default()) }
+    }
+
     /// Returns a RAII guard that will immediately emit a start the provided section, and then emit
     /// its end when it is dropped.
     pub fn section_guard<'a>(
@@ -70,7 +99,7 @@ pub fn section_guard<'a>(
     pub fn start_section(&self, diag_ctxt: DiagCtxtHandle<'_>, section: TimingSection) {
         if let Some(origin) = self.origin {
             let mut opened = self.opened_sections.borrow_mut();
-            if !opened.insert(section) {
+            if !opened.insert(section.clone()) {
                 diag_ctxt
                     .bug(format!("Section `{section:?}` was started again before it was finished"));
             }
@@ -91,7 +120,7 @@ pub fn end_section(&self, diag_ctxt: DiagCtxtHandle<'_>, section: TimingSection)
         }
     }
 
-    fn is_enabled(&self) -> bool {
+    pub fn is_enabled(&self) -> bool {
         self.origin.is_some()
     }
 }
@@ -106,7 +135,7 @@ pub struct TimingSectionGuard<'a> {
 impl<'a> TimingSectionGuard<'a> {
     fn create(dcx: DiagCtxtHandle<'a>, section: TimingSection, origin: Option<Instant>) -> Self {
         if let Some(origin) = origin {
-            dcx.emit_timing_section_start(TimingRecord::from_origin(origin, section));
+            dcx.emit_timing_section_start(TimingRecord::from_origin(origin, section.clone()));
         }
         Self { dcx, section, origin }
     }
@@ -115,7 +144,8 @@ fn create(dcx: DiagCtxtHandle<'a>, section: TimingSection, origin: Option<Instan
 impl<'a> Drop for TimingSectionGuard<'a> {
     fn drop(&mut self) {
         if let Some(origin) = self.origin {
-            self.dcx.emit_timing_section_end(TimingRecord::from_origin(origin, self.section));
+            let section = self.section.clone();
+            self.dcx.emit_timing_section_end(TimingRecord::from_origin(origin, section));
         }
     }
 }
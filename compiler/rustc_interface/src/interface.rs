@@ -8,6 +8,7 @@
 use rustc_data_structures::jobserver::{self, Proxy};
 use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::EarlySarifResults;
 use rustc_errors::{DiagCtxtHandle, ErrorGuaranteed};
 use rustc_lint::LintStore;
 use rustc_middle::ty;
@@ -376,6 +377,10 @@ pub struct Config {
     /// enabled. Makes it so that "please report a bug" is hidden, as ICEs with
     /// internal features are wontfix, and they are usually the cause of the ICEs.
     pub using_internal_features: &'static std::sync::atomic::AtomicBool,
+
+    /// The SARIF results of the driver's early diagnostics, which are written to the log of the
+    /// session. See [`EarlyDiagCtxt::sarif_results`].
+    pub early_sarif_results: EarlySarifResults,
 }
 
 /// Initialize jobserver before getting `jobserver::client` and `build_session`.
@@ -399,7 +404,10 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
     rustc_data_structures::sync::set_dyn_thread_safe_mode(config.opts.unstable_opts.threads > 1);
 
     // Check jobserver before run_in_thread_pool_with_globals, which call jobserver::acquire_thread
-    let early_dcx = EarlyDiagCtxt::new(config.opts.error_format);
+    let early_dcx = EarlyDiagCtxt::with_sarif_results(
+        config.opts.error_format,
+        config.early_sarif_results.clone(),
+    );
     initialize_checked_jobserver(&early_dcx);
 
     crate::callbacks::setup_callbacks();
@@ -423,7 +431,10 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
         |current_gcx, jobserver_proxy| {
             // The previous `early_dcx` can't be reused here because it doesn't
             // impl `Send`. Creating a new one is fine.
-            let early_dcx = EarlyDiagCtxt::new(config.opts.error_format);
+            let early_dcx = EarlyDiagCtxt::with_sarif_results(
+                config.opts.error_format,
+                config.early_sarif_results,
+            );
 
             let codegen_backend = match config.make_codegen_backend {
                 None => util::get_codegen_backend(
@@ -474,6 +485,7 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
                 util::rustc_version_str().unwrap_or("unknown"),
                 config.ice_file,
                 config.using_internal_features,
+                early_dcx.sarif_results(),
             );
 
             codegen_backend.init(&sess);
@@ -34,7 +34,9 @@
 use rustc_passes::{abi_test, input_stats, layout_test};
 use rustc_resolve::{Resolver, ResolverOutputs};
 use rustc_session::Session;
-use rustc_session::config::{CrateType, Input, OutFileName, OutputFilenames, OutputType};
+use rustc_session::config::{
+    CrateType, Input, OutFileName, OutputFilenames, OutputType, PrintTypeSizesFormat,
+};
 use rustc_session::cstore::Untracked;
 use rustc_session::output::{collect_crate_types, filename_for_input};
 use rustc_session::parse::feature_err;
@@ -52,6 +54,7 @@
 pub fn parse<'a>(sess: &'a Session) -> ast::Crate {
     let mut krate = sess
         .time("parse_crate", || {
+            let _timing = sess.timings.section_guard(sess.dcx(), TimingSection::Parse);
             let mut parser = unwrap_or_emit_fatal(match &sess.io.input {
                 Input::File(file) => new_parser_from_file(
                     &sess.psess,
@@ -165,6 +168,8 @@ fn configure_and_expand(
 
     // Expand all macros
     krate = sess.time("macro_expand_crate", || {
+        let _timing = sess.timings.section_guard(sess.dcx(), TimingSection::MacroExpansion);
+
         // Windows dlls do not have rpaths, so they don't know how to find their
         // dependencies. It's up to us to tell the system where to find all the
         // dependent dlls. Note that this uses cfg!(windows) as opposed to
@@ -302,7 +307,10 @@ fn configure_and_expand(
 
     // Done with macro expansion!
 
-    resolver.resolve_crate(&krate);
+    {
+        let _timing = sess.timings.section_guard(sess.dcx(), TimingSection::NameResolution);
+        resolver.resolve_crate(&krate);
+    }
 
     CStore::from_tcx(tcx).report_incompatible_target_modifiers(tcx, &krate);
     CStore::from_tcx(tcx).report_incompatible_async_drop_feature(tcx, &krate);
@@ -1083,7 +1091,10 @@ fn run_required_analyses(tcx: TyCtxt<'_>) {
         );
     });
 
-    rustc_hir_analysis::check_crate(tcx);
+    {
+        let _timing = sess.timings.section_guard(sess.dcx(), TimingSection::TypeCheck);
+        rustc_hir_analysis::check_crate(tcx);
+    }
     // Freeze definitions as we don't add new ones at this point.
     // We need to wait until now since we synthesize a by-move body
     // for all coroutine-closures.
@@ -1092,6 +1103,7 @@ fn run_required_analyses(tcx: TyCtxt<'_>) {
     tcx.untracked().definitions.freeze();
 
     sess.time("MIR_borrow_checking", || {
+        let _timing = sess.timings.section_guard(sess.dcx(), TimingSection::BorrowCheck);
         tcx.par_hir_body_owners(|def_id| {
             let not_typeck_child = !tcx.is_typeck_child(def_id.to_def_id());
             if not_typeck_child {
@@ -1269,8 +1281,12 @@ pub(crate) fn start_codegen<'tcx>(
 
     // This must run after monomorphization so that all generic types
     // have been instantiated.
-    if tcx.sess.opts.unstable_opts.print_type_sizes {
-        tcx.sess.code_stats.print_type_sizes();
+    match tcx.sess.opts.unstable_opts.print_type_sizes {
+        Some(PrintTypeSizesFormat::Text) => tcx.sess.code_stats.print_type_sizes(),
+        Some(PrintTypeSizesFormat::Json) => {
+            tcx.sess.code_stats.print_type_sizes_json(tcx.sess.source_map())
+        }
+        None => {}
     }
 
     (codegen, metadata)
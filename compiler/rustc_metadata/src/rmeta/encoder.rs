@@ -10,6 +10,7 @@
 use rustc_data_structures::sync::{join, par_for_each_in};
 use rustc_data_structures::temp_dir::MaybeTempDir;
 use rustc_data_structures::thousands::usize_with_underscores;
+use rustc_errors::timings::TimingSection;
 use rustc_feature::Features;
 use rustc_hir as hir;
 use rustc_hir::attrs::{AttributeKind, EncodeCrossCrate};
@@ -2241,6 +2242,9 @@ fn prefetch_mir(tcx: TyCtxt<'_>) {
         return;
     }
 
+    // This is where the MIR encoded in the metadata is optimized, so report it as its own section.
+    let _timing = tcx.sess.timings.section_guard(tcx.dcx(), TimingSection::MirOptimization);
+
     let reachable_set = tcx.reachable_set(());
     par_for_each_in(tcx.mir_keys(()), |&&def_id| {
         if tcx.is_trivial_const(def_id) {
@@ -2423,6 +2427,10 @@ pub fn encode_metadata(tcx: TyCtxt<'_>, path: &Path, ref_path: Option<&Path>) {
                 let _ = tcx.exported_generic_symbols(LOCAL_CRATE);
             },
         );
+    } else if tcx.sess.timings.is_enabled() {
+        // Optimize the MIR up front anyway, so that it gets its own timing section instead of
+        // being part of the encoding.
+        prefetch_mir(tcx);
     }
 
     // Perform metadata encoding inside a task, so the dep-graph can check if any encoded
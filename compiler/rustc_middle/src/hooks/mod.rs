@@ -3,6 +3,7 @@
 //! similar to queries, but queries come with a lot of machinery for caching and incremental
 //! compilation, whereas hooks are just plain function pointers without any of the query magic.
 
+use rustc_data_structures::fx::FxHashMap;
 use rustc_hir::def_id::{DefId, DefPathHash};
 use rustc_session::StableCrateId;
 use rustc_span::def_id::{CrateNum, LocalDefId};
@@ -86,6 +87,10 @@ fn clone(&self) -> Self { *self }
     /// of a normal function because external tools might want to override it.
     hook should_codegen_locally(instance: crate::ty::Instance<'tcx>) -> bool;
 
+    /// Writes the `-Zdump-generic-bloat` report, given the number of LLVM IR instructions of each
+    /// function that was codegened, by symbol name.
+    hook dump_generic_bloat(llvm_instructions: &FxHashMap<String, usize>) -> ();
+
     hook alloc_self_profile_query_strings() -> ();
 
     /// Saves and writes the DepGraph to the file system.
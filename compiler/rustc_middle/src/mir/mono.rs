@@ -340,6 +340,76 @@ pub struct MonoItemPartitions<'tcx> {
     pub all_mono_items: &'tcx DefIdSet,
 }
 
+/// The graph of mono items built by the `mono_item_graph` query: for every collected item, the
+/// items it uses and how it uses them. Only items that are codegened in the local crate are part
+/// of the graph.
+#[derive(Debug, Default)]
+pub struct MonoItemGraph<'tcx> {
+    pub used_items: UnordMap<MonoItem<'tcx>, Vec<(MonoItem<'tcx>, MonoItemUsages)>>,
+}
+
+/// How a mono item uses another mono item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MonoItemUsage {
+    /// The function is called directly, including the panic functions called by assertions.
+    Call,
+    /// The drop glue is called to drop a value.
+    Drop,
+    /// The function is a method or the destructor in a vtable.
+    VTable,
+    /// The function is turned into a function pointer, e.g. by a cast, by a constant or by a
+    /// `sym` operand of inline assembly.
+    FnPtr,
+    /// The static, or the thread-local shim of a static, is referenced.
+    Static,
+}
+
+impl MonoItemUsage {
+    pub const ALL: [MonoItemUsage; 5] = [
+        MonoItemUsage::Call,
+        MonoItemUsage::Drop,
+        MonoItemUsage::VTable,
+        MonoItemUsage::FnPtr,
+        MonoItemUsage::Static,
+    ];
+}
+
+/// A set of [`MonoItemUsage`]s, since an item can use another item in several ways.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MonoItemUsages(u8);
+
+impl MonoItemUsages {
+    pub fn insert(&mut self, usage: MonoItemUsage) {
+        self.0 |= 1 << usage as u8;
+    }
+
+    pub fn union(self, other: MonoItemUsages) -> MonoItemUsages {
+        MonoItemUsages(self.0 | other.0)
+    }
+
+    pub fn contains(self, usage: MonoItemUsage) -> bool {
+        self.0 & (1 << usage as u8) != 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = MonoItemUsage> {
+        MonoItemUsage::ALL.into_iter().filter(move |&usage| self.contains(usage))
+    }
+}
+
+impl From<MonoItemUsage> for MonoItemUsages {
+    fn from(usage: MonoItemUsage) -> Self {
+        let mut usages = MonoItemUsages::default();
+        usages.insert(usage);
+        usages
+    }
+}
+
+impl fmt::Debug for MonoItemUsages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
 #[derive(Debug, HashStable)]
 pub struct CodegenUnit<'tcx> {
     /// A name for this CGU. Incremental compilation requires that
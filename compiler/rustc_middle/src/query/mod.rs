@@ -67,8 +67,10 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use rustc_abi as abi;
 use rustc_abi::Align;
 use rustc_arena::TypedArena;
+use rustc_ast as ast;
 use rustc_ast::expand::allocator::AllocatorKind;
 use rustc_data_structures::fx::{FxIndexMap, FxIndexSet};
 use rustc_data_structures::sorted_map::SortedMap;
@@ -76,6 +78,7 @@
 use rustc_data_structures::svh::Svh;
 use rustc_data_structures::unord::{UnordMap, UnordSet};
 use rustc_errors::ErrorGuaranteed;
+use rustc_hir as hir;
 use rustc_hir::attrs::StrippedCfgItem;
 use rustc_hir::def::{DefKind, DocLinkResMap};
 use rustc_hir::def_id::{
@@ -98,7 +101,6 @@
 use rustc_span::source_map::Spanned;
 use rustc_span::{DUMMY_SP, Span, Symbol};
 use rustc_target::spec::{PanicStrategy, SanitizerSet};
-use {rustc_abi as abi, rustc_ast as ast, rustc_hir as hir};
 
 pub use self::keys::{AsLocalKey, Key, LocalCrate};
 pub use self::plumbing::{IntoQueryParam, TyCtxtAt, TyCtxtEnsureDone, TyCtxtEnsureOk};
@@ -118,7 +120,8 @@
     EvalToValTreeResult, GlobalId, LitToConstInput,
 };
 use crate::mir::mono::{
-    CodegenUnit, CollectionMode, MonoItem, MonoItemPartitions, NormalizationErrorInMono,
+    CodegenUnit, CollectionMode, MonoItem, MonoItemGraph, MonoItemPartitions,
+    NormalizationErrorInMono,
 };
 use crate::query::erase::{Erase, erase, restore};
 use crate::query::plumbing::{CyclePlaceholder, DynamicQuery};
@@ -2384,6 +2387,18 @@
         desc { "collect_and_partition_mono_items" }
     }
 
+    /// The graph of the mono items collected by `collect_and_partition_mono_items`, with how each
+    /// item uses the others.
+    ///
+    /// The items are collected again to build the graph, so this is only meant for the tools and
+    /// reports that ask for it.
+    query mono_item_graph(_: ()) -> &'tcx MonoItemGraph<'tcx> {
+        arena_cache
+        eval_always
+        no_hash
+        desc { "building the mono item graph" }
+    }
+
     query is_codegened_item(def_id: DefId) -> bool {
         desc { |tcx| "determining whether `{}` needs codegen", tcx.def_path_str(def_id) }
     }
@@ -2704,6 +2719,16 @@
         desc { "functions to skip for move-size check" }
     }
 
+    /// The MIR that is collected and codegened for a monomorphic instance.
+    ///
+    /// This is [`TyCtxt::instance_mir`] of the instance, unless a driver overrides this query to
+    /// rewrite bodies before codegen. Overridden bodies may already be monomorphic, so consumers
+    /// must still instantiate the result with the instance's arguments.
+    query mono_instance_mir(key: ty::Instance<'tcx>) -> &'tcx mir::Body<'tcx> {
+        desc { "getting the MIR to codegen `{}`", key }
+        no_hash
+    }
+
     query items_of_instance(key: (ty::Instance<'tcx>, CollectionMode)) -> Result<(&'tcx [Spanned<MonoItem<'tcx>>], &'tcx [Spanned<MonoItem<'tcx>>]), NormalizationErrorInMono> {
         desc { "collecting items used by `{}`", key.0 }
         cache_on_disk_if { true }
@@ -200,7 +200,7 @@ mod simplify_branches : SimplifyConstCondition {
     mod strip_debuginfo : StripDebugInfo;
     mod unreachable_enum_branching : UnreachableEnumBranching;
     mod unreachable_prop : UnreachablePropagation;
-    mod validate : Validator;
+    pub mod validate : Validator;
 }
 
 rustc_fluent_macro::fluent_messages! { "../messages.ftl" }
@@ -541,7 +541,7 @@ fn visit_source_scope(&mut self, scope: SourceScope) {
 ///
 /// `caller_body` is used to detect cycles in MIR inlining and MIR validation before
 /// `optimized_mir` is available.
-pub(super) fn validate_types<'tcx>(
+pub fn validate_types<'tcx>(
     tcx: TyCtxt<'tcx>,
     typing_env: ty::TypingEnv<'tcx>,
     body: &Body<'tcx>,
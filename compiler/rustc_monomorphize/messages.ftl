@@ -36,6 +36,9 @@ monomorphize_abi_required_target_feature =
   } here
   .help = consider enabling it globally (`-C target-feature=+{$required_feature}`) or locally (`#[target_feature(enable="{$required_feature}")]`)
 
+monomorphize_couldnt_dump_generic_bloat =
+    unexpected error occurred while dumping the code size of generic instantiations: {$error}
+
 monomorphize_couldnt_dump_mono_stats =
     unexpected error occurred while dumping monomorphization stats: {$error}
 
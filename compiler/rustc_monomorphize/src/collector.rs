@@ -213,6 +213,7 @@
 use rustc_data_structures::fx::FxIndexMap;
 use rustc_data_structures::sync::{MTLock, par_for_each_in};
 use rustc_data_structures::unord::{UnordMap, UnordSet};
+use rustc_errors::timings::TimingSection;
 use rustc_hir as hir;
 use rustc_hir::attrs::InlineAttr;
 use rustc_hir::def::DefKind;
@@ -222,7 +223,8 @@
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
 use rustc_middle::mir::interpret::{AllocId, ErrorHandled, GlobalAlloc, Scalar};
 use rustc_middle::mir::mono::{
-    CollectionMode, InstantiationMode, MonoItem, NormalizationErrorInMono,
+    CollectionMode, InstantiationMode, MonoItem, MonoItemUsage, MonoItemUsages,
+    NormalizationErrorInMono,
 };
 use rustc_middle::mir::visit::Visitor as MirVisitor;
 use rustc_middle::mir::{self, Body, Location, MentionedItem, traversal};
@@ -261,11 +263,15 @@ struct SharedState<'tcx> {
     mentioned: MTLock<UnordSet<MonoItem<'tcx>>>,
     /// Which items are being used where, for better errors.
     usage_map: MTLock<UsageMap<'tcx>>,
+    /// Whether to record how each item uses the items it uses, for the `mono_item_graph` query.
+    /// The cached `items_of_instance` query doesn't keep that, so the items of every instance are
+    /// collected again instead.
+    record_usages: bool,
 }
 
 pub(crate) struct UsageMap<'tcx> {
-    // Maps every mono item to the mono items used by it.
-    pub used_map: UnordMap<MonoItem<'tcx>, Vec<MonoItem<'tcx>>>,
+    // Maps every mono item to the mono items used by it, and how they are used.
+    pub used_map: UnordMap<MonoItem<'tcx>, Vec<(MonoItem<'tcx>, MonoItemUsages)>>,
 
     // Maps every mono item to the mono items that use it.
     user_map: UnordMap<MonoItem<'tcx>, Vec<MonoItem<'tcx>>>,
@@ -284,7 +290,8 @@ fn record_used<'a>(&mut self, user_item: MonoItem<'tcx>, used_items: &'a MonoIte
             self.user_map.entry(used_item).or_default().push(user_item);
         }
 
-        assert!(self.used_map.insert(user_item, used_items.items().collect()).is_none());
+        let used = used_items.items.iter().map(|(&item, &(_, usages))| (item, usages)).collect();
+        assert!(self.used_map.insert(user_item, used).is_none());
     }
 
     pub(crate) fn get_user_items(&self, item: MonoItem<'tcx>) -> &[MonoItem<'tcx>] {
@@ -301,7 +308,7 @@ pub(crate) fn for_each_inlined_used_item<F>(
         F: FnMut(MonoItem<'tcx>),
     {
         let used_items = self.used_map.get(&item).unwrap();
-        for used_item in used_items.iter() {
+        for (used_item, _) in used_items.iter() {
             let is_inlined = used_item.instantiation_mode(tcx) == InstantiationMode::LocalCopy;
             if is_inlined {
                 f(*used_item);
@@ -313,7 +320,8 @@ pub(crate) fn for_each_inlined_used_item<F>(
 struct MonoItems<'tcx> {
     // We want a set of MonoItem + Span where trying to re-insert a MonoItem with a different Span
     // is ignored. Map does that, but it looks odd.
-    items: FxIndexMap<MonoItem<'tcx>, Span>,
+    // The usages of an item are the union of all the ways it was pushed with.
+    items: FxIndexMap<MonoItem<'tcx>, (Span, MonoItemUsages)>,
 }
 
 impl<'tcx> MonoItems<'tcx> {
@@ -325,10 +333,21 @@ fn is_empty(&self) -> bool {
         self.items.is_empty()
     }
 
+    /// Pushes an item without recording how it is used, e.g. a root.
     fn push(&mut self, item: Spanned<MonoItem<'tcx>>) {
+        self.push_with_usages(item, MonoItemUsages::default());
+    }
+
+    fn push_use(&mut self, item: Spanned<MonoItem<'tcx>>, usage: MonoItemUsage) {
+        self.push_with_usages(item, usage.into());
+    }
+
+    fn push_with_usages(&mut self, item: Spanned<MonoItem<'tcx>>, usages: MonoItemUsages) {
         // Insert only if the entry does not exist. A normal insert would stomp the first span that
         // got inserted.
-        self.items.entry(item.node).or_insert(item.span);
+        let (_, entry) =
+            self.items.entry(item.node).or_insert((item.span, MonoItemUsages::default()));
+        *entry = entry.union(usages);
     }
 
     fn items(&self) -> impl Iterator<Item = MonoItem<'tcx>> {
@@ -341,7 +360,7 @@ impl<'tcx> IntoIterator for MonoItems<'tcx> {
     type IntoIter = impl Iterator<Item = Spanned<MonoItem<'tcx>>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.items.into_iter().map(|(item, span)| respan(span, item))
+        self.items.into_iter().map(|(item, (span, _))| respan(span, item))
     }
 }
 
@@ -439,23 +458,33 @@ fn collect_items_rec<'tcx>(
                 // Nested statics have no type.
                 if !nested {
                     let ty = instance.ty(tcx, ty::TypingEnv::fully_monomorphized());
-                    visit_drop_use(tcx, ty, true, starting_item.span, &mut used_items);
+                    visit_drop_use(
+                        tcx,
+                        ty,
+                        true,
+                        starting_item.span,
+                        MonoItemUsage::Drop,
+                        &mut used_items,
+                    );
                 }
 
                 if let Ok(alloc) = tcx.eval_static_initializer(def_id) {
                     for &prov in alloc.inner().provenance().ptrs().values() {
-                        collect_alloc(tcx, prov.alloc_id(), &mut used_items);
+                        collect_alloc(tcx, prov.alloc_id(), MonoItemUsage::FnPtr, &mut used_items);
                     }
                 }
 
                 if tcx.needs_thread_local_shim(def_id) {
-                    used_items.push(respan(
-                        starting_item.span,
-                        MonoItem::Fn(Instance {
-                            def: InstanceKind::ThreadLocalShim(def_id),
-                            args: GenericArgs::empty(),
-                        }),
-                    ));
+                    used_items.push_use(
+                        respan(
+                            starting_item.span,
+                            MonoItem::Fn(Instance {
+                                def: InstanceKind::ThreadLocalShim(def_id),
+                                args: GenericArgs::empty(),
+                            }),
+                        ),
+                        MonoItemUsage::Static,
+                    );
                 }
             }
 
@@ -477,7 +506,18 @@ fn collect_items_rec<'tcx>(
             ));
 
             rustc_data_structures::stack::ensure_sufficient_stack(|| {
-                let Ok((used, mentioned)) = tcx.items_of_instance((instance, mode)) else {
+                let result = if state.record_usages {
+                    collect_items_of_instance(tcx, instance, mode).map(|(used, mentioned)| {
+                        used_items = used;
+                        mentioned_items = mentioned;
+                    })
+                } else {
+                    tcx.items_of_instance((instance, mode)).map(|(used, mentioned)| {
+                        used_items.extend(used.into_iter().copied());
+                        mentioned_items.extend(mentioned.into_iter().copied());
+                    })
+                };
+                if result.is_err() {
                     // Normalization errors here are usually due to trait solving overflow.
                     // FIXME: I assume that there are few type errors at post-analysis stage, but not
                     // entirely sure.
@@ -492,9 +532,7 @@ fn collect_items_rec<'tcx>(
                         def_span,
                         def_path_str,
                     });
-                };
-                used_items.extend(used.into_iter().copied());
-                mentioned_items.extend(mentioned.into_iter().copied());
+                }
             });
         }
         MonoItem::GlobalAsm(item_id) => {
@@ -515,13 +553,23 @@ fn collect_items_rec<'tcx>(
                         }
                         hir::InlineAsmOperand::SymFn { expr } => {
                             let fn_ty = tcx.typeck(item_id.owner_id).expr_ty(expr);
-                            visit_fn_use(tcx, fn_ty, false, *op_sp, &mut used_items);
+                            visit_fn_use(
+                                tcx,
+                                fn_ty,
+                                false,
+                                *op_sp,
+                                MonoItemUsage::FnPtr,
+                                &mut used_items,
+                            );
                         }
                         hir::InlineAsmOperand::SymStatic { path: _, def_id } => {
                             let instance = Instance::mono(tcx, def_id);
                             if tcx.should_codegen_locally(instance) {
                                 trace!("collecting static {:?}", def_id);
-                                used_items.push(dummy_spanned(MonoItem::Static(def_id)));
+                                used_items.push_use(
+                                    dummy_spanned(MonoItem::Static(def_id)),
+                                    MonoItemUsage::Static,
+                                );
                             }
                         }
                         hir::InlineAsmOperand::In { .. }
@@ -773,7 +821,7 @@ fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: Location) {
                 // *Before* monomorphizing, record that we already handled this mention.
                 self.used_mentioned_items.insert(MentionedItem::Fn(fn_ty));
                 let fn_ty = self.monomorphize(fn_ty);
-                visit_fn_use(self.tcx, fn_ty, false, span, self.used_items);
+                visit_fn_use(self.tcx, fn_ty, false, span, MonoItemUsage::FnPtr, self.used_items);
             }
             mir::Rvalue::Cast(
                 mir::CastKind::PointerCoercion(PointerCoercion::ClosureFnPointer(_), _),
@@ -788,7 +836,10 @@ fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: Location) {
                     let instance =
                         Instance::resolve_closure(self.tcx, def_id, args, ty::ClosureKind::FnOnce);
                     if self.tcx.should_codegen_locally(instance) {
-                        self.used_items.push(create_fn_mono_item(self.tcx, instance, span));
+                        self.used_items.push_use(
+                            create_fn_mono_item(self.tcx, instance, span),
+                            MonoItemUsage::FnPtr,
+                        );
                     }
                 } else {
                     bug!()
@@ -799,7 +850,8 @@ fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: Location) {
                 let instance = Instance::mono(self.tcx, def_id);
                 if self.tcx.should_codegen_locally(instance) {
                     trace!("collecting thread-local static {:?}", def_id);
-                    self.used_items.push(respan(span, MonoItem::Static(def_id)));
+                    self.used_items
+                        .push_use(respan(span, MonoItem::Static(def_id)), MonoItemUsage::Static);
                 }
             }
             _ => { /* not interesting */ }
@@ -825,7 +877,8 @@ fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: Loc
         let push_mono_lang_item = |this: &mut Self, lang_item: LangItem| {
             let instance = Instance::mono(tcx, tcx.require_lang_item(lang_item, source));
             if tcx.should_codegen_locally(instance) {
-                this.used_items.push(create_fn_mono_item(tcx, instance, source));
+                this.used_items
+                    .push_use(create_fn_mono_item(tcx, instance, source), MonoItemUsage::Call);
             }
         };
 
@@ -863,6 +916,7 @@ fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: Loc
                     callee_ty,
                     !force_indirect_call,
                     source,
+                    MonoItemUsage::Call,
                     &mut self.used_items,
                 )
             }
@@ -871,7 +925,7 @@ fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: Loc
                 // *Before* monomorphizing, record that we already handled this mention.
                 self.used_mentioned_items.insert(MentionedItem::Drop(ty));
                 let ty = self.monomorphize(ty);
-                visit_drop_use(self.tcx, ty, true, source, self.used_items);
+                visit_drop_use(self.tcx, ty, true, source, MonoItemUsage::Drop, self.used_items);
             }
             mir::TerminatorKind::InlineAsm { ref operands, .. } => {
                 for op in operands {
@@ -881,13 +935,23 @@ fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: Loc
                             // *Before* monomorphizing, record that we already handled this mention.
                             self.used_mentioned_items.insert(MentionedItem::Fn(fn_ty));
                             let fn_ty = self.monomorphize(fn_ty);
-                            visit_fn_use(self.tcx, fn_ty, false, source, self.used_items);
+                            visit_fn_use(
+                                self.tcx,
+                                fn_ty,
+                                false,
+                                source,
+                                MonoItemUsage::FnPtr,
+                                self.used_items,
+                            );
                         }
                         mir::InlineAsmOperand::SymStatic { def_id } => {
                             let instance = Instance::mono(self.tcx, def_id);
                             if self.tcx.should_codegen_locally(instance) {
                                 trace!("collecting asm sym static {:?}", def_id);
-                                self.used_items.push(respan(source, MonoItem::Static(def_id)));
+                                self.used_items.push_use(
+                                    respan(source, MonoItem::Static(def_id)),
+                                    MonoItemUsage::Static,
+                                );
                             }
                         }
                         _ => {}
@@ -938,10 +1002,11 @@ fn visit_drop_use<'tcx>(
     ty: Ty<'tcx>,
     is_direct_call: bool,
     source: Span,
+    usage: MonoItemUsage,
     output: &mut MonoItems<'tcx>,
 ) {
     let instance = Instance::resolve_drop_in_place(tcx, ty);
-    visit_instance_use(tcx, instance, is_direct_call, source, output);
+    visit_instance_use(tcx, instance, is_direct_call, source, usage, output);
 }
 
 /// For every call of this function in the visitor, make sure there is a matching call in the
@@ -951,6 +1016,7 @@ fn visit_fn_use<'tcx>(
     ty: Ty<'tcx>,
     is_direct_call: bool,
     source: Span,
+    usage: MonoItemUsage,
     output: &mut MonoItems<'tcx>,
 ) {
     if let ty::FnDef(def_id, args) = *ty.kind() {
@@ -973,15 +1039,17 @@ fn visit_fn_use<'tcx>(
                 _ => bug!("failed to resolve instance for {ty}"),
             }
         };
-        visit_instance_use(tcx, instance, is_direct_call, source, output);
+        visit_instance_use(tcx, instance, is_direct_call, source, usage, output);
     }
 }
 
+/// `usage` is how the item being collected uses `instance`.
 fn visit_instance_use<'tcx>(
     tcx: TyCtxt<'tcx>,
     instance: ty::Instance<'tcx>,
     is_direct_call: bool,
     source: Span,
+    usage: MonoItemUsage,
     output: &mut MonoItems<'tcx>,
 ) {
     debug!("visit_item_use({:?}, is_direct_call={:?})", instance, is_direct_call);
@@ -999,7 +1067,10 @@ fn visit_instance_use<'tcx>(
             let def_id = tcx.require_lang_item(LangItem::PanicNounwind, source);
             let panic_instance = Instance::mono(tcx, def_id);
             if tcx.should_codegen_locally(panic_instance) {
-                output.push(create_fn_mono_item(tcx, panic_instance, source));
+                output.push_use(
+                    create_fn_mono_item(tcx, panic_instance, source),
+                    MonoItemUsage::Call,
+                );
             }
         } else if !intrinsic.must_be_overridden {
             // Codegen the fallback body of intrinsics with fallback bodies.
@@ -1008,7 +1079,7 @@ fn visit_instance_use<'tcx>(
             // override the implementation.
             let instance = ty::Instance::new_raw(instance.def_id(), instance.args);
             if tcx.should_codegen_locally(instance) {
-                output.push(create_fn_mono_item(tcx, instance, source));
+                output.push_use(create_fn_mono_item(tcx, instance, source), usage);
             }
         }
     }
@@ -1028,7 +1099,7 @@ fn visit_instance_use<'tcx>(
             // Note that we also optimize away the call to visit_instance_use in vtable construction
             // (see create_mono_items_for_vtable_methods).
             if !is_direct_call {
-                output.push(create_fn_mono_item(tcx, instance, source));
+                output.push_use(create_fn_mono_item(tcx, instance, source), usage);
             }
         }
         ty::InstanceKind::DropGlue(_, Some(_))
@@ -1043,7 +1114,7 @@ fn visit_instance_use<'tcx>(
         | ty::InstanceKind::FnPtrShim(..)
         | ty::InstanceKind::CloneShim(..)
         | ty::InstanceKind::FnPtrAddrShim(..) => {
-            output.push(create_fn_mono_item(tcx, instance, source));
+            output.push_use(create_fn_mono_item(tcx, instance, source), usage);
         }
     }
 }
@@ -1245,7 +1316,9 @@ fn create_mono_items_for_vtable_methods<'tcx>(
                 }
             })
             .map(|item| create_fn_mono_item(tcx, item, source));
-        output.extend(methods);
+        for method in methods {
+            output.push_use(method, MonoItemUsage::VTable);
+        }
     }
 
     // Also add the destructor, if it's necessary.
@@ -1253,19 +1326,27 @@ fn create_mono_items_for_vtable_methods<'tcx>(
     // This matches the check in vtable_allocation_provider in middle/ty/vtable.rs,
     // if we don't need drop we're not adding an actual pointer to the vtable.
     if impl_ty.needs_drop(tcx, ty::TypingEnv::fully_monomorphized()) {
-        visit_drop_use(tcx, impl_ty, false, source, output);
+        visit_drop_use(tcx, impl_ty, false, source, MonoItemUsage::VTable, output);
     }
 }
 
 /// Scans the CTFE alloc in order to find function pointers and statics that must be monomorphized.
-fn collect_alloc<'tcx>(tcx: TyCtxt<'tcx>, alloc_id: AllocId, output: &mut MonoItems<'tcx>) {
+///
+/// `fn_usage` is how the functions pointed to are used: as function pointers, or as vtable entries
+/// once we are inside of a vtable.
+fn collect_alloc<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    alloc_id: AllocId,
+    fn_usage: MonoItemUsage,
+    output: &mut MonoItems<'tcx>,
+) {
     match tcx.global_alloc(alloc_id) {
         GlobalAlloc::Static(def_id) => {
             assert!(!tcx.is_thread_local_static(def_id));
             let instance = Instance::mono(tcx, def_id);
             if tcx.should_codegen_locally(instance) {
                 trace!("collecting static {:?}", def_id);
-                output.push(dummy_spanned(MonoItem::Static(def_id)));
+                output.push_use(dummy_spanned(MonoItem::Static(def_id)), MonoItemUsage::Static);
             }
         }
         GlobalAlloc::Memory(alloc) => {
@@ -1275,7 +1356,7 @@ fn collect_alloc<'tcx>(tcx: TyCtxt<'tcx>, alloc_id: AllocId, output: &mut MonoIt
             if !ptrs.is_empty() {
                 rustc_data_structures::stack::ensure_sufficient_stack(move || {
                     for &prov in ptrs.values() {
-                        collect_alloc(tcx, prov.alloc_id(), output);
+                        collect_alloc(tcx, prov.alloc_id(), fn_usage, output);
                     }
                 });
             }
@@ -1283,7 +1364,7 @@ fn collect_alloc<'tcx>(tcx: TyCtxt<'tcx>, alloc_id: AllocId, output: &mut MonoIt
         GlobalAlloc::Function { instance, .. } => {
             if tcx.should_codegen_locally(instance) {
                 trace!("collecting {:?} with {:#?}", alloc_id, instance);
-                output.push(create_fn_mono_item(tcx, instance, DUMMY_SP));
+                output.push_use(create_fn_mono_item(tcx, instance, DUMMY_SP), fn_usage);
             }
         }
         GlobalAlloc::VTable(ty, dyn_ty) => {
@@ -1293,7 +1374,7 @@ fn collect_alloc<'tcx>(tcx: TyCtxt<'tcx>, alloc_id: AllocId, output: &mut MonoIt
                     .principal()
                     .map(|principal| tcx.instantiate_bound_regions_with_erased(principal)),
             ));
-            collect_alloc(tcx, alloc_id, output)
+            collect_alloc(tcx, alloc_id, MonoItemUsage::VTable, output)
         }
         GlobalAlloc::TypeId { .. } => {}
     }
@@ -1309,7 +1390,7 @@ fn collect_items_of_instance<'tcx>(
     mode: CollectionMode,
 ) -> Result<(MonoItems<'tcx>, MonoItems<'tcx>), NormalizationErrorInMono> {
     // This item is getting monomorphized, do mono-time checks.
-    let body = tcx.instance_mir(instance.def);
+    let body = tcx.mono_instance_mir(instance);
     // Plenty of code paths later assume that everything can be normalized. So we have to check
     // normalization first.
     // We choose to emit the error outside to provide helpful diagnostics.
@@ -1405,11 +1486,25 @@ fn visit_mentioned_item<'tcx>(
                 // for "mentioned" item collection.
                 // We can set `is_direct_call`; that just means we'll skip a bunch of shims that anyway
                 // can't have their own failing constants.
-                visit_instance_use(tcx, instance, /*is_direct_call*/ true, span, output);
+                visit_instance_use(
+                    tcx,
+                    instance,
+                    /*is_direct_call*/ true,
+                    span,
+                    MonoItemUsage::Call,
+                    output,
+                );
             }
         }
         MentionedItem::Drop(ty) => {
-            visit_drop_use(tcx, ty, /*is_direct_call*/ true, span, output);
+            visit_drop_use(
+                tcx,
+                ty,
+                /*is_direct_call*/ true,
+                span,
+                MonoItemUsage::Drop,
+                output,
+            );
         }
         MentionedItem::UnsizeCast { source_ty, target_ty } => {
             let (source_ty, target_ty) =
@@ -1426,7 +1521,7 @@ fn visit_mentioned_item<'tcx>(
                 let instance =
                     Instance::resolve_closure(tcx, def_id, args, ty::ClosureKind::FnOnce);
                 if tcx.should_codegen_locally(instance) {
-                    output.push(create_fn_mono_item(tcx, instance, span));
+                    output.push_use(create_fn_mono_item(tcx, instance, span), MonoItemUsage::FnPtr);
                 }
             } else {
                 bug!()
@@ -1443,10 +1538,12 @@ fn collect_const_value<'tcx>(
 ) {
     match value {
         mir::ConstValue::Scalar(Scalar::Ptr(ptr, _size)) => {
-            collect_alloc(tcx, ptr.provenance.alloc_id(), output)
+            collect_alloc(tcx, ptr.provenance.alloc_id(), MonoItemUsage::FnPtr, output)
         }
         mir::ConstValue::Indirect { alloc_id, .. }
-        | mir::ConstValue::Slice { alloc_id, meta: _ } => collect_alloc(tcx, alloc_id, output),
+        | mir::ConstValue::Slice { alloc_id, meta: _ } => {
+            collect_alloc(tcx, alloc_id, MonoItemUsage::FnPtr, output)
+        }
         _ => {}
     }
 }
@@ -1540,7 +1637,7 @@ fn process_item(&mut self, id: hir::ItemId) {
                     let ty =
                         self.tcx.type_of(id.owner_id.to_def_id()).instantiate(self.tcx, id_args);
                     assert!(!ty.has_non_region_param());
-                    visit_drop_use(self.tcx, ty, true, DUMMY_SP, self.output);
+                    visit_drop_use(self.tcx, ty, true, DUMMY_SP, MonoItemUsage::Drop, self.output);
                 }
             }
             DefKind::GlobalAsm => {
@@ -1789,8 +1886,10 @@ fn create_mono_items_for_default_impls<'tcx>(
 pub(crate) fn collect_crate_mono_items<'tcx>(
     tcx: TyCtxt<'tcx>,
     strategy: MonoItemCollectionStrategy,
+    record_usages: bool,
 ) -> (Vec<MonoItem<'tcx>>, UsageMap<'tcx>) {
     let _prof_timer = tcx.prof.generic_activity("monomorphization_collector");
+    let _timing = tcx.sess.timings.section_guard(tcx.dcx(), TimingSection::MonoCollection);
 
     let roots = tcx
         .sess
@@ -1802,6 +1901,7 @@ pub(crate) fn collect_crate_mono_items<'tcx>(
         visited: MTLock::new(UnordSet::default()),
         mentioned: MTLock::new(UnordSet::default()),
         usage_map: MTLock::new(UsageMap::new()),
+        record_usages,
     };
     let recursion_limit = tcx.recursion_limit();
 
@@ -1823,4 +1923,5 @@ pub(crate) fn collect_crate_mono_items<'tcx>(
 pub(crate) fn provide(providers: &mut Providers) {
     providers.hooks.should_codegen_locally = should_codegen_locally;
     providers.items_of_instance = items_of_instance;
+    providers.mono_instance_mir = |tcx, instance| tcx.instance_mir(instance.def);
 }
@@ -1,4 +1,5 @@
 use rustc_middle::bug;
+use rustc_middle::mir::mono::MonoItemUsage;
 use rustc_middle::ty::{self, GenericArg, IntrinsicDef, TyCtxt};
 
 use crate::collector::{MonoItems, create_fn_mono_item};
@@ -44,5 +45,5 @@ fn collect_autodiff_fn_from_arg<'tcx>(
         _ => bug!("expected type when matching autodiff arg"),
     };
 
-    output.push(create_fn_mono_item(tcx, instance, span));
+    output.push_use(create_fn_mono_item(tcx, instance, span), MonoItemUsage::Call);
 }
@@ -40,6 +40,12 @@ pub(crate) struct SymbolAlreadyDefined {
     pub symbol: String,
 }
 
+#[derive(Diagnostic)]
+#[diag(monomorphize_couldnt_dump_generic_bloat)]
+pub(crate) struct CouldntDumpGenericBloat {
+    pub error: String,
+}
+
 #[derive(Diagnostic)]
 #[diag(monomorphize_couldnt_dump_mono_stats)]
 pub(crate) struct CouldntDumpMonoStats {
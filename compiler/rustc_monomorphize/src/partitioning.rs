@@ -92,13 +92,14 @@
 //! source-level module, functions from the same module will be available for
 //! inlining, even when they are not marked `#[inline]`.
 
-use std::cmp;
+use std::collections::VecDeque;
 use std::collections::hash_map::Entry;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::{cmp, iter};
 
-use rustc_data_structures::fx::{FxIndexMap, FxIndexSet};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap, FxIndexSet};
 use rustc_data_structures::sync;
 use rustc_data_structures::unord::{UnordMap, UnordSet};
 use rustc_hir::LangItem;
@@ -110,7 +111,7 @@
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
 use rustc_middle::middle::exported_symbols::{SymbolExportInfo, SymbolExportLevel};
 use rustc_middle::mir::mono::{
-    CodegenUnit, CodegenUnitNameBuilder, InstantiationMode, MonoItem, MonoItemData,
+    CodegenUnit, CodegenUnitNameBuilder, InstantiationMode, MonoItem, MonoItemData, MonoItemGraph,
     MonoItemPartitions, Visibility,
 };
 use rustc_middle::ty::print::{characteristic_def_id_of_type, with_no_trimmed_paths};
@@ -123,7 +124,7 @@
 use tracing::debug;
 
 use crate::collector::{self, MonoItemCollectionStrategy, UsageMap};
-use crate::errors::{CouldntDumpMonoStats, SymbolAlreadyDefined};
+use crate::errors::{CouldntDumpGenericBloat, CouldntDumpMonoStats, SymbolAlreadyDefined};
 
 struct PartitioningCx<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
@@ -1109,14 +1110,17 @@ fn assert_symbols_are_distinct<'a, 'tcx, I>(tcx: TyCtxt<'tcx>, mono_items: I)
     }
 }
 
-fn collect_and_partition_mono_items(tcx: TyCtxt<'_>, (): ()) -> MonoItemPartitions<'_> {
-    let collection_strategy = if tcx.sess.link_dead_code() {
+fn collection_strategy(tcx: TyCtxt<'_>) -> MonoItemCollectionStrategy {
+    if tcx.sess.link_dead_code() {
         MonoItemCollectionStrategy::Eager
     } else {
         MonoItemCollectionStrategy::Lazy
-    };
+    }
+}
 
-    let (items, usage_map) = collector::collect_crate_mono_items(tcx, collection_strategy);
+fn collect_and_partition_mono_items(tcx: TyCtxt<'_>, (): ()) -> MonoItemPartitions<'_> {
+    let (items, usage_map) =
+        collector::collect_crate_mono_items(tcx, collection_strategy(tcx), false);
 
     // If there was an error during collection (e.g. from one of the constants we evaluated),
     // then we stop here. This way codegen does not have to worry about failing constants.
@@ -1214,6 +1218,13 @@ fn collect_and_partition_mono_items(tcx: TyCtxt<'_>, (): ()) -> MonoItemPartitio
     MonoItemPartitions { all_mono_items: tcx.arena.alloc(mono_items), codegen_units }
 }
 
+fn mono_item_graph(tcx: TyCtxt<'_>, (): ()) -> MonoItemGraph<'_> {
+    // Collect the items again, but only after the first collection has reported its errors.
+    tcx.ensure_done().collect_and_partition_mono_items(());
+    let (_, usage_map) = collector::collect_crate_mono_items(tcx, collection_strategy(tcx), true);
+    MonoItemGraph { used_items: usage_map.used_map }
+}
+
 /// Outputs stats about instantiation counts and estimated size, per `MonoItem`'s
 /// def, to a file in the given output directory.
 fn dump_mono_items_stats<'tcx>(
@@ -1291,8 +1302,129 @@ struct MonoItem {
     Ok(())
 }
 
+/// Outputs, per generic definition, the code size of its instantiations and the callers that
+/// caused each instantiation, to `{crate_name}.generic_bloat.json` in the directory given to
+/// `-Zdump-generic-bloat`. `llvm_instructions` has the number of LLVM IR instructions emitted for
+/// each function, by symbol name, as counted by the codegen backend.
+fn dump_generic_bloat<'tcx>(tcx: TyCtxt<'tcx>, llvm_instructions: &FxHashMap<String, usize>) {
+    if let SwitchWithOptPath::Enabled(ref path) = tcx.sess.opts.unstable_opts.dump_generic_bloat
+        && let Err(err) = dump_generic_bloat_to(tcx, path, llvm_instructions)
+    {
+        tcx.dcx().emit_fatal(CouldntDumpGenericBloat { error: err.to_string() });
+    }
+}
+
+fn dump_generic_bloat_to<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    output_directory: &Option<PathBuf>,
+    llvm_instructions: &FxHashMap<String, usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_directory = if let Some(directory) = output_directory {
+        fs::create_dir_all(directory)?;
+        directory
+    } else {
+        Path::new(".")
+    };
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let output_path = output_directory.join(format!("{crate_name}.generic_bloat.json"));
+    let file = File::create_buffered(&output_path)?;
+
+    let codegen_units = tcx.collect_and_partition_mono_items(()).codegen_units;
+    let mono_item_graph = tcx.mono_item_graph(());
+    let items: FxIndexSet<MonoItem<'tcx>> =
+        codegen_units.iter().flat_map(|cgu| cgu.items().keys().copied()).collect();
+
+    // Find the user that caused each item to be instantiated with a breadth-first search, so
+    // that the chain of callers is as short as possible. Non-generic items don't depend on
+    // their callers, so they start the chains. Generic items that aren't reachable from them
+    // start their own chains.
+    let mut caller: FxHashMap<MonoItem<'tcx>, MonoItem<'tcx>> = Default::default();
+    let mut queue: VecDeque<_> =
+        items.iter().copied().filter(|item| !item.is_generic_fn()).collect();
+    let mut visited: FxHashSet<_> = queue.iter().copied().collect();
+    let mut generic_items = items.iter().copied().filter(|item| item.is_generic_fn());
+    loop {
+        while let Some(user) = queue.pop_front() {
+            for &(used, _) in mono_item_graph.used_items.get(&user).into_iter().flatten() {
+                if visited.insert(used) {
+                    caller.insert(used, user);
+                    queue.push_back(used);
+                }
+            }
+        }
+        match generic_items.find(|item| !visited.contains(item)) {
+            Some(item) => {
+                visited.insert(item);
+                queue.push_back(item);
+            }
+            None => break,
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    struct Instantiation {
+        name: String,
+        mir_statements: usize,
+        llvm_instructions: Option<usize>,
+        callers: Vec<String>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct GenericDefinition {
+        name: String,
+        instantiation_count: usize,
+        mir_statements: usize,
+        llvm_instructions: Option<usize>,
+        instantiations: Vec<Instantiation>,
+    }
+
+    let mut instantiations_per_def_id: FxIndexMap<_, Vec<_>> = Default::default();
+    for &item in items.iter().filter(|item| item.is_generic_fn()) {
+        let MonoItem::Fn(instance) = item else { continue };
+        let mir = tcx.mono_instance_mir(instance);
+        let callers = iter::successors(caller.get(&item), |&user| caller.get(user))
+            .map(|user| with_no_trimmed_paths!(user.to_string()))
+            .collect();
+        instantiations_per_def_id.entry(instance.def_id()).or_default().push(Instantiation {
+            name: with_no_trimmed_paths!(item.to_string()),
+            // Count the terminator of each block as a statement too.
+            mir_statements: mir.basic_blocks.iter().map(|bb| bb.statements.len() + 1).sum(),
+            llvm_instructions: llvm_instructions.get(item.symbol_name(tcx).name).copied(),
+            callers,
+        });
+    }
+
+    // Output the definitions that cost the most code first.
+    let size_key = |llvm_instructions: Option<usize>, mir_statements: usize| {
+        cmp::Reverse((llvm_instructions.unwrap_or(0), mir_statements))
+    };
+    let mut definitions: Vec<_> = instantiations_per_def_id
+        .into_iter()
+        .map(|(def_id, mut instantiations)| {
+            instantiations
+                .sort_by_key(|inst| size_key(inst.llvm_instructions, inst.mir_statements));
+            GenericDefinition {
+                name: with_no_trimmed_paths!(tcx.def_path_str(def_id)),
+                instantiation_count: instantiations.len(),
+                mir_statements: instantiations.iter().map(|inst| inst.mir_statements).sum(),
+                llvm_instructions: instantiations
+                    .iter()
+                    .filter_map(|inst| inst.llvm_instructions)
+                    .reduce(|a, b| a + b),
+                instantiations,
+            }
+        })
+        .collect();
+    definitions.sort_by_key(|def| size_key(def.llvm_instructions, def.mir_statements));
+
+    serde_json::to_writer(file, &definitions)?;
+    Ok(())
+}
+
 pub(crate) fn provide(providers: &mut Providers) {
     providers.collect_and_partition_mono_items = collect_and_partition_mono_items;
+    providers.mono_item_graph = mono_item_graph;
+    providers.hooks.dump_generic_bloat = dump_generic_bloat;
 
     providers.is_codegened_item =
         |tcx, def_id| tcx.collect_and_partition_mono_items(()).all_mono_items.contains(&def_id);
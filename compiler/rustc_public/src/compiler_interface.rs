@@ -13,14 +13,15 @@
 use crate::abi::{FnAbi, Layout, LayoutShape, ReprOptions};
 use crate::crate_def::Attribute;
 use crate::mir::alloc::{AllocId, GlobalAlloc};
-use crate::mir::mono::{Instance, InstanceDef, StaticDef};
+use crate::mir::mono::{CodegenUnit, Instance, InstanceDef, MonoItem, MonoItemEdge, StaticDef};
 use crate::mir::{BinOp, Body, Place, UnOp};
 use crate::target::{MachineInfo, MachineSize};
 use crate::ty::{
-    AdtDef, AdtKind, Allocation, ClosureDef, ClosureKind, CoroutineDef, Discr, FieldDef, FnDef,
-    ForeignDef, ForeignItemKind, ForeignModule, ForeignModuleDef, GenericArgs, GenericPredicates,
-    Generics, ImplDef, ImplTrait, IntrinsicDef, LineInfo, MirConst, PolyFnSig, RigidTy, Span,
-    TraitDecl, TraitDef, Ty, TyConst, TyConstId, TyKind, UintTy, VariantDef, VariantIdx,
+    AdtDef, AdtKind, Allocation, AssocDef, ClosureDef, ClosureKind, CoroutineDef, Discr, FieldDef,
+    FnDef, ForeignDef, ForeignItemKind, ForeignModule, ForeignModuleDef, GenericArgs,
+    GenericPredicates, Generics, ImplDef, ImplSource, ImplTrait, IntrinsicDef, LineInfo, MirConst,
+    ParamEnv, PolyFnSig, RigidTy, Span, TraitDecl, TraitDef, TraitRef, Ty, TyConst, TyConstId,
+    TyKind, UintTy, VariantDef, VariantIdx,
 };
 use crate::unstable::{RustcInternal, Stable, new_item_kind};
 use crate::{
@@ -697,6 +698,75 @@ pub(crate) fn resolve_closure(
         cx.resolve_closure(def_id, args_ref, closure_kind).map(|inst| inst.stable(&mut *tables, cx))
     }
 
+    /// Get all mono items codegened in the local crate.
+    pub(crate) fn all_mono_items(&self) -> Vec<MonoItem> {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        cx.all_mono_items().iter().map(|item| item.stable(&mut *tables, cx)).collect()
+    }
+
+    /// Get the codegen units of the local crate.
+    pub(crate) fn codegen_units(&self) -> Vec<CodegenUnit> {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        cx.codegen_units().iter().map(|cgu| cgu.stable(&mut *tables, cx)).collect()
+    }
+
+    /// Get the edges of the mono item graph of the local crate.
+    pub(crate) fn mono_item_edges(&self) -> Vec<MonoItemEdge> {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        cx.mono_item_edges()
+            .iter()
+            .map(|(user, used, usages)| MonoItemEdge {
+                user: user.stable(&mut *tables, cx),
+                used: used.stable(&mut *tables, cx),
+                usages: usages.iter().map(|usage| usage.stable(&mut *tables, cx)).collect(),
+            })
+            .collect()
+    }
+
+    /// Check whether a trait reference holds in the given environment.
+    pub(crate) fn trait_ref_is_implemented(&self, trait_ref: &TraitRef, env: &ParamEnv) -> bool {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        let typing_env = cx.typing_env(param_env_item(env, &tables));
+        let trait_ref = trait_ref.internal(&mut *tables, cx.tcx);
+        cx.trait_ref_is_implemented(typing_env, trait_ref)
+    }
+
+    /// Select the candidate that proves a trait reference in the given environment.
+    pub(crate) fn select_trait_candidate(
+        &self,
+        trait_ref: &TraitRef,
+        env: &ParamEnv,
+    ) -> Result<Option<ImplSource>, Error> {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        let typing_env = cx.typing_env(param_env_item(env, &tables));
+        let trait_ref = trait_ref.internal(&mut *tables, cx.tcx);
+        cx.select_trait_candidate(typing_env, trait_ref)
+            .map(|source| source.stable(&mut *tables, cx))
+    }
+
+    /// Normalize a type in the given environment.
+    pub(crate) fn normalize_ty(&self, ty: Ty, env: &ParamEnv) -> Result<Ty, Error> {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        let typing_env = cx.typing_env(param_env_item(env, &tables));
+        let ty = ty.internal(&mut *tables, cx.tcx);
+        cx.normalize_ty(typing_env, ty).map(|ty| ty.stable(&mut *tables, cx))
+    }
+
+    /// Create a projection of an associated type.
+    pub(crate) fn new_projection_ty(&self, def: AssocDef, args: &GenericArgs) -> Ty {
+        let mut tables = self.tables.borrow_mut();
+        let cx = &*self.cx.borrow();
+        let def_id = tables[def.0];
+        let args = args.internal(&mut *tables, cx.tcx);
+        cx.new_projection_ty(def_id, args).stable(&mut *tables, cx)
+    }
+
     /// Evaluate a static's initializer.
     pub(crate) fn eval_static_initializer(&self, def: StaticDef) -> Result<Allocation, Error> {
         let mut tables = self.tables.borrow_mut();
@@ -847,6 +917,19 @@ pub(crate) fn run<'tcx, F, T>(interface: &CompilerInterface<'tcx>, f: F) -> Resu
     }
 }
 
+/// Like [`run`], but replaces any interface that is already running until `f` returns.
+///
+/// This is needed by query providers, which may be invoked while another rustc_public callback
+/// is running. Items created by the replaced interface must not be used within `f`.
+#[cfg(feature = "rustc_internal")]
+pub(crate) fn run_reentrant<'tcx, F, T>(interface: &CompilerInterface<'tcx>, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let ptr: *const () = (&raw const interface) as _;
+    TLV.set(&Cell::new(ptr), f)
+}
+
 /// Execute the given function with access the [`CompilerInterface`].
 ///
 /// I.e., This function will load the current interface and calls a function with it.
@@ -870,3 +953,14 @@ fn smir_crate<'tcx>(
     debug!(?name, ?crate_num, "smir_crate");
     Crate { id, name, is_local }
 }
+
+/// The item whose where-clauses may be assumed by trait queries in the given environment.
+fn param_env_item(
+    env: &ParamEnv,
+    tables: &Tables<'_, BridgeTys>,
+) -> Option<rustc_span::def_id::DefId> {
+    match env {
+        ParamEnv::FullyMonomorphized => None,
+        ParamEnv::Item(def_id) => Some(tables[*def_id]),
+    }
+}
@@ -34,7 +34,7 @@
 use crate::compiler_interface::with;
 pub use crate::crate_def::{CrateDef, CrateDefItems, CrateDefType, DefId};
 pub use crate::error::*;
-use crate::mir::mono::StaticDef;
+use crate::mir::mono::{CodegenUnit, MonoItem, MonoItemEdge, StaticDef};
 use crate::mir::{Body, Mutability};
 use crate::ty::{AssocItem, FnDef, ForeignModuleDef, ImplDef, ProvenanceMap, Span, TraitDef, Ty};
 use crate::unstable::Stable;
@@ -214,6 +214,26 @@ pub fn all_local_items() -> CrateItems {
     with(|cx| cx.all_local_items())
 }
 
+/// Retrieve all mono items that are codegened in the local crate.
+///
+/// This runs the monomorphization collector, which may abort compilation if it finds
+/// post-monomorphization errors.
+pub fn all_mono_items() -> Vec<MonoItem> {
+    with(|cx| cx.all_mono_items())
+}
+
+/// Retrieve the codegen units that the mono items of the local crate are partitioned into.
+pub fn codegen_units() -> Vec<CodegenUnit> {
+    with(|cx| cx.codegen_units())
+}
+
+/// Retrieve the edges of the mono item graph of the local crate, i.e., which mono items use
+/// which other mono items and how. Uses of items that are not codegened in the local crate,
+/// like non-generic functions of other crates, are not included.
+pub fn mono_item_edges() -> Vec<MonoItemEdge> {
+    with(|cx| cx.mono_item_edges())
+}
+
 pub fn all_trait_decls() -> TraitDecls {
     with(|cx| cx.all_trait_decls())
 }
@@ -17,6 +17,41 @@ pub enum MonoItem {
     GlobalAsm(Opaque),
 }
 
+/// How a mono item uses another mono item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum MonoItemUsage {
+    /// The function is called directly, including the panic functions called by assertions.
+    Call,
+    /// The drop glue is called to drop a value.
+    Drop,
+    /// The function is a method or the destructor in a vtable.
+    VTable,
+    /// The function is turned into a function pointer, e.g. by a cast or by a constant.
+    FnPtr,
+    /// The static, or the thread-local shim of a static, is referenced.
+    Static,
+}
+
+/// An edge of the mono item graph: `user` uses `used` in all the ways listed in `usages`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MonoItemEdge {
+    pub user: MonoItem,
+    pub used: MonoItem,
+    pub usages: Vec<MonoItemUsage>,
+}
+
+/// A codegen unit, i.e., a group of mono items that are codegened into the same object file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CodegenUnit {
+    pub name: Symbol,
+    /// Whether this is the primary unit, which codegen uses for items that belong to no other
+    /// unit, e.g. the entry wrapper of an upstream `main`.
+    pub is_primary: bool,
+    /// An estimate of the size of the unit, based on the MIR of its items.
+    pub size_estimate: usize,
+    pub items: Vec<MonoItem>,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Instance {
     /// The type of instance.
@@ -0,0 +1,39 @@
+//! Hooks that let tools rewrite function bodies before they are codegened.
+
+use std::sync::OnceLock;
+
+use crate::Error;
+use crate::mir::Body;
+use crate::mir::mono::Instance;
+
+/// A transformation of the monomorphic body of an instance.
+///
+/// Returning `None` keeps the body unchanged.
+pub type MonoBodyTransform = dyn Fn(Instance, Body) -> Option<Body> + Send + Sync;
+
+pub(crate) static MONO_BODY_TRANSFORM: OnceLock<Box<MonoBodyTransform>> = OnceLock::new();
+
+/// Register a transformation that rewrites function bodies before they are codegened.
+///
+/// The transformation is invoked with the monomorphic body of every instance that is codegened
+/// in the local crate, and the body it returns is used by the collector and by codegen instead.
+/// It must keep the signature of the instance, i.e., the types of the return local and of the
+/// argument locals. Upstream items that are not codegened locally are never transformed.
+///
+/// The transformation must be registered before the compiler is started with [`run!`], and each
+/// invocation runs in its own rustc_public context. Thus, items obtained outside of the
+/// transformation must not be used inside of it, and vice versa.
+///
+/// Bodies that contain inline assembly, tail calls or coverage statements cannot be converted
+/// back into the compiler's representation, so they are not passed to the transformation.
+/// Returned bodies that cannot be converted back or that are ill-typed make the compilation fail
+/// with an error.
+///
+/// [`run!`]: crate::run!
+pub fn register_mono_body_transform(
+    transform: impl Fn(Instance, Body) -> Option<Body> + Send + Sync + 'static,
+) -> Result<(), Error> {
+    MONO_BODY_TRANSFORM
+        .set(Box::new(transform))
+        .map_err(|_| Error::from("A MIR transformation has already been registered"))
+}
@@ -6,8 +6,10 @@
 use std::cell::RefCell;
 
 use rustc_middle::ty::TyCtxt;
+use rustc_middle::util::Providers;
 use rustc_public_bridge::Tables;
 use rustc_public_bridge::context::CompilerCtxt;
+use rustc_session::Session;
 use rustc_span::def_id::CrateNum;
 
 use crate::Error;
@@ -15,6 +17,7 @@
 use crate::unstable::{RustcInternal, Stable};
 
 pub mod pretty;
+mod transform;
 
 /// Convert an internal Rust compiler item into its stable counterpart, if one exists.
 ///
@@ -79,6 +82,15 @@ pub fn run<F, T>(tcx: TyCtxt<'_>, f: F) -> Result<T, Error>
     crate::compiler_interface::run(&compiler_interface, || f())
 }
 
+/// Override the compiler queries that rustc_public hooks into.
+///
+/// This installs the transformation registered with
+/// [`register_mono_body_transform`](crate::mir::transform::register_mono_body_transform), if any.
+/// [run!] does this automatically, other drivers should set it as `Config::override_queries`.
+pub fn override_queries(_session: &Session, providers: &mut Providers) {
+    transform::provide(providers);
+}
+
 /// Instantiate and run the compiler with the provided arguments and callback.
 ///
 /// The callback will be invoked after the compiler ran all its analyses, but before code generation.
@@ -226,6 +238,12 @@ impl<B, C, F> Callbacks for RustcPublic<B, C, F>
             C: Send,
             F: FnOnce($($crate::optional!($with_tcx TyCtxt))?) -> ControlFlow<B, C> + Send,
         {
+            /// Called before creating the compiler instance. Installs the query overrides
+            /// needed by rustc_public.
+            fn config(&mut self, config: &mut interface::Config) {
+                config.override_queries = Some(rustc_internal::override_queries);
+            }
+
             /// Called after analysis. Return value instructs the compiler whether to
             /// continue the compilation afterwards (defaults to `Compilation::Continue`)
             fn after_analysis<'tcx>(
@@ -0,0 +1,233 @@
+//! Query overrides that apply the transformation registered with
+//! [`register_mono_body_transform`](crate::mir::transform::register_mono_body_transform).
+
+use std::cell::RefCell;
+use std::mem;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_middle::util::Providers;
+use rustc_mir_transform::validate::validate_types;
+use rustc_public_bridge::Tables;
+use rustc_public_bridge::context::CompilerCtxt;
+use rustc_span::Span;
+
+use super::{stable, with_bridge};
+use crate::compiler_interface::{BridgeTys, CompilerInterface, run_reentrant};
+use crate::mir::transform::MONO_BODY_TRANSFORM;
+use crate::mir::{BinOp, Body, BorrowKind, Rvalue, StatementKind, TerminatorKind, UnwindAction};
+use crate::unstable::RustcInternal;
+
+pub(super) fn provide(providers: &mut Providers) {
+    if MONO_BODY_TRANSFORM.get().is_some() {
+        providers.queries.mono_instance_mir = mono_instance_mir;
+        // Parameter attributes are deduced from the original MIR, and the transformation may
+        // invalidate them.
+        providers.queries.deduced_param_attrs = |_, _| &[];
+        providers.extern_queries.deduced_param_attrs = |_, _| &[];
+    }
+}
+
+fn mono_instance_mir<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instance: ty::Instance<'tcx>,
+) -> &'tcx mir::Body<'tcx> {
+    let body = tcx.instance_mir(instance.def);
+    if !can_transform(body) {
+        return body;
+    }
+    let transform = MONO_BODY_TRANSFORM.get().unwrap();
+    let interface = CompilerInterface {
+        tables: RefCell::new(Tables::default()),
+        cx: RefCell::new(CompilerCtxt::new(tcx)),
+    };
+    let transformed = run_reentrant(&interface, || {
+        let stable_instance = stable(instance);
+        let new_body = transform(stable_instance, stable_instance.body()?)?;
+        Some(with_bridge(|tables, _| build_body(tables, tcx, instance, body, &new_body)))
+    });
+    match transformed {
+        Some(Ok(new_body)) => tcx.arena.alloc(new_body),
+        Some(Err(msg)) => tcx.dcx().span_fatal(
+            body.span,
+            format!("the MIR transformation returned an invalid body for `{instance}`: {msg}"),
+        ),
+        None => body,
+    }
+}
+
+/// Whether the body can be translated to rustc_public and back.
+fn can_transform(body: &mir::Body<'_>) -> bool {
+    body.basic_blocks.iter().all(|data| {
+        !matches!(
+            data.terminator().kind,
+            mir::TerminatorKind::InlineAsm { .. } | mir::TerminatorKind::TailCall { .. }
+        ) && data
+            .statements
+            .iter()
+            .all(|stmt| !matches!(stmt.kind, mir::StatementKind::Coverage(_)))
+    })
+}
+
+/// Convert the transformed body back to rustc MIR.
+///
+/// Anything rustc_public doesn't represent, such as source scopes and the coroutine layout, is
+/// taken from the monomorphized original body.
+fn build_body<'tcx>(
+    tables: &mut Tables<'_, BridgeTys>,
+    tcx: TyCtxt<'tcx>,
+    instance: ty::Instance<'tcx>,
+    body: &'tcx mir::Body<'tcx>,
+    new_body: &Body,
+) -> Result<mir::Body<'tcx>, String> {
+    check_body(new_body)?;
+    let mut mono_body = instance.instantiate_mir_and_normalize_erasing_regions(
+        tcx,
+        ty::TypingEnv::fully_monomorphized(),
+        ty::EarlyBinder::bind(body.clone()),
+    );
+
+    if new_body.arg_locals().len() != mono_body.arg_count {
+        return Err(format!(
+            "expected {} arguments, found {}",
+            mono_body.arg_count,
+            new_body.arg_locals().len()
+        ));
+    }
+    for (idx, decl) in new_body.locals().iter().take(mono_body.arg_count + 1).enumerate() {
+        let expected = mono_body.local_decls[mir::Local::from_usize(idx)].ty;
+        let found = decl.ty.internal(tables, tcx);
+        if expected != found {
+            return Err(format!("expected `_{idx}` to have type `{expected}`, found `{found}`"));
+        }
+    }
+    if let Some(info) = new_body
+        .var_debug_info
+        .iter()
+        .find(|info| info.source_info.scope as usize >= mono_body.source_scopes.len())
+    {
+        return Err(format!("unknown source scope in the debug info of `{}`", info.name));
+    }
+
+    // rustc_public only keeps spans, so new statements get the scope of an original statement
+    // with the same span.
+    let mut scopes = FxHashMap::default();
+    for data in mono_body.basic_blocks.iter() {
+        let terminator = data.terminator().source_info;
+        for source_info in data.statements.iter().map(|stmt| stmt.source_info).chain([terminator]) {
+            scopes.entry(source_info.span).or_insert(source_info.scope);
+        }
+    }
+    for decl in &mono_body.local_decls {
+        scopes.entry(decl.source_info.span).or_insert(decl.source_info.scope);
+    }
+    let scope_of = |span: Span| scopes.get(&span).copied().unwrap_or(mir::OUTERMOST_SOURCE_SCOPE);
+
+    let blocks = new_body
+        .blocks
+        .iter()
+        .zip(cleanup_blocks(new_body))
+        .map(|(block, is_cleanup)| {
+            let statements = block
+                .statements
+                .iter()
+                .map(|stmt| {
+                    let mut stmt = stmt.internal(tables, tcx);
+                    stmt.source_info.scope = scope_of(stmt.source_info.span);
+                    stmt
+                })
+                .collect();
+            let mut terminator = block.terminator.internal(tables, tcx);
+            terminator.source_info.scope = scope_of(terminator.source_info.span);
+            if is_cleanup {
+                match &mut terminator.kind {
+                    mir::TerminatorKind::UnwindTerminate(reason) => {
+                        *reason = mir::UnwindTerminateReason::InCleanup
+                    }
+                    kind => {
+                        if let Some(mir::UnwindAction::Terminate(reason)) = kind.unwind_mut() {
+                            *reason = mir::UnwindTerminateReason::InCleanup
+                        }
+                    }
+                }
+            }
+            mir::BasicBlockData::new_stmts(statements, Some(terminator), is_cleanup)
+        })
+        .collect();
+    mono_body.basic_blocks = mir::BasicBlocks::new(blocks);
+    mono_body.local_decls = new_body
+        .locals()
+        .iter()
+        .map(|decl| {
+            let mut decl = decl.internal(tables, tcx);
+            decl.source_info.scope = scope_of(decl.source_info.span);
+            decl
+        })
+        .collect();
+    mono_body.var_debug_info = new_body.var_debug_info.internal(tables, tcx);
+
+    // Report ill-typed MIR here, instead of letting codegen run into it.
+    let typing_env = ty::TypingEnv::fully_monomorphized();
+    if let Some((location, msg)) =
+        validate_types(tcx, typing_env, &mono_body, &mono_body).into_iter().next()
+    {
+        return Err(format!("{msg} at {location:?}"));
+    }
+    Ok(mono_body)
+}
+
+/// Reject the constructs that cannot be converted back, or that are not allowed in the MIR that
+/// is codegened.
+fn check_body(body: &Body) -> Result<(), String> {
+    let num_blocks = body.blocks.len();
+    for (idx, block) in body.blocks.iter().enumerate() {
+        for stmt in &block.statements {
+            match &stmt.kind {
+                StatementKind::FakeRead(..)
+                | StatementKind::AscribeUserType { .. }
+                | StatementKind::Coverage(_)
+                | StatementKind::Assign(_, Rvalue::Len(_))
+                | StatementKind::Assign(_, Rvalue::Ref(_, BorrowKind::Fake(_), _)) => {
+                    return Err(format!("unsupported statement in `bb{idx}`: {:?}", stmt.kind));
+                }
+                StatementKind::Assign(_, Rvalue::CheckedBinaryOp(op, ..))
+                    if !matches!(op, BinOp::Add | BinOp::Sub | BinOp::Mul) =>
+                {
+                    return Err(format!(
+                        "`{op:?}` cannot be used in a checked binary operation in `bb{idx}`"
+                    ));
+                }
+                _ => {}
+            }
+        }
+        if let TerminatorKind::InlineAsm { .. } = block.terminator.kind {
+            return Err(format!("unsupported inline assembly in `bb{idx}`"));
+        }
+        if let Some(target) =
+            block.terminator.successors().into_iter().find(|target| *target >= num_blocks)
+        {
+            return Err(format!("`bb{idx}` jumps to unknown block `bb{target}`"));
+        }
+    }
+    Ok(())
+}
+
+/// Blocks that are only reachable while unwinding, which rustc_public doesn't record.
+fn cleanup_blocks(body: &Body) -> Vec<bool> {
+    let mut is_cleanup = vec![false; body.blocks.len()];
+    let mut worklist: Vec<_> = body
+        .blocks
+        .iter()
+        .filter_map(|block| match block.terminator.kind.unwind() {
+            Some(UnwindAction::Cleanup(target)) => Some(*target),
+            _ => None,
+        })
+        .collect();
+    while let Some(bb) = worklist.pop() {
+        if !mem::replace(&mut is_cleanup[bb], true) {
+            worklist.extend(body.blocks[bb].terminator.successors());
+        }
+    }
+    is_cleanup
+}
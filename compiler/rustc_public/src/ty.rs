@@ -74,6 +74,12 @@ pub fn new_box(inner_ty: Ty) -> Ty {
         with(|cx| cx.new_box_ty(inner_ty))
     }
 
+    /// Create a projection of the associated type `def` with the given generic arguments,
+    /// e.g., `<T as Iterator>::Item`. The first argument is the `Self` type.
+    pub fn new_projection(def: AssocDef, args: &GenericArgs) -> Ty {
+        with(|cx| cx.new_projection_ty(def, args))
+    }
+
     /// Create a type representing `usize`.
     pub fn usize_ty() -> Ty {
         Ty::from_rigid_kind(RigidTy::Uint(UintTy::Usize))
@@ -98,6 +104,12 @@ pub fn unsigned_ty(inner: UintTy) -> Ty {
     pub fn layout(self) -> Result<Layout, Error> {
         with(|cx| cx.ty_layout(self))
     }
+
+    /// Normalize this type in the given environment, e.g., resolve projections to the
+    /// associated type of the selected impl. Regions are erased.
+    pub fn normalize(&self, env: &ParamEnv) -> Result<Ty, Error> {
+        with(|cx| cx.normalize_ty(*self, env))
+    }
 }
 
 impl Ty {
@@ -1445,6 +1457,52 @@ pub fn self_ty(&self) -> Ty {
         };
         self_ty
     }
+
+    /// Check whether this trait reference holds in the given environment. Regions are ignored.
+    pub fn is_implemented(&self, env: &ParamEnv) -> bool {
+        with(|cx| cx.trait_ref_is_implemented(self, env))
+    }
+
+    /// Select the candidate that proves this trait reference in the given environment.
+    ///
+    /// Returns `Ok(None)` if the selection is ambiguous, e.g., because the trait reference
+    /// still has generic parameters that the environment does not constrain. Returns an error
+    /// if the trait is not implemented.
+    pub fn select(&self, env: &ParamEnv) -> Result<Option<ImplSource>, Error> {
+        with(|cx| cx.select_trait_candidate(self, env))
+    }
+}
+
+/// The environment in which trait queries are evaluated.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum ParamEnv {
+    /// Nothing can be assumed, so the query should not refer to generic parameters.
+    FullyMonomorphized,
+    /// The where-clauses of the given item can be assumed to hold.
+    Item(DefId),
+}
+
+/// The candidate selected to prove a trait reference.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum ImplSource {
+    /// A user-written impl, with the generic arguments it is instantiated with.
+    UserDefined { impl_def: ImplDef, args: GenericArgs },
+    /// A where-clause of the environment.
+    Param,
+    /// An implementation provided by the compiler.
+    Builtin(BuiltinImplSource),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum BuiltinImplSource {
+    /// A builtin impl without nested requirements, e.g., `Sized` for `u8`.
+    Trivial,
+    /// Any other builtin impl, e.g., `Clone` for tuples or `Fn` for closures.
+    Misc,
+    /// The implementation of the trait by a trait object.
+    Object,
+    /// The upcasting of a trait object to one of its supertraits.
+    TraitUpcasting,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
@@ -5,6 +5,7 @@
 
 // Prefer importing rustc_public over internal rustc constructs to make this file more readable.
 
+use rustc_middle::bug;
 use rustc_middle::ty::{self as rustc_ty, Const as InternalConst, Ty as InternalTy};
 use rustc_public_bridge::Tables;
 
@@ -12,7 +13,13 @@
 use crate::compiler_interface::BridgeTys;
 use crate::mir::alloc::AllocId;
 use crate::mir::mono::{Instance, MonoItem, StaticDef};
-use crate::mir::{BinOp, Mutability, Place, ProjectionElem, RawPtrKind, Safety, UnOp};
+use crate::mir::{
+    AggregateKind, AssertMessage, BinOp, BorrowKind, CastKind, ConstOperand, CoroutineDesugaring,
+    CoroutineKind, CoroutineSource, FakeBorrowKind, LocalDecl, MutBorrowKind, Mutability,
+    NonDivergingIntrinsic, NullOp, Operand, Place, PointerCoercion, ProjectionElem, RawPtrKind,
+    RetagKind, Rvalue, Safety, Statement, StatementKind, Terminator, TerminatorKind, UnOp,
+    UnwindAction, VarDebugInfo, VarDebugInfoContents,
+};
 use crate::ty::{
     Abi, AdtDef, Binder, BoundRegionKind, BoundTyKind, BoundVariableKind, ClosureKind,
     ExistentialPredicate, ExistentialProjection, ExistentialTraitRef, FloatTy, FnSig,
@@ -759,6 +766,598 @@ fn internal<'tcx>(
     }
 }
 
+impl RustcInternal for Operand {
+    type T<'tcx> = rustc_middle::mir::Operand<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            Operand::Copy(place) => rustc_middle::mir::Operand::Copy(place.internal(tables, tcx)),
+            Operand::Move(place) => rustc_middle::mir::Operand::Move(place.internal(tables, tcx)),
+            Operand::Constant(constant) => {
+                rustc_middle::mir::Operand::Constant(Box::new(constant.internal(tables, tcx)))
+            }
+        }
+    }
+}
+
+impl RustcInternal for ConstOperand {
+    type T<'tcx> = rustc_middle::mir::ConstOperand<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        rustc_middle::mir::ConstOperand {
+            span: self.span.internal(tables, tcx),
+            user_ty: self.user_ty.map(rustc_ty::UserTypeAnnotationIndex::from_usize),
+            const_: self.const_.internal(tables, tcx),
+        }
+    }
+}
+
+impl RustcInternal for Rvalue {
+    type T<'tcx> = rustc_middle::mir::Rvalue<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            Rvalue::AddressOf(kind, place) => rustc_middle::mir::Rvalue::RawPtr(
+                kind.internal(tables, tcx),
+                place.internal(tables, tcx),
+            ),
+            Rvalue::Aggregate(kind, operands) => rustc_middle::mir::Rvalue::Aggregate(
+                Box::new(kind.internal(tables, tcx)),
+                operands.iter().map(|operand| operand.internal(tables, tcx)).collect(),
+            ),
+            Rvalue::BinaryOp(bin_op, lhs, rhs) => rustc_middle::mir::Rvalue::BinaryOp(
+                bin_op.internal(tables, tcx),
+                Box::new((lhs.internal(tables, tcx), rhs.internal(tables, tcx))),
+            ),
+            Rvalue::Cast(kind, operand, ty) => rustc_middle::mir::Rvalue::Cast(
+                kind.internal(tables, tcx),
+                operand.internal(tables, tcx),
+                ty.internal(tables, tcx),
+            ),
+            Rvalue::CheckedBinaryOp(bin_op, lhs, rhs) => {
+                let bin_op = bin_op.internal(tables, tcx);
+                rustc_middle::mir::Rvalue::BinaryOp(
+                    bin_op.wrapping_to_overflowing().unwrap_or_else(|| {
+                        panic!("`{bin_op:?}` cannot be used in a checked binary operation")
+                    }),
+                    Box::new((lhs.internal(tables, tcx), rhs.internal(tables, tcx))),
+                )
+            }
+            Rvalue::CopyForDeref(place) => {
+                rustc_middle::mir::Rvalue::CopyForDeref(place.internal(tables, tcx))
+            }
+            Rvalue::Discriminant(place) => {
+                rustc_middle::mir::Rvalue::Discriminant(place.internal(tables, tcx))
+            }
+            Rvalue::Len(_) => bug!("`Rvalue::Len` should have been rejected by `check_body`"),
+            Rvalue::Ref(region, kind, place) => rustc_middle::mir::Rvalue::Ref(
+                region.internal(tables, tcx),
+                kind.internal(tables, tcx),
+                place.internal(tables, tcx),
+            ),
+            Rvalue::Repeat(operand, count) => rustc_middle::mir::Rvalue::Repeat(
+                operand.internal(tables, tcx),
+                count.internal(tables, tcx),
+            ),
+            Rvalue::ShallowInitBox(operand, ty) => rustc_middle::mir::Rvalue::ShallowInitBox(
+                operand.internal(tables, tcx),
+                ty.internal(tables, tcx),
+            ),
+            Rvalue::ThreadLocalRef(item) => {
+                rustc_middle::mir::Rvalue::ThreadLocalRef(item.internal(tables, tcx))
+            }
+            Rvalue::NullaryOp(null_op, ty) => rustc_middle::mir::Rvalue::NullaryOp(
+                null_op.internal(tables, tcx),
+                ty.internal(tables, tcx),
+            ),
+            Rvalue::UnaryOp(un_op, operand) => rustc_middle::mir::Rvalue::UnaryOp(
+                un_op.internal(tables, tcx),
+                operand.internal(tables, tcx),
+            ),
+            Rvalue::Use(operand) => rustc_middle::mir::Rvalue::Use(operand.internal(tables, tcx)),
+        }
+    }
+}
+
+impl RustcInternal for AggregateKind {
+    type T<'tcx> = rustc_middle::mir::AggregateKind<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            AggregateKind::Array(ty) => {
+                rustc_middle::mir::AggregateKind::Array(ty.internal(tables, tcx))
+            }
+            AggregateKind::Tuple => rustc_middle::mir::AggregateKind::Tuple,
+            AggregateKind::Adt(adt_def, variant, args, user_ty, field) => {
+                rustc_middle::mir::AggregateKind::Adt(
+                    adt_def.0.internal(tables, tcx),
+                    variant.internal(tables, tcx),
+                    args.internal(tables, tcx),
+                    user_ty.map(rustc_ty::UserTypeAnnotationIndex::from_usize),
+                    field.map(rustc_abi::FieldIdx::from_usize),
+                )
+            }
+            AggregateKind::Closure(def, args) => rustc_middle::mir::AggregateKind::Closure(
+                def.0.internal(tables, tcx),
+                args.internal(tables, tcx),
+            ),
+            AggregateKind::Coroutine(def, args) => rustc_middle::mir::AggregateKind::Coroutine(
+                def.0.internal(tables, tcx),
+                args.internal(tables, tcx),
+            ),
+            AggregateKind::CoroutineClosure(def, args) => {
+                rustc_middle::mir::AggregateKind::CoroutineClosure(
+                    def.0.internal(tables, tcx),
+                    args.internal(tables, tcx),
+                )
+            }
+            AggregateKind::RawPtr(ty, mutability) => rustc_middle::mir::AggregateKind::RawPtr(
+                ty.internal(tables, tcx),
+                mutability.internal(tables, tcx),
+            ),
+        }
+    }
+}
+
+impl RustcInternal for CastKind {
+    type T<'tcx> = rustc_middle::mir::CastKind;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        use rustc_middle::mir::CastKind as InternalCastKind;
+        match self {
+            CastKind::PointerExposeAddress => InternalCastKind::PointerExposeProvenance,
+            CastKind::PointerWithExposedProvenance => {
+                InternalCastKind::PointerWithExposedProvenance
+            }
+            // The source of a coercion only matters for borrowck, which has already run.
+            CastKind::PointerCoercion(coercion) => InternalCastKind::PointerCoercion(
+                coercion.internal(tables, tcx),
+                rustc_middle::mir::CoercionSource::Implicit,
+            ),
+            CastKind::IntToInt => InternalCastKind::IntToInt,
+            CastKind::FloatToInt => InternalCastKind::FloatToInt,
+            CastKind::FloatToFloat => InternalCastKind::FloatToFloat,
+            CastKind::IntToFloat => InternalCastKind::IntToFloat,
+            CastKind::PtrToPtr => InternalCastKind::PtrToPtr,
+            CastKind::FnPtrToPtr => InternalCastKind::FnPtrToPtr,
+            CastKind::Transmute => InternalCastKind::Transmute,
+            CastKind::Subtype => InternalCastKind::Subtype,
+        }
+    }
+}
+
+impl RustcInternal for PointerCoercion {
+    type T<'tcx> = rustc_ty::adjustment::PointerCoercion;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        use rustc_ty::adjustment::PointerCoercion as InternalPointerCoercion;
+        match self {
+            PointerCoercion::ReifyFnPointer => InternalPointerCoercion::ReifyFnPointer,
+            PointerCoercion::UnsafeFnPointer => InternalPointerCoercion::UnsafeFnPointer,
+            PointerCoercion::ClosureFnPointer(safety) => {
+                InternalPointerCoercion::ClosureFnPointer(safety.internal(tables, tcx))
+            }
+            PointerCoercion::MutToConstPointer => InternalPointerCoercion::MutToConstPointer,
+            PointerCoercion::ArrayToPointer => InternalPointerCoercion::ArrayToPointer,
+            PointerCoercion::Unsize => InternalPointerCoercion::Unsize,
+        }
+    }
+}
+
+impl RustcInternal for NullOp {
+    type T<'tcx> = rustc_middle::mir::NullOp<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            NullOp::OffsetOf(indices) => rustc_middle::mir::NullOp::OffsetOf(
+                tcx.tcx().mk_offset_of_from_iter(indices.iter().map(|(variant, field)| {
+                    (variant.internal(tables, tcx), rustc_abi::FieldIdx::from_usize(*field))
+                })),
+            ),
+            NullOp::UbChecks => rustc_middle::mir::NullOp::UbChecks,
+            NullOp::ContractChecks => rustc_middle::mir::NullOp::ContractChecks,
+        }
+    }
+}
+
+impl RustcInternal for BorrowKind {
+    type T<'tcx> = rustc_middle::mir::BorrowKind;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            BorrowKind::Shared => rustc_middle::mir::BorrowKind::Shared,
+            BorrowKind::Fake(kind) => rustc_middle::mir::BorrowKind::Fake(match kind {
+                FakeBorrowKind::Deep => rustc_middle::mir::FakeBorrowKind::Deep,
+                FakeBorrowKind::Shallow => rustc_middle::mir::FakeBorrowKind::Shallow,
+            }),
+            BorrowKind::Mut { kind } => {
+                rustc_middle::mir::BorrowKind::Mut { kind: kind.internal(tables, tcx) }
+            }
+        }
+    }
+}
+
+impl RustcInternal for MutBorrowKind {
+    type T<'tcx> = rustc_middle::mir::MutBorrowKind;
+
+    fn internal<'tcx>(
+        &self,
+        _tables: &mut Tables<'_, BridgeTys>,
+        _tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            MutBorrowKind::Default => rustc_middle::mir::MutBorrowKind::Default,
+            MutBorrowKind::TwoPhaseBorrow => rustc_middle::mir::MutBorrowKind::TwoPhaseBorrow,
+            MutBorrowKind::ClosureCapture => rustc_middle::mir::MutBorrowKind::ClosureCapture,
+        }
+    }
+}
+
+impl RustcInternal for RetagKind {
+    type T<'tcx> = rustc_middle::mir::RetagKind;
+
+    fn internal<'tcx>(
+        &self,
+        _tables: &mut Tables<'_, BridgeTys>,
+        _tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            RetagKind::FnEntry => rustc_middle::mir::RetagKind::FnEntry,
+            RetagKind::TwoPhase => rustc_middle::mir::RetagKind::TwoPhase,
+            RetagKind::Raw => rustc_middle::mir::RetagKind::Raw,
+            RetagKind::Default => rustc_middle::mir::RetagKind::Default,
+        }
+    }
+}
+
+impl RustcInternal for NonDivergingIntrinsic {
+    type T<'tcx> = rustc_middle::mir::NonDivergingIntrinsic<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            NonDivergingIntrinsic::Assume(operand) => {
+                rustc_middle::mir::NonDivergingIntrinsic::Assume(operand.internal(tables, tcx))
+            }
+            NonDivergingIntrinsic::CopyNonOverlapping(copy) => {
+                rustc_middle::mir::NonDivergingIntrinsic::CopyNonOverlapping(
+                    rustc_middle::mir::CopyNonOverlapping {
+                        src: copy.src.internal(tables, tcx),
+                        dst: copy.dst.internal(tables, tcx),
+                        count: copy.count.internal(tables, tcx),
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl RustcInternal for StatementKind {
+    type T<'tcx> = rustc_middle::mir::StatementKind<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            StatementKind::Assign(place, rvalue) => rustc_middle::mir::StatementKind::Assign(
+                Box::new((place.internal(tables, tcx), rvalue.internal(tables, tcx))),
+            ),
+            StatementKind::SetDiscriminant { place, variant_index } => {
+                rustc_middle::mir::StatementKind::SetDiscriminant {
+                    place: Box::new(place.internal(tables, tcx)),
+                    variant_index: variant_index.internal(tables, tcx),
+                }
+            }
+            StatementKind::StorageLive(local) => rustc_middle::mir::StatementKind::StorageLive(
+                rustc_middle::mir::Local::from_usize(*local),
+            ),
+            StatementKind::StorageDead(local) => rustc_middle::mir::StatementKind::StorageDead(
+                rustc_middle::mir::Local::from_usize(*local),
+            ),
+            StatementKind::Retag(kind, place) => rustc_middle::mir::StatementKind::Retag(
+                kind.internal(tables, tcx),
+                Box::new(place.internal(tables, tcx)),
+            ),
+            StatementKind::PlaceMention(place) => rustc_middle::mir::StatementKind::PlaceMention(
+                Box::new(place.internal(tables, tcx)),
+            ),
+            StatementKind::Intrinsic(intrinsic) => rustc_middle::mir::StatementKind::Intrinsic(
+                Box::new(intrinsic.internal(tables, tcx)),
+            ),
+            StatementKind::ConstEvalCounter => rustc_middle::mir::StatementKind::ConstEvalCounter,
+            StatementKind::Nop => rustc_middle::mir::StatementKind::Nop,
+            // These statements carry opaque data that cannot be translated back.
+            StatementKind::FakeRead(..)
+            | StatementKind::AscribeUserType { .. }
+            | StatementKind::Coverage(_) => {
+                bug!("`{self:?}` should have been rejected by `check_body`")
+            }
+        }
+    }
+}
+
+impl RustcInternal for Statement {
+    type T<'tcx> = rustc_middle::mir::Statement<'tcx>;
+
+    /// The statement is placed in the outermost source scope.
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        rustc_middle::mir::Statement::new(
+            rustc_middle::mir::SourceInfo::outermost(self.span.internal(tables, tcx)),
+            self.kind.internal(tables, tcx),
+        )
+    }
+}
+
+impl RustcInternal for UnwindAction {
+    type T<'tcx> = rustc_middle::mir::UnwindAction;
+
+    /// The reason to terminate is lost in rustc_public, so `Terminate` is converted as if the
+    /// function cannot unwind, which only affects the panic message.
+    fn internal<'tcx>(
+        &self,
+        _tables: &mut Tables<'_, BridgeTys>,
+        _tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            UnwindAction::Continue => rustc_middle::mir::UnwindAction::Continue,
+            UnwindAction::Unreachable => rustc_middle::mir::UnwindAction::Unreachable,
+            UnwindAction::Terminate => rustc_middle::mir::UnwindAction::Terminate(
+                rustc_middle::mir::UnwindTerminateReason::Abi,
+            ),
+            UnwindAction::Cleanup(bb) => rustc_middle::mir::UnwindAction::Cleanup(
+                rustc_middle::mir::BasicBlock::from_usize(*bb),
+            ),
+        }
+    }
+}
+
+impl RustcInternal for CoroutineKind {
+    type T<'tcx> = rustc_hir::CoroutineKind;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        match self {
+            CoroutineKind::Desugared(desugaring, source) => {
+                let desugaring = match desugaring {
+                    CoroutineDesugaring::Async => rustc_hir::CoroutineDesugaring::Async,
+                    CoroutineDesugaring::Gen => rustc_hir::CoroutineDesugaring::Gen,
+                    CoroutineDesugaring::AsyncGen => rustc_hir::CoroutineDesugaring::AsyncGen,
+                };
+                let source = match source {
+                    CoroutineSource::Block => rustc_hir::CoroutineSource::Block,
+                    CoroutineSource::Closure => rustc_hir::CoroutineSource::Closure,
+                    CoroutineSource::Fn => rustc_hir::CoroutineSource::Fn,
+                };
+                rustc_hir::CoroutineKind::Desugared(desugaring, source)
+            }
+            CoroutineKind::Coroutine(movability) => {
+                rustc_hir::CoroutineKind::Coroutine(movability.internal(tables, tcx))
+            }
+        }
+    }
+}
+
+impl RustcInternal for AssertMessage {
+    type T<'tcx> = rustc_middle::mir::AssertMessage<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        use rustc_middle::mir::AssertKind;
+        match self {
+            AssertMessage::BoundsCheck { len, index } => AssertKind::BoundsCheck {
+                len: len.internal(tables, tcx),
+                index: index.internal(tables, tcx),
+            },
+            AssertMessage::Overflow(bin_op, lhs, rhs) => AssertKind::Overflow(
+                bin_op.internal(tables, tcx),
+                lhs.internal(tables, tcx),
+                rhs.internal(tables, tcx),
+            ),
+            AssertMessage::OverflowNeg(operand) => {
+                AssertKind::OverflowNeg(operand.internal(tables, tcx))
+            }
+            AssertMessage::DivisionByZero(operand) => {
+                AssertKind::DivisionByZero(operand.internal(tables, tcx))
+            }
+            AssertMessage::RemainderByZero(operand) => {
+                AssertKind::RemainderByZero(operand.internal(tables, tcx))
+            }
+            AssertMessage::ResumedAfterReturn(kind) => {
+                AssertKind::ResumedAfterReturn(kind.internal(tables, tcx))
+            }
+            AssertMessage::ResumedAfterPanic(kind) => {
+                AssertKind::ResumedAfterPanic(kind.internal(tables, tcx))
+            }
+            AssertMessage::ResumedAfterDrop(kind) => {
+                AssertKind::ResumedAfterDrop(kind.internal(tables, tcx))
+            }
+            AssertMessage::MisalignedPointerDereference { required, found } => {
+                AssertKind::MisalignedPointerDereference {
+                    required: required.internal(tables, tcx),
+                    found: found.internal(tables, tcx),
+                }
+            }
+            AssertMessage::NullPointerDereference => AssertKind::NullPointerDereference,
+            AssertMessage::InvalidEnumConstruction(operand) => {
+                AssertKind::InvalidEnumConstruction(operand.internal(tables, tcx))
+            }
+        }
+    }
+}
+
+impl RustcInternal for Terminator {
+    type T<'tcx> = rustc_middle::mir::Terminator<'tcx>;
+
+    /// The terminator is placed in the outermost source scope.
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        use rustc_middle::mir::{BasicBlock, TerminatorKind as InternalTerminatorKind};
+        let span = self.span.internal(tables, tcx);
+        let kind = match &self.kind {
+            TerminatorKind::Goto { target } => {
+                InternalTerminatorKind::Goto { target: BasicBlock::from_usize(*target) }
+            }
+            TerminatorKind::SwitchInt { discr, targets } => InternalTerminatorKind::SwitchInt {
+                discr: discr.internal(tables, tcx),
+                targets: rustc_middle::mir::SwitchTargets::new(
+                    targets
+                        .branches()
+                        .map(|(value, target)| (value, BasicBlock::from_usize(target))),
+                    BasicBlock::from_usize(targets.otherwise()),
+                ),
+            },
+            TerminatorKind::Resume => InternalTerminatorKind::UnwindResume,
+            TerminatorKind::Abort => InternalTerminatorKind::UnwindTerminate(
+                rustc_middle::mir::UnwindTerminateReason::Abi,
+            ),
+            TerminatorKind::Return => InternalTerminatorKind::Return,
+            TerminatorKind::Unreachable => InternalTerminatorKind::Unreachable,
+            TerminatorKind::Drop { place, target, unwind } => InternalTerminatorKind::Drop {
+                place: place.internal(tables, tcx),
+                target: BasicBlock::from_usize(*target),
+                unwind: unwind.internal(tables, tcx),
+                replace: false,
+                drop: None,
+                async_fut: None,
+            },
+            TerminatorKind::Call { func, args, destination, target, unwind } => {
+                InternalTerminatorKind::Call {
+                    func: func.internal(tables, tcx),
+                    args: args
+                        .iter()
+                        .map(|arg| rustc_span::source_map::Spanned {
+                            node: arg.internal(tables, tcx),
+                            span,
+                        })
+                        .collect(),
+                    destination: destination.internal(tables, tcx),
+                    target: target.map(BasicBlock::from_usize),
+                    unwind: unwind.internal(tables, tcx),
+                    call_source: rustc_middle::mir::CallSource::Normal,
+                    fn_span: span,
+                }
+            }
+            TerminatorKind::Assert { cond, expected, msg, target, unwind } => {
+                InternalTerminatorKind::Assert {
+                    cond: cond.internal(tables, tcx),
+                    expected: *expected,
+                    msg: Box::new(msg.internal(tables, tcx)),
+                    target: BasicBlock::from_usize(*target),
+                    unwind: unwind.internal(tables, tcx),
+                }
+            }
+            // Inline assembly is only kept as its textual representation.
+            TerminatorKind::InlineAsm { .. } => {
+                bug!("`{self:?}` should have been rejected by `check_body`")
+            }
+        };
+        rustc_middle::mir::Terminator {
+            source_info: rustc_middle::mir::SourceInfo::outermost(span),
+            kind,
+        }
+    }
+}
+
+impl RustcInternal for LocalDecl {
+    type T<'tcx> = rustc_middle::mir::LocalDecl<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        let mut decl = rustc_middle::mir::LocalDecl::new(
+            self.ty.internal(tables, tcx),
+            self.span.internal(tables, tcx),
+        );
+        decl.mutability = self.mutability.internal(tables, tcx);
+        decl
+    }
+}
+
+impl RustcInternal for VarDebugInfo {
+    type T<'tcx> = rustc_middle::mir::VarDebugInfo<'tcx>;
+
+    fn internal<'tcx>(
+        &self,
+        tables: &mut Tables<'_, BridgeTys>,
+        tcx: impl InternalCx<'tcx>,
+    ) -> Self::T<'tcx> {
+        rustc_middle::mir::VarDebugInfo {
+            name: rustc_span::Symbol::intern(&self.name),
+            source_info: rustc_middle::mir::SourceInfo {
+                span: self.source_info.span.internal(tables, tcx),
+                scope: rustc_middle::mir::SourceScope::from_u32(self.source_info.scope),
+            },
+            composite: self.composite.as_ref().map(|composite| {
+                Box::new(rustc_middle::mir::VarDebugInfoFragment {
+                    ty: composite.ty.internal(tables, tcx),
+                    projection: composite.projection.internal(tables, tcx),
+                })
+            }),
+            value: match &self.value {
+                VarDebugInfoContents::Place(place) => {
+                    rustc_middle::mir::VarDebugInfoContents::Place(place.internal(tables, tcx))
+                }
+                VarDebugInfoContents::Const(constant) => {
+                    rustc_middle::mir::VarDebugInfoContents::Const(constant.internal(tables, tcx))
+                }
+            },
+            argument_index: self.argument_index,
+        }
+    }
+}
+
 impl<T> RustcInternal for &T
 where
     T: RustcInternal,
@@ -926,3 +926,41 @@ fn stable<'cx>(
         }
     }
 }
+
+impl<'tcx> Stable<'tcx> for rustc_middle::mir::mono::MonoItemUsage {
+    type T = crate::mir::mono::MonoItemUsage;
+
+    fn stable(&self, _: &mut Tables<'_, BridgeTys>, _: &CompilerCtxt<'_, BridgeTys>) -> Self::T {
+        use rustc_middle::mir::mono::MonoItemUsage;
+
+        use crate::mir::mono::MonoItemUsage as StableMonoItemUsage;
+        match self {
+            MonoItemUsage::Call => StableMonoItemUsage::Call,
+            MonoItemUsage::Drop => StableMonoItemUsage::Drop,
+            MonoItemUsage::VTable => StableMonoItemUsage::VTable,
+            MonoItemUsage::FnPtr => StableMonoItemUsage::FnPtr,
+            MonoItemUsage::Static => StableMonoItemUsage::Static,
+        }
+    }
+}
+
+impl<'tcx> Stable<'tcx> for rustc_middle::mir::mono::CodegenUnit<'tcx> {
+    type T = crate::mir::mono::CodegenUnit;
+
+    fn stable<'cx>(
+        &self,
+        tables: &mut Tables<'cx, BridgeTys>,
+        cx: &CompilerCtxt<'cx, BridgeTys>,
+    ) -> Self::T {
+        crate::mir::mono::CodegenUnit {
+            name: self.name().to_string(),
+            is_primary: self.is_primary(),
+            size_estimate: self.size_estimate(),
+            items: self
+                .items_in_deterministic_order(cx.tcx)
+                .iter()
+                .map(|(item, _)| item.stable(tables, cx))
+                .collect(),
+        }
+    }
+}
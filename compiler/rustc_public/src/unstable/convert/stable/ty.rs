@@ -1136,3 +1136,43 @@ fn stable<'cx>(
         crate::ty::Discr { val: self.val, ty: self.ty.stable(tables, cx) }
     }
 }
+
+impl<'tcx> Stable<'tcx> for rustc_middle::traits::ImplSource<'tcx, ()> {
+    type T = crate::ty::ImplSource;
+
+    fn stable<'cx>(
+        &self,
+        tables: &mut Tables<'cx, BridgeTys>,
+        cx: &CompilerCtxt<'cx, BridgeTys>,
+    ) -> Self::T {
+        use rustc_middle::traits::{ImplSource, ImplSourceUserDefinedData};
+
+        use crate::ty::ImplSource as StableImplSource;
+        match self {
+            ImplSource::UserDefined(ImplSourceUserDefinedData { impl_def_id, args, .. }) => {
+                StableImplSource::UserDefined {
+                    impl_def: tables.impl_def(*impl_def_id),
+                    args: args.stable(tables, cx),
+                }
+            }
+            ImplSource::Param(_) => StableImplSource::Param,
+            ImplSource::Builtin(source, _) => StableImplSource::Builtin(source.stable(tables, cx)),
+        }
+    }
+}
+
+impl<'tcx> Stable<'tcx> for rustc_middle::traits::BuiltinImplSource {
+    type T = crate::ty::BuiltinImplSource;
+
+    fn stable(&self, _: &mut Tables<'_, BridgeTys>, _: &CompilerCtxt<'_, BridgeTys>) -> Self::T {
+        use rustc_middle::traits::BuiltinImplSource;
+
+        use crate::ty::BuiltinImplSource as StableBuiltinImplSource;
+        match self {
+            BuiltinImplSource::Trivial => StableBuiltinImplSource::Trivial,
+            BuiltinImplSource::Misc => StableBuiltinImplSource::Misc,
+            BuiltinImplSource::Object(_) => StableBuiltinImplSource::Object,
+            BuiltinImplSource::TraitUpcasting(_) => StableBuiltinImplSource::TraitUpcasting,
+        }
+    }
+}
@@ -7,7 +7,9 @@
 use rustc_abi::{Endian, Layout, ReprOptions};
 use rustc_hir::def::DefKind;
 use rustc_hir::{Attribute, LangItem};
+use rustc_infer::infer::TyCtxtInferExt;
 use rustc_middle::mir::interpret::{AllocId, ConstAllocation, ErrorHandled, GlobalAlloc, Scalar};
+use rustc_middle::mir::mono::{CodegenUnit, MonoItem, MonoItemUsages};
 use rustc_middle::mir::{BinOp, Body, Const as MirConst, ConstValue, UnOp};
 use rustc_middle::ty::layout::{FnAbiOf, LayoutOf};
 use rustc_middle::ty::print::{with_forced_trimmed_paths, with_no_trimmed_paths};
@@ -23,6 +25,10 @@
 use rustc_span::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc_span::{FileNameDisplayPreference, Span, Symbol};
 use rustc_target::callconv::FnAbi;
+use rustc_trait_selection::traits::query::evaluate_obligation::InferCtxtExt;
+use rustc_trait_selection::traits::{
+    ImplSource, Obligation, ObligationCause, ObligationCtxt, SelectionContext, SelectionError,
+};
 
 use super::{AllocRangeHelpers, CompilerCtxt, TyHelpers, TypingEnvHelpers};
 use crate::builder::BodyBuilder;
@@ -675,6 +681,113 @@ pub fn resolve_closure(
         Some(Instance::resolve_closure(self.tcx, def_id, args_ref, closure_kind))
     }
 
+    /// The typing environment used by trait queries. With an `item`, the where-clauses of that
+    /// item may be assumed to hold; otherwise the query must be fully monomorphic.
+    pub fn typing_env(&self, item: Option<DefId>) -> ty::TypingEnv<'tcx> {
+        match item {
+            Some(def_id) => ty::TypingEnv::post_analysis(self.tcx, def_id),
+            None => self.fully_monomorphized(),
+        }
+    }
+
+    /// Check whether the trait reference holds in the given environment, ignoring regions.
+    pub fn trait_ref_is_implemented(
+        &self,
+        typing_env: ty::TypingEnv<'tcx>,
+        trait_ref: TraitRef<'tcx>,
+    ) -> bool {
+        let tcx = self.tcx;
+        let trait_ref =
+            tcx.try_normalize_erasing_regions(typing_env, trait_ref).unwrap_or(trait_ref);
+        let (infcx, param_env) =
+            tcx.infer_ctxt().ignoring_regions().build_with_typing_env(typing_env);
+        let obligation = Obligation::new(tcx, ObligationCause::dummy(), param_env, trait_ref);
+        infcx.predicate_must_hold_modulo_regions(&obligation)
+    }
+
+    /// Select the candidate that proves the trait reference in the given environment.
+    ///
+    /// Returns `Ok(None)` if the selection is ambiguous. This follows what codegen does to
+    /// resolve trait methods, except that it never ICEs and it also works with an environment
+    /// that has where-clauses.
+    pub fn select_trait_candidate(
+        &self,
+        typing_env: ty::TypingEnv<'tcx>,
+        trait_ref: TraitRef<'tcx>,
+    ) -> Result<Option<ImplSource<'tcx, ()>>, B::Error> {
+        let tcx = self.tcx;
+        let trait_ref = tcx
+            .try_normalize_erasing_regions(typing_env, trait_ref)
+            .map_err(|_| B::Error::new(format!("Failed to normalize `{trait_ref}`")))?;
+        let (infcx, param_env) =
+            tcx.infer_ctxt().ignoring_regions().build_with_typing_env(typing_env);
+        let mut selcx = SelectionContext::new(&infcx);
+        let obligation = Obligation::new(tcx, ObligationCause::dummy(), param_env, trait_ref);
+        let selection = match selcx.select(&obligation) {
+            Ok(Some(selection)) => selection,
+            Ok(None) => return Ok(None),
+            Err(SelectionError::Unimplemented) => {
+                return Err(B::Error::new(format!("Trait `{trait_ref}` is not implemented")));
+            }
+            Err(e) => {
+                return Err(B::Error::new(format!("Failed to select `{trait_ref}`: {e:?}")));
+            }
+        };
+
+        // Resolve the nested obligations, since they can constrain the impl's generic arguments.
+        let ocx = ObligationCtxt::new(&infcx);
+        let impl_source = selection.map(|obligation| ocx.register_obligation(obligation));
+        if !ocx.try_evaluate_obligations().is_empty() {
+            return Err(B::Error::new(format!("Trait `{trait_ref}` is not implemented")));
+        }
+
+        let impl_source = infcx.resolve_vars_if_possible(impl_source);
+        let impl_source = tcx.erase_and_anonymize_regions(impl_source);
+        // Generic parameters that the nested obligations do not constrain stay unresolved.
+        if impl_source.has_non_region_infer() { Ok(None) } else { Ok(Some(impl_source)) }
+    }
+
+    /// Normalize the type in the given environment, erasing its regions.
+    pub fn normalize_ty(
+        &self,
+        typing_env: ty::TypingEnv<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> Result<Ty<'tcx>, B::Error> {
+        self.tcx
+            .try_normalize_erasing_regions(typing_env, ty)
+            .map_err(|_| B::Error::new(format!("Failed to normalize `{ty}`")))
+    }
+
+    /// Create a projection of the associated item with the given arguments.
+    pub fn new_projection_ty(&self, def_id: DefId, args: GenericArgsRef<'tcx>) -> Ty<'tcx> {
+        Ty::new_projection_from_args(self.tcx, def_id, args)
+    }
+
+    /// All mono items codegened in the local crate, in a stable order.
+    pub fn all_mono_items(&self) -> Vec<MonoItem<'tcx>> {
+        self.sorted_mono_item_graph().into_iter().map(|(item, _)| *item).collect()
+    }
+
+    /// The codegen units of the local crate.
+    pub fn codegen_units(&self) -> &'tcx [CodegenUnit<'tcx>] {
+        self.tcx.collect_and_partition_mono_items(()).codegen_units
+    }
+
+    /// The edges of the mono item graph, ordered by user and then in the order of the uses.
+    pub fn mono_item_edges(&self) -> Vec<(MonoItem<'tcx>, MonoItem<'tcx>, MonoItemUsages)> {
+        self.sorted_mono_item_graph()
+            .into_iter()
+            .flat_map(|(&user, used)| used.iter().map(move |&(used, usages)| (user, used, usages)))
+            .collect()
+    }
+
+    fn sorted_mono_item_graph(
+        &self,
+    ) -> Vec<(&'tcx MonoItem<'tcx>, &'tcx Vec<(MonoItem<'tcx>, MonoItemUsages)>)> {
+        let graph = self.tcx.mono_item_graph(());
+        self.tcx.with_stable_hashing_context(|hcx| graph.used_items.to_sorted(&hcx, true))
+    }
+
     /// Try to evaluate an instance into a constant.
     pub fn eval_instance(&self, instance: ty::Instance<'tcx>) -> Result<ConstValue, ErrorHandled> {
         self.tcx.const_eval_instance(
@@ -3,7 +3,9 @@
 use rustc_abi::{Align, Size};
 use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::sync::Lock;
-use rustc_span::Symbol;
+use rustc_span::source_map::SourceMap;
+use rustc_span::{Span, Symbol};
+use serde_json::{Value, json};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -59,6 +61,37 @@ pub enum DataTypeKind {
     Coroutine,
 }
 
+/// How the active variant of a type with multiple variants is stored.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DiscriminantEncoding {
+    /// The tag holds the discriminant.
+    Direct,
+    /// The tag is stored in the niche of a field of `untagged_variant`. `niche_variants` are
+    /// encoded as consecutive invalid values starting at `niche_start`, and any other value means
+    /// `untagged_variant`.
+    Niche { untagged_variant: Symbol, niche_variants: Vec<Symbol>, niche_start: u128 },
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DiscriminantInfo {
+    pub offset: u64,
+    pub size: u64,
+    pub encoding: DiscriminantEncoding,
+}
+
+/// The largest niche of a type, which is where an enclosing enum can store its discriminant.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NicheInfo {
+    pub offset: u64,
+    pub size: u64,
+    /// The valid values of the niche, which wrap around if `valid_range_start` is larger than
+    /// `valid_range_end`.
+    pub valid_range_start: u128,
+    pub valid_range_end: u128,
+    /// How many invalid values are available to encode other variants.
+    pub available: u128,
+}
+
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct TypeSizeInfo {
     pub kind: DataTypeKind,
@@ -68,6 +101,10 @@ pub struct TypeSizeInfo {
     pub packed: bool,
     pub opt_discr_size: Option<u64>,
     pub variants: Vec<VariantInfo>,
+    /// The definition of the type, if it has one.
+    pub span: Option<Span>,
+    pub discriminant: Option<DiscriminantInfo>,
+    pub niche: Option<NicheInfo>,
 }
 
 #[derive(Default)]
@@ -87,6 +124,9 @@ pub fn record_type_size<S: ToString>(
         packed: bool,
         opt_discr_size: Option<Size>,
         mut variants: Vec<VariantInfo>,
+        span: Option<Span>,
+        discriminant: Option<DiscriminantInfo>,
+        niche: Option<NicheInfo>,
     ) {
         // Sort variants so the largest ones are shown first. A stable sort is
         // used here so that source code order is preserved for all variants
@@ -104,21 +144,16 @@ pub fn record_type_size<S: ToString>(
             packed,
             opt_discr_size: opt_discr_size.map(|s| s.bytes()),
             variants,
+            span,
+            discriminant,
+            niche,
         };
         self.type_sizes.borrow_mut().insert(info);
     }
 
     pub fn print_type_sizes(&self) {
         let type_sizes = self.type_sizes.borrow();
-        // We will soon sort, so the initial order does not matter.
-        #[allow(rustc::potential_query_instability)]
-        let mut sorted: Vec<_> = type_sizes.iter().collect();
-
-        // Primary sort: large-to-small.
-        // Secondary sort: description (dictionary order)
-        sorted.sort_by_key(|info| (cmp::Reverse(info.overall_size), &info.type_description));
-
-        for info in sorted {
+        for info in sorted_type_sizes(&type_sizes) {
             let TypeSizeInfo { type_description, overall_size, align, kind, variants, .. } = info;
             println!(
                 "print-type-size type: `{type_description}`: {overall_size} bytes, alignment: {align} bytes"
@@ -211,4 +246,126 @@ pub fn print_type_sizes(&self) {
             }
         }
     }
+
+    /// Prints the recorded layouts as a JSON array, in the same order as `print_type_sizes`.
+    pub fn print_type_sizes_json(&self, source_map: &SourceMap) {
+        let type_sizes = self.type_sizes.borrow();
+        let types: Vec<_> =
+            sorted_type_sizes(&type_sizes).map(|info| type_size_json(info, source_map)).collect();
+        println!("{}", serde_json::to_string_pretty(&types).unwrap());
+    }
+}
+
+fn sorted_type_sizes(type_sizes: &FxHashSet<TypeSizeInfo>) -> impl Iterator<Item = &TypeSizeInfo> {
+    // We will soon sort, so the initial order does not matter.
+    #[allow(rustc::potential_query_instability)]
+    let mut sorted: Vec<_> = type_sizes.iter().collect();
+
+    // Primary sort: large-to-small.
+    // Secondary sort: description (dictionary order)
+    sorted.sort_by_key(|info| (cmp::Reverse(info.overall_size), &info.type_description));
+    sorted.into_iter()
+}
+
+fn type_size_json(info: &TypeSizeInfo, source_map: &SourceMap) -> Value {
+    let kind = match info.kind {
+        DataTypeKind::Struct => "struct",
+        DataTypeKind::Union => "union",
+        DataTypeKind::Enum => "enum",
+        DataTypeKind::Closure => "closure",
+        DataTypeKind::Coroutine => "coroutine",
+    };
+    let span = info.span.map(|span| {
+        let lo = source_map.lookup_char_pos(span.lo());
+        let hi = source_map.lookup_char_pos(span.hi());
+        json!({
+            "file": source_map.filename_for_diagnostics(&lo.file.name).to_string(),
+            "line_start": lo.line,
+            "column_start": lo.col.0 + 1,
+            "line_end": hi.line,
+            "column_end": hi.col.0 + 1,
+        })
+    });
+    let discriminant = info.discriminant.as_ref().map(|discr| {
+        let mut value = json!({ "offset": discr.offset, "size": discr.size });
+        match &discr.encoding {
+            DiscriminantEncoding::Direct => value["encoding"] = json!("direct"),
+            DiscriminantEncoding::Niche { untagged_variant, niche_variants, niche_start } => {
+                value["encoding"] = json!("niche");
+                value["untagged_variant"] = json!(untagged_variant.as_str());
+                value["niche_variants"] = niche_variants.iter().map(|name| name.as_str()).collect();
+                value["niche_start"] = u128_json(*niche_start);
+            }
+        }
+        value
+    });
+    let niche = info.niche.map(|niche| {
+        json!({
+            "offset": niche.offset,
+            "size": niche.size,
+            "valid_range_start": u128_json(niche.valid_range_start),
+            "valid_range_end": u128_json(niche.valid_range_end),
+            "available": u128_json(niche.available),
+        })
+    });
+
+    // Padding is computed the same way as in `print_type_sizes`: a directly stored discriminant
+    // is assumed to come first, and is included in the size of each variant.
+    let discr_size = info.opt_discr_size.unwrap_or(0);
+    let mut max_variant_size = discr_size;
+    let variants: Vec<_> = info
+        .variants
+        .iter()
+        .map(|variant| {
+            max_variant_size = cmp::max(max_variant_size, variant.size);
+            let mut min_offset = discr_size;
+            let mut fields = variant.fields.clone();
+            fields.sort_by_key(|f| (f.offset, f.size));
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|field| {
+                    let padding_before = field.offset.saturating_sub(min_offset);
+                    min_offset = field.offset + field.size;
+                    json!({
+                        "kind": field.kind.to_string(),
+                        "name": field.name.as_str(),
+                        "offset": field.offset,
+                        "size": field.size,
+                        "align": field.align,
+                        "type": field.type_name.map(|name| name.to_string()),
+                        "padding_before": padding_before,
+                    })
+                })
+                .collect();
+            json!({
+                "name": variant.name.map(|name| name.to_string()),
+                "size": variant.size,
+                "size_kind": match variant.kind {
+                    SizeKind::Exact => "exact",
+                    SizeKind::Min => "min",
+                },
+                "align": variant.align,
+                "fields": fields,
+            })
+        })
+        .collect();
+
+    json!({
+        "type": info.type_description,
+        "kind": kind,
+        "span": span,
+        "size": info.overall_size,
+        "align": info.align,
+        "packed": info.packed,
+        "discriminant": discriminant,
+        "niche": niche,
+        "variants": variants,
+        // Unlike `print_type_sizes`, don't panic on inconsistent layouts, but leave the padding out.
+        "end_padding": info.overall_size.checked_sub(max_variant_size),
+    })
+}
+
+/// JSON numbers beyond `u64::MAX` are not portable, so those are written as strings instead.
+fn u128_json(n: u128) -> Value {
+    u64::try_from(n).map_or_else(|_| Value::from(n.to_string()), Value::from)
 }
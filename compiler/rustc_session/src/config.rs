@@ -64,6 +64,7 @@
     ("relocation-models", PrintKind::RelocationModels),
     ("split-debuginfo", PrintKind::SplitDebuginfo),
     ("stack-protector-strategies", PrintKind::StackProtectorStrategies),
+    ("stack-usage", PrintKind::StackUsage),
     ("supported-crate-types", PrintKind::SupportedCrateTypes),
     ("sysroot", PrintKind::Sysroot),
     ("target-cpus", PrintKind::TargetCPUs),
@@ -838,6 +839,8 @@ pub enum ErrorOutputType {
         json_rendered: HumanReadableErrorType,
         color_config: ColorConfig,
     },
+    /// A SARIF 2.1.0 log, for code scanning tools.
+    Sarif,
 }
 
 #[derive(Clone, Hash, Debug)]
@@ -1029,6 +1032,7 @@ pub enum PrintKind {
     RelocationModels,
     SplitDebuginfo,
     StackProtectorStrategies,
+    StackUsage,
     SupportedCrateTypes,
     Sysroot,
     TargetCPUs,
@@ -2111,6 +2115,7 @@ pub fn parse_error_format(
             Some("short") => {
                 ErrorOutputType::HumanReadable { kind: HumanReadableErrorType::Short, color_config }
             }
+            Some("sarif") => ErrorOutputType::Sarif,
             Some("human-unicode") => ErrorOutputType::HumanReadable {
                 kind: HumanReadableErrorType::Unicode,
                 color_config,
@@ -2119,7 +2124,8 @@ pub fn parse_error_format(
                 early_dcx.set_error_format(ErrorOutputType::HumanReadable { color_config, .. });
                 early_dcx.early_fatal(format!(
                     "argument for `--error-format` must be `human`, `human-annotate-rs`, \
-                    `human-unicode`, `json`, `pretty-json` or `short` (instead was `{arg}`)"
+                    `human-unicode`, `json`, `pretty-json`, `sarif` or `short` \
+                    (instead was `{arg}`)"
                 ))
             }
         }
@@ -2179,6 +2185,7 @@ fn check_error_format_stability(
     }
     let format = match format {
         ErrorOutputType::Json { pretty: true, .. } => "pretty-json",
+        ErrorOutputType::Sarif => "sarif",
         ErrorOutputType::HumanReadable { kind, .. } => match kind {
             HumanReadableErrorType::AnnotateSnippet => "human-annotate-rs",
             HumanReadableErrorType::Unicode => "human-unicode",
@@ -2343,6 +2350,7 @@ fn is_print_request_stable(print_kind: PrintKind) -> bool {
         PrintKind::AllTargetSpecsJson
         | PrintKind::CheckCfg
         | PrintKind::CrateRootLintLevels
+        | PrintKind::StackUsage
         | PrintKind::SupportedCrateTypes
         | PrintKind::TargetSpecJson
         | PrintKind::TargetSpecJsonSchema => false,
@@ -3522,6 +3530,15 @@ pub fn extension(self) -> &'static str {
     }
 }
 
+/// Which format to use for `-Z print-type-sizes`
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum PrintTypeSizesFormat {
+    /// Print `print-type-size` lines
+    Text,
+    /// Emit a JSON array with one entry per type
+    Json,
+}
+
 /// `-Z patchable-function-entry` representation - how many nops to put before and after function
 /// entry.
 #[derive(Clone, Copy, PartialEq, Hash, Debug, Default)]
@@ -821,6 +821,8 @@ mod desc {
     pub(crate) const parse_strip: &str = "either `none`, `debuginfo`, or `symbols`";
     pub(crate) const parse_linker_flavor: &str = ::rustc_target::spec::LinkerFlavorCli::one_of();
     pub(crate) const parse_dump_mono_stats: &str = "`markdown` (default) or `json`";
+    pub(crate) const parse_print_type_sizes: &str =
+        "either a boolean (`yes`, `no`, `on`, `off`, etc), `text`, or `json`";
     pub(crate) const parse_instrument_coverage: &str = parse_bool;
     pub(crate) const parse_coverage_options: &str = "`block` | `branch` | `condition`";
     pub(crate) const parse_instrument_xray: &str = "either a boolean (`yes`, `no`, `on`, `off`, etc), or a comma separated list of settings: `always` or `never` (mutually exclusive), `ignore-loops`, `instruction-threshold=N`, `skip-entry`, `skip-exit`";
@@ -1425,6 +1427,20 @@ pub(crate) fn parse_dump_mono_stats(slot: &mut DumpMonoStatsFormat, v: Option<&s
         }
     }
 
+    pub(crate) fn parse_print_type_sizes(
+        slot: &mut Option<PrintTypeSizesFormat>,
+        v: Option<&str>,
+    ) -> bool {
+        let mut yes = false;
+        match v {
+            _ if parse_bool(&mut yes, v) => *slot = yes.then_some(PrintTypeSizesFormat::Text),
+            Some("text") => *slot = Some(PrintTypeSizesFormat::Text),
+            Some("json") => *slot = Some(PrintTypeSizesFormat::Json),
+            _ => return false,
+        }
+        true
+    }
+
     pub(crate) fn parse_offload(slot: &mut Vec<Offload>, v: Option<&str>) -> bool {
         let Some(v) = v else {
             *slot = vec![];
@@ -2266,6 +2282,9 @@ pub(crate) fn parse_align(slot: &mut Option<Align>, v: Option<&str>) -> bool {
     dump_dep_graph: bool = (false, parse_bool, [UNTRACKED],
         "dump the dependency graph to $RUST_DEP_GRAPH (default: /tmp/dep_graph.gv) \
         (default: no)"),
+    dump_generic_bloat: SwitchWithOptPath = (SwitchWithOptPath::Disabled,
+        parse_switch_with_opt_path, [UNTRACKED],
+        "output the code size of the instantiations of each generic definition as JSON"),
     dump_mir: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "dump MIR state to file.
         `val` is used to select which passes and functions to dump. For example:
@@ -2536,8 +2555,8 @@ pub(crate) fn parse_align(slot: &mut Option<Align>, v: Option<&str>) -> bool {
         "print the LLVM optimization passes being run (default: no)"),
     print_mono_items: bool = (false, parse_bool, [UNTRACKED],
         "print the result of the monomorphization collection pass (default: no)"),
-    print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
-        "print layout information for each type encountered (default: no)"),
+    print_type_sizes: Option<PrintTypeSizesFormat> = (None, parse_print_type_sizes, [UNTRACKED],
+        "print layout information for each type encountered, as `text` or `json` (default: no)"),
     proc_macro_backtrace: bool = (false, parse_bool, [UNTRACKED],
          "show backtraces for panics during proc-macro execution (default: no)"),
     proc_macro_execution_strategy: ProcMacroExecutionStrategy = (ProcMacroExecutionStrategy::SameThread,
@@ -18,6 +18,7 @@
     DynEmitter, HumanEmitter, HumanReadableErrorType, OutputTheme, stderr_destination,
 };
 use rustc_errors::json::JsonEmitter;
+use rustc_errors::sarif::{EarlySarifResults, SarifEmitter};
 use rustc_errors::timings::TimingSectionHandler;
 use rustc_errors::translation::Translator;
 use rustc_errors::{
@@ -38,7 +39,10 @@
 };
 
 use crate::code_stats::CodeStats;
-pub use crate::code_stats::{DataTypeKind, FieldInfo, FieldKind, SizeKind, VariantInfo};
+pub use crate::code_stats::{
+    DataTypeKind, DiscriminantEncoding, DiscriminantInfo, FieldInfo, FieldKind, NicheInfo,
+    SizeKind, VariantInfo,
+};
 use crate::config::{
     self, CoverageLevel, CoverageOptions, CrateType, DebugInfo, ErrorOutputType, FunctionReturn,
     Input, InstrumentCoverage, OptLevel, OutFileName, OutputType, RemapPathScopeComponents,
@@ -270,7 +274,7 @@ pub fn create_feature_err<'a>(&'a self, err: impl Diagnostic<'a>, feature: Symbo
     /// Record the fact that we called `trimmed_def_paths`, and do some
     /// checking about whether its cost was justified.
     pub fn record_trimmed_def_paths(&self) {
-        if self.opts.unstable_opts.print_type_sizes
+        if self.opts.unstable_opts.print_type_sizes.is_some()
             || self.opts.unstable_opts.query_dep_graph
             || self.opts.unstable_opts.dump_mir.is_some()
             || self.opts.unstable_opts.unpretty.is_some()
@@ -930,6 +934,7 @@ fn default_emitter(
     sopts: &config::Options,
     source_map: Arc<SourceMap>,
     translator: Translator,
+    early_sarif_results: EarlySarifResults,
 ) -> Box<DynEmitter> {
     let macro_backtrace = sopts.unstable_opts.macro_backtrace;
     let track_diagnostics = sopts.unstable_opts.track_diagnostics;
@@ -1011,6 +1016,11 @@ fn default_emitter(
             .track_diagnostics(track_diagnostics)
             .terminal_url(terminal_url),
         ),
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(Box::new(io::BufWriter::new(io::stderr())), source_map, translator)
+                .always_emit(true)
+                .early_results(early_sarif_results),
+        ),
     }
 }
 
@@ -1028,6 +1038,7 @@ pub fn build_session(
     cfg_version: &'static str,
     ice_file: Option<PathBuf>,
     using_internal_features: &'static AtomicBool,
+    early_sarif_results: EarlySarifResults,
 ) -> Session {
     // FIXME: This is not general enough to make the warning lint completely override
     // normal diagnostic warnings, since the warning lint can also be denied and changed
@@ -1048,7 +1059,7 @@ pub fn build_session(
         ),
     };
     let source_map = rustc_span::source_map::get_source_map().unwrap();
-    let emitter = default_emitter(&sopts, Arc::clone(&source_map), translator);
+    let emitter = default_emitter(&sopts, Arc::clone(&source_map), translator, early_sarif_results);
 
     let mut dcx = DiagCtxt::new(emitter)
         .with_flags(sopts.unstable_opts.dcx_flags(can_emit_warnings))
@@ -1432,12 +1443,20 @@ enum IncrCompSession {
 /// A wrapper around an [`DiagCtxt`] that is used for early error emissions.
 pub struct EarlyDiagCtxt {
     dcx: DiagCtxt,
+    /// Where a SARIF emitter puts its results, to be written by the emitter of the session.
+    sarif_results: EarlySarifResults,
 }
 
 impl EarlyDiagCtxt {
     pub fn new(output: ErrorOutputType) -> Self {
-        let emitter = mk_emitter(output);
-        Self { dcx: DiagCtxt::new(emitter) }
+        Self::with_sarif_results(output, EarlySarifResults::default())
+    }
+
+    /// Creates an early dcx that adds its SARIF results to `sarif_results`, e.g. to continue
+    /// collecting the results of another early dcx.
+    pub fn with_sarif_results(output: ErrorOutputType, sarif_results: EarlySarifResults) -> Self {
+        let emitter = mk_emitter(output, &sarif_results);
+        Self { dcx: DiagCtxt::new(emitter), sarif_results }
     }
 
     /// Swap out the underlying dcx once we acquire the user's preference on error emission
@@ -1445,10 +1464,16 @@ pub fn new(output: ErrorOutputType) -> Self {
     pub fn set_error_format(&mut self, output: ErrorOutputType) {
         assert!(self.dcx.handle().has_errors().is_none());
 
-        let emitter = mk_emitter(output);
+        let emitter = mk_emitter(output, &self.sarif_results);
         self.dcx = DiagCtxt::new(emitter);
     }
 
+    /// The SARIF results emitted so far, which should be passed to [`build_session`] to be
+    /// written to the log of the session.
+    pub fn sarif_results(&self) -> EarlySarifResults {
+        self.sarif_results.clone()
+    }
+
     #[allow(rustc::untranslatable_diagnostic)]
     #[allow(rustc::diagnostic_outside_of_impl)]
     pub fn early_note(&self, msg: impl Into<DiagMessage>) {
@@ -1493,7 +1518,7 @@ pub fn early_struct_warn(&self, msg: impl Into<DiagMessage>) -> Diag<'_, ()> {
     }
 }
 
-fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
+fn mk_emitter(output: ErrorOutputType, sarif_results: &EarlySarifResults) -> Box<DynEmitter> {
     // FIXME(#100717): early errors aren't translated at the moment, so this is fine, but it will
     // need to reference every crate that might emit an early error for translation to work.
     let translator =
@@ -1521,6 +1546,15 @@ fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
                 color_config,
             ))
         }
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                Some(Arc::new(SourceMap::new(FilePathMapping::empty()))),
+                translator,
+            )
+            .early(true)
+            .early_results(sarif_results.clone()),
+        ),
     };
     emitter
 }
@@ -2178,6 +2178,10 @@
         sys_mutex_lock,
         sys_mutex_try_lock,
         sys_mutex_unlock,
+        sys_rwlock_read,
+        sys_rwlock_read_unlock,
+        sys_rwlock_write,
+        sys_rwlock_write_unlock,
         t32,
         target,
         target_abi,
@@ -20,7 +20,10 @@
 use rustc_middle::ty::{
     self, AdtDef, CoroutineArgsExt, EarlyBinder, PseudoCanonicalInput, Ty, TyCtxt, TypeVisitableExt,
 };
-use rustc_session::{DataTypeKind, FieldInfo, FieldKind, SizeKind, VariantInfo};
+use rustc_session::{
+    DataTypeKind, DiscriminantEncoding, DiscriminantInfo, FieldInfo, FieldKind, NicheInfo,
+    SizeKind, VariantInfo,
+};
 use rustc_span::{Symbol, sym};
 use tracing::{debug, instrument};
 use {rustc_abi as abi, rustc_hir as hir};
@@ -72,7 +75,7 @@ fn layout_of<'tcx>(
 
     // If we are running with `-Zprint-type-sizes`, maybe record layouts
     // for dumping later.
-    if cx.tcx().sess.opts.unstable_opts.print_type_sizes {
+    if cx.tcx().sess.opts.unstable_opts.print_type_sizes.is_some() {
         record_layout_for_printing(&cx, layout);
     }
 
@@ -761,8 +764,15 @@ fn record_layout_for_printing<'tcx>(cx: &LayoutCx<'tcx>, layout: TyAndLayout<'tc
     }
 
     // (delay format until we actually need it)
-    let record = |kind, packed, opt_discr_size, variants| {
+    let record = |kind, packed, opt_discr_size, variants, def_id, discriminant| {
         let type_desc = with_no_trimmed_paths!(format!("{}", layout.ty));
+        let niche = layout.largest_niche.map(|niche| NicheInfo {
+            offset: niche.offset.bytes(),
+            size: niche.value.size(cx).bytes(),
+            valid_range_start: niche.valid_range.start,
+            valid_range_end: niche.valid_range.end,
+            available: niche.available(cx),
+        });
         cx.tcx().sess.code_stats.record_type_size(
             kind,
             type_desc,
@@ -771,6 +781,9 @@ fn record_layout_for_printing<'tcx>(cx: &LayoutCx<'tcx>, layout: TyAndLayout<'tc
             packed,
             opt_discr_size,
             variants,
+            Some(cx.tcx().def_span(def_id)),
+            discriminant,
+            niche,
         );
     };
 
@@ -780,7 +793,15 @@ fn record_layout_for_printing<'tcx>(cx: &LayoutCx<'tcx>, layout: TyAndLayout<'tc
             let adt_kind = adt_def.adt_kind();
             let adt_packed = adt_def.repr().pack.is_some();
             let (variant_infos, opt_discr_size) = variant_info_for_adt(cx, layout, adt_def);
-            record(adt_kind.into(), adt_packed, opt_discr_size, variant_infos);
+            let discriminant = discriminant_info(cx, layout, |i| adt_def.variant(i).name);
+            record(
+                adt_kind.into(),
+                adt_packed,
+                opt_discr_size,
+                variant_infos,
+                adt_def.did(),
+                discriminant,
+            );
         }
 
         ty::Coroutine(def_id, args) => {
@@ -788,12 +809,22 @@ fn record_layout_for_printing<'tcx>(cx: &LayoutCx<'tcx>, layout: TyAndLayout<'tc
             // Coroutines always have a begin/poisoned/end state with additional suspend points
             let (variant_infos, opt_discr_size) =
                 variant_info_for_coroutine(cx, layout, def_id, args);
-            record(DataTypeKind::Coroutine, false, opt_discr_size, variant_infos);
+            let discriminant = discriminant_info(cx, layout, |i| {
+                Symbol::intern(&ty::CoroutineArgs::variant_name(i))
+            });
+            record(
+                DataTypeKind::Coroutine,
+                false,
+                opt_discr_size,
+                variant_infos,
+                def_id,
+                discriminant,
+            );
         }
 
-        ty::Closure(..) => {
+        ty::Closure(def_id, _) => {
             debug!("print-type-size t: `{:?}` record closure", layout.ty);
-            record(DataTypeKind::Closure, false, None, vec![]);
+            record(DataTypeKind::Closure, false, None, vec![], def_id, None);
         }
 
         _ => {
@@ -802,6 +833,35 @@ fn record_layout_for_printing<'tcx>(cx: &LayoutCx<'tcx>, layout: TyAndLayout<'tc
     };
 }
 
+/// Describes where the discriminant of a layout with multiple variants is stored and how it is
+/// encoded, naming the variants with `variant_name`.
+fn discriminant_info<'tcx>(
+    cx: &LayoutCx<'tcx>,
+    layout: TyAndLayout<'tcx>,
+    variant_name: impl Fn(VariantIdx) -> Symbol,
+) -> Option<DiscriminantInfo> {
+    let Variants::Multiple { tag, ref tag_encoding, tag_field, .. } = layout.variants else {
+        return None;
+    };
+    let encoding = match *tag_encoding {
+        TagEncoding::Direct => DiscriminantEncoding::Direct,
+        TagEncoding::Niche { untagged_variant, ref niche_variants, niche_start } => {
+            DiscriminantEncoding::Niche {
+                untagged_variant: variant_name(untagged_variant),
+                niche_variants: (niche_variants.start().as_u32()..=niche_variants.end().as_u32())
+                    .map(|i| variant_name(VariantIdx::from_u32(i)))
+                    .collect(),
+                niche_start,
+            }
+        }
+    };
+    Some(DiscriminantInfo {
+        offset: layout.fields.offset(tag_field.as_usize()).bytes(),
+        size: tag.size(cx).bytes(),
+        encoding,
+    })
+}
+
 fn variant_info_for_adt<'tcx>(
     cx: &LayoutCx<'tcx>,
     layout: TyAndLayout<'tcx>,
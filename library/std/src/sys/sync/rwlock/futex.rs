@@ -91,6 +91,8 @@ pub fn try_read(&self) -> bool {
     }
 
     #[inline]
+    // Make this a diagnostic item for Miri's lock-order check.
+    #[cfg_attr(not(test), rustc_diagnostic_item = "sys_rwlock_read")]
     pub fn read(&self) {
         let state = self.state.load(Relaxed);
         if !is_read_lockable(state)
@@ -107,6 +109,8 @@ pub fn read(&self) {
     ///
     /// The `RwLock` must be read-locked (N readers) in order to call this.
     #[inline]
+    // Make this a diagnostic item for Miri's lock-order check.
+    #[cfg_attr(not(test), rustc_diagnostic_item = "sys_rwlock_read_unlock")]
     pub unsafe fn read_unlock(&self) {
         let state = self.state.fetch_sub(READ_LOCKED, Release) - READ_LOCKED;
 
@@ -169,6 +173,8 @@ pub fn try_write(&self) -> bool {
     }
 
     #[inline]
+    // Make this a diagnostic item for Miri's lock-order check.
+    #[cfg_attr(not(test), rustc_diagnostic_item = "sys_rwlock_write")]
     pub fn write(&self) {
         if self.state.compare_exchange_weak(0, WRITE_LOCKED, Acquire, Relaxed).is_err() {
             self.write_contended();
@@ -179,6 +185,8 @@ pub fn write(&self) {
     ///
     /// The `RwLock` must be write-locked (single writer) in order to call this.
     #[inline]
+    // Make this a diagnostic item for Miri's lock-order check.
+    #[cfg_attr(not(test), rustc_diagnostic_item = "sys_rwlock_write_unlock")]
     pub unsafe fn write_unlock(&self) {
         let state = self.state.fetch_sub(WRITE_LOCKED, Release) - WRITE_LOCKED;
 
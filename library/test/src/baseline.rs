@@ -0,0 +1,409 @@
+//! Saving benchmark results with `--save-baseline`, and comparing later runs against them with
+//! `--baseline`.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use super::bench::BENCH_SAMPLES;
+use super::stats::Summary;
+
+#[cfg(test)]
+mod tests;
+
+/// Two-sided critical value of the standard normal distribution at a 95% confidence level.
+const CRITICAL_VALUE: f64 = 1.96;
+
+/// Relative changes of the median below this are never reported, as they are usually caused by
+/// noise that is not visible within a single run, such as frequency scaling.
+const NOISE_THRESHOLD: f64 = 0.02;
+
+/// Summaries of benchmark results, keyed by benchmark name.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Baseline {
+    benchmarks: BTreeMap<String, Summary>,
+}
+
+/// The result of comparing a benchmark against its baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BenchComparison {
+    pub baseline_median: f64,
+    /// Relative change of the median, e.g. `0.1` if the benchmark got 10% slower.
+    pub change: f64,
+    pub significant: bool,
+}
+
+/// Whether a benchmark changed significantly compared to its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BenchVerdict {
+    NoChange,
+    Regressed,
+    Improved,
+}
+
+impl BenchVerdict {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            BenchVerdict::NoChange => "no change",
+            BenchVerdict::Regressed => "regressed",
+            BenchVerdict::Improved => "improved",
+        }
+    }
+}
+
+impl BenchComparison {
+    pub(crate) fn is_regression(&self) -> bool {
+        self.verdict() == BenchVerdict::Regressed
+    }
+
+    pub(crate) fn verdict(&self) -> BenchVerdict {
+        match (self.significant, self.change > 0.0) {
+            (false, _) => BenchVerdict::NoChange,
+            (true, true) => BenchVerdict::Regressed,
+            (true, false) => BenchVerdict::Improved,
+        }
+    }
+}
+
+impl Baseline {
+    pub(crate) fn load(name: &str) -> io::Result<Baseline> {
+        let path = baseline_path(name)?;
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to read baseline `{name}` from {}: {e}", path.display()),
+            )
+        })?;
+        Baseline::from_json(&contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to parse baseline `{name}` from {}: {e}", path.display()),
+            )
+        })
+    }
+
+    /// Saves the benchmarks into the baseline `name`, keeping the results of benchmarks that
+    /// were saved previously but did not run this time.
+    pub(crate) fn save(&self, name: &str) -> io::Result<()> {
+        let path = baseline_path(name)?;
+        let mut baseline = match Baseline::load(name) {
+            Ok(baseline) => baseline,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Baseline::default(),
+            Err(e) => return Err(e),
+        };
+        baseline.benchmarks.extend(self.benchmarks.iter().map(|(k, v)| (k.clone(), *v)));
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, baseline.to_json())
+    }
+
+    pub(crate) fn insert(&mut self, name: &str, summary: &Summary) {
+        self.benchmarks.insert(name.to_owned(), *summary);
+    }
+
+    /// Compares the medians of a benchmark and its baseline.
+    ///
+    /// The median absolute deviation of each run is used as an estimate of its standard
+    /// deviation, from which the standard error of the median is derived. The change is
+    /// significant if the medians are further apart than the critical value allows, and the
+    /// change exceeds the noise threshold.
+    pub(crate) fn compare(&self, name: &str, summary: &Summary) -> Option<BenchComparison> {
+        let baseline = self.benchmarks.get(name)?;
+        // A baseline loaded from `null` is NaN, and infinite medians have no relative change.
+        if !baseline.median.is_finite() || baseline.median <= 0.0 || !summary.median.is_finite() {
+            return None;
+        }
+
+        // The standard error of the median is about `sqrt(pi / 2)` times that of the mean.
+        let standard_error =
+            |summary: &Summary| 1.2533 * summary.median_abs_dev / (BENCH_SAMPLES as f64).sqrt();
+        let difference = summary.median - baseline.median;
+        let error = standard_error(summary).hypot(standard_error(baseline));
+        let change = difference / baseline.median;
+        let significant = change.abs() >= NOISE_THRESHOLD
+            && (error == 0.0 || (difference / error).abs() > CRITICAL_VALUE);
+
+        Some(BenchComparison { baseline_median: baseline.median, change, significant })
+    }
+
+    fn to_json(&self) -> String {
+        let mut json = String::from("{\n  \"benchmarks\": {");
+        for (i, (name, summary)) in self.benchmarks.iter().enumerate() {
+            let Summary {
+                sum,
+                min,
+                max,
+                mean,
+                median,
+                var,
+                std_dev,
+                std_dev_pct,
+                median_abs_dev,
+                median_abs_dev_pct,
+                quartiles: (q1, q2, q3),
+                iqr,
+            } = *summary;
+            let n = json_number;
+            let separator = if i == 0 { "" } else { "," };
+            json.push_str(&format!(
+                "{separator}\n    \"{}\": {{ \"sum\": {}, \"min\": {}, \"max\": {}, \"mean\": {}, \
+                 \"median\": {}, \"var\": {}, \"std_dev\": {}, \"std_dev_pct\": {}, \
+                 \"median_abs_dev\": {}, \"median_abs_dev_pct\": {}, \
+                 \"quartiles\": [{}, {}, {}], \"iqr\": {} }}",
+                escape(name),
+                n(sum),
+                n(min),
+                n(max),
+                n(mean),
+                n(median),
+                n(var),
+                n(std_dev),
+                n(std_dev_pct),
+                n(median_abs_dev),
+                n(median_abs_dev_pct),
+                n(q1),
+                n(q2),
+                n(q3),
+                n(iqr),
+            ));
+        }
+        json.push_str("\n  }\n}\n");
+        json
+    }
+
+    fn from_json(s: &str) -> Result<Baseline, String> {
+        let mut parser = Parser { s: s.as_bytes(), pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.s.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        let mut baseline = Baseline::default();
+        for (name, bench) in value.field("benchmarks")?.as_object()? {
+            let n = |key: &str| bench.field(key)?.as_number();
+            let quartiles = match bench.field("quartiles")? {
+                Json::Array(q) if q.len() == 3 => {
+                    (q[0].as_number()?, q[1].as_number()?, q[2].as_number()?)
+                }
+                _ => return Err("`quartiles` must be an array of 3 numbers".to_string()),
+            };
+            let summary = Summary {
+                sum: n("sum")?,
+                min: n("min")?,
+                max: n("max")?,
+                mean: n("mean")?,
+                median: n("median")?,
+                var: n("var")?,
+                std_dev: n("std_dev")?,
+                std_dev_pct: n("std_dev_pct")?,
+                median_abs_dev: n("median_abs_dev")?,
+                median_abs_dev_pct: n("median_abs_dev_pct")?,
+                quartiles,
+                iqr: n("iqr")?,
+            };
+            baseline.benchmarks.insert(name.clone(), summary);
+        }
+        Ok(baseline)
+    }
+}
+
+/// Baselines are stored in `RUST_TEST_BASELINE_DIR`, or next to the test executable.
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let dir = match env::var_os("RUST_TEST_BASELINE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let exe = env::current_exe()?;
+            exe.parent().map(|dir| dir.join("bench-baselines")).unwrap_or_default()
+        }
+    };
+    Ok(dir.join(format!("{name}.json")))
+}
+
+/// Formats a number as JSON, which has no representation for NaN and infinities.
+pub(crate) fn json_number(n: f64) -> String {
+    if n.is_finite() { n.to_string() } else { "null".to_string() }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The subset of JSON used by baselines.
+enum Json {
+    Null,
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn as_object(&self) -> Result<&[(String, Json)], String> {
+        match self {
+            Json::Object(fields) => Ok(fields),
+            _ => Err("expected an object".to_string()),
+        }
+    }
+
+    fn field(&self, key: &str) -> Result<&Json, String> {
+        self.as_object()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .ok_or_else(|| format!("missing field `{key}`"))
+    }
+
+    fn as_number(&self) -> Result<f64, String> {
+        match *self {
+            Json::Number(n) => Ok(n),
+            Json::Null => Ok(f64::NAN),
+            Json::String(ref s) => Err(format!("expected a number, found \"{s}\"")),
+            _ => Err("expected a number".to_string()),
+        }
+    }
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> String {
+        format!("{msg} at byte {}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.s.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.s.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        if self.peek() != Some(b) {
+            return Err(self.error(&format!("expected `{}`", b as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(values));
+                        }
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            }
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'n') if self.s[self.pos..].starts_with(b"null") => {
+                self.pos += 4;
+                Ok(Json::Null)
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while self
+                    .s
+                    .get(self.pos)
+                    .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                {
+                    self.pos += 1;
+                }
+                let number = std::str::from_utf8(&self.s[start..self.pos]).unwrap();
+                number.parse().map(Json::Number).map_err(|_| self.error("invalid number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&b) = self.s.get(self.pos) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.s.get(self.pos).copied();
+                    self.pos += 1;
+                    let c = match escaped {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let hex = self
+                                .s
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32);
+                            self.pos += 4;
+                            hex.ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                b => bytes.push(b),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+fn summary(median: f64, median_abs_dev: f64) -> Summary {
+    Summary {
+        sum: median * BENCH_SAMPLES as f64,
+        min: median - median_abs_dev,
+        max: median + median_abs_dev,
+        mean: median,
+        median,
+        var: median_abs_dev * median_abs_dev,
+        std_dev: median_abs_dev,
+        std_dev_pct: f64::NAN,
+        median_abs_dev,
+        median_abs_dev_pct: median_abs_dev / median * 100.0,
+        quartiles: (median - median_abs_dev, median, median + median_abs_dev),
+        iqr: 2.0 * median_abs_dev,
+    }
+}
+
+#[test]
+fn json_roundtrip() {
+    let mut baseline = Baseline::default();
+    baseline.insert("bench::plain", &summary(1234.5, 10.0));
+    baseline.insert("bench::\"quoted\\\"\t", &summary(0.25, 0.0));
+
+    let json = baseline.to_json();
+    let parsed = Baseline::from_json(&json).unwrap();
+    assert_eq!(parsed.benchmarks.len(), 2);
+    for (name, summary) in &baseline.benchmarks {
+        let parsed = parsed.benchmarks[name];
+        assert_eq!(parsed.median, summary.median);
+        assert_eq!(parsed.quartiles, summary.quartiles);
+        // NaN is saved as `null`, and read back as NaN.
+        assert!(parsed.std_dev_pct.is_nan());
+    }
+    assert_eq!(parsed.to_json(), json);
+}
+
+#[test]
+fn invalid_json() {
+    assert!(Baseline::from_json("").is_err());
+    assert!(Baseline::from_json("{}").is_err());
+    assert!(Baseline::from_json(r#"{ "benchmarks": {} } x"#).is_err());
+    assert!(Baseline::from_json(r#"{ "benchmarks": { "a": { "median": 1 } } }"#).is_err());
+    assert_eq!(Baseline::from_json(r#"{ "benchmarks": {} }"#), Ok(Baseline::default()));
+}
+
+#[test]
+fn compare() {
+    let mut baseline = Baseline::default();
+    baseline.insert("bench", &summary(1000.0, 10.0));
+
+    assert_eq!(baseline.compare("other", &summary(1000.0, 10.0)), None);
+
+    // Well outside the noise of both runs.
+    let slower = baseline.compare("bench", &summary(1100.0, 10.0)).unwrap();
+    assert_eq!(slower.baseline_median, 1000.0);
+    assert!((slower.change - 0.1).abs() < 1e-9);
+    assert!(slower.is_regression());
+    assert_eq!(slower.verdict(), BenchVerdict::Regressed);
+
+    let faster = baseline.compare("bench", &summary(900.0, 10.0)).unwrap();
+    assert!(!faster.is_regression());
+    assert_eq!(faster.verdict(), BenchVerdict::Improved);
+
+    // Below the noise threshold.
+    let same = baseline.compare("bench", &summary(1010.0, 0.0)).unwrap();
+    assert_eq!(same.verdict(), BenchVerdict::NoChange);
+
+    // Within the noise of a run.
+    let noisy = baseline.compare("bench", &summary(1100.0, 400.0)).unwrap();
+    assert!(!noisy.significant);
+}
+
+#[test]
+fn compare_non_finite() {
+    let mut baseline = Baseline::default();
+    baseline.insert("nan", &summary(f64::NAN, 10.0));
+    baseline.insert("inf", &summary(f64::INFINITY, 10.0));
+    baseline.insert("bench", &summary(1000.0, 10.0));
+
+    assert_eq!(baseline.compare("nan", &summary(1000.0, 10.0)), None);
+    assert_eq!(baseline.compare("inf", &summary(1000.0, 10.0)), None);
+    assert_eq!(baseline.compare("bench", &summary(f64::INFINITY, 10.0)), None);
+    assert_eq!(json_number(f64::NAN), "null");
+}
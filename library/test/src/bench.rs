@@ -12,6 +12,9 @@
 use super::types::{TestDesc, TestId};
 use crate::stats;
 
+/// Number of samples the summary of a benchmark is computed from.
+pub(crate) const BENCH_SAMPLES: usize = 50;
+
 /// An identity function that *__hints__* to the compiler to be maximally pessimistic about what
 /// `black_box` could do.
 ///
@@ -140,7 +143,7 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
     n = cmp::max(1, n);
 
     let mut total_run = Duration::new(0, 0);
-    let samples: &mut [f64] = &mut [0.0_f64; 50];
+    let samples: &mut [f64] = &mut [0.0_f64; BENCH_SAMPLES];
     loop {
         let loop_start = Instant::now();
 
@@ -3,8 +3,10 @@
 use std::env;
 use std::io::{self, IsTerminal, Write};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
+use super::resources::TestResourceOptions;
 use super::time::TestTimeOptions;
 
 #[derive(Debug)]
@@ -23,9 +25,27 @@ pub struct TestOpts {
     pub format: OutputFormat,
     pub shuffle: bool,
     pub shuffle_seed: Option<u64>,
+    /// Only run the tests of this shard, out of `shard_count`.
+    pub shard_index: Option<usize>,
+    /// Number of shards the tests are partitioned into.
+    pub shard_count: Option<usize>,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
+    /// Only run tests defined in these files, or in submodules of the modules they define.
+    pub changed_files: Option<Vec<String>>,
     pub time_options: Option<TestTimeOptions>,
+    /// Report the resources used by tests run in a subprocess.
+    pub resource_options: Option<TestResourceOptions>,
+    /// Fail tests that run for longer than this, killing them if they
+    /// run in a subprocess.
+    pub test_timeout: Option<Duration>,
+    /// Number of times a failing test is run again before it is
+    /// considered failed.
+    pub retries: usize,
+    /// Save the results of benchmarks as this baseline.
+    pub save_baseline: Option<String>,
+    /// Compare the results of benchmarks against this baseline.
+    pub baseline: Option<String>,
     /// Stop at first failing test.
     /// May run a few more tests due to threading, but will
     /// abort as soon as possible.
@@ -103,8 +123,9 @@ fn optgroups() -> getopts::Options {
             pretty = Print verbose output;
             terse  = Display one character per test;
             json   = Output a json document;
-            junit  = Output a JUnit document",
-            "pretty|terse|json|junit",
+            junit  = Output a JUnit document;
+            tap    = Output a TAP 14 document",
+            "pretty|terse|json|junit|tap",
         )
         .optflag("", "show-output", "Show captured stdout of successful tests")
         .optopt(
@@ -143,12 +164,79 @@ fn optgroups() -> getopts::Options {
             `CRITICAL_TIME` here means the limit that should not be exceeded by test.
             ",
         )
+        .optflag(
+            "",
+            "report-resources",
+            "Report the CPU time and peak memory usage of each test.
+
+            Only available for tests run in a subprocess (e.g. with
+            `-C panic=abort`), on Linux, macOS and the BSDs.",
+        )
+        .optopt(
+            "",
+            "max-rss",
+            "Fail tests whose peak memory usage (resident set size) exceeds
+            MIB mebibytes. Implies --report-resources",
+            "MIB",
+        )
+        .optopt(
+            "",
+            "test-timeout",
+            "Fail tests that are still running after SECS seconds.
+
+            Tests run in a subprocess (e.g. with `-C panic=abort`) are killed.
+            Tests run in-process can't be stopped, and are left running in the
+            background while the rest of the suite continues.",
+            "SECS",
+        )
+        .optopt(
+            "",
+            "retries",
+            "Run failing tests again up to N times. Tests that pass when retried
+            are reported as flaky",
+            "N",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the results of benchmarks as the baseline NAME, to
+            compare later runs against it with --baseline",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the results of benchmarks against the baseline NAME,
+            reporting significant changes",
+            "NAME",
+        )
         .optflag("", "shuffle", "Run tests in random order")
         .optopt(
             "",
             "shuffle-seed",
             "Run tests in random order; seed the random number generator with SEED",
             "SEED",
+        )
+        .optmulti(
+            "",
+            "changed-files",
+            "Only run tests defined in the comma-separated list of files, or
+            in submodules of the modules defined there",
+            "LIST",
+        )
+        .optopt(
+            "",
+            "shard-index",
+            "Only run the tests of shard INDEX, counting from 0. Requires
+            --shard-count",
+            "INDEX",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Partition the tests into COUNT shards, based on a hash of their
+            names",
+            "COUNT",
         );
     opts
 }
@@ -171,6 +259,11 @@ fn usage(binary: &str, options: &getopts::Options) {
 --shuffle and --shuffle-seed do not affect whether the tests are run in
 parallel.
 
+Use --shard-count and --shard-index to split the tests across several runs,
+for example on different machines. Every test is assigned to exactly one
+shard, based on its name, after the filters have been applied. With --list
+--format json, the shard of each test is reported.
+
 All tests have their standard output and standard error captured by default.
 This can be overridden with the --no-capture flag to a value other than "0".
 Logging is not captured by default.
@@ -263,8 +356,14 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
     let fail_fast = unstable_optflag!(matches, allow_unstable, "fail-fast");
     let time_options = get_time_options(&matches, allow_unstable)?;
+    let resource_options = get_resource_options(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
+    let retries = get_retries(&matches, allow_unstable)?;
+    let (save_baseline, baseline) = get_baselines(&matches, allow_unstable)?;
     let shuffle = get_shuffle(&matches, allow_unstable)?;
     let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
+    let (shard_index, shard_count) = get_shard(&matches, allow_unstable)?;
+    let changed_files = get_changed_files(&matches, allow_unstable)?;
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
@@ -304,9 +403,17 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         format,
         shuffle,
         shuffle_seed,
+        shard_index,
+        shard_count,
         test_threads,
         skip,
+        changed_files,
         time_options,
+        resource_options,
+        test_timeout,
+        retries,
+        save_baseline,
+        baseline,
         options,
         fail_fast,
     };
@@ -344,6 +451,96 @@ fn get_time_options(
     Ok(options)
 }
 
+fn get_resource_options(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<TestResourceOptions>> {
+    let report_resources = unstable_optflag!(matches, allow_unstable, "report-resources");
+    let max_rss = match unstable_optopt!(matches, allow_unstable, "max-rss") {
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(0) => return Err("argument for --max-rss must not be 0".to_string()),
+            Ok(n) => match n.checked_mul(1024 * 1024) {
+                Some(bytes) => Some(bytes),
+                None => return Err(format!("argument for --max-rss is too large: {n}")),
+            },
+            Err(e) => {
+                return Err(format!(
+                    "argument for --max-rss must be a number > 0 \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let options = if report_resources || max_rss.is_some() {
+        Some(TestResourceOptions { max_rss })
+    } else {
+        None
+    };
+
+    Ok(options)
+}
+
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    let test_timeout = match unstable_optopt!(matches, allow_unstable, "test-timeout") {
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(0) => return Err("argument for --test-timeout must not be 0".to_string()),
+            Ok(n) => Some(Duration::from_secs(n)),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --test-timeout must be a number > 0 \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(test_timeout)
+}
+
+fn get_retries(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<usize> {
+    let retries = match unstable_optopt!(matches, allow_unstable, "retries") {
+        Some(n_str) => match n_str.parse::<usize>() {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(format!(
+                    "argument for --retries must be a number \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => 0,
+    };
+
+    Ok(retries)
+}
+
+fn get_baselines(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<(Option<String>, Option<String>)> {
+    let save_baseline = unstable_optopt!(matches, allow_unstable, "save-baseline");
+    let baseline = unstable_optopt!(matches, allow_unstable, "baseline");
+
+    // Baselines are stored as files in a common directory.
+    for (option_name, name) in [("save-baseline", &save_baseline), ("baseline", &baseline)] {
+        if let Some(name) = name
+            && (name.is_empty() || name.contains(['/', '\\']))
+        {
+            return Err(format!(
+                "argument for --{option_name} must be a non-empty name without path separators"
+            ));
+        }
+    }
+
+    Ok((save_baseline, baseline))
+}
+
 fn get_shuffle(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<bool> {
     let mut shuffle = unstable_optflag!(matches, allow_unstable, "shuffle");
     if !shuffle && allow_unstable {
@@ -383,6 +580,70 @@ fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPart
     Ok(shuffle_seed)
 }
 
+fn get_changed_files(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Vec<String>>> {
+    let lists = matches.opt_strs("changed-files");
+    if lists.is_empty() {
+        return Ok(None);
+    }
+    if !allow_unstable {
+        return Err("The \"changed-files\" option is only accepted on the nightly compiler with -Z unstable-options".into());
+    }
+
+    let changed_files = lists
+        .iter()
+        .flat_map(|list| list.split(','))
+        .map(str::trim)
+        .filter(|file| !file.is_empty())
+        .map(str::to_owned)
+        .collect();
+    Ok(Some(changed_files))
+}
+
+fn get_shard(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<(Option<usize>, Option<usize>)> {
+    let shard_count = match unstable_optopt!(matches, allow_unstable, "shard-count") {
+        Some(n_str) => match n_str.parse::<usize>() {
+            Ok(0) => return Err("argument for --shard-count must not be 0".to_string()),
+            Ok(n) => Some(n),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --shard-count must be a number > 0 \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let shard_index = match unstable_optopt!(matches, allow_unstable, "shard-index") {
+        Some(n_str) => match n_str.parse::<usize>() {
+            Ok(n) => Some(n),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --shard-index must be a number \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    match (shard_index, shard_count) {
+        (Some(_), None) => {
+            Err("argument --shard-index requires --shard-count to be set".to_string())
+        }
+        (Some(index), Some(count)) if index >= count => Err(format!(
+            "argument for --shard-index must be less than --shard-count ({count}), got {index}"
+        )),
+        _ => Ok((shard_index, shard_count)),
+    }
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
@@ -422,9 +683,15 @@ fn get_format(
             }
             OutputFormat::Junit
         }
+        Some("tap") => {
+            if !allow_unstable {
+                return Err("The \"tap\" format is only accepted on the nightly compiler with -Z unstable-options".into());
+            }
+            OutputFormat::Tap
+        }
         Some(v) => {
             return Err(format!(
-                "argument for --format must be pretty, terse, json or junit (was \
+                "argument for --format must be pretty, terse, json, junit or tap (was \
                  {v})"
             ));
         }
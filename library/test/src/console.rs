@@ -5,14 +5,16 @@
 use std::io::prelude::Write;
 use std::time::Instant;
 
-use super::bench::fmt_bench_samples;
+use super::baseline::{Baseline, BenchComparison};
+use super::bench::{BenchSamples, fmt_bench_samples};
 use super::cli::TestOpts;
 use super::event::{CompletedTest, TestEvent};
 use super::formatters::{
-    JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter,
+    JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TapFormatter, TerseFormatter,
 };
 use super::helpers::concurrency::get_concurrency;
 use super::helpers::metrics::MetricMap;
+use super::helpers::shard::test_shard;
 use super::options::{Options, OutputFormat};
 use super::test_result::TestResult;
 use super::time::{TestExecTime, TestSuiteExecTime};
@@ -79,15 +81,22 @@ pub(crate) struct ConsoleTestState {
     pub total: usize,
     pub passed: usize,
     pub failed: usize,
+    pub flaky: usize,
     pub ignored: usize,
     pub filtered_out: usize,
     pub measured: usize,
+    pub regressed: usize,
     pub exec_time: Option<TestSuiteExecTime>,
     pub metrics: MetricMap,
     pub failures: Vec<(TestDesc, Vec<u8>)>,
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub ignores: Vec<(TestDesc, Vec<u8>)>,
     pub time_failures: Vec<(TestDesc, Vec<u8>)>,
+    pub flakes: Vec<(TestDesc, Vec<u8>)>,
+    /// The baseline that benchmarks are compared against, from `--baseline`.
+    pub baseline: Option<Baseline>,
+    /// The results of the benchmarks that ran, saved with `--save-baseline`.
+    pub bench_results: Baseline,
     pub options: Options,
 }
 
@@ -103,15 +112,20 @@ pub(crate) fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             total: 0,
             passed: 0,
             failed: 0,
+            flaky: 0,
             ignored: 0,
             filtered_out: 0,
             measured: 0,
+            regressed: 0,
             exec_time: None,
             metrics: MetricMap::new(),
             failures: Vec::new(),
             not_failures: Vec::new(),
             ignores: Vec::new(),
             time_failures: Vec::new(),
+            flakes: Vec::new(),
+            baseline: opts.baseline.as_deref().map(Baseline::load).transpose()?,
+            bench_results: Baseline::default(),
             options: opts.options,
         })
     }
@@ -154,6 +168,9 @@ pub(crate) fn write_log_result(
                     }
                     TestResult::TrBench(ref bs) => fmt_bench_samples(bs),
                     TestResult::TrTimedFail => "failed (time limit exceeded)".to_owned(),
+                    TestResult::TrTimedOut => "failed (timed out)".to_owned(),
+                    TestResult::TrFlaky(attempts) =>
+                        format!("flaky (passed on attempt {attempts})"),
                 },
                 name,
             )
@@ -164,8 +181,17 @@ pub(crate) fn write_log_result(
         self.write_log(|| "\n")
     }
 
+    /// Compares the results of a benchmark against the baseline, if any.
+    pub(crate) fn compare_bench(
+        &self,
+        test: &TestDesc,
+        bs: &BenchSamples,
+    ) -> Option<BenchComparison> {
+        self.baseline.as_ref()?.compare(test.name.as_slice(), &bs.ns_iter_summ)
+    }
+
     fn current_test_count(&self) -> usize {
-        self.passed + self.failed + self.ignored + self.measured
+        self.passed + self.failed + self.flaky + self.ignored + self.measured
     }
 }
 
@@ -177,7 +203,7 @@ pub(crate) fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) ->
     };
 
     let mut out: Box<dyn OutputFormatter> = match opts.format {
-        OutputFormat::Pretty | OutputFormat::Junit => {
+        OutputFormat::Pretty | OutputFormat::Junit | OutputFormat::Tap => {
             Box::new(PrettyFormatter::new(output, false, 0, false, None))
         }
         OutputFormat::Terse => Box::new(TerseFormatter::new(output, false, 0, false)),
@@ -204,7 +230,8 @@ pub(crate) fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) ->
 
         st.ignored += if desc.ignore { 1 } else { 0 };
 
-        out.write_test_discovered(&desc, fntype)?;
+        let shard = opts.shard_count.map(|shard_count| test_shard(&desc.name, shard_count));
+        out.write_test_discovered(&desc, fntype, shard)?;
         st.write_log(|| format!("{fntype} {}\n", desc.name))?;
     }
 
@@ -230,6 +257,10 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
                 bs.ns_iter_summ.median,
                 bs.ns_iter_summ.max - bs.ns_iter_summ.min,
             );
+            if st.compare_bench(&test, &bs).is_some_and(|comparison| comparison.is_regression()) {
+                st.regressed += 1;
+            }
+            st.bench_results.insert(test.name.as_slice(), &bs.ns_iter_summ);
             st.measured += 1
         }
         TestResult::TrFailed => {
@@ -246,6 +277,17 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
             st.failed += 1;
             st.time_failures.push((test, stdout));
         }
+        TestResult::TrFlaky(_) => {
+            st.flaky += 1;
+            st.flakes.push((test, stdout));
+        }
+        TestResult::TrTimedOut => {
+            st.failed += 1;
+            let mut stdout = stdout;
+            stdout
+                .extend_from_slice(b"note: test did not finish within the `--test-timeout` limit");
+            st.failures.push((test, stdout));
+        }
     }
 }
 
@@ -270,11 +312,12 @@ fn on_test_event(
             let test = &completed_test.desc;
             let result = &completed_test.result;
             let exec_time = &completed_test.exec_time;
+            let resources = &completed_test.resources;
             let stdout = &completed_test.stdout;
 
             st.write_log_result(test, result, exec_time.as_ref())?;
-            out.write_result(test, result, exec_time.as_ref(), stdout, st)?;
-            handle_test_result(st, completed_test);
+            out.write_result(test, result, exec_time.as_ref(), resources.as_ref(), stdout, st)?;
+            handle_test_result(st, *completed_test);
         }
     }
 
@@ -302,6 +345,7 @@ pub(crate) fn get_formatter(opts: &TestOpts, max_name_len: usize) -> Box<dyn Out
         }
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
         OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
+        OutputFormat::Tap => Box::new(TapFormatter::new(output)),
     }
 }
 
@@ -329,6 +373,10 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
 
     assert!(opts.fail_fast || st.current_test_count() == st.total);
 
+    if let Some(ref name) = opts.save_baseline {
+        st.bench_results.save(name)?;
+    }
+
     out.write_run_finish(&st)
 }
 
@@ -1,6 +1,7 @@
 //! Module containing different events that can occur
 //! during tests execution process.
 
+use super::resources::TestResourceUsage;
 use super::test_result::TestResult;
 use super::time::TestExecTime;
 use super::types::{TestDesc, TestId};
@@ -11,6 +12,8 @@ pub struct CompletedTest {
     pub desc: TestDesc,
     pub result: TestResult,
     pub exec_time: Option<TestExecTime>,
+    /// Only measured for tests that ran in a subprocess, with `--report-resources`.
+    pub resources: Option<TestResourceUsage>,
     pub stdout: Vec<u8>,
 }
 
@@ -22,7 +25,7 @@ pub fn new(
         exec_time: Option<TestExecTime>,
         stdout: Vec<u8>,
     ) -> Self {
-        Self { id, desc, result, exec_time, stdout }
+        Self { id, desc, result, exec_time, resources: None, stdout }
     }
 }
 
@@ -30,7 +33,7 @@ pub fn new(
 pub enum TestEvent {
     TeFiltered(usize, Option<u64>),
     TeWait(TestDesc),
-    TeResult(CompletedTest),
+    TeResult(Box<CompletedTest>),
     TeTimeout(TestDesc),
     TeFilteredOut(usize),
 }
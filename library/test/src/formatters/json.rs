@@ -3,7 +3,9 @@
 use std::io::prelude::Write;
 
 use super::OutputFormatter;
+use crate::baseline::json_number;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::resources::TestResourceUsage;
 use crate::test_result::TestResult;
 use crate::time;
 use crate::types::TestDesc;
@@ -28,20 +30,30 @@ fn writeln_message(&mut self, s: &str) -> io::Result<()> {
 
     fn write_event(
         &mut self,
-        ty: &str,
-        name: &str,
+        desc: &TestDesc,
         event: &str,
         exec_time: Option<&time::TestExecTime>,
+        resources: Option<&TestResourceUsage>,
         stdout: Option<Cow<'_, str>>,
         extra: Option<&str>,
     ) -> io::Result<()> {
         // A doc test's name includes a filename which must be escaped for correct json.
-        let name = EscapedString(name);
+        let name = EscapedString(desc.name.as_slice());
         let exec_time_json = if let Some(exec_time) = exec_time {
             format!(r#", "exec_time": {}"#, exec_time.0.as_secs_f64())
         } else {
             String::from("")
         };
+        let resources_json = if let Some(resources) = resources {
+            format!(
+                r#", "user_time": {}, "system_time": {}, "max_rss": {}"#,
+                resources.user_time.as_secs_f64(),
+                resources.system_time.as_secs_f64(),
+                resources.max_rss
+            )
+        } else {
+            String::from("")
+        };
         let stdout_json = if let Some(stdout) = stdout {
             format!(r#", "stdout": "{}""#, EscapedString(stdout))
         } else {
@@ -52,7 +64,7 @@ fn write_event(
         let newline = "\n";
 
         self.writeln_message(&format!(
-                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{exec_time_json}{stdout_json}{extra_json} }}{newline}"#))
+                r#"{{ "type": "test", "name": "{name}", "event": "{event}"{exec_time_json}{resources_json}{stdout_json}{extra_json} }}{newline}"#))
     }
 }
 
@@ -61,7 +73,12 @@ fn write_discovery_start(&mut self) -> io::Result<()> {
         self.writeln_message(concat!(r#"{ "type": "suite", "event": "discovery" }"#, "\n"))
     }
 
-    fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
+    fn write_test_discovered(
+        &mut self,
+        desc: &TestDesc,
+        test_type: &str,
+        shard: Option<usize>,
+    ) -> io::Result<()> {
         let TestDesc {
             name,
             ignore,
@@ -77,10 +94,12 @@ fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Res
         let name = EscapedString(name.as_slice());
         let ignore_message = ignore_message.unwrap_or("");
         let source_path = EscapedString(source_file);
+        let shard_json =
+            if let Some(shard) = shard { format!(r#", "shard": {shard}"#) } else { String::new() };
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col} }}{newline}"#
+            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col}{shard_json} }}{newline}"#
         ))
     }
 
@@ -119,6 +138,7 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        resources: Option<&TestResourceUsage>,
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()> {
@@ -129,37 +149,53 @@ fn write_result(
             None
         };
         match *result {
-            TestResult::TrOk => {
-                self.write_event("test", desc.name.as_slice(), "ok", exec_time, stdout, None)
-            }
+            TestResult::TrOk => self.write_event(desc, "ok", exec_time, resources, stdout, None),
 
             TestResult::TrFailed => {
-                self.write_event("test", desc.name.as_slice(), "failed", exec_time, stdout, None)
+                self.write_event(desc, "failed", exec_time, resources, stdout, None)
             }
 
             TestResult::TrTimedFail => self.write_event(
-                "test",
-                desc.name.as_slice(),
+                desc,
                 "failed",
                 exec_time,
+                resources,
                 stdout,
                 Some(r#""reason": "time limit exceeded""#),
             ),
 
+            TestResult::TrTimedOut => self.write_event(
+                desc,
+                "failed",
+                exec_time,
+                resources,
+                stdout,
+                Some(r#""reason": "timed out""#),
+            ),
+
+            TestResult::TrFlaky(attempts) => self.write_event(
+                desc,
+                "flaky",
+                exec_time,
+                resources,
+                stdout,
+                Some(&*format!(r#""attempts": {attempts}"#)),
+            ),
+
             TestResult::TrFailedMsg(ref m) => self.write_event(
-                "test",
-                desc.name.as_slice(),
+                desc,
                 "failed",
                 exec_time,
+                resources,
                 stdout,
                 Some(&*format!(r#""message": "{}""#, EscapedString(m))),
             ),
 
             TestResult::TrIgnored => self.write_event(
-                "test",
-                desc.name.as_slice(),
+                desc,
                 "ignored",
                 exec_time,
+                resources,
                 stdout,
                 desc.ignore_message
                     .map(|msg| format!(r#""message": "{}""#, EscapedString(msg)))
@@ -167,21 +203,31 @@ fn write_result(
             ),
 
             TestResult::TrBench(ref bs) => {
-                let median = bs.ns_iter_summ.median;
-                let deviation = bs.ns_iter_summ.max - bs.ns_iter_summ.min;
+                let median = json_number(bs.ns_iter_summ.median);
+                let deviation = json_number(bs.ns_iter_summ.max - bs.ns_iter_summ.min);
 
                 let mbps = if bs.mb_s == 0 {
                     String::new()
                 } else {
                     format!(r#", "mib_per_second": {}"#, bs.mb_s)
                 };
+                let baseline = match state.compare_bench(desc, bs) {
+                    Some(comparison) => format!(
+                        r#", "baseline_median": {}, "change": {}, "significant": {}, "regressed": {}"#,
+                        json_number(comparison.baseline_median),
+                        json_number(comparison.change),
+                        comparison.significant,
+                        comparison.is_regression()
+                    ),
+                    None => String::new(),
+                };
                 let name = EscapedString(desc.name.as_slice());
 
                 self.writeln_message(&format!(
                     "{{ \"type\": \"bench\", \
                      \"name\": \"{name}\", \
                      \"median\": {median}, \
-                     \"deviation\": {deviation}{mbps} }}\n",
+                     \"deviation\": {deviation}{mbps}{baseline} }}\n",
                 ))
             }
         }
@@ -199,8 +245,15 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let event = if state.failed == 0 { "ok" } else { "failed" };
         let passed = state.passed;
         let failed = state.failed;
+        let flaky_json =
+            if state.flaky > 0 { format!(r#", "flaky": {}"#, state.flaky) } else { String::new() };
         let ignored = state.ignored;
         let measured = state.measured;
+        let regressed_json = if state.regressed > 0 {
+            format!(r#", "regressed": {}"#, state.regressed)
+        } else {
+            String::new()
+        };
         let filtered_out = state.filtered_out;
         let exec_time_json = if let Some(ref exec_time) = state.exec_time {
             format!(r#", "exec_time": {}"#, exec_time.0.as_secs_f64())
@@ -210,7 +263,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{exec_time_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}{flaky_json}, "ignored": {ignored}, "measured": {measured}{regressed_json}, "filtered_out": {filtered_out}{exec_time_json} }}{newline}"#
         ))?;
 
         Ok(state.failed == 0)
@@ -4,13 +4,22 @@
 
 use super::OutputFormatter;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::resources::TestResourceUsage;
 use crate::test_result::TestResult;
 use crate::time;
 use crate::types::{TestDesc, TestType};
 
 pub(crate) struct JunitFormatter<T> {
     out: OutputLocation<T>,
-    results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+    results: Vec<JunitResult>,
+}
+
+struct JunitResult {
+    desc: TestDesc,
+    result: TestResult,
+    duration: Duration,
+    resources: Option<TestResourceUsage>,
+    stdout: Vec<u8>,
 }
 
 impl<T: Write> JunitFormatter<T> {
@@ -23,6 +32,26 @@ fn write_message(&mut self, s: &str) -> io::Result<()> {
 
         self.out.write_all(s.as_ref())
     }
+
+    fn write_properties(&mut self, resources: Option<&TestResourceUsage>) -> io::Result<()> {
+        let Some(resources) = resources else {
+            return Ok(());
+        };
+        self.write_message("<properties>")?;
+        self.write_message(&format!(
+            "<property name=\"user_time\" value=\"{}\"/>",
+            resources.user_time.as_secs_f64()
+        ))?;
+        self.write_message(&format!(
+            "<property name=\"system_time\" value=\"{}\"/>",
+            resources.system_time.as_secs_f64()
+        ))?;
+        self.write_message(&format!(
+            "<property name=\"max_rss\" value=\"{}\"/>",
+            resources.max_rss
+        ))?;
+        self.write_message("</properties>")
+    }
 }
 
 fn str_to_cdata(s: &str) -> String {
@@ -42,7 +71,12 @@ fn write_discovery_start(&mut self) -> io::Result<()> {
         Err(io::const_error!(io::ErrorKind::NotFound, "not yet implemented!"))
     }
 
-    fn write_test_discovered(&mut self, _desc: &TestDesc, _test_type: &str) -> io::Result<()> {
+    fn write_test_discovered(
+        &mut self,
+        _desc: &TestDesc,
+        _test_type: &str,
+        _shard: Option<usize>,
+    ) -> io::Result<()> {
         Err(io::const_error!(io::ErrorKind::NotFound, "not yet implemented!"))
     }
 
@@ -74,6 +108,7 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        resources: Option<&TestResourceUsage>,
         stdout: &[u8],
         _state: &ConsoleTestState,
     ) -> io::Result<()> {
@@ -81,7 +116,13 @@ fn write_result(
         // until all of the tests have finished. Instead of writing every result as they come in, we add
         // them to a Vec and write them all at once when run is complete.
         let duration = exec_time.map(|t| t.0).unwrap_or_default();
-        self.results.push((desc.clone(), result.clone(), duration, stdout.to_vec()));
+        self.results.push(JunitResult {
+            desc: desc.clone(),
+            result: result.clone(),
+            duration,
+            resources: resources.copied(),
+            stdout: stdout.to_vec(),
+        });
         Ok(())
     }
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
@@ -96,7 +137,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
              >",
             state.failed, state.total, state.ignored
         ))?;
-        for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
+        for JunitResult { desc, result, duration, resources, stdout } in
+            std::mem::take(&mut self.results)
+        {
             let (class_name, test_name) = parse_class_name(&desc);
             match result {
                 TestResult::TrIgnored => { /* no-op */ }
@@ -108,6 +151,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         test_name,
                         duration.as_secs_f64()
                     ))?;
+                    self.write_properties(resources.as_ref())?;
                     self.write_message("<failure type=\"assert\"/>")?;
                     if !stdout.is_empty() {
                         self.write_message("<system-out>")?;
@@ -125,6 +169,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         test_name,
                         duration.as_secs_f64()
                     ))?;
+                    self.write_properties(resources.as_ref())?;
                     self.write_message(&format!("<failure message=\"{m}\" type=\"assert\"/>"))?;
                     if !stdout.is_empty() {
                         self.write_message("<system-out>")?;
@@ -142,10 +187,29 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         test_name,
                         duration.as_secs_f64()
                     ))?;
+                    self.write_properties(resources.as_ref())?;
                     self.write_message("<failure type=\"timeout\"/>")?;
                     self.write_message("</testcase>")?;
                 }
 
+                TestResult::TrTimedOut => {
+                    self.write_message(&format!(
+                        "<testcase classname=\"{}\" \
+                         name=\"{}\" time=\"{}\">",
+                        class_name,
+                        test_name,
+                        duration.as_secs_f64()
+                    ))?;
+                    self.write_properties(resources.as_ref())?;
+                    self.write_message("<failure message=\"timed out\" type=\"timeout\"/>")?;
+                    if !stdout.is_empty() {
+                        self.write_message("<system-out>")?;
+                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
+                        self.write_message("</system-out>")?;
+                    }
+                    self.write_message("</testcase>")?;
+                }
+
                 TestResult::TrBench(ref b) => {
                     self.write_message(&format!(
                         "<testcase classname=\"benchmark::{}\" \
@@ -154,7 +218,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                     ))?;
                 }
 
-                TestResult::TrOk => {
+                TestResult::TrOk | TestResult::TrFlaky(_) => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
                          name=\"{}\" time=\"{}\"",
@@ -162,12 +226,17 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         test_name,
                         duration.as_secs_f64()
                     ))?;
-                    if stdout.is_empty() || !state.options.display_output {
+                    let display_stdout = !stdout.is_empty() && state.options.display_output;
+                    if !display_stdout && resources.is_none() {
                         self.write_message("/>")?;
                     } else {
-                        self.write_message("><system-out>")?;
-                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
-                        self.write_message("</system-out>")?;
+                        self.write_message(">")?;
+                        self.write_properties(resources.as_ref())?;
+                        if display_stdout {
+                            self.write_message("<system-out>")?;
+                            self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
+                            self.write_message("</system-out>")?;
+                        }
                         self.write_message("</testcase>")?;
                     }
                 }
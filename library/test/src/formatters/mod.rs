@@ -1,24 +1,33 @@
 use std::io;
 use std::io::prelude::Write;
 
+use crate::baseline::{BenchComparison, BenchVerdict};
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState};
+use crate::resources::TestResourceUsage;
 use crate::test_result::TestResult;
-use crate::time;
 use crate::types::{TestDesc, TestName};
+use crate::{term, time};
 
 mod json;
 mod junit;
 mod pretty;
+mod tap;
 mod terse;
 
 pub(crate) use self::json::JsonFormatter;
 pub(crate) use self::junit::JunitFormatter;
 pub(crate) use self::pretty::PrettyFormatter;
+pub(crate) use self::tap::TapFormatter;
 pub(crate) use self::terse::TerseFormatter;
 
 pub(crate) trait OutputFormatter {
     fn write_discovery_start(&mut self) -> io::Result<()>;
-    fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()>;
+    fn write_test_discovered(
+        &mut self,
+        desc: &TestDesc,
+        test_type: &str,
+        shard: Option<usize>,
+    ) -> io::Result<()>;
     fn write_discovery_finish(&mut self, state: &ConsoleTestDiscoveryState) -> io::Result<()>;
 
     fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()>;
@@ -29,6 +38,7 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        resources: Option<&TestResourceUsage>,
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()>;
@@ -40,6 +50,23 @@ fn write_merged_doctests_times(
     ) -> io::Result<()>;
 }
 
+/// Writes how a benchmark compares to its baseline, with `write`, which writes the text in the
+/// given color, or plainly if there is none.
+pub(crate) fn write_bench_comparison(
+    comparison: &BenchComparison,
+    mut write: impl FnMut(&str, Option<term::color::Color>) -> io::Result<()>,
+) -> io::Result<()> {
+    write(&format!(" ({:+.2}%, ", comparison.change * 100.0), None)?;
+    let verdict = comparison.verdict();
+    let color = match verdict {
+        BenchVerdict::NoChange => None,
+        BenchVerdict::Regressed => Some(term::color::RED),
+        BenchVerdict::Improved => Some(term::color::GREEN),
+    };
+    write(verdict.as_str(), color)?;
+    write(")", None)
+}
+
 pub(crate) fn write_stderr_delimiter(test_output: &mut Vec<u8>, test_name: &TestName) {
     match test_output.last() {
         Some(b'\n') => (),
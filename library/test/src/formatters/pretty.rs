@@ -1,9 +1,10 @@
 use std::io;
 use std::io::prelude::Write;
 
-use super::OutputFormatter;
+use super::{OutputFormatter, write_bench_comparison};
 use crate::bench::fmt_bench_samples;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::resources::TestResourceUsage;
 use crate::test_result::TestResult;
 use crate::types::TestDesc;
 use crate::{term, time};
@@ -55,6 +56,14 @@ pub(crate) fn write_time_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED (time limit exceeded)", term::color::RED)
     }
 
+    pub(crate) fn write_flaky(&mut self) -> io::Result<()> {
+        self.write_short_result("flaky", term::color::YELLOW)
+    }
+
+    pub(crate) fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("FAILED (timed out)", term::color::RED)
+    }
+
     pub(crate) fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -166,6 +175,10 @@ pub(crate) fn write_time_failures(&mut self, state: &ConsoleTestState) -> io::Re
         self.write_results(&state.time_failures, "failures (time limit exceeded)")
     }
 
+    pub(crate) fn write_flakes(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_results(&state.flakes, "flaky tests")
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         if let Some(test_mode) = desc.test_mode() {
@@ -183,7 +196,12 @@ fn write_discovery_start(&mut self) -> io::Result<()> {
         Ok(())
     }
 
-    fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
+    fn write_test_discovered(
+        &mut self,
+        desc: &TestDesc,
+        test_type: &str,
+        _shard: Option<usize>,
+    ) -> io::Result<()> {
         self.write_plain(format!("{}: {test_type}\n", desc.name))
     }
 
@@ -233,8 +251,9 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        _: Option<&TestResourceUsage>,
         _: &[u8],
-        _: &ConsoleTestState,
+        state: &ConsoleTestState,
     ) -> io::Result<()> {
         if self.is_multithreaded {
             self.write_test_name(desc)?;
@@ -247,8 +266,16 @@ fn write_result(
             TestResult::TrBench(ref bs) => {
                 self.write_bench()?;
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
+                if let Some(comparison) = state.compare_bench(desc, bs) {
+                    write_bench_comparison(&comparison, |text, color| match color {
+                        Some(color) => self.write_pretty(text, color),
+                        None => self.write_plain(text),
+                    })?;
+                }
             }
             TestResult::TrTimedFail => self.write_time_failed()?,
+            TestResult::TrTimedOut => self.write_timed_out()?,
+            TestResult::TrFlaky(_) => self.write_flaky()?,
         }
 
         self.write_time(desc, exec_time)?;
@@ -278,6 +305,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             }
         }
 
+        if !state.flakes.is_empty() {
+            self.write_flakes(state)?;
+        }
+
         self.write_plain("\ntest result: ")?;
 
         if success {
@@ -287,8 +318,15 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
+        let flaky =
+            if state.flaky > 0 { format!("; {} flaky", state.flaky) } else { String::new() };
+        let regressed = if state.regressed > 0 {
+            format!("; {} regressed", state.regressed)
+        } else {
+            String::new()
+        };
         let s = format!(
-            ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            ". {} passed; {} failed{flaky}; {} ignored; {} measured{regressed}; {} filtered out",
             state.passed, state.failed, state.ignored, state.measured, state.filtered_out
         );
 
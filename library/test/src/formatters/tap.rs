@@ -0,0 +1,323 @@
+use std::io;
+use std::io::prelude::Write;
+
+use super::OutputFormatter;
+use crate::bench::fmt_bench_samples;
+use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::resources::TestResourceUsage;
+use crate::test_result::TestResult;
+use crate::time;
+use crate::types::{TestDesc, TestType};
+
+/// Indentation of a subtest relative to its parent, as mandated by TAP 14.
+const SUBTEST_INDENT: &str = "    ";
+
+/// Emits a [TAP 14](https://testanything.org/tap-version-14-specification.html) document.
+///
+/// Test points are numbered in completion order and the plan is written at the end of the run,
+/// so that `--fail-fast` and concurrently running tests still produce a consistent document.
+/// Doctests are collected into one subtest per documented file.
+pub(crate) struct TapFormatter<T> {
+    out: OutputLocation<T>,
+    test_number: usize,
+    doctests: Vec<(String, Vec<TapResult>)>,
+}
+
+/// A value in a YAML diagnostic block.
+enum YamlValue {
+    Number(String),
+    Text(String),
+}
+
+struct TapResult {
+    desc: TestDesc,
+    result: TestResult,
+    exec_time: Option<time::TestExecTime>,
+    stdout: Option<String>,
+}
+
+impl<T: Write> TapFormatter<T> {
+    pub(crate) fn new(out: OutputLocation<T>) -> Self {
+        Self { out, test_number: 0, doctests: Vec::new() }
+    }
+
+    fn write_line(&mut self, indent: &str, s: &str) -> io::Result<()> {
+        // Like the JSON formatter, write every line at once to keep it from being interleaved
+        // with output of concurrently running tests.
+        self.out.write_all(format!("{indent}{s}\n").as_bytes())
+    }
+
+    fn write_test_point(
+        &mut self,
+        indent: &str,
+        number: usize,
+        tap_result: &TapResult,
+    ) -> io::Result<()> {
+        let TapResult { desc, result, exec_time, stdout } = tap_result;
+        let name = EscapedDescription(desc.name.as_slice());
+        let (status, directive) = match *result {
+            TestResult::TrOk | TestResult::TrFlaky(_) | TestResult::TrBench(_) => {
+                ("ok", String::new())
+            }
+            TestResult::TrFailed
+            | TestResult::TrFailedMsg(_)
+            | TestResult::TrTimedFail
+            | TestResult::TrTimedOut => ("not ok", String::new()),
+            TestResult::TrIgnored => match desc.ignore_message {
+                Some(msg) => ("ok", format!(" # SKIP {}", EscapedDescription(msg))),
+                None => ("ok", String::from(" # SKIP")),
+            },
+        };
+        self.write_line(indent, &format!("{status} {number} - {name}{directive}"))?;
+
+        let mut diagnostics = Vec::new();
+        match *result {
+            TestResult::TrFailedMsg(ref msg) => {
+                diagnostics.push(("message", YamlValue::Text(msg.clone())))
+            }
+            TestResult::TrTimedFail => {
+                diagnostics.push(("message", YamlValue::Text("time limit exceeded".to_owned())))
+            }
+            TestResult::TrTimedOut => {
+                diagnostics.push(("message", YamlValue::Text("timed out".to_owned())))
+            }
+            TestResult::TrFlaky(attempts) => {
+                let message = format!("flaky, passed on attempt {attempts}");
+                diagnostics.push(("message", YamlValue::Text(message)));
+                diagnostics.push(("attempts", YamlValue::Number(attempts.to_string())));
+            }
+            TestResult::TrBench(ref bs) => {
+                let median = bs.ns_iter_summ.median;
+                let deviation = bs.ns_iter_summ.max - bs.ns_iter_summ.min;
+                diagnostics
+                    .push(("bench", YamlValue::Text(fmt_bench_samples(bs).trim().to_owned())));
+                diagnostics.push(("median", YamlValue::Number(median.to_string())));
+                diagnostics.push(("deviation", YamlValue::Number(deviation.to_string())));
+                if bs.mb_s != 0 {
+                    diagnostics.push(("mib_per_second", YamlValue::Number(bs.mb_s.to_string())));
+                }
+            }
+            _ => {}
+        }
+        if let Some(exec_time) = exec_time {
+            let duration_ms = exec_time.0.as_secs_f64() * 1000.0;
+            diagnostics.push(("duration_ms", YamlValue::Number(format!("{duration_ms:.3}"))));
+        }
+        if let Some(stdout) = stdout {
+            diagnostics.push(("stdout", YamlValue::Text(stdout.clone())));
+        }
+
+        self.write_yaml_block(indent, &diagnostics)
+    }
+
+    /// Writes a YAML diagnostic block following a test point.
+    fn write_yaml_block(&mut self, indent: &str, entries: &[(&str, YamlValue)]) -> io::Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        self.write_line(indent, "  ---")?;
+        for (key, value) in entries {
+            let value = match value {
+                YamlValue::Number(number) => {
+                    self.write_line(indent, &format!("  {key}: {number}"))?;
+                    continue;
+                }
+                YamlValue::Text(text) => text,
+            };
+            if value.contains('\n') || value.starts_with(' ') {
+                // Use a literal block scalar, so that multi-line output stays readable. An explicit
+                // indentation indicator is needed if the first line starts with a space.
+                let value = value.trim_end_matches('\n');
+                let header = if value.starts_with(' ') { "|2-" } else { "|-" };
+                self.write_line(indent, &format!("  {key}: {header}"))?;
+                for line in value.split('\n') {
+                    // Blank lines keep the subtest indentation, so that they stay inside the
+                    // subtest for parsers of nested TAP.
+                    if line.is_empty() {
+                        self.write_line(indent, "")?;
+                    } else {
+                        self.write_line(indent, &format!("    {line}"))?;
+                    }
+                }
+            } else {
+                self.write_line(indent, &format!("  {key}: {}", YamlString(value)))?;
+            }
+        }
+        self.write_line(indent, "  ...")
+    }
+
+    fn write_doctest_subtests(&mut self) -> io::Result<()> {
+        for (file, results) in std::mem::take(&mut self.doctests) {
+            self.write_line("", &format!("# Subtest: {file}"))?;
+            for (number, tap_result) in results.iter().enumerate() {
+                self.write_test_point(SUBTEST_INDENT, number + 1, tap_result)?;
+            }
+            self.write_line(SUBTEST_INDENT, &format!("1..{}", results.len()))?;
+
+            let failed = results.iter().any(|r| {
+                matches!(
+                    r.result,
+                    TestResult::TrFailed
+                        | TestResult::TrFailedMsg(_)
+                        | TestResult::TrTimedFail
+                        | TestResult::TrTimedOut
+                )
+            });
+            self.test_number += 1;
+            let status = if failed { "not ok" } else { "ok" };
+            let name = EscapedDescription(&file);
+            self.write_line("", &format!("{status} {} - {name}", self.test_number))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Write> OutputFormatter for TapFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        Err(io::const_error!(io::ErrorKind::NotFound, "not yet implemented!"))
+    }
+
+    fn write_test_discovered(
+        &mut self,
+        _desc: &TestDesc,
+        _test_type: &str,
+        _shard: Option<usize>,
+    ) -> io::Result<()> {
+        Err(io::const_error!(io::ErrorKind::NotFound, "not yet implemented!"))
+    }
+
+    fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        Err(io::const_error!(io::ErrorKind::NotFound, "not yet implemented!"))
+    }
+
+    fn write_run_start(&mut self, _test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.write_line("", "TAP version 14")?;
+        if let Some(shuffle_seed) = shuffle_seed {
+            self.write_line("", &format!("# shuffle seed: {shuffle_seed}"))?;
+        }
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // We do not output anything on test start.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.write_line(
+            "",
+            &format!(
+                "# test {} has been running for over {} seconds",
+                desc.name,
+                time::TEST_WARN_TIMEOUT_S
+            ),
+        )
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        _: Option<&TestResourceUsage>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let display_stdout = state.options.display_output || *result != TestResult::TrOk;
+        let stdout = if display_stdout && !stdout.is_empty() {
+            Some(String::from_utf8_lossy(stdout).into_owned())
+        } else {
+            None
+        };
+        let tap_result = TapResult {
+            desc: desc.clone(),
+            result: result.clone(),
+            exec_time: exec_time.cloned(),
+            stdout,
+        };
+
+        if desc.test_type == TestType::DocTest {
+            // Doctests are named `path/to/file.rs - item (line N)`, group them by file.
+            let name = desc.name.as_slice();
+            let file = name.split_once(" - ").map_or(name, |(file, _)| file).trim();
+            match self.doctests.iter_mut().find(|(f, _)| f == file) {
+                Some((_, results)) => results.push(tap_result),
+                None => self.doctests.push((file.to_owned(), vec![tap_result])),
+            }
+            return Ok(());
+        }
+
+        self.test_number += 1;
+        self.write_test_point("", self.test_number, &tap_result)
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        self.write_doctest_subtests()?;
+        self.write_line("", &format!("1..{}", self.test_number))?;
+
+        let mut summary = format!(
+            "# passed: {}, failed: {}, ignored: {}, measured: {}, filtered out: {}",
+            state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+        );
+        if state.flaky > 0 {
+            summary.push_str(&format!(", flaky: {}", state.flaky));
+        }
+        if let Some(ref exec_time) = state.exec_time {
+            summary.push_str(&format!(", finished in {exec_time}"));
+        }
+        self.write_line("", &summary)?;
+
+        Ok(state.failed == 0)
+    }
+
+    fn write_merged_doctests_times(
+        &mut self,
+        total_time: f64,
+        compilation_time: f64,
+    ) -> io::Result<()> {
+        self.write_line(
+            "",
+            &format!(
+                "# all doctests ran in {total_time:.2}s; merged doctests compilation took {compilation_time:.2}s"
+            ),
+        )
+    }
+}
+
+/// Escapes `#` and `\` in a test point description, so that they are not confused with a
+/// directive.
+struct EscapedDescription<'a>(&'a str);
+
+impl std::fmt::Display for EscapedDescription<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '#' => f.write_str("\\#")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' | '\r' => f.write_str(" ")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a single-line value as a double-quoted YAML scalar.
+struct YamlString<'a>(&'a str);
+
+impl std::fmt::Display for YamlString<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("\"")?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
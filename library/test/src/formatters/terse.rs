@@ -1,9 +1,10 @@
 use std::io;
 use std::io::prelude::Write;
 
-use super::OutputFormatter;
+use super::{OutputFormatter, write_bench_comparison};
 use crate::bench::fmt_bench_samples;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::resources::TestResourceUsage;
 use crate::test_result::TestResult;
 use crate::types::{NamePadding, TestDesc};
 use crate::{term, time};
@@ -62,6 +63,10 @@ pub(crate) fn write_failed(&mut self, name: &str) -> io::Result<()> {
         self.write_plain("\n")
     }
 
+    pub(crate) fn write_flaky(&mut self) -> io::Result<()> {
+        self.write_short_result("f", term::color::YELLOW)
+    }
+
     pub(crate) fn write_ignored(&mut self) -> io::Result<()> {
         self.write_short_result("i", term::color::YELLOW)
     }
@@ -172,6 +177,32 @@ pub(crate) fn write_failures(&mut self, state: &ConsoleTestState) -> io::Result<
         Ok(())
     }
 
+    pub(crate) fn write_flakes(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_plain("\nflaky tests:\n")?;
+        let mut flakes = Vec::new();
+        let mut flaky_out = String::new();
+        for (f, stdout) in &state.flakes {
+            flakes.push(f.name.to_string());
+            if !stdout.is_empty() {
+                flaky_out.push_str(&format!("---- {} stdout ----\n", f.name));
+                let output = String::from_utf8_lossy(stdout);
+                flaky_out.push_str(&output);
+                flaky_out.push('\n');
+            }
+        }
+        if !flaky_out.is_empty() {
+            self.write_plain("\n")?;
+            self.write_plain(flaky_out)?;
+        }
+
+        self.write_plain("\nflaky tests:\n")?;
+        flakes.sort();
+        for name in &flakes {
+            self.write_plain(format!("    {name}\n"))?;
+        }
+        Ok(())
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         if let Some(test_mode) = desc.test_mode() {
@@ -189,7 +220,12 @@ fn write_discovery_start(&mut self) -> io::Result<()> {
         Ok(())
     }
 
-    fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
+    fn write_test_discovered(
+        &mut self,
+        desc: &TestDesc,
+        test_type: &str,
+        _shard: Option<usize>,
+    ) -> io::Result<()> {
         self.write_plain(format!("{}: {test_type}\n", desc.name))
     }
 
@@ -225,21 +261,31 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         _: Option<&time::TestExecTime>,
+        _: Option<&TestResourceUsage>,
         _: &[u8],
-        _: &ConsoleTestState,
+        state: &ConsoleTestState,
     ) -> io::Result<()> {
         match *result {
             TestResult::TrOk => self.write_ok(),
-            TestResult::TrFailed | TestResult::TrFailedMsg(_) | TestResult::TrTimedFail => {
-                self.write_failed(desc.name.as_slice())
-            }
+            TestResult::TrFailed
+            | TestResult::TrFailedMsg(_)
+            | TestResult::TrTimedFail
+            | TestResult::TrTimedOut => self.write_failed(desc.name.as_slice()),
+            TestResult::TrFlaky(_) => self.write_flaky(),
             TestResult::TrIgnored => self.write_ignored(),
             TestResult::TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
                 }
                 self.write_bench()?;
-                self.write_plain(format!(": {}\n", fmt_bench_samples(bs)))
+                self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
+                if let Some(comparison) = state.compare_bench(desc, bs) {
+                    write_bench_comparison(&comparison, |text, color| match color {
+                        Some(color) => self.write_pretty(text, color),
+                        None => self.write_plain(text),
+                    })?;
+                }
+                self.write_plain("\n")
             }
         }
     }
@@ -260,6 +306,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if !success {
             self.write_failures(state)?;
         }
+        if !state.flakes.is_empty() {
+            self.write_flakes(state)?;
+        }
 
         self.write_plain("\ntest result: ")?;
 
@@ -270,8 +319,15 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
+        let flaky =
+            if state.flaky > 0 { format!("; {} flaky", state.flaky) } else { String::new() };
+        let regressed = if state.regressed > 0 {
+            format!("; {} regressed", state.regressed)
+        } else {
+            String::new()
+        };
         let s = format!(
-            ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            ". {} passed; {} failed{flaky}; {} ignored; {} measured{regressed}; {} filtered out",
             state.passed, state.failed, state.ignored, state.measured, state.filtered_out
         );
 
@@ -0,0 +1,31 @@
+//! Selection of the tests affected by a set of changed source files.
+
+/// Returns whether a test defined in `source_file` may be affected by a change to
+/// `changed_file`: either both are the same file, or the test is defined in a submodule of the
+/// module in `changed_file`.
+///
+/// Paths are compared component-wise, so they need to be relative to the same directory, like
+/// the paths passed to the compiler.
+pub(crate) fn is_affected_by(source_file: &str, changed_file: &str) -> bool {
+    let source: Vec<&str> = components(source_file).collect();
+    let changed: Vec<&str> = components(changed_file).collect();
+    if source == changed {
+        return true;
+    }
+
+    // The directory holding the submodules of the changed module: `foo/` for `foo.rs` and
+    // `foo/mod.rs`, and the crate root's directory for `lib.rs` and `main.rs`.
+    let module_dir = match changed.split_last() {
+        Some((&"mod.rs" | &"lib.rs" | &"main.rs", dir)) => dir.to_vec(),
+        Some((file, dir)) => match file.strip_suffix(".rs") {
+            Some(module) => dir.iter().copied().chain([module]).collect(),
+            None => return false,
+        },
+        None => return false,
+    };
+    source.len() > module_dir.len() && source.starts_with(&module_dir)
+}
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\']).filter(|component| !component.is_empty() && *component != ".")
+}
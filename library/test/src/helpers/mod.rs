@@ -1,6 +1,8 @@
 //! Module with common helpers not directly related to tests
 //! but used in `libtest`.
 
+pub(crate) mod changed_files;
 pub(crate) mod concurrency;
 pub(crate) mod metrics;
+pub(crate) mod shard;
 pub(crate) mod shuffle;
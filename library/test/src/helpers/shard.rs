@@ -0,0 +1,17 @@
+use crate::types::TestName;
+
+/// Returns the shard, out of `shard_count`, that a test belongs to.
+///
+/// Only the name of the test is hashed, so that a test stays in the same shard regardless of
+/// the other tests in the binary, the filters and the order tests are run in. The name is hashed
+/// with 64-bit FNV-1a rather than `DefaultHasher`, whose algorithm may change between Rust
+/// releases, so that tests don't move between shards when the toolchain is updated.
+pub(crate) fn test_shard(name: &TestName, shard_count: usize) -> usize {
+    (fnv1a(name.as_slice().as_bytes()) % shard_count as u64) as usize
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
@@ -45,6 +45,7 @@ pub mod test {
     pub use crate::cli::{TestOpts, parse_opts};
     pub use crate::helpers::metrics::{Metric, MetricMap};
     pub use crate::options::{Options, RunIgnored, RunStrategy, ShouldPanic};
+    pub use crate::resources::{TestResourceOptions, TestResourceUsage};
     pub use crate::test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk};
     pub use crate::time::{TestExecTime, TestTimeOptions};
     pub use crate::types::{
@@ -55,15 +56,16 @@ pub mod test {
 }
 
 use std::collections::VecDeque;
-use std::io::prelude::Write;
-use std::mem::ManuallyDrop;
+use std::io::prelude::{Read, Write};
+use std::mem::{self, ManuallyDrop};
 use std::panic::{self, AssertUnwindSafe, PanicHookInfo, catch_unwind};
 use std::process::{self, Command, Termination};
-use std::sync::mpsc::{Sender, channel};
+use std::sync::mpsc::{RecvTimeoutError, Sender, channel};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use std::{env, io, thread};
 
+mod baseline;
 pub mod bench;
 mod cli;
 mod console;
@@ -71,6 +73,7 @@ pub mod test {
 mod formatters;
 mod helpers;
 mod options;
+mod resources;
 pub mod stats;
 mod term;
 mod test_result;
@@ -83,9 +86,12 @@ pub mod test {
 use core::any::Any;
 
 use event::{CompletedTest, TestEvent};
+use helpers::changed_files::is_affected_by;
 use helpers::concurrency::get_concurrency;
+use helpers::shard::test_shard;
 use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::RunStrategy;
+use resources::{TestResourceOptions, TestResourceUsage};
 use test_result::*;
 use time::TestExecTime;
 
@@ -306,7 +312,6 @@ pub fn run_tests<F>(
 {
     use std::collections::HashMap;
     use std::hash::{BuildHasherDefault, DefaultHasher};
-    use std::sync::mpsc::RecvTimeoutError;
 
     struct RunningTest {
         join_handle: Option<thread::JoinHandle<()>>,
@@ -334,6 +339,14 @@ struct TimeoutEntry {
         timeout: Instant,
     }
 
+    struct RetryEntry {
+        testfn: TestFn,
+        attempts: usize,
+        output: Vec<u8>,
+    }
+
+    type RetryMap = HashMap<TestId, RetryEntry, BuildHasherDefault<DefaultHasher>>;
+
     let tests_len = tests.len();
 
     let mut filtered = FilteredTests { tests: Vec::new(), benches: Vec::new(), next_id: 0 };
@@ -383,14 +396,31 @@ struct TimeoutEntry {
     } else {
         RunStrategy::InProcess
     };
+    if let RunStrategy::InProcess = run_strategy
+        && opts.resource_options.is_some()
+    {
+        let _ = writeln!(
+            io::stderr(),
+            "warning: `--report-resources` and `--max-rss` only apply to tests that run in a \
+             subprocess, which requires `-C panic=abort`"
+        );
+    }
 
     let mut running_tests: TestMap = HashMap::default();
     let mut timeout_queue: VecDeque<TimeoutEntry> = VecDeque::new();
 
+    // Tests running in a subprocess enforce `--test-timeout` themselves by killing the child,
+    // the ones running in-process have to be abandoned by the runner.
+    let test_timeout = match run_strategy {
+        RunStrategy::InProcess => opts.test_timeout,
+        RunStrategy::SpawnPrimary => None,
+    };
+    let mut deadline_queue: VecDeque<TimeoutEntry> = VecDeque::new();
+
     fn get_timed_out_tests(
         running_tests: &TestMap,
         timeout_queue: &mut VecDeque<TimeoutEntry>,
-    ) -> Vec<TestDesc> {
+    ) -> Vec<TimeoutEntry> {
         let now = Instant::now();
         let mut timed_out = Vec::new();
         while let Some(timeout_entry) = timeout_queue.front() {
@@ -399,12 +429,94 @@ fn get_timed_out_tests(
             }
             let timeout_entry = timeout_queue.pop_front().unwrap();
             if running_tests.contains_key(&timeout_entry.id) {
-                timed_out.push(timeout_entry.desc);
+                timed_out.push(timeout_entry);
             }
         }
         timed_out
     }
 
+    fn timed_out_test(opts: &TestOpts, id: TestId, desc: TestDesc) -> CompletedTest {
+        let exec_time = opts.time_options.and(opts.test_timeout).map(TestExecTime);
+        CompletedTest::new(id, desc, TrTimedOut, exec_time, Vec::new())
+    }
+
+    let mut retry_map: RetryMap = HashMap::default();
+
+    // Returns whether the test is being retried, and keeps a copy of it for retries otherwise.
+    fn start_attempt(
+        opts: &TestOpts,
+        retry_map: &mut RetryMap,
+        id: TestId,
+        test: &TestDescAndFn,
+    ) -> bool {
+        if retry_map.contains_key(&id) {
+            return true;
+        }
+        if opts.retries > 0
+            && let Some(testfn) = test.testfn.copy_static()
+        {
+            retry_map.insert(id, RetryEntry { testfn, attempts: 1, output: Vec::new() });
+        }
+        false
+    }
+
+    // Returns a fresh copy of the test if it failed and should be run again. Otherwise, once a
+    // test has been retried, the output of all attempts is collected and tests which eventually
+    // passed are marked as flaky.
+    fn retry_test(
+        opts: &TestOpts,
+        retry_map: &mut RetryMap,
+        completed_test: &mut CompletedTest,
+    ) -> Option<TestDescAndFn> {
+        let Some(entry) = retry_map.get_mut(&completed_test.id) else {
+            // Dynamic tests, like doctests, can only be run once.
+            if opts.retries > 0
+                && matches!(completed_test.result, TrFailed | TrFailedMsg(_) | TrTimedFail)
+            {
+                let output = &mut completed_test.stdout;
+                if output.last().is_some_and(|&b| b != b'\n') {
+                    output.push(b'\n');
+                }
+                writeln!(output, "note: only static tests can be retried, this test was not")
+                    .unwrap();
+            }
+            return None;
+        };
+        let name = &completed_test.desc.name;
+        match completed_test.result {
+            // A test that timed out may still be running, so it is not retried.
+            TrFailed | TrFailedMsg(_) | TrTimedFail if entry.attempts <= opts.retries => {
+                write_attempt_delimiter(&mut entry.output, name, entry.attempts);
+                entry.output.append(&mut completed_test.stdout);
+                if let TrFailedMsg(ref msg) = completed_test.result {
+                    write!(entry.output, "note: {msg}").unwrap();
+                }
+                entry.attempts += 1;
+                let testfn = entry.testfn.copy_static().unwrap();
+                return Some(TestDescAndFn { desc: completed_test.desc.clone(), testfn });
+            }
+            _ => {}
+        }
+
+        let mut entry = retry_map.remove(&completed_test.id).unwrap();
+        if entry.attempts > 1 {
+            write_attempt_delimiter(&mut entry.output, name, entry.attempts);
+            entry.output.append(&mut completed_test.stdout);
+            completed_test.stdout = entry.output;
+            if completed_test.result == TrOk {
+                completed_test.result = TrFlaky(entry.attempts);
+            }
+        }
+        None
+    }
+
+    fn write_attempt_delimiter(output: &mut Vec<u8>, name: &TestName, attempt: usize) {
+        if output.last().is_some_and(|&b| b != b'\n') {
+            output.push(b'\n');
+        }
+        writeln!(output, "---- {name} attempt {attempt} ----").unwrap();
+    }
+
     fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
         timeout_queue.front().map(|&TimeoutEntry { timeout: next_timeout, .. }| {
             let now = Instant::now();
@@ -415,19 +527,47 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
     if concurrency == 1 {
         while !remaining.is_empty() {
             let (id, test) = remaining.pop_front().unwrap();
-            let event = TestEvent::TeWait(test.desc.clone());
-            notify_about_test_event(event)?;
+            let desc = test.desc.clone();
+            if !start_attempt(opts, &mut retry_map, id, &test) {
+                let event = TestEvent::TeWait(desc.clone());
+                notify_about_test_event(event)?;
+            }
+            let deadline = test_timeout.map(|timeout| Instant::now() + timeout);
             let join_handle = run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
-            // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
-            RunningTest { join_handle }.join(&mut completed_test);
+            // Wait for the test to complete, skipping late results of tests that timed out.
+            let completed_test = loop {
+                let res = match deadline {
+                    Some(deadline) => {
+                        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match res {
+                    Ok(completed_test) if completed_test.id != id => {}
+                    Err(RecvTimeoutError::Timeout) => break None,
+                    res => break Some(res.unwrap()),
+                }
+            };
+            let mut completed_test = match completed_test {
+                Some(mut completed_test) => {
+                    RunningTest { join_handle }.join(&mut completed_test);
+                    completed_test
+                }
+                // The test thread can't be stopped, so it is left running in the background.
+                None => timed_out_test(opts, id, desc),
+            };
+
+            if let Some(test) = retry_test(opts, &mut retry_map, &mut completed_test) {
+                remaining.push_front((id, test));
+                continue;
+            }
 
             let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+                TrIgnored | TrOk | TrFlaky(_) | TrBench(_) => false,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut => opts.fail_fast,
             };
 
-            let event = TestEvent::TeResult(completed_test);
+            let event = TestEvent::TeResult(Box::new(completed_test));
             notify_about_test_event(event)?;
 
             if fail_fast {
@@ -441,8 +581,14 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 let timeout = time::get_default_test_timeout();
                 let desc = test.desc.clone();
 
-                let event = TestEvent::TeWait(desc.clone());
-                notify_about_test_event(event)?; //here no pad
+                if !start_attempt(opts, &mut retry_map, id, &test) {
+                    let event = TestEvent::TeWait(desc.clone());
+                    notify_about_test_event(event)?; //here no pad
+                }
+                if let Some(test_timeout) = test_timeout {
+                    let timeout = Instant::now() + test_timeout;
+                    deadline_queue.push_back(TimeoutEntry { id, desc: desc.clone(), timeout });
+                }
                 let join_handle =
                     run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
                 running_tests.insert(id, RunningTest { join_handle });
@@ -450,47 +596,66 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 pending += 1;
             }
 
-            let mut res;
-            loop {
-                if let Some(timeout) = calc_timeout(&timeout_queue) {
-                    res = rx.recv_timeout(timeout);
-                    for test in get_timed_out_tests(&running_tests, &mut timeout_queue) {
-                        let event = TestEvent::TeTimeout(test);
-                        notify_about_test_event(event)?;
+            let mut completed_tests = Vec::new();
+            while completed_tests.is_empty() {
+                let timeout = calc_timeout(&timeout_queue)
+                    .into_iter()
+                    .chain(calc_timeout(&deadline_queue))
+                    .min();
+                let res = match timeout {
+                    Some(timeout) => rx.recv_timeout(timeout),
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match res {
+                    Err(RecvTimeoutError::Timeout) => {
+                        // Result is not yet ready, continue waiting.
                     }
-
-                    match res {
-                        Err(RecvTimeoutError::Timeout) => {
-                            // Result is not yet ready, continue waiting.
-                        }
-                        _ => {
-                            // We've got a result, stop the loop.
-                            break;
+                    res => {
+                        let mut completed_test = res.unwrap();
+                        // Tests that already timed out are no longer tracked, drop their results.
+                        if let Some(running_test) = running_tests.remove(&completed_test.id) {
+                            running_test.join(&mut completed_test);
+                            completed_tests.push(completed_test);
                         }
                     }
-                } else {
-                    res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
-                    break;
+                }
+
+                for test in get_timed_out_tests(&running_tests, &mut timeout_queue) {
+                    let event = TestEvent::TeTimeout(test.desc);
+                    notify_about_test_event(event)?;
+                }
+                for test in get_timed_out_tests(&running_tests, &mut deadline_queue) {
+                    // The test thread can't be stopped, so it is left running in the background.
+                    running_tests.remove(&test.id);
+                    completed_tests.push(timed_out_test(opts, test.id, test.desc));
                 }
             }
 
-            let mut completed_test = res.unwrap();
-            let running_test = running_tests.remove(&completed_test.id).unwrap();
-            running_test.join(&mut completed_test);
+            for mut completed_test in completed_tests {
+                if let Some(test) = retry_test(opts, &mut retry_map, &mut completed_test) {
+                    // Forget about the previous attempt before running the test again.
+                    let id = completed_test.id;
+                    timeout_queue.retain(|entry| entry.id != id);
+                    deadline_queue.retain(|entry| entry.id != id);
+                    remaining.push_front((id, test));
+                    pending -= 1;
+                    continue;
+                }
 
-            let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
-            };
+                let fail_fast = match completed_test.result {
+                    TrIgnored | TrOk | TrFlaky(_) | TrBench(_) => false,
+                    TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut => opts.fail_fast,
+                };
 
-            let event = TestEvent::TeResult(completed_test);
-            notify_about_test_event(event)?;
-            pending -= 1;
+                let event = TestEvent::TeResult(Box::new(completed_test));
+                notify_about_test_event(event)?;
+                pending -= 1;
 
-            if fail_fast {
-                // Prevent remaining test threads from panicking
-                std::mem::forget(rx);
-                return Ok(());
+                if fail_fast {
+                    // Prevent remaining test threads from panicking
+                    std::mem::forget(rx);
+                    return Ok(());
+                }
             }
         }
     }
@@ -501,11 +666,16 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
             let event = TestEvent::TeWait(b.desc.clone());
             notify_about_test_event(event)?;
             let join_handle = run_test(opts, false, id, b, run_strategy, tx.clone());
-            // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
+            // Wait for the test to complete, skipping late results of tests that timed out.
+            let mut completed_test = loop {
+                let completed_test = rx.recv().unwrap();
+                if completed_test.id == id {
+                    break completed_test;
+                }
+            };
             RunningTest { join_handle }.join(&mut completed_test);
 
-            let event = TestEvent::TeResult(completed_test);
+            let event = TestEvent::TeResult(Box::new(completed_test));
             notify_about_test_event(event)?;
         }
     }
@@ -550,6 +720,18 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         RunIgnored::No => {}
     }
 
+    // Only keep the tests that may be affected by the changed files
+    if let Some(ref changed_files) = opts.changed_files {
+        filtered.retain(|test| {
+            changed_files.iter().any(|file| is_affected_by(test.desc.source_file, file))
+        });
+    }
+
+    // Only keep the tests of the requested shard
+    if let (Some(shard_index), Some(shard_count)) = (opts.shard_index, opts.shard_count) {
+        filtered.retain(|test| test_shard(&test.desc.name, shard_count) == shard_index);
+    }
+
     filtered
 }
 
@@ -601,7 +783,13 @@ pub fn run_test(
             let name = desc.name.clone();
             let nocapture = opts.nocapture;
             let time_options = opts.time_options;
-            let bench_benchmarks = opts.bench_benchmarks;
+            let subprocess_opts = SubprocessOpts {
+                nocapture,
+                time_opts: time_options,
+                test_timeout: opts.test_timeout,
+                resource_opts: opts.resource_options,
+                bench_benchmarks: opts.bench_benchmarks,
+            };
 
             let runtest = move || match strategy {
                 RunStrategy::InProcess => run_test_in_process(
@@ -613,15 +801,9 @@ pub fn run_test(
                     monitor_ch,
                     time_options,
                 ),
-                RunStrategy::SpawnPrimary => spawn_test_subprocess(
-                    id,
-                    desc,
-                    nocapture,
-                    time_options.is_some(),
-                    monitor_ch,
-                    time_options,
-                    bench_benchmarks,
-                ),
+                RunStrategy::SpawnPrimary => {
+                    spawn_test_subprocess(id, desc, monitor_ch, subprocess_opts)
+                }
             };
 
             // If the platform is single-threaded we're just going to run
@@ -714,16 +896,26 @@ fn fold_err<T, E>(
     }
 }
 
+/// The options of a test that is run in a subprocess.
+#[derive(Copy, Clone)]
+struct SubprocessOpts {
+    nocapture: bool,
+    time_opts: Option<time::TestTimeOptions>,
+    test_timeout: Option<Duration>,
+    resource_opts: Option<TestResourceOptions>,
+    bench_benchmarks: bool,
+}
+
 fn spawn_test_subprocess(
     id: TestId,
     desc: TestDesc,
-    nocapture: bool,
-    report_time: bool,
     monitor_ch: Sender<CompletedTest>,
-    time_opts: Option<time::TestTimeOptions>,
-    bench_benchmarks: bool,
+    opts: SubprocessOpts,
 ) {
-    let (result, test_output, exec_time) = (|| {
+    let SubprocessOpts { nocapture, time_opts, test_timeout, resource_opts, bench_benchmarks } =
+        opts;
+    let report_time = time_opts.is_some();
+    let (result, test_output, exec_time, resources) = (|| {
         let args = env::args().collect::<Vec<_>>();
         let current_exe = &args[0];
 
@@ -735,16 +927,25 @@ fn spawn_test_subprocess(
         if nocapture {
             command.stdout(process::Stdio::inherit());
             command.stderr(process::Stdio::inherit());
+        } else {
+            command.stdout(process::Stdio::piped());
+            command.stderr(process::Stdio::piped());
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let output = if test_timeout.is_some() || resource_opts.is_some() {
+            test_process_output(&mut command, test_timeout)
+        } else {
+            command.output().map(|output| (output, false, None))
+        };
+        let (output, timed_out, resources) = match output {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
-                return (TrFailed, err.into_bytes(), None);
+                return (TrFailed, err.into_bytes(), None, None);
             }
         };
+        let resources = resources.filter(|_| resource_opts.is_some());
         let exec_time = start.map(|start| {
             let duration = start.elapsed();
             TestExecTime(duration)
@@ -755,15 +956,100 @@ fn spawn_test_subprocess(
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
-        let result =
-            get_result_from_exit_code(&desc, status, time_opts.as_ref(), exec_time.as_ref());
-        (result, test_output, exec_time)
+        let mut result = if timed_out {
+            TrTimedOut
+        } else {
+            get_result_from_exit_code(&desc, status, time_opts.as_ref(), exec_time.as_ref())
+        };
+        if let Some(max_rss) = resource_opts.and_then(|opts| opts.max_rss)
+            && let Some(ref resources) = resources
+            && resources.max_rss > max_rss
+            && result == TrOk
+        {
+            const MIB: u64 = 1024 * 1024;
+            result = TrFailedMsg(format!(
+                "peak memory usage of {} MiB exceeded the `--max-rss` limit of {} MiB",
+                resources.max_rss.div_ceil(MIB),
+                max_rss / MIB
+            ));
+        }
+        (result, test_output, exec_time, resources)
     })();
 
-    let message = CompletedTest::new(id, desc, result, exec_time, test_output);
+    let mut message = CompletedTest::new(id, desc, result, exec_time, test_output);
+    message.resources = resources;
     monitor_ch.send(message).unwrap();
 }
 
+/// Like `Command::output`, but kills the child process if it is still running after `timeout`.
+/// Also returns whether the child had to be killed, and the resources it used where supported.
+fn test_process_output(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<(process::Output, bool, Option<TestResourceUsage>)> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut child = command.spawn()?;
+
+    // Drain the pipes concurrently, so that the child can't block on a full pipe buffer. The
+    // output is collected into a shared buffer, so that it can be taken without waiting for the
+    // end of the pipe, which never comes if a killed child left processes behind that inherited
+    // it.
+    struct Drain {
+        buf: Arc<Mutex<Vec<u8>>>,
+        handle: thread::JoinHandle<()>,
+    }
+    fn drain(pipe: Option<impl Read + Send + 'static>) -> Option<Drain> {
+        pipe.map(|mut pipe| {
+            let buf = Arc::new(Mutex::new(Vec::new()));
+            let thread_buf = buf.clone();
+            let handle = thread::spawn(move || {
+                let mut chunk = [0; 8192];
+                while let Ok(n @ 1..) = pipe.read(&mut chunk) {
+                    thread_buf.lock().unwrap().extend_from_slice(&chunk[..n]);
+                }
+            });
+            Drain { buf, handle }
+        })
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let mut timed_out = false;
+    let (status, resources) = loop {
+        let Some(deadline) = deadline else {
+            break resources::wait_child(&mut child, true)?.unwrap();
+        };
+        if let Some(exited) = resources::wait_child(&mut child, false)? {
+            break exited;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            timed_out = true;
+            // The child may have exited in the meantime, in which case waiting still succeeds.
+            let _ = child.kill();
+            break resources::wait_child(&mut child, true)?.unwrap();
+        }
+        thread::sleep((deadline - now).min(Duration::from_millis(10)));
+    };
+
+    // After a kill, give the pipes a moment to be drained, then detach the threads that are
+    // still blocked on them.
+    let drain_deadline = Instant::now() + Duration::from_millis(500);
+    let collect = |drain: Option<Drain>| {
+        let Some(Drain { buf, handle }) = drain else { return Vec::new() };
+        if timed_out {
+            while !handle.is_finished() && Instant::now() < drain_deadline {
+                thread::sleep(Duration::from_millis(10));
+            }
+        } else {
+            let _ = handle.join();
+        }
+        mem::take(&mut *buf.lock().unwrap_or_else(|e| e.into_inner()))
+    };
+    let output = process::Output { status, stdout: collect(stdout), stderr: collect(stderr) };
+    Ok((output, timed_out, resources))
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -> ! {
     let builtin_panic_hook = panic::take_hook();
     let record_result = Arc::new(move |panic_info: Option<&'_ PanicHookInfo<'_>>| {
@@ -0,0 +1,92 @@
+//! Measurement of the resources used by tests that run in a subprocess.
+
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::time::Duration;
+
+/// Options for resource usage reporting, from `--report-resources` and `--max-rss`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestResourceOptions {
+    /// Tests whose peak resident set size exceeds this many bytes fail.
+    pub max_rss: Option<u64>,
+}
+
+/// The resources used by a test that ran in a subprocess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestResourceUsage {
+    /// CPU time spent in user mode.
+    pub user_time: Duration,
+    /// CPU time spent in the kernel on behalf of the test.
+    pub system_time: Duration,
+    /// Peak resident set size, in bytes.
+    pub max_rss: u64,
+}
+
+/// Waits for the child to exit, or only checks whether it exited if `block` is false. Also
+/// returns its resource usage where supported.
+///
+/// This reaps the child, so `Child::wait` must not be used afterwards.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+))]
+pub(crate) fn wait_child(
+    child: &mut Child,
+    block: bool,
+) -> io::Result<Option<(ExitStatus, Option<TestResourceUsage>)>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child.id() as libc::pid_t;
+    let flags = if block { 0 } else { libc::WNOHANG };
+    let mut status = 0;
+    // SAFETY: `rusage` is a plain C struct, for which all zeroes is a valid value.
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: the pointers are valid for the duration of the call.
+        match unsafe { libc::wait4(pid, &mut status, flags, &mut rusage) } {
+            0 => return Ok(None),
+            -1 => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+            _ => break,
+        }
+    }
+
+    let duration = |time: libc::timeval| {
+        Duration::new(time.tv_sec as u64, 0) + Duration::from_micros(time.tv_usec as u64)
+    };
+    // `ru_maxrss` is in bytes on Apple platforms, and in kilobytes elsewhere.
+    let max_rss = rusage.ru_maxrss as u64;
+    let max_rss = if cfg!(target_vendor = "apple") { max_rss } else { max_rss * 1024 };
+    let usage = TestResourceUsage {
+        user_time: duration(rusage.ru_utime),
+        system_time: duration(rusage.ru_stime),
+        max_rss,
+    };
+    Ok(Some((ExitStatus::from_raw(status), Some(usage))))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+)))]
+pub(crate) fn wait_child(
+    child: &mut Child,
+    block: bool,
+) -> io::Result<Option<(ExitStatus, Option<TestResourceUsage>)>> {
+    let status = if block { Some(child.wait()?) } else { child.try_wait()? };
+    Ok(status.map(|status| (status, None)))
+}
@@ -35,6 +35,11 @@ pub enum TestResult {
     TrIgnored,
     TrBench(BenchSamples),
     TrTimedFail,
+    /// The test was still running when `--test-timeout` expired.
+    TrTimedOut,
+    /// The test failed, but passed when retried with `--retries`. Holds the
+    /// number of attempts it took.
+    TrFlaky(usize),
 }
 
 /// Creates a `TestResult` depending on the raw result of test execution
@@ -2,6 +2,7 @@
 use crate::{
     console::OutputLocation,
     formatters::PrettyFormatter,
+    resources::TestResourceOptions,
     test::{
         MetricMap,
         // FIXME (introduced by #65251)
@@ -29,9 +30,17 @@ fn new() -> TestOpts {
             format: OutputFormat::Pretty,
             shuffle: false,
             shuffle_seed: None,
+            shard_index: None,
+            shard_count: None,
             test_threads: None,
             skip: vec![],
+            changed_files: None,
             time_options: None,
+            resource_options: None,
+            test_timeout: None,
+            retries: 0,
+            save_baseline: None,
+            baseline: None,
             options: Options::new(),
             fail_fast: false,
         }
@@ -392,6 +401,166 @@ fn test_error_on_exceed() {
     assert_eq!(result, TestResult::TrOk);
 }
 
+fn test_timeout_template(test_threads: usize) {
+    fn hang() -> Result<(), String> {
+        thread::sleep(Duration::from_secs(600));
+        Ok(())
+    }
+    let tests = ["hang", "pass"]
+        .into_iter()
+        .map(|name| TestDescAndFn {
+            desc: TestDesc { name: StaticTestName(name), ..typed_test_desc(TestType::Unknown) },
+            testfn: DynTestFn(Box::new(move || if name == "hang" { hang() } else { Ok(()) })),
+        })
+        .collect();
+    let test_opts = TestOpts {
+        run_tests: true,
+        test_threads: Some(test_threads),
+        test_timeout: Some(Duration::from_millis(100)),
+        ..TestOpts::new()
+    };
+
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        if let TestEvent::TeResult(result) = event {
+            tx.send(result).unwrap();
+        }
+        Ok(())
+    };
+    run_tests(&test_opts, tests, notify).unwrap();
+
+    let mut results: Vec<_> =
+        rx.iter().map(|test| (test.desc.name.to_string(), test.result)).collect();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(results, [("hang".to_string(), TrTimedOut), ("pass".to_string(), TrOk)]);
+}
+
+#[test]
+fn test_timeout_serial() {
+    test_timeout_template(1);
+}
+
+#[test]
+fn test_timeout_concurrent() {
+    test_timeout_template(2);
+}
+
+// A spawned test that times out may leave processes behind that keep its output pipes open. The
+// timeout must not wait for them.
+#[test]
+#[cfg(unix)]
+fn test_timeout_spawned_child() {
+    let mut command = Command::new("sh");
+    command
+        .args(["-c", "echo started; sleep 30 & sleep 30"])
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped());
+    let start = Instant::now();
+    let (output, timed_out, _) =
+        test_process_output(&mut command, Some(Duration::from_millis(100))).unwrap();
+    assert!(timed_out);
+    assert!(start.elapsed() < Duration::from_secs(20));
+    assert_eq!(output.stdout, b"started\n");
+}
+
+#[test]
+fn test_retries_dynamic_test() {
+    let tests = vec![TestDescAndFn {
+        desc: TestDesc { name: StaticTestName("dynamic"), ..typed_test_desc(TestType::DocTest) },
+        testfn: DynTestFn(Box::new(|| Err("failed".to_string()))),
+    }];
+    let test_opts = TestOpts { run_tests: true, retries: 2, ..TestOpts::new() };
+
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        if let TestEvent::TeResult(result) = event {
+            tx.send(result).unwrap();
+        }
+        Ok(())
+    };
+    run_tests(&test_opts, tests, notify).unwrap();
+
+    // The test is run once, and its output explains why it wasn't retried.
+    let results: Vec<_> = rx.iter().collect();
+    assert_eq!(results.len(), 1);
+    assert!(matches!(results[0].result, TrFailed | TrFailedMsg(_)));
+    let output = String::from_utf8_lossy(&results[0].stdout);
+    assert!(output.contains("note: only static tests can be retried"), "{output}");
+}
+
+fn retries_template(test_threads: usize) {
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // The call counter is shared by all callers of this template.
+    static LOCK: Mutex<()> = Mutex::new(());
+    static FLAKY_CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn flaky() -> Result<(), String> {
+        if FLAKY_CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Err("first attempt".to_string());
+        }
+        Ok(())
+    }
+    fn broken() -> Result<(), String> {
+        Err("always".to_string())
+    }
+    let _guard = LOCK.lock().unwrap();
+    FLAKY_CALLS.store(0, Ordering::SeqCst);
+
+    let tests = vec![
+        TestDescAndFn {
+            desc: TestDesc { name: StaticTestName("flaky"), ..typed_test_desc(TestType::Unknown) },
+            testfn: StaticTestFn(flaky),
+        },
+        TestDescAndFn {
+            desc: TestDesc { name: StaticTestName("broken"), ..typed_test_desc(TestType::Unknown) },
+            testfn: StaticTestFn(broken),
+        },
+    ];
+    let test_opts = TestOpts {
+        run_tests: true,
+        test_threads: Some(test_threads),
+        retries: 2,
+        ..TestOpts::new()
+    };
+
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        match event {
+            TestEvent::TeWait(desc) => tx.send((desc.name.to_string(), None)).unwrap(),
+            TestEvent::TeResult(result) => {
+                tx.send((result.desc.name.to_string(), Some(result.result))).unwrap()
+            }
+            _ => {}
+        }
+        Ok(())
+    };
+    run_tests(&test_opts, tests, notify).unwrap();
+
+    let mut events: Vec<_> = rx.iter().collect();
+    events.sort_by(|a, b| a.0.cmp(&b.0));
+    // Retried tests are only announced once.
+    assert_eq!(
+        events,
+        [
+            ("broken".to_string(), None),
+            ("broken".to_string(), Some(TrFailed)),
+            ("flaky".to_string(), None),
+            ("flaky".to_string(), Some(TrFlaky(2))),
+        ]
+    );
+}
+
+#[test]
+fn test_retries_serial() {
+    retries_template(1);
+}
+
+#[test]
+fn test_retries_concurrent() {
+    retries_template(2);
+}
+
 fn typed_test_desc(test_type: TestType) -> TestDesc {
     TestDesc {
         name: StaticTestName("whatever"),
@@ -461,6 +630,40 @@ fn parse_show_output_flag() {
     assert!(opts.options.display_output);
 }
 
+#[test]
+fn parse_test_timeout_flag() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--test-timeout=30".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.test_timeout, Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn parse_resource_options_flags() {
+    let parse = |flag: &str| {
+        let args = vec!["progname".to_string(), "-Zunstable-options".to_string(), flag.to_string()];
+        parse_opts(&args).unwrap()
+    };
+
+    let opts = parse("--report-resources").unwrap();
+    assert_eq!(opts.resource_options, Some(TestResourceOptions { max_rss: None }));
+    let opts = parse("--max-rss=512").unwrap();
+    assert_eq!(opts.resource_options, Some(TestResourceOptions { max_rss: Some(512 << 20) }));
+    assert!(parse("--max-rss=0").is_err());
+    assert!(parse(&format!("--max-rss={}", u64::MAX)).is_err());
+}
+
+#[test]
+fn parse_retries_flag() {
+    let args =
+        vec!["progname".to_string(), "-Zunstable-options".to_string(), "--retries=3".to_string()];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.retries, 3);
+}
+
 #[test]
 fn parse_include_ignored_flag() {
     let args = vec!["progname".to_string(), "filter".to_string(), "--include-ignored".to_string()];
@@ -615,6 +818,62 @@ fn tests() -> Vec<TestDescAndFn> {
     assert_eq!(exact.len(), 2);
 }
 
+#[test]
+fn changed_files_filter() {
+    fn tests() -> Vec<TestDescAndFn> {
+        [
+            ("root", "src/lib.rs"),
+            ("parser", "src/parser.rs"),
+            ("parser::lexer", "src/parser/lexer.rs"),
+            ("parser::lexer::tokens", "src/parser/lexer/tokens.rs"),
+            ("codegen", "src/codegen/mod.rs"),
+            ("codegen::llvm", "src/codegen/llvm.rs"),
+            ("parser_tests", "tests/parser.rs"),
+        ]
+        .into_iter()
+        .map(|(name, source_file)| TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName(name),
+                source_file,
+                ..typed_test_desc(TestType::UnitTest)
+            },
+            testfn: DynTestFn(Box::new(move || Ok(()))),
+        })
+        .collect()
+    }
+    let filter = |changed_files: &[&str]| -> Vec<String> {
+        let changed_files = Some(changed_files.iter().map(|file| file.to_string()).collect());
+        let opts = TestOpts { changed_files, ..TestOpts::new() };
+        filter_tests(&opts, tests()).into_iter().map(|test| test.desc.name.to_string()).collect()
+    };
+
+    assert_eq!(filter(&[]), Vec::<String>::new());
+    assert_eq!(filter(&["src/parser/lexer/tokens.rs"]), ["parser::lexer::tokens"]);
+    assert_eq!(filter(&["./src/parser.rs"]), ["parser", "parser::lexer", "parser::lexer::tokens"]);
+    assert_eq!(filter(&["src/codegen/mod.rs", "README.md"]), ["codegen", "codegen::llvm"]);
+    assert_eq!(filter(&["src/lib.rs"]).len(), 6);
+    assert_eq!(filter(&["src\\parser\\lexer.rs"]), ["parser::lexer", "parser::lexer::tokens"]);
+}
+
+#[test]
+fn parse_changed_files_flag() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--changed-files=src/a.rs,src/b.rs".to_string(),
+        "--changed-files".to_string(),
+        "src/c.rs".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(
+        opts.changed_files,
+        Some(vec!["src/a.rs".into(), "src/b.rs".into(), "src/c.rs".into()])
+    );
+
+    let args = vec!["progname".to_string(), "--changed-files=src/a.rs".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
 fn sample_tests() -> Vec<TestDescAndFn> {
     let names = vec![
         "sha1::test".to_string(),
@@ -721,6 +980,62 @@ fn order_depends_on_more_than_seed() {
     assert!(left.iter().zip(right).any(|(a, b)| a.0 != b.0));
 }
 
+#[test]
+fn shard_tests() {
+    let names = |tests: Vec<TestDescAndFn>| -> Vec<String> {
+        tests.into_iter().map(|test| test.desc.name.to_string()).collect()
+    };
+    let shard = |shard_index| {
+        let opts =
+            TestOpts { shard_index: Some(shard_index), shard_count: Some(3), ..TestOpts::new() };
+        names(filter_tests(&opts, sample_tests()))
+    };
+
+    // Every test is run by exactly one shard.
+    let mut all: Vec<_> = (0..3).flat_map(shard).collect();
+    all.sort();
+    let mut expected = names(sample_tests());
+    expected.sort();
+    assert_eq!(all, expected);
+
+    // A shard keeps the same tests when other tests are filtered out.
+    let opts = TestOpts {
+        filters: vec!["test::".into()],
+        shard_index: Some(1),
+        shard_count: Some(3),
+        ..TestOpts::new()
+    };
+    let filtered = names(filter_tests(&opts, sample_tests()));
+    let unfiltered: Vec<_> = shard(1).into_iter().filter(|name| name.contains("test::")).collect();
+    assert_eq!(filtered, unfiltered);
+}
+
+#[test]
+fn shard_is_stable() {
+    // Tests must stay in their shard across Rust releases.
+    assert_eq!(test_shard(&StaticTestName("a"), 7), 5);
+    assert_eq!(test_shard(&DynTestName("tests::foo".to_string()), 7), 6);
+}
+
+#[test]
+fn parse_shard_flags() {
+    let parse = |args: &[&str]| {
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        args.insert(0, "progname".to_string());
+        args.insert(1, "-Zunstable-options".to_string());
+        parse_opts(&args).unwrap()
+    };
+
+    let opts = parse(&["--shard-index=1", "--shard-count=4"]).unwrap();
+    assert_eq!((opts.shard_index, opts.shard_count), (Some(1), Some(4)));
+    let opts = parse(&["--shard-count=4"]).unwrap();
+    assert_eq!((opts.shard_index, opts.shard_count), (None, Some(4)));
+
+    assert!(parse(&["--shard-index=1"]).is_err());
+    assert!(parse(&["--shard-index=4", "--shard-count=4"]).is_err());
+    assert!(parse(&["--shard-index=0", "--shard-count=0"]).is_err());
+}
+
 #[test]
 fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -855,9 +1170,11 @@ fn should_sort_failures_before_printing_them() {
         total: 0,
         passed: 0,
         failed: 0,
+        flaky: 0,
         ignored: 0,
         filtered_out: 0,
         measured: 0,
+        regressed: 0,
         exec_time: None,
         metrics: MetricMap::new(),
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
@@ -865,6 +1182,9 @@ fn should_sort_failures_before_printing_them() {
         not_failures: Vec::new(),
         ignores: Vec::new(),
         time_failures: Vec::new(),
+        flakes: Vec::new(),
+        baseline: None,
+        bench_results: baseline::Baseline::default(),
     };
 
     out.write_failures(&st).unwrap();
@@ -102,6 +102,16 @@ pub fn padding(&self) -> NamePadding {
         }
     }
 
+    /// Returns a copy of a static test function, so that it can be run again.
+    /// Dynamic test functions can only be run once.
+    pub(crate) fn copy_static(&self) -> Option<TestFn> {
+        match *self {
+            StaticTestFn(f) => Some(StaticTestFn(f)),
+            StaticBenchAsTestFn(f) => Some(StaticBenchAsTestFn(f)),
+            _ => None,
+        }
+    }
+
     pub(crate) fn into_runnable(self) -> Runnable {
         match self {
             StaticTestFn(f) => Runnable::Test(RunnableTest::Static(f)),
@@ -10,6 +10,7 @@
     DynEmitter, HumanEmitter, HumanReadableErrorType, OutputTheme, stderr_destination,
 };
 use rustc_errors::json::JsonEmitter;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_feature::UnstableFeatures;
 use rustc_hir::def::Res;
 use rustc_hir::def_id::{DefId, DefIdMap, DefIdSet, LocalDefId};
@@ -142,8 +143,8 @@ pub(crate) fn is_json_output(&self) -> bool {
 
 /// Creates a new `DiagCtxt` that can be used to emit warnings and errors.
 ///
-/// If the given `error_format` is `ErrorOutputType::Json` and no `SourceMap` is given, a new one
-/// will be created for the `DiagCtxt`.
+/// If the given `error_format` is `ErrorOutputType::Json` or `ErrorOutputType::Sarif` and no
+/// `SourceMap` is given, a new one will be created for the `DiagCtxt`.
 pub(crate) fn new_dcx(
     error_format: ErrorOutputType,
     source_map: Option<Arc<source_map::SourceMap>>,
@@ -187,6 +188,16 @@ pub(crate) fn new_dcx(
                 .terminal_url(TerminalUrl::No),
             )
         }
+        ErrorOutputType::Sarif => {
+            let source_map = source_map.unwrap_or_else(|| {
+                Arc::new(source_map::SourceMap::new(source_map::FilePathMapping::empty()))
+            });
+            Box::new(SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                Some(source_map),
+                translator,
+            ))
+        }
     };
 
     rustc_errors::DiagCtxt::new(emitter).with_flags(unstable_opts.dcx_flags(true))
@@ -322,6 +333,7 @@ pub(crate) fn create_config(
         registry: rustc_driver::diagnostics_registry(),
         ice_file: None,
         using_internal_features: &USING_INTERNAL_FEATURES,
+        early_sarif_results: Default::default(),
     }
 }
 
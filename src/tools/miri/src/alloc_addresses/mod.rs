@@ -185,6 +185,9 @@ fn addr_from_alloc_id_uncached(
         if let Some((reuse_addr, clock)) =
             reuse.take_addr(&mut *rng, info.size, info.align, memory_kind, this.active_thread())
         {
+            if let Some(nondet) = &this.machine.nondet {
+                nondet.check("reuse", reuse_addr);
+            }
             if let Some(clock) = clock {
                 this.acquire_clock(&clock)?;
             }
@@ -248,6 +251,19 @@ fn alloc_id_from_addr(&self, addr: u64, size: i64) -> Option<AllocId> {
         }
     }
 
+    /// Returns the live allocation containing `addr` together with its base address, whether or
+    /// not it has been exposed. This is meant for inspecting the machine from the outside (e.g.
+    /// from a debugger), not for the interpreted program.
+    fn alloc_containing_addr(&self, addr: u64) -> Option<(AllocId, u64)> {
+        let this = self.eval_context_ref();
+        let global_state = this.machine.alloc_addresses.borrow();
+        let pos = global_state.int_to_ptr_map.partition_point(|&(base, _)| base <= addr);
+        let (base, alloc_id) = global_state.int_to_ptr_map[pos.checked_sub(1)?];
+        let size = this.get_alloc_info(alloc_id).size;
+        // `base <= addr` by construction of `pos`.
+        if addr - base < size.bytes() { Some((alloc_id, base)) } else { None }
+    }
+
     /// Returns the base address of an allocation, or an error if no base address could be found
     ///
     /// # Panics
@@ -33,8 +33,9 @@
 use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
 
 use miri::{
-    BacktraceStyle, BorrowTrackerMethod, GenmcConfig, GenmcCtx, MiriConfig, MiriEntryFnType,
-    ProvenanceMode, RetagFields, TreeBorrowsParams, ValidationMode, run_genmc_mode,
+    BacktraceStyle, BorrowTrackerMethod, GdbServerAddr, GenmcConfig, GenmcCtx, MiriConfig,
+    MiriEntryFnType, ProvenanceMode, RecordReplay, ReportFormat, RetagFields, TreeBorrowsParams,
+    ValidationMode, run_genmc_mode,
 };
 use rustc_abi::ExternAbi;
 use rustc_data_structures::sync;
@@ -530,6 +531,8 @@ fn main() {
                         "`-Zmiri-tree-borrows` is required before `-Zmiri-tree-borrows-no-precise-interior-mut`"
                     ),
             };
+        } else if arg == "-Zmiri-lock-order-check" {
+            miri_config.lock_order_check = true;
         } else if arg == "-Zmiri-disable-data-race-detector" {
             miri_config.data_race_detector = false;
             miri_config.weak_memory_emulation = false;
@@ -570,6 +573,20 @@ fn main() {
             miri_config.float_rounding_error = miri::FloatRoundingErrorMode::Max;
         } else if arg == "-Zmiri-no-short-fd-operations" {
             miri_config.short_fd_operations = false;
+        } else if arg == "-Zmiri-spawn-self" {
+            miri_config.spawn_self = true;
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-gdbserver=") {
+            miri_config.gdbserver = Some(GdbServerAddr::parse(param));
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-record=") {
+            if miri_config.record_replay.is_some() {
+                fatal_error!("Only one of `-Zmiri-record` and `-Zmiri-replay` can be set");
+            }
+            miri_config.record_replay = Some(RecordReplay::Record(PathBuf::from(param)));
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-replay=") {
+            if miri_config.record_replay.is_some() {
+                fatal_error!("Only one of `-Zmiri-record` and `-Zmiri-replay` can be set");
+            }
+            miri_config.record_replay = Some(RecordReplay::Replay(PathBuf::from(param)));
         } else if arg == "-Zmiri-strict-provenance" {
             miri_config.provenance_mode = ProvenanceMode::Strict;
         } else if arg == "-Zmiri-permissive-provenance" {
@@ -637,6 +654,8 @@ fn main() {
             miri_config.tracked_alloc_ids.extend(ids.into_iter().map(miri::AllocId));
         } else if arg == "-Zmiri-track-alloc-accesses" {
             miri_config.track_alloc_accesses = true;
+        } else if arg == "-Zmiri-track-uninit-origins" {
+            miri_config.track_uninit_origins = true;
         } else if let Some(param) = arg.strip_prefix("-Zmiri-address-reuse-rate=") {
             miri_config.address_reuse_rate = parse_rate(param)
                 .unwrap_or_else(|err| fatal_error!("-Zmiri-address-reuse-rate {err}"));
@@ -665,6 +684,8 @@ fn main() {
             miri_config.gc_interval = interval;
         } else if let Some(param) = arg.strip_prefix("-Zmiri-measureme=") {
             miri_config.measureme_out = Some(param.to_string());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-profile=") {
+            miri_config.profile = Some(PathBuf::from(param));
         } else if let Some(param) = arg.strip_prefix("-Zmiri-backtrace=") {
             miri_config.backtrace_style = match param {
                 "0" => BacktraceStyle::Off,
@@ -672,6 +693,12 @@ fn main() {
                 "full" => BacktraceStyle::Full,
                 _ => fatal_error!("-Zmiri-backtrace may only be 0, 1, or full"),
             };
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-report-format=") {
+            miri_config.report_format = match param {
+                "human" => ReportFormat::Human,
+                "json" => ReportFormat::Json,
+                _ => fatal_error!("-Zmiri-report-format may only be human or json"),
+            };
         } else if let Some(param) = arg.strip_prefix("-Zmiri-native-lib=") {
             let filename = param.to_string();
             let file_path = std::path::Path::new(&filename);
@@ -736,6 +763,31 @@ fn main() {
         fatal_error!("Only one of `-Zmiri-seed` and `-Zmiri-many-seeds can be set");
     }
 
+    // A debugger can only follow a single execution.
+    if miri_config.gdbserver.is_some() && many_seeds.is_some() {
+        fatal_error!("`-Zmiri-gdbserver` cannot be combined with `-Zmiri-many-seeds`");
+    }
+    if miri_config.gdbserver.is_some() && miri_config.genmc_config.is_some() {
+        fatal_error!("`-Zmiri-gdbserver` cannot be combined with GenMC mode");
+    }
+
+    // A recording covers a single execution, and a replay takes its seed from the recording.
+    if miri_config.record_replay.is_some() && many_seeds.is_some() {
+        fatal_error!(
+            "`-Zmiri-record` and `-Zmiri-replay` cannot be combined with `-Zmiri-many-seeds`"
+        );
+    }
+    if miri_config.record_replay.is_some() && miri_config.genmc_config.is_some() {
+        fatal_error!("`-Zmiri-record` and `-Zmiri-replay` cannot be combined with GenMC mode");
+    }
+    if matches!(miri_config.record_replay, Some(RecordReplay::Replay(_)))
+        && miri_config.seed.is_some()
+    {
+        fatal_error!(
+            "`-Zmiri-replay` uses the seed of the recording, so `-Zmiri-seed` cannot be set"
+        );
+    }
+
     // Ensure we have parallelism for many-seeds mode.
     if many_seeds.is_some() && !rustc_args.iter().any(|arg| arg.starts_with("-Zthreads=")) {
         // Clamp to 20 threads; things get a less efficient beyond that due to lock contention.
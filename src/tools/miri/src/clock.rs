@@ -1,5 +1,8 @@
 use std::cell::Cell;
-use std::time::{Duration, Instant as StdInstant};
+use std::rc::Rc;
+use std::time::{Duration, Instant as StdInstant, SystemTime};
+
+use crate::NondetLog;
 
 /// When using a virtual clock, this defines how many nanoseconds we pretend are passing for each
 /// basic block.
@@ -79,6 +82,8 @@ enum MonotonicClockKind {
         /// The "epoch" for this machine's monotone clock:
         /// the moment we consider to be time = 0.
         epoch: StdInstant,
+        /// If set, all readings of the host clocks are recorded or replayed.
+        nondet: Option<Rc<NondetLog>>,
     },
     Virtual {
         /// The "current virtual time".
@@ -88,9 +93,9 @@ enum MonotonicClockKind {
 
 impl MonotonicClock {
     /// Create a new clock based on the availability of communication with the host.
-    pub fn new(communicate: bool) -> Self {
+    pub fn new(communicate: bool, nondet: Option<Rc<NondetLog>>) -> Self {
         let kind = if communicate {
-            MonotonicClockKind::Host { epoch: StdInstant::now() }
+            MonotonicClockKind::Host { epoch: StdInstant::now(), nondet }
         } else {
             MonotonicClockKind::Virtual { nanoseconds: 0.into() }
         };
@@ -128,18 +133,58 @@ pub fn sleep(&self, duration: Duration) {
     /// Return the `epoch` instant (time = 0), to convert between monotone instants and absolute durations.
     pub fn epoch(&self) -> Instant {
         match &self.kind {
-            MonotonicClockKind::Host { epoch } => Instant { kind: InstantKind::Host(*epoch) },
+            MonotonicClockKind::Host { epoch, .. } => Instant { kind: InstantKind::Host(*epoch) },
             MonotonicClockKind::Virtual { .. } =>
                 Instant { kind: InstantKind::Virtual { nanoseconds: 0 } },
         }
     }
 
+    /// How much time has passed since the `epoch`.
+    pub fn elapsed(&self) -> Duration {
+        self.now().duration_since(self.epoch())
+    }
+
     pub fn now(&self) -> Instant {
         match &self.kind {
-            MonotonicClockKind::Host { .. } =>
+            MonotonicClockKind::Host { nondet: None, .. } =>
                 Instant { kind: InstantKind::Host(StdInstant::now()) },
+            MonotonicClockKind::Host { epoch, nondet: Some(nondet) } => {
+                let nanoseconds = nondet
+                    .host("monotonic", || StdInstant::now().duration_since(*epoch).as_nanos());
+                let elapsed = duration_from_recorded_nanos(nanoseconds);
+                Instant { kind: InstantKind::Host(*epoch + elapsed) }
+            }
             MonotonicClockKind::Virtual { nanoseconds } =>
                 Instant { kind: InstantKind::Virtual { nanoseconds: nanoseconds.get() } },
         }
     }
+
+    /// The host's current system time. This is only meaningful when communicating with the host,
+    /// but it lives here so that it is recorded and replayed like the monotonic clock.
+    pub fn system_now(&self) -> SystemTime {
+        match &self.kind {
+            MonotonicClockKind::Host { nondet: Some(nondet), .. } => {
+                let nanoseconds = nondet.host("system", || {
+                    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+                        Ok(after) => i128::try_from(after.as_nanos()).unwrap(),
+                        Err(before) => -i128::try_from(before.duration().as_nanos()).unwrap(),
+                    }
+                });
+                let offset = duration_from_recorded_nanos(nanoseconds.unsigned_abs());
+                if nanoseconds >= 0 {
+                    SystemTime::UNIX_EPOCH + offset
+                } else {
+                    SystemTime::UNIX_EPOCH - offset
+                }
+            }
+            _ => SystemTime::now(),
+        }
+    }
+}
+
+/// Converts a recorded clock reading back into a `Duration`.
+fn duration_from_recorded_nanos(nanoseconds: u128) -> Duration {
+    let seconds = u64::try_from(nanoseconds / 1_000_000_000).unwrap();
+    let nanoseconds = u32::try_from(nanoseconds % 1_000_000_000).unwrap();
+    Duration::new(seconds, nanoseconds)
 }
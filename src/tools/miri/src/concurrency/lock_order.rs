@@ -0,0 +1,254 @@
+//! Lock-order checking, see `-Zmiri-lock-order-check`.
+//!
+//! Whenever a thread acquires a lock while holding another one, we add an edge from the held lock
+//! to the acquired lock to a global graph. A cycle in that graph means that the locks involved are
+//! acquired in inconsistent orders, so a different schedule could have made the threads wait for
+//! each other forever -- even if this execution did not deadlock.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+
+use rustc_abi::Size;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::ty;
+use rustc_span::{DUMMY_SP, SpanData, sym};
+
+use crate::diagnostics::prune_stacktrace;
+use crate::*;
+
+/// Identifies a lock for the lock-order check. Locks get their ID when they are first acquired,
+/// and IDs are never reused, so that locks whose memory gets reused are not confused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LockId(u64);
+
+impl fmt::Display for LockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lock #{}", self.0)
+    }
+}
+
+/// A lock acquisition, with the (pruned) backtrace of where it happened.
+#[derive(Debug)]
+struct Acquisition<'tcx> {
+    thread: ThreadId,
+    stacktrace: Vec<FrameInfo<'tcx>>,
+}
+
+/// An edge in the lock-order graph: a thread acquired `acquired` while holding `held`.
+#[derive(Clone, Debug)]
+struct Edge<'tcx> {
+    held: LockId,
+    held_at: Rc<Acquisition<'tcx>>,
+    acquired: LockId,
+    acquired_at: Rc<Acquisition<'tcx>>,
+}
+
+/// An acquisition that is part of a lock-order cycle, prepared for reporting.
+#[derive(Debug)]
+pub struct LockOrderEdge {
+    pub thread: String,
+    pub held: LockId,
+    /// Where the held lock was acquired.
+    pub held_at: SpanData,
+    pub acquired: LockId,
+    /// Where the lock was acquired while holding the other one, innermost frame first.
+    pub backtrace: Vec<(String, SpanData)>,
+}
+
+#[derive(Debug, Default)]
+pub struct LockOrder<'tcx> {
+    /// The locks each thread currently holds, in the order they were acquired.
+    held: FxHashMap<ThreadId, Vec<(LockId, Rc<Acquisition<'tcx>>)>>,
+    /// The lock-order graph; for each lock, the first observed acquisition of each other lock
+    /// while holding it.
+    graph: FxHashMap<LockId, FxHashMap<LockId, Edge<'tcx>>>,
+    /// The ID of the next lock.
+    next_id: u64,
+    /// The IDs of the standard library locks, by their location. Allocation IDs are never
+    /// reused, so neither are these locations.
+    std_locks: FxHashMap<(AllocId, Size), LockId>,
+}
+
+impl<'tcx> LockOrder<'tcx> {
+    fn fresh_id(&mut self) -> LockId {
+        let id = LockId(self.next_id);
+        self.next_id = self.next_id.strict_add(1);
+        id
+    }
+
+    fn std_lock_id(&mut self, alloc_id: AllocId, offset: Size) -> LockId {
+        if let Some(id) = self.std_locks.get(&(alloc_id, offset)) {
+            return *id;
+        }
+        let id = self.fresh_id();
+        self.std_locks.insert((alloc_id, offset), id);
+        id
+    }
+
+    /// Records that `thread` acquired `lock`. If this is the first time that `lock` is acquired
+    /// while holding some lock, and that closes a cycle in the lock-order graph, the *other*
+    /// edges of that cycle are returned, in order from `lock` back to the held lock.
+    pub fn acquire(
+        &mut self,
+        thread: ThreadId,
+        lock: LockId,
+        stacktrace: Vec<FrameInfo<'tcx>>,
+        machine: &MiriMachine<'tcx>,
+    ) -> Option<Vec<LockOrderEdge>> {
+        let acquisition = Rc::new(Acquisition { thread, stacktrace });
+        let held = self.held.entry(thread).or_default();
+        let mut cycle = None;
+        for (held_lock, held_at) in held.iter() {
+            if *held_lock == lock
+                || self.graph.get(held_lock).is_some_and(|e| e.contains_key(&lock))
+            {
+                continue;
+            }
+            if cycle.is_none() {
+                cycle = Self::path(&self.graph, lock, *held_lock)
+                    .map(|path| path.iter().map(|edge| edge.prepare(machine)).collect());
+            }
+            let edge = Edge {
+                held: *held_lock,
+                held_at: held_at.clone(),
+                acquired: lock,
+                acquired_at: acquisition.clone(),
+            };
+            self.graph.entry(*held_lock).or_default().insert(lock, edge);
+        }
+        held.push((lock, acquisition));
+        cycle
+    }
+
+    /// Records that `thread` released `lock`.
+    pub fn release(&mut self, thread: ThreadId, lock: LockId) {
+        let held = self.held.entry(thread).or_default();
+        if let Some(idx) = held.iter().rposition(|(held_lock, _)| *held_lock == lock) {
+            held.remove(idx);
+        }
+    }
+
+    /// Finds a path from `from` to `to` in the lock-order graph.
+    fn path<'a>(
+        graph: &'a FxHashMap<LockId, FxHashMap<LockId, Edge<'tcx>>>,
+        from: LockId,
+        to: LockId,
+    ) -> Option<Vec<&'a Edge<'tcx>>> {
+        // Breadth-first search, remembering how we reached each lock.
+        let mut reached_by: FxHashMap<LockId, Option<&Edge<'tcx>>> = FxHashMap::default();
+        reached_by.insert(from, None);
+        let mut queue = VecDeque::from([from]);
+        while let Some(lock) = queue.pop_front() {
+            if lock == to {
+                let mut path = Vec::new();
+                let mut lock = to;
+                while let Some(edge) = reached_by[&lock] {
+                    path.push(edge);
+                    lock = edge.held;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (next, edge) in graph.get(&lock).into_iter().flatten() {
+                reached_by.entry(*next).or_insert_with(|| {
+                    queue.push_back(*next);
+                    Some(edge)
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<'tcx> Edge<'tcx> {
+    fn prepare(&self, machine: &MiriMachine<'tcx>) -> LockOrderEdge {
+        let held_at = self.held_at.stacktrace.first().map_or(DUMMY_SP, |frame| frame.span);
+        LockOrderEdge {
+            thread: machine.threads.get_thread_display_name(self.acquired_at.thread),
+            held: self.held,
+            held_at: held_at.data(),
+            acquired: self.acquired,
+            backtrace: self
+                .acquired_at
+                .stacktrace
+                .iter()
+                .map(|frame| (frame.instance.to_string(), frame.span.data()))
+                .collect(),
+        }
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Returns the ID of a lock for the lock-order check, assigning it a fresh one if it does not
+    /// have one yet. Returns `None` if the check is disabled.
+    fn lock_order_id(&self, id: &mut Option<LockId>) -> Option<LockId> {
+        let this = self.eval_context_ref();
+        let lock_order = this.machine.lock_order.as_ref()?;
+        Some(*id.get_or_insert_with(|| lock_order.borrow_mut().fresh_id()))
+    }
+
+    /// Tells the lock-order check that the active thread acquired this lock.
+    fn lock_order_acquire(&mut self, lock: LockId) {
+        let this = self.eval_context_mut();
+        let Some(lock_order) = &this.machine.lock_order else {
+            return;
+        };
+        let stacktrace = this.generate_stacktrace();
+        let (stacktrace, _was_pruned) = prune_stacktrace(stacktrace, &this.machine);
+        let thread = this.active_thread();
+        let cycle = lock_order.borrow_mut().acquire(thread, lock, stacktrace, &this.machine);
+        if let Some(cycle) = cycle {
+            let held = cycle.last().unwrap().acquired;
+            this.emit_diagnostic(NonHaltingDiagnostic::PotentialDeadlock { lock, held, cycle });
+        }
+    }
+
+    /// Tells the lock-order check that the active thread released this lock.
+    fn lock_order_release(&mut self, lock: LockId) {
+        let this = self.eval_context_mut();
+        if let Some(lock_order) = &this.machine.lock_order {
+            lock_order.borrow_mut().release(this.active_thread(), lock);
+        }
+    }
+
+    /// Tells the lock-order check about a call to one of the futex-based `Mutex` and `RwLock` of
+    /// the standard library, which do not go through Miri's lock shims. The lock counts as
+    /// acquired as soon as the thread starts waiting for it, which is what matters for the lock
+    /// order. Locks taken with `try_lock`, `try_read`, or `try_write` are not tracked.
+    fn lock_order_std_call(
+        &mut self,
+        instance: ty::Instance<'tcx>,
+        args: &[FnArg<'tcx>],
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let def_id = instance.def_id();
+        let acquire = match this.tcx.get_diagnostic_name(def_id) {
+            Some(sym::sys_mutex_lock | sym::sys_rwlock_read | sym::sys_rwlock_write) => true,
+            Some(
+                sym::sys_mutex_unlock | sym::sys_rwlock_read_unlock | sym::sys_rwlock_write_unlock,
+            ) => false,
+            _ => return interp_ok(()),
+        };
+        // The other implementations are built on top of the pthread or Windows locks, which Miri
+        // emulates and therefore checks already.
+        let module = this.tcx.parent(this.tcx.parent(def_id));
+        if this.tcx.item_name(module).as_str() != "futex" {
+            return interp_ok(());
+        }
+        let [lock] = &*this.copy_fn_args(args) else {
+            panic!("{instance} is a diagnostic item expected to have 1 argument");
+        };
+        let lock = this.deref_pointer(lock)?;
+        let (alloc_id, offset, _) = this.ptr_get_alloc_id(lock.ptr(), 0)?;
+        let id =
+            this.machine.lock_order.as_ref().unwrap().borrow_mut().std_lock_id(alloc_id, offset);
+        if acquire {
+            this.lock_order_acquire(id);
+        } else {
+            this.lock_order_release(id);
+        }
+        interp_ok(())
+    }
+}
@@ -2,6 +2,7 @@
 pub mod data_race;
 mod data_race_handler;
 pub mod init_once;
+pub mod lock_order;
 pub mod sync;
 pub mod thread;
 mod vector_clock;
@@ -15,6 +15,8 @@
 /// The mutex state.
 #[derive(Default, Debug)]
 struct Mutex {
+    /// Identifies this mutex for the lock-order check, once it was acquired with the check enabled.
+    id: Option<LockId>,
     /// The thread that currently owns the lock.
     owner: Option<ThreadId>,
     /// How many times the mutex was locked by the owner.
@@ -47,6 +49,8 @@ fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {}
 /// The read-write lock state.
 #[derive(Default, Debug)]
 struct RwLock {
+    /// Identifies this lock for the lock-order check, once it was acquired with the check enabled.
+    id: Option<LockId>,
     /// The writer thread that currently owns the lock.
     writer: Option<ThreadId>,
     /// The readers that currently own the lock and how many times they acquired
@@ -353,6 +357,12 @@ fn mutex_lock(&mut self, mutex_ref: &MutexRef) -> InterpResult<'tcx> {
         }
         mutex.lock_count = mutex.lock_count.strict_add(1);
         this.acquire_clock(&mutex.clock)?;
+        if mutex.lock_count == 1
+            && let Some(id) = this.lock_order_id(&mut mutex.id)
+        {
+            drop(mutex);
+            this.lock_order_acquire(id);
+        }
         interp_ok(())
     }
 
@@ -373,6 +383,9 @@ fn mutex_unlock(&mut self, mutex_ref: &MutexRef) -> InterpResult<'tcx, Option<us
             mutex.lock_count = old_lock_count.strict_sub(1);
             if mutex.lock_count == 0 {
                 mutex.owner = None;
+                if let Some(id) = mutex.id {
+                    this.lock_order_release(id);
+                }
                 // The mutex is completely unlocked. Try transferring ownership
                 // to another thread.
 
@@ -442,7 +455,12 @@ fn rwlock_reader_lock(&mut self, rwlock_ref: &RwLockRef) -> InterpResult<'tcx> {
         assert!(!rwlock.is_write_locked(), "the lock is write locked");
         let count = rwlock.readers.entry(thread).or_insert(0);
         *count = count.strict_add(1);
+        let first_acquisition = *count == 1;
         this.acquire_clock(&rwlock.clock_unlocked)?;
+        if first_acquisition && let Some(id) = this.lock_order_id(&mut rwlock.id) {
+            drop(rwlock);
+            this.lock_order_acquire(id);
+        }
         interp_ok(())
     }
 
@@ -460,6 +478,9 @@ fn rwlock_reader_unlock(&mut self, rwlock_ref: &RwLockRef) -> InterpResult<'tcx,
                 if *count == 0 {
                     trace!("rwlock_reader_unlock: no longer held by {:?}", thread);
                     entry.remove();
+                    if let Some(id) = rwlock.id {
+                        this.lock_order_release(id);
+                    }
                 } else {
                     trace!("rwlock_reader_unlock: held one less time by {:?}", thread);
                 }
@@ -530,6 +551,10 @@ fn rwlock_writer_lock(&mut self, rwlock_ref: &RwLockRef) -> InterpResult<'tcx> {
         assert!(!rwlock.is_locked(), "the rwlock is already locked");
         rwlock.writer = Some(thread);
         this.acquire_clock(&rwlock.clock_unlocked)?;
+        if let Some(id) = this.lock_order_id(&mut rwlock.id) {
+            drop(rwlock);
+            this.lock_order_acquire(id);
+        }
         interp_ok(())
     }
 
@@ -547,6 +572,9 @@ fn rwlock_writer_unlock(&mut self, rwlock_ref: &RwLockRef) -> InterpResult<'tcx,
             }
             rwlock.writer = None;
             trace!("rwlock_writer_unlock: unlocked by {:?}", thread);
+            if let Some(id) = rwlock.id {
+                this.lock_order_release(id);
+            }
             // Record release clock for next lock holder.
             this.release_clock(|clock| rwlock.clock_unlocked.clone_from(clock))?;
 
@@ -108,8 +108,16 @@ pub enum BlockReason {
     Epoll,
     /// Blocked on eventfd.
     Eventfd,
+    /// Blocked on timerfd.
+    Timerfd,
+    /// Blocked on signalfd.
+    Signalfd,
+    /// Blocked on inotify.
+    Inotify,
     /// Blocked on unnamed_socket.
     UnnamedSocket,
+    /// Blocked on a socket of the emulated loopback network.
+    Socket,
     /// Blocked for any reason related to GenMC, such as `assume` statements (GenMC mode only).
     /// Will be implicitly unblocked when GenMC schedules this thread again.
     Genmc,
@@ -363,7 +371,7 @@ fn get_wait_time(&self, clock: &MonotonicClock) -> Duration {
         match self {
             Timeout::Monotonic(instant) => instant.duration_since(clock.now()),
             Timeout::RealTime(time) =>
-                time.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO),
+                time.duration_since(clock.system_now()).unwrap_or(Duration::ZERO),
         }
     }
 
@@ -506,6 +514,22 @@ pub fn all_stacks(
         self.threads.iter_enumerated().map(|(id, t)| (id, &t.stack[..]))
     }
 
+    /// Iterates over all threads that have not terminated yet, with their stack and a short
+    /// description of what they are doing.
+    pub fn live_threads(
+        &self,
+    ) -> impl Iterator<Item = (ThreadId, &[Frame<'tcx, Provenance, FrameExtra<'tcx>>], String)>
+    {
+        self.threads.iter_enumerated().filter_map(|(id, thread)| {
+            let state = match &thread.state {
+                ThreadState::Enabled => "runnable".to_string(),
+                ThreadState::Blocked { reason, .. } => format!("blocked on {reason:?}"),
+                ThreadState::Terminated => return None,
+            };
+            Some((id, &thread.stack[..], state))
+        })
+    }
+
     /// Create a new thread and returns its id.
     fn create_thread(&mut self, on_stack_empty: StackEmptyCallback<'tcx>) -> ThreadId {
         let new_thread_id = ThreadId::new(self.threads.len());
@@ -777,7 +801,11 @@ fn schedule(&mut self) -> InterpResult<'tcx, SchedulingAction> {
         let new_thread = if thread_manager.fixed_scheduling {
             threads_iter.next()
         } else {
-            threads_iter.choose(rng)
+            let new_thread = threads_iter.choose(rng);
+            if let (Some(nondet), Some((id, _thread))) = (&this.machine.nondet, new_thread) {
+                nondet.check("schedule", id.to_u32());
+            }
+            new_thread
         };
 
         if let Some((id, _thread)) = new_thread {
@@ -1021,7 +1049,7 @@ fn block_thread(
                     );
                     Timeout::RealTime(match anchor {
                         TimeoutAnchor::Absolute => SystemTime::UNIX_EPOCH,
-                        TimeoutAnchor::Relative => SystemTime::now(),
+                        TimeoutAnchor::Relative => this.machine.monotonic_clock.system_now(),
                     })
                 }
                 TimeoutClock::Monotonic =>
@@ -1247,6 +1275,9 @@ fn maybe_preempt_active_thread(&mut self) {
         if !this.machine.threads.fixed_scheduling
             && this.machine.rng.get_mut().random_bool(this.machine.preemption_rate)
         {
+            if let Some(nondet) = &this.machine.nondet {
+                nondet.check("preempt", this.machine.basic_block_count);
+            }
             this.yield_active_thread();
         }
     }
@@ -1260,8 +1291,15 @@ fn run_threads(&mut self) -> InterpResult<'tcx, !> {
                 this.machine.handle_abnormal_termination();
                 throw_machine_stop!(TerminationInfo::Interrupted);
             }
+            if let Some(divergence) =
+                this.machine.nondet.as_ref().and_then(|nondet| nondet.take_divergence())
+            {
+                this.machine.handle_abnormal_termination();
+                throw_machine_stop!(TerminationInfo::ReplayDiverged(divergence));
+            }
             match this.schedule()? {
                 SchedulingAction::ExecuteStep => {
+                    this.gdbserver_before_step()?;
                     if !this.step()? {
                         // See if this thread can do something else.
                         match this.run_on_stack_empty()? {
@@ -282,6 +282,7 @@ fn buffered_read(
         thread_mgr: &ThreadManager<'_>,
         is_seqcst: bool,
         rng: &mut (impl rand::Rng + ?Sized),
+        nondet: Option<&NondetLog>,
         validate: impl FnOnce(Option<&VClock>) -> InterpResult<'tcx>,
     ) -> InterpResult<'tcx, (Option<Scalar>, LoadRecency)> {
         // Having a live borrow to store_buffer while calling validate_atomic_load is fine
@@ -292,7 +293,7 @@ fn buffered_read(
             // as the race detector will update it
             let (.., clocks) = global.active_thread_state(thread_mgr);
             // Load from a valid entry in the store buffer
-            self.fetch_store(is_seqcst, &clocks, &mut *rng)
+            self.fetch_store(is_seqcst, &clocks, &mut *rng, nondet)
         };
 
         // Unlike in buffered_atomic_write, thread clock updates have to be done
@@ -326,6 +327,7 @@ fn fetch_store<R: rand::Rng + ?Sized>(
         is_seqcst: bool,
         clocks: &ThreadClockSet,
         rng: &mut R,
+        nondet: Option<&NondetLog>,
     ) -> (&StoreElement, LoadRecency) {
         use rand::seq::IteratorRandom;
         let mut found_sc = false;
@@ -396,6 +398,11 @@ fn fetch_store<R: rand::Rng + ?Sized>(
             });
 
         let chosen = candidates.choose(rng).expect("store buffer cannot be empty");
+        if let Some(nondet) = nondet {
+            // Log how far back in modification order the chosen store is.
+            let age = self.buffer.iter().rev().position(|elem| std::ptr::eq(elem, chosen)).unwrap();
+            nondet.check("load", age);
+        }
         if std::ptr::eq(chosen, self.buffer.back().expect("store buffer cannot be empty")) {
             (chosen, LoadRecency::Latest)
         } else {
@@ -535,6 +542,7 @@ fn buffered_atomic_read(
                         &this.machine.threads,
                         atomic == AtomicReadOrd::SeqCst,
                         &mut *rng,
+                        this.machine.nondet.as_deref(),
                         validate,
                     )?;
                     if global.track_outdated_loads && recency == LoadRecency::Outdated {
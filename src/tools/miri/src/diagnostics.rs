@@ -9,7 +9,7 @@
 
 use crate::borrow_tracker::stacked_borrows::diagnostics::TagHistory;
 use crate::borrow_tracker::tree_borrows::diagnostics as tree_diagnostics;
-use crate::*;
+use crate::{json_report, *};
 
 /// Details of premature program termination.
 pub enum TerminationInfo {
@@ -56,6 +56,12 @@ pub enum TerminationInfo {
         retag_explain: bool,
     },
     UnsupportedForeignItem(String),
+    /// An emulated child process stopped with an error, which has already been reported.
+    ChildProcessError {
+        pid: i32,
+    },
+    /// The execution no longer matches the recording it is replayed from.
+    ReplayDiverged(String),
 }
 
 pub struct RacingOp {
@@ -97,6 +103,9 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                     op2.thread_info
                 ),
             UnsupportedForeignItem(msg) => write!(f, "{msg}"),
+            ChildProcessError { pid } =>
+                write!(f, "the child process with PID {pid} was stopped by the error above"),
+            ReplayDiverged(msg) => write!(f, "{msg}"),
         }
     }
 }
@@ -151,6 +160,13 @@ pub enum NonHaltingDiagnostic {
         failure_ordering: AtomicReadOrd,
         effective_failure_ordering: AtomicReadOrd,
     },
+    /// Acquiring `lock` while holding `held` closes a cycle in the lock-order graph, whose other
+    /// edges are `cycle`.
+    PotentialDeadlock {
+        lock: LockId,
+        held: LockId,
+        cycle: Vec<LockOrderEdge>,
+    },
 }
 
 /// Level of Miri specific diagnostics
@@ -263,6 +279,8 @@ pub fn report_error<'tcx>(
                 return Some((0, false));
             }
             MultipleSymbolDefinitions { .. } | SymbolShimClashing { .. } => None,
+            ChildProcessError { .. } => None,
+            ReplayDiverged(_) => None,
         };
         #[rustfmt::skip]
         let helps = match info {
@@ -313,6 +331,10 @@ pub fn report_error<'tcx>(
                 ],
             SymbolShimClashing { link_name, span } =>
                 vec![note_span!(*span, "the `{link_name}` symbol is defined here")],
+            ReplayDiverged(_) =>
+                vec![
+                    note!("a recording can only be replayed with the same program, Miri version, and flags it was recorded with"),
+                ],
             Int2PtrWithStrictProvenance =>
                 vec![note!("use Strict Provenance APIs (https://doc.rust-lang.org/nightly/std/ptr/index.html#strict-provenance, https://crates.io/crates/sptr) instead")],
             DataRace { op1, extra, retag_explain, .. } => {
@@ -396,6 +418,23 @@ pub fn report_error<'tcx>(
                             helps.push(note_span!(span, "{:?} was deallocated here:", alloc_id));
                         }
                     }
+                    InvalidUninitBytes(Some((alloc_id, access))) => {
+                        let origins = ecx
+                            .get_alloc_extra(*alloc_id)
+                            .discard_err()
+                            .and_then(|extra| extra.uninit_origins.as_ref());
+                        if let Some(origins) = origins {
+                            for (range, origin) in origins.explain(access.bad) {
+                                let range = format!("{alloc_id:?}[{:#x}..{:#x}]", range.start, range.end);
+                                helps.push(match origin {
+                                    UninitOrigin::Allocated(span) =>
+                                        note_span!(span, "{range} was never initialized since it was allocated here:"),
+                                    UninitOrigin::Written(span) =>
+                                        note_span!(span, "{range} was last written here, which left it uninitialized:"),
+                                });
+                            }
+                        }
+                    }
                     AbiMismatchArgument { .. } | AbiMismatchReturn { .. } => {
                         helps.push(note!("this means these two types are not *guaranteed* to be ABI-compatible across all targets"));
                         helps.push(note!("if you think this code should be accepted anyway, please report an issue with Miri"));
@@ -416,6 +455,28 @@ pub fn report_error<'tcx>(
         (Some(title), helps)
     };
 
+    if ecx.machine.report_format == ReportFormat::Json {
+        let (kind, class) = json_report::classify(e.kind());
+        let allocations = json_report::involved_allocations(e.kind());
+        let tag_history = json_report::tag_history(e.kind());
+        let mut message = String::new();
+        if let Some(title) = title {
+            write!(message, "{title}: ").unwrap();
+        }
+        write!(message, "{}", format_interp_error(ecx.tcx.dcx(), e)).unwrap();
+        let report = json_report::ErrorReport {
+            kind,
+            class,
+            message,
+            labels: &labels,
+            notes: &helps,
+            allocations,
+            tag_history,
+        };
+        json_report::emit_error(ecx, report);
+        return None;
+    }
+
     let stacktrace = ecx.generate_stacktrace();
     let (stacktrace, mut any_pruned) = prune_stacktrace(stacktrace, &ecx.machine);
 
@@ -521,6 +582,18 @@ pub fn report_leaks<'tcx>(
             alloc.size().bytes(),
             alloc.align.bytes()
         );
+        if ecx.machine.report_format == ReportFormat::Json {
+            json_report::emit_leak(
+                &ecx.machine,
+                title,
+                id,
+                kind,
+                alloc.size(),
+                alloc.align,
+                alloc.extra.backtrace.as_deref(),
+            );
+            continue;
+        }
         let Some(backtrace) = alloc.extra.backtrace else {
             ecx.tcx.dcx().err(title);
             continue;
@@ -654,6 +727,8 @@ pub fn emit_diagnostic(&self, e: NonHaltingDiagnostic) {
                 ("reborrow of reference to `extern type`".to_string(), DiagLevel::Warning),
             GenmcCompareExchangeWeak | GenmcCompareExchangeOrderingMismatch { .. } =>
                 ("GenMC might miss possible behaviors of this code".to_string(), DiagLevel::Warning),
+            PotentialDeadlock { .. } =>
+                ("this acquisition inverts the lock order".to_string(), DiagLevel::Warning),
             CreatedPointerTag(..)
             | PoppedPointerTag(..)
             | CreatedAlloc(..)
@@ -711,6 +786,8 @@ pub fn emit_diagnostic(&self, e: NonHaltingDiagnostic) {
                 };
                 format!("GenMC currently does not model the failure ordering for `compare_exchange`. {was_upgraded_msg}. Miri with GenMC might miss bugs related to this memory access.")
             }
+            PotentialDeadlock { lock, held, .. } =>
+                format!("potential deadlock: {lock} is acquired while holding {held}, but {held} was acquired while holding {lock} before"),
         };
 
         let notes = match &e {
@@ -808,6 +885,31 @@ pub fn emit_diagnostic(&self, e: NonHaltingDiagnostic) {
                     ),
                 ]
             }
+            PotentialDeadlock { cycle, .. } => {
+                let sm = self.tcx.sess.source_map();
+                let mut helps = Vec::new();
+                for edge in cycle {
+                    let acquired_at =
+                        edge.backtrace.first().map_or(DUMMY_SP.data(), |(_, span)| *span);
+                    helps.push(note_span!(
+                        acquired_at,
+                        "thread `{}` acquired {} here while holding {}",
+                        edge.thread,
+                        edge.acquired,
+                        edge.held,
+                    ));
+                    helps.push(note_span!(edge.held_at, "{} had been acquired here", edge.held));
+                    helps.push(note!("backtrace of the acquisition of {}:", edge.acquired));
+                    for (function, span) in &edge.backtrace {
+                        let span = sm.span_to_embeddable_string(span.span());
+                        helps.push(note!("inside `{function}` at {span}"));
+                    }
+                }
+                helps.push(note!(
+                    "with a different schedule, the threads could wait for each other forever"
+                ));
+                helps
+            }
             _ => vec![],
         };
 
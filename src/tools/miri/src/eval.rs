@@ -19,7 +19,7 @@
 use crate::concurrency::thread::TlsAllocAction;
 use crate::diagnostics::report_leaks;
 use crate::shims::{global_ctor, tls};
-use crate::*;
+use crate::{json_report, *};
 
 #[derive(Copy, Clone, Debug)]
 pub enum MiriEntryFnType {
@@ -62,8 +62,12 @@ pub struct MiriConfig {
     pub tracked_alloc_ids: FxHashSet<AllocId>,
     /// For the tracked alloc ids, also report read/write accesses.
     pub track_alloc_accesses: bool,
+    /// Record where uninitialized memory came from, to explain uninitialized reads.
+    pub track_uninit_origins: bool,
     /// Determine if data race detection should be enabled.
     pub data_race_detector: bool,
+    /// Check that locks are always acquired in a consistent order.
+    pub lock_order_check: bool,
     /// Determine if weak memory emulation should be enabled. Requires data race detection to be enabled.
     pub weak_memory_emulation: bool,
     /// Determine if we are running in GenMC mode and with which settings. In GenMC mode, Miri will explore multiple concurrent executions of the given program.
@@ -78,6 +82,8 @@ pub struct MiriConfig {
     pub measureme_out: Option<String>,
     /// Which style to use for printing backtraces.
     pub backtrace_style: BacktraceStyle,
+    /// How errors and leaks are reported.
+    pub report_format: ReportFormat,
     /// Which provenance to use for int2ptr casts.
     pub provenance_mode: ProvenanceMode,
     /// Whether to ignore any output by the program. This is helpful when debugging miri
@@ -115,6 +121,15 @@ pub struct MiriConfig {
     pub float_rounding_error: FloatRoundingErrorMode,
     /// Whether Miri artifically introduces short reads/writes on file descriptors.
     pub short_fd_operations: bool,
+    /// Whether the program may spawn itself as a child process, which is then run in a nested
+    /// interpreter.
+    pub spawn_self: bool,
+    /// If set, wait for a debugger to connect on this address before starting the program.
+    pub gdbserver: Option<GdbServerAddr>,
+    /// If set, record the nondeterminism of this execution to a file, or replay it from one.
+    pub record_replay: Option<RecordReplay>,
+    /// If set, profile the interpreted program and write the profile to this file.
+    pub profile: Option<PathBuf>,
 }
 
 impl Default for MiriConfig {
@@ -133,13 +148,16 @@ fn default() -> MiriConfig {
             tracked_pointer_tags: FxHashSet::default(),
             tracked_alloc_ids: FxHashSet::default(),
             track_alloc_accesses: false,
+            track_uninit_origins: false,
             data_race_detector: true,
+            lock_order_check: false,
             weak_memory_emulation: true,
             genmc_config: None,
             track_outdated_loads: false,
             cmpxchg_weak_failure_rate: 0.8, // 80%
             measureme_out: None,
             backtrace_style: BacktraceStyle::Short,
+            report_format: ReportFormat::Human,
             provenance_mode: ProvenanceMode::Default,
             mute_stdout_stderr: false,
             preemption_rate: 0.01, // 1%
@@ -158,6 +176,10 @@ fn default() -> MiriConfig {
             float_nondet: true,
             float_rounding_error: FloatRoundingErrorMode::Random,
             short_fd_operations: true,
+            spawn_self: false,
+            gdbserver: None,
+            record_replay: None,
+            profile: None,
         }
     }
 }
@@ -375,6 +397,10 @@ pub fn create_ecx<'tcx>(
         Box::new(move |m| main_thread_state.on_main_stack_empty(m))
     })?;
 
+    if config.spawn_self {
+        ecx.machine.child_processes.enable_spawn_self(entry_id, entry_type, config.clone());
+    }
+
     interp_ok(ecx)
 }
 
@@ -471,6 +497,14 @@ pub fn eval_entry<'tcx>(
         }
     };
 
+    // Wait for the debugger, so that it can stop the program before its first step.
+    if let Some(addr) = &config.gdbserver {
+        let server = GdbServer::listen(addr, tcx.dcx()).unwrap_or_else(|err| {
+            tcx.dcx().fatal(format!("failed to accept a debugger connection on {addr}: {err}"))
+        });
+        ecx.machine.gdbserver = Some(server);
+    }
+
     // Perform the main execution.
     let res: thread::Result<InterpResult<'_, !>> =
         panic::catch_unwind(AssertUnwindSafe(|| ecx.run_threads()));
@@ -483,30 +517,105 @@ pub fn eval_entry<'tcx>(
     let Err(err) = res.report_err();
 
     // Show diagnostic, if any.
-    let (return_code, leak_check) = report_error(&ecx, err)?;
+    let res = report_error(&ecx, err);
+    // The stack is still intact, so an attached debugger can inspect where we stopped.
+    ecx.gdbserver_report_termination(res.map(|(return_code, _)| return_code));
+    if let Some(warning) = ecx.machine.nondet.as_ref().and_then(|nondet| nondet.finish()) {
+        tcx.dcx().warn(warning);
+    }
+    ecx.machine.finish_interp_profile();
+    let (return_code, leak_check) = res?;
 
     // If we get here there was no fatal error.
 
     // Possibly check for memory leaks.
-    if leak_check && !ignore_leaks {
-        // Check for thread leaks.
-        if !ecx.have_all_terminated() {
-            tcx.dcx().err("the main thread terminated without waiting for all remaining threads");
-            tcx.dcx().note("set `MIRIFLAGS=-Zmiri-ignore-leaks` to disable this check");
-            return None;
+    if leak_check && !ignore_leaks && !check_leaks(&mut ecx) {
+        // Ignore the provided return code - let the reported error
+        // determine the return code.
+        return None;
+    }
+    Some(return_code)
+}
+
+/// Checks for threads and memory that are still around after the program finished.
+/// Returns `false` if there were any; they have already been reported.
+fn check_leaks<'tcx>(ecx: &mut MiriInterpCx<'tcx>) -> bool {
+    let tcx = *ecx.tcx;
+    // Check for thread leaks.
+    if !ecx.have_all_terminated() {
+        let msg = "the main thread terminated without waiting for all remaining threads";
+        if ecx.machine.report_format == ReportFormat::Json {
+            json_report::emit_thread_leak(ecx, msg);
+            return false;
         }
-        // Check for memory leaks.
-        info!("Additional static roots: {:?}", ecx.machine.static_roots);
-        let leaks = ecx.take_leaked_allocations(|ecx| &ecx.machine.static_roots);
-        if !leaks.is_empty() {
-            report_leaks(&ecx, leaks);
+        tcx.dcx().err(msg);
+        tcx.dcx().note("set `MIRIFLAGS=-Zmiri-ignore-leaks` to disable this check");
+        return false;
+    }
+    // Check for memory leaks.
+    info!("Additional static roots: {:?}", ecx.machine.static_roots);
+    let leaks = ecx.take_leaked_allocations(|ecx| &ecx.machine.static_roots);
+    if !leaks.is_empty() {
+        report_leaks(ecx, leaks);
+        if ecx.machine.report_format == ReportFormat::Human {
             tcx.dcx().note("set `MIRIFLAGS=-Zmiri-ignore-leaks` to disable this check");
-            // Ignore the provided return code - let the reported error
-            // determine the return code.
-            return None;
         }
+        return false;
     }
-    Some(return_code)
+    true
+}
+
+/// How an emulated child process terminated.
+#[derive(Copy, Clone, Debug)]
+pub enum ChildTermination {
+    /// The child exited with the given exit code.
+    Exited(i32),
+    /// The child called `abort`.
+    Aborted,
+}
+
+/// Evaluates the entry function again in a fresh interpreter, as a child process of the program
+/// currently being interpreted. `setup` can adjust the new machine before execution starts.
+/// Returns `None` if an evaluation error occurred; that error has already been reported.
+pub(crate) fn eval_child<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: MiriEntryFnType,
+    config: &MiriConfig,
+    setup: impl FnOnce(&mut MiriInterpCx<'tcx>) -> InterpResult<'tcx>,
+) -> Option<ChildTermination> {
+    let mut ecx = match create_ecx(tcx, entry_id, entry_type, config, None).report_err() {
+        Ok(v) => v,
+        Err(err) => {
+            let (kind, backtrace) = err.into_parts();
+            backtrace.print_backtrace();
+            panic!("Miri initialization error: {kind:?}")
+        }
+    };
+    if let Err(err) = setup(&mut ecx).report_err() {
+        report_error(&ecx, err);
+        return None;
+    }
+
+    let res: thread::Result<InterpResult<'_, !>> =
+        panic::catch_unwind(AssertUnwindSafe(|| ecx.run_threads()));
+    let res = res.unwrap_or_else(|panic_payload| {
+        ecx.handle_ice();
+        panic::resume_unwind(panic_payload)
+    });
+    let Err(err) = res.report_err();
+
+    // An abort is not an error from the point of view of the parent; it just sees the signal.
+    if let InterpErrorKind::MachineStop(info) = err.kind()
+        && let Some(TerminationInfo::Abort(_)) = info.downcast_ref::<TerminationInfo>()
+    {
+        return Some(ChildTermination::Aborted);
+    }
+    let (return_code, leak_check) = report_error(&ecx, err)?;
+    if leak_check && !config.ignore_leaks && !check_leaks(&mut ecx) {
+        return None;
+    }
+    Some(ChildTermination::Exited(return_code))
 }
 
 /// Turns an array of arguments into a Windows command line string.
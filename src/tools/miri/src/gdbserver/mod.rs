@@ -0,0 +1,831 @@
+//! A stub for the GDB remote serial protocol, see `-Zmiri-gdbserver`.
+//!
+//! The interpreted program has no machine code, so this does not pretend to be a regular target.
+//! Instead, the client gets an all-stop view of the interpreter:
+//! - Miri threads are reported as the threads of the target.
+//! - Memory reads go straight to the allocation containing the address, whether or not it has
+//!   been exposed, and without triggering any of the machine's access hooks.
+//! - There is a single register, `pc`, holding the MIR location of the selected thread's innermost
+//!   frame as `basic_block << 32 | statement_index`.
+//! - `stepi` executes one MIR statement or terminator of the selected thread. With
+//!   `monitor step-mode line` it instead runs until that thread reaches a different source line.
+//! - Everything else (breakpoints on functions, backtraces, locals) is provided as `monitor`
+//!   commands, since the client has no debug info to resolve them itself.
+//!
+//! When the program stops with an error, the debugger gets one last chance to inspect the state
+//! right where the error occurred before Miri exits.
+
+mod packet;
+
+use std::fmt::{self, Write as _};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+
+use rustc_data_structures::either::Either;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_errors::DiagCtxtHandle;
+use rustc_middle::mir;
+use rustc_span::BytePos;
+
+use self::packet::{Connection, Incoming, escape, parse_hex, to_hex};
+use crate::*;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+const SIGABRT: u8 = 6;
+
+/// How many steps we take before checking whether the client asked to interrupt the program.
+const INTERRUPT_POLL_INTERVAL: u32 = 4096;
+
+/// Describes the single `pc` register, see the module docs.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rust-lang.miri">
+    <reg name="pc" bitsize="64" type="code_ptr" regnum="0"/>
+  </feature>
+</target>
+"#;
+
+const HELP: &str = "\
+Miri gdb server commands:
+  monitor break <fn>            stop whenever a function whose path ends in <fn> is called
+  monitor breakpoints           list all breakpoints
+  monitor delete [<n>]          delete breakpoint <n>, or all breakpoints
+  monitor step-mode <mode>      make `stepi` step by `statement`, `line`, or `line-over`
+  monitor threads               list all threads and what they are doing
+  monitor backtrace             show the stack of the selected thread
+  monitor frame [<n>]           select frame <n> of the selected thread (0 is the innermost)
+  monitor locals                show the named locals of the selected frame
+  monitor alloc <addr>          describe the allocation containing <addr>
+";
+
+/// Where the gdb server listens for a client.
+#[derive(Clone, Debug)]
+pub enum GdbServerAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl GdbServerAddr {
+    /// Parses the argument of `-Zmiri-gdbserver`: either a port on localhost, a socket address, or
+    /// the path of a Unix socket.
+    pub fn parse(s: &str) -> Self {
+        if let Ok(port) = s.parse::<u16>() {
+            GdbServerAddr::Tcp(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+        } else if let Ok(addr) = s.parse::<SocketAddr>() {
+            GdbServerAddr::Tcp(addr)
+        } else {
+            GdbServerAddr::Unix(s.into())
+        }
+    }
+}
+
+impl fmt::Display for GdbServerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GdbServerAddr::Tcp(addr) => write!(f, "{addr}"),
+            GdbServerAddr::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// How fine-grained `stepi` is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum StepMode {
+    /// Execute a single MIR statement or terminator.
+    Statement,
+    /// Run until a different source line is reached, stepping into calls.
+    Line,
+    /// Run until a different source line is reached in the same or an outer frame.
+    LineOver,
+}
+
+impl StepMode {
+    fn name(self) -> &'static str {
+        match self {
+            StepMode::Statement => "statement",
+            StepMode::Line => "line",
+            StepMode::LineOver => "line-over",
+        }
+    }
+}
+
+/// Identifies a source line: the start of its file in the source map, and the line number.
+type LineKey = (BytePos, usize);
+
+/// When to stop the program next.
+enum Resume {
+    /// Before the next step of any thread.
+    Stop,
+    /// Only at breakpoints or when the client interrupts us.
+    Continue,
+    /// Once `thread` made progress according to `mode`, starting from the given stack depth and
+    /// source line.
+    Step {
+        thread: ThreadId,
+        mode: StepMode,
+        depth: usize,
+        line: Option<LineKey>,
+        user_relevant: bool,
+    },
+}
+
+/// Why the program stopped.
+enum StopReason {
+    Breakpoint(usize),
+    Step,
+    Interrupt,
+}
+
+/// What to do after handling a packet.
+enum Action {
+    Reply(Vec<u8>),
+    Resume(Resume),
+    Detach,
+    Kill { reply: bool },
+}
+
+/// Whether the client is still around after we handed control back to the interpreter.
+enum Session {
+    Attached,
+    Detached,
+    Killed,
+}
+
+pub struct GdbServer {
+    conn: Connection,
+    resume: Resume,
+    /// Whether the client resumed the program and is waiting for us to report the next stop.
+    running: bool,
+    /// Function paths to stop at, set with `monitor break`. Deleted breakpoints are kept as `None`
+    /// so that the numbers of the remaining ones do not change.
+    breakpoints: Vec<Option<String>>,
+    /// Threads that just called a function with a breakpoint, and which breakpoint that was. The
+    /// program stops before such a thread takes its next step.
+    pending_breakpoints: FxHashMap<ThreadId, usize>,
+    step_mode: StepMode,
+    /// The thread and frame (0 being the innermost) that register reads and `monitor` commands
+    /// refer to.
+    selected_thread: ThreadId,
+    selected_frame: usize,
+    /// The stop reply describing why the program last stopped.
+    last_stop: String,
+    steps_since_poll: u32,
+}
+
+impl GdbServer {
+    /// Waits for a client to connect, telling the user once we are listening. The program is
+    /// stopped before its first step.
+    pub fn listen(addr: &GdbServerAddr, dcx: DiagCtxtHandle<'_>) -> io::Result<Self> {
+        let waiting = || dcx.note(format!("waiting for a debugger to connect on {addr}"));
+        let stream: Box<dyn packet::Stream> = match addr {
+            GdbServerAddr::Tcp(addr) => {
+                let listener = TcpListener::bind(addr)?;
+                waiting();
+                let (stream, _) = listener.accept()?;
+                stream.set_nodelay(true)?;
+                Box::new(stream)
+            }
+            #[cfg(unix)]
+            GdbServerAddr::Unix(path) => {
+                let listener = UnixListener::bind(path)?;
+                waiting();
+                let (stream, _) = listener.accept()?;
+                // Nobody else can connect, so the socket file has served its purpose.
+                let _ = std::fs::remove_file(path);
+                Box::new(stream)
+            }
+            #[cfg(not(unix))]
+            GdbServerAddr::Unix(_) =>
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are only supported on Unix hosts",
+                )),
+        };
+        Ok(GdbServer {
+            conn: Connection::new(stream),
+            resume: Resume::Stop,
+            running: false,
+            breakpoints: Vec::new(),
+            pending_breakpoints: FxHashMap::default(),
+            step_mode: StepMode::Statement,
+            selected_thread: ThreadId::MAIN_THREAD,
+            selected_frame: 0,
+            last_stop: format!("S{SIGTRAP:02x}"),
+            steps_since_poll: 0,
+        })
+    }
+
+    fn before_step<'tcx>(&mut self, ecx: &mut MiriInterpCx<'tcx>) -> io::Result<Session> {
+        if ecx.active_thread_stack().is_empty() {
+            // The thread has nothing to execute, there is no state to stop in.
+            return Ok(Session::Attached);
+        }
+        let reason = if let Some(n) = self.pending_breakpoints.remove(&ecx.active_thread()) {
+            Some(StopReason::Breakpoint(n))
+        } else if self.step_done(ecx) {
+            Some(StopReason::Step)
+        } else {
+            self.steps_since_poll += 1;
+            if self.steps_since_poll >= INTERRUPT_POLL_INTERVAL {
+                self.steps_since_poll = 0;
+                self.conn.poll_interrupt()?.then_some(StopReason::Interrupt)
+            } else {
+                None
+            }
+        };
+        match reason {
+            Some(reason) => self.stop(ecx, reason),
+            None => Ok(Session::Attached),
+        }
+    }
+
+    /// Remembers whether the frame just pushed on the active thread hits a breakpoint. Breakpoints
+    /// are hit when the function is called, i.e., before its first statement; jumping back to the
+    /// start block from within the function does not hit them again.
+    fn after_stack_push<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>) {
+        if self.breakpoints.is_empty() {
+            return;
+        }
+        let frame = ecx.active_thread_stack().last().unwrap();
+        if let Some(n) = self.hit_breakpoint(ecx, frame) {
+            self.pending_breakpoints.insert(ecx.active_thread(), n);
+        }
+    }
+
+    /// Returns the index of the breakpoint on the function of `frame`, if any.
+    fn hit_breakpoint<'tcx>(
+        &self,
+        ecx: &MiriInterpCx<'tcx>,
+        frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+    ) -> Option<usize> {
+        let path = ecx.tcx.def_path_str(frame.instance().def_id());
+        self.breakpoints.iter().position(|bp| {
+            bp.as_ref().is_some_and(|bp| {
+                path == *bp
+                    || path.strip_suffix(bp.as_str()).is_some_and(|prefix| prefix.ends_with("::"))
+            })
+        })
+    }
+
+    fn step_done<'tcx>(&self, ecx: &MiriInterpCx<'tcx>) -> bool {
+        match self.resume {
+            Resume::Stop => true,
+            Resume::Continue => false,
+            Resume::Step { thread, mode, depth, line, user_relevant } => {
+                if ecx.active_thread() != thread {
+                    return false;
+                }
+                let stack = ecx.active_thread_stack();
+                let frame = stack.last().unwrap();
+                match mode {
+                    StepMode::Statement => true,
+                    StepMode::Line | StepMode::LineOver => {
+                        // Do not stop in the standard library when stepping through user code.
+                        if user_relevant && !frame.extra.is_user_relevant {
+                            return false;
+                        }
+                        match stack.len().cmp(&depth) {
+                            std::cmp::Ordering::Less => true,
+                            std::cmp::Ordering::Equal => line_key(ecx, frame) != line,
+                            std::cmp::Ordering::Greater => mode == StepMode::Line,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop<'tcx>(
+        &mut self,
+        ecx: &mut MiriInterpCx<'tcx>,
+        reason: StopReason,
+    ) -> io::Result<Session> {
+        let thread = ecx.active_thread();
+        self.selected_thread = thread;
+        self.selected_frame = 0;
+        let signal = match reason {
+            StopReason::Interrupt => SIGINT,
+            StopReason::Breakpoint(_) | StopReason::Step => SIGTRAP,
+        };
+        self.last_stop = stop_reply(signal, thread);
+        if self.running {
+            let mut msg = match reason {
+                StopReason::Breakpoint(n) => format!("Breakpoint {}, ", n.strict_add(1)),
+                StopReason::Step | StopReason::Interrupt => String::new(),
+            };
+            let frame = ecx.active_thread_stack().last().unwrap();
+            writeln!(msg, "{}", describe_frame(ecx, frame)).unwrap();
+            self.send_console(&msg)?;
+            self.conn.send(self.last_stop.as_bytes())?;
+            self.running = false;
+        }
+        self.serve(ecx)
+    }
+
+    /// Lets the client inspect the program after it terminated. `exit_code` is `None` if the
+    /// program was stopped by an error.
+    fn report_termination<'tcx>(
+        &mut self,
+        ecx: &mut MiriInterpCx<'tcx>,
+        exit_code: Option<i32>,
+    ) -> io::Result<()> {
+        if let Some(code) = exit_code {
+            if self.running {
+                self.conn.send(format!("W{:02x}", code & 0xff).as_bytes())?;
+            }
+            return Ok(());
+        }
+        let thread = ecx.active_thread();
+        self.selected_thread = thread;
+        self.selected_frame = 0;
+        self.last_stop = stop_reply(SIGABRT, thread);
+        if self.running {
+            self.send_console(
+                "Miri stopped the program because of the error reported above; \
+                its state can still be inspected\n",
+            )?;
+            self.conn.send(self.last_stop.as_bytes())?;
+            self.running = false;
+        }
+        if let Session::Attached = self.serve(ecx)? {
+            // The client tried to resume the program, but there is nothing left to run.
+            self.conn.send(format!("X{SIGABRT:02x}").as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Handles packets until the client resumes the program, detaches, or kills it.
+    fn serve<'tcx>(&mut self, ecx: &mut MiriInterpCx<'tcx>) -> io::Result<Session> {
+        loop {
+            let Incoming::Packet(packet) = self.conn.recv()? else {
+                // The program is already stopped.
+                continue;
+            };
+            match self.handle_packet(ecx, &packet) {
+                Action::Reply(reply) => self.conn.send(&reply)?,
+                Action::Resume(resume) => {
+                    self.resume = resume;
+                    self.running = true;
+                    self.steps_since_poll = 0;
+                    return Ok(Session::Attached);
+                }
+                Action::Detach => {
+                    self.conn.send(b"OK")?;
+                    return Ok(Session::Detached);
+                }
+                Action::Kill { reply } => {
+                    if reply {
+                        self.conn.send(b"OK")?;
+                    }
+                    return Ok(Session::Killed);
+                }
+            }
+        }
+    }
+
+    fn handle_packet<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>, packet: &[u8]) -> Action {
+        let reply = |s: &str| Action::Reply(s.as_bytes().to_vec());
+        let Some((&kind, args)) = packet.split_first() else {
+            return reply("");
+        };
+        match kind {
+            b'?' => reply(&self.last_stop),
+            b'c' | b'C' => Action::Resume(Resume::Continue),
+            b's' | b'S' => Action::Resume(self.step(ecx, ecx.active_thread())),
+            b'D' => Action::Detach,
+            b'k' => Action::Kill { reply: false },
+            b'g' => reply(&self.read_registers(ecx)),
+            b'p' =>
+                match parse_hex(args) {
+                    Some(0) => reply(&self.read_registers(ecx)),
+                    _ => reply("E01"),
+                },
+            b'm' => {
+                let memory = parse_range(args).and_then(|(addr, len)| read_memory(ecx, addr, len));
+                match memory {
+                    Some(bytes) => reply(&to_hex(&bytes)),
+                    None => reply("E01"),
+                }
+            }
+            b'H' =>
+                match args.split_first() {
+                    Some((b'g', thread)) =>
+                        match parse_thread(ecx, thread) {
+                            Some(thread) => {
+                                self.selected_thread = thread;
+                                self.selected_frame = 0;
+                                reply("OK")
+                            }
+                            None => reply("E01"),
+                        },
+                    // All threads run when the program is resumed, there is nothing to select.
+                    _ => reply("OK"),
+                },
+            b'T' =>
+                match parse_thread(ecx, args) {
+                    Some(_) => reply("OK"),
+                    None => reply("E01"),
+                },
+            // Registers and memory are read-only.
+            b'G' | b'P' | b'M' | b'X' => reply("E01"),
+            b'q' => self.handle_query(ecx, args),
+            b'v' => self.handle_v_packet(ecx, args),
+            _ => reply(""),
+        }
+    }
+
+    fn handle_query<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>, query: &[u8]) -> Action {
+        let reply = |s: &str| Action::Reply(s.as_bytes().to_vec());
+        if query.starts_with(b"Supported") {
+            reply("PacketSize=4000;qXfer:features:read+;qXfer:threads:read+;vContSupported+")
+        } else if query == b"Attached" {
+            reply("1")
+        } else if query == b"C" {
+            reply(&format!("QC{:x}", gdb_thread_id(self.selected_thread)))
+        } else if query == b"fThreadInfo" {
+            let ids: Vec<String> = ecx
+                .machine
+                .threads
+                .live_threads()
+                .map(|(id, ..)| format!("{:x}", gdb_thread_id(id)))
+                .collect();
+            reply(&format!("m{}", ids.join(",")))
+        } else if query == b"sThreadInfo" {
+            reply("l")
+        } else if let Some(thread) = query.strip_prefix(b"ThreadExtraInfo,") {
+            let state = parse_thread(ecx, thread).and_then(|thread| {
+                ecx.machine.threads.live_threads().find(|(id, ..)| *id == thread)
+            });
+            match state {
+                Some((_, _, state)) => reply(&to_hex(state.as_bytes())),
+                None => reply("E01"),
+            }
+        } else if let Some(range) = query.strip_prefix(b"Xfer:features:read:target.xml:") {
+            xfer(TARGET_XML, range)
+        } else if let Some(range) = query.strip_prefix(b"Xfer:threads:read::") {
+            xfer(&threads_xml(ecx), range)
+        } else if let Some(cmd) = query.strip_prefix(b"Rcmd,") {
+            let Some(cmd) = packet::from_hex(cmd) else {
+                return reply("E01");
+            };
+            let output = self.monitor(ecx, &String::from_utf8_lossy(&cmd));
+            if output.is_empty() { reply("OK") } else { reply(&to_hex(output.as_bytes())) }
+        } else {
+            reply("")
+        }
+    }
+
+    fn handle_v_packet<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>, packet: &[u8]) -> Action {
+        let reply = |s: &str| Action::Reply(s.as_bytes().to_vec());
+        if packet == b"Cont?" {
+            reply("vCont;c;C;s;S")
+        } else if let Some(actions) = packet.strip_prefix(b"Cont;") {
+            // We only support stepping a single thread while all others run freely, so the first
+            // step action (if any) determines what happens.
+            for action in actions.split(|&b| b == b';') {
+                let (action, thread) = match action.iter().position(|&b| b == b':') {
+                    Some(colon) => (&action[..colon], parse_thread(ecx, &action[colon + 1..])),
+                    None => (action, None),
+                };
+                if matches!(action.first(), Some(b's' | b'S')) {
+                    let thread = thread.unwrap_or_else(|| ecx.active_thread());
+                    return Action::Resume(self.step(ecx, thread));
+                }
+            }
+            Action::Resume(Resume::Continue)
+        } else if packet.starts_with(b"Kill") {
+            Action::Kill { reply: true }
+        } else {
+            reply("")
+        }
+    }
+
+    /// Prepares stepping `thread` according to the current step mode.
+    fn step<'tcx>(&self, ecx: &MiriInterpCx<'tcx>, thread: ThreadId) -> Resume {
+        let stack = thread_stack(ecx, thread);
+        Resume::Step {
+            thread,
+            mode: self.step_mode,
+            depth: stack.len(),
+            line: stack.last().and_then(|frame| line_key(ecx, frame)),
+            user_relevant: stack.last().is_some_and(|frame| frame.extra.is_user_relevant),
+        }
+    }
+
+    fn selected_frame<'a, 'tcx>(
+        &self,
+        ecx: &'a MiriInterpCx<'tcx>,
+    ) -> Option<&'a Frame<'tcx, Provenance, FrameExtra<'tcx>>> {
+        thread_stack(ecx, self.selected_thread).iter().rev().nth(self.selected_frame)
+    }
+
+    fn read_registers<'tcx>(&self, ecx: &MiriInterpCx<'tcx>) -> String {
+        match self.selected_frame(ecx).map(|frame| frame.current_loc()) {
+            Some(Either::Left(loc)) => {
+                let pc = (u64::from(loc.block.as_u32()) << 32) | loc.statement_index.to_u64();
+                to_hex(&pc.to_le_bytes())
+            }
+            // The frame has no MIR body we could be in.
+            _ => "x".repeat(16),
+        }
+    }
+
+    fn send_console(&mut self, msg: &str) -> io::Result<()> {
+        self.conn.send(format!("O{}", to_hex(msg.as_bytes())).as_bytes())
+    }
+
+    /// Runs a `monitor` command and returns its output.
+    fn monitor<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>, cmd: &str) -> String {
+        let cmd = cmd.trim();
+        let (name, arg) = cmd.split_once(' ').map_or((cmd, ""), |(name, arg)| (name, arg.trim()));
+        let mut out = String::new();
+        match name {
+            "" | "help" => out.push_str(HELP),
+            "break" =>
+                if arg.is_empty() {
+                    out.push_str("usage: monitor break <fn>\n");
+                } else {
+                    self.breakpoints.push(Some(arg.to_owned()));
+                    writeln!(out, "Breakpoint {} on `{arg}`", self.breakpoints.len()).unwrap();
+                },
+            "breakpoints" =>
+                for (n, bp) in self.breakpoints.iter().enumerate() {
+                    if let Some(bp) = bp {
+                        writeln!(out, "{}: `{bp}`", n.strict_add(1)).unwrap();
+                    }
+                },
+            "delete" =>
+                if arg.is_empty() {
+                    self.breakpoints.clear();
+                } else {
+                    match arg.parse::<usize>().ok().and_then(|n| n.checked_sub(1)) {
+                        Some(n) if self.breakpoints.get(n).is_some_and(Option::is_some) =>
+                            self.breakpoints[n] = None,
+                        _ => writeln!(out, "no breakpoint number {arg}").unwrap(),
+                    }
+                },
+            "step-mode" => {
+                match arg {
+                    "statement" => self.step_mode = StepMode::Statement,
+                    "line" => self.step_mode = StepMode::Line,
+                    "line-over" => self.step_mode = StepMode::LineOver,
+                    "" => {}
+                    _ => writeln!(out, "unknown step mode `{arg}`").unwrap(),
+                }
+                writeln!(out, "`stepi` steps by {}", self.step_mode.name()).unwrap();
+            }
+            "threads" =>
+                for (id, stack, state) in ecx.machine.threads.live_threads() {
+                    let marker = if id == self.selected_thread { '*' } else { ' ' };
+                    write!(
+                        out,
+                        "{marker} {} `{}` ({state})",
+                        gdb_thread_id(id),
+                        ecx.machine.threads.get_thread_display_name(id),
+                    )
+                    .unwrap();
+                    match stack.last() {
+                        Some(frame) => writeln!(out, " in {}", describe_frame(ecx, frame)).unwrap(),
+                        None => out.push('\n'),
+                    }
+                },
+            "backtrace" | "bt" =>
+                for (n, frame) in thread_stack(ecx, self.selected_thread).iter().rev().enumerate() {
+                    let marker = if n == self.selected_frame { '*' } else { ' ' };
+                    writeln!(out, "{marker} #{n} {}", describe_frame(ecx, frame)).unwrap();
+                },
+            "frame" => {
+                if !arg.is_empty() {
+                    match arg.parse::<usize>() {
+                        Ok(n) if n < thread_stack(ecx, self.selected_thread).len() =>
+                            self.selected_frame = n,
+                        _ => writeln!(out, "no frame number {arg}").unwrap(),
+                    }
+                }
+                if let Some(frame) = self.selected_frame(ecx) {
+                    writeln!(out, "#{} {}", self.selected_frame, describe_frame(ecx, frame))
+                        .unwrap();
+                }
+            }
+            "locals" =>
+                match self.selected_frame(ecx) {
+                    Some(frame) => out = describe_locals(ecx, frame),
+                    None => out.push_str("the selected thread has no frames\n"),
+                },
+            "alloc" => {
+                let addr = match arg.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                    None => arg.parse::<u64>().ok(),
+                };
+                match addr.and_then(|addr| ecx.alloc_containing_addr(addr)) {
+                    Some((alloc_id, base)) => {
+                        let info = ecx.get_alloc_info(alloc_id);
+                        writeln!(
+                            out,
+                            "{alloc_id:?}: {} bytes at {base:#x}, {:?}, aligned to {} bytes",
+                            info.size.bytes(),
+                            info.kind,
+                            info.align.bytes(),
+                        )
+                        .unwrap();
+                        if let Some(span) = ecx.machine.allocated_span(alloc_id) {
+                            let span =
+                                ecx.tcx.sess.source_map().span_to_diagnostic_string(span.span());
+                            writeln!(out, "allocated at {span}").unwrap();
+                        }
+                    }
+                    None => writeln!(out, "no live allocation contains address `{arg}`").unwrap(),
+                }
+            }
+            _ => writeln!(out, "unknown command `{name}`, see `monitor help`").unwrap(),
+        }
+        out
+    }
+}
+
+/// The thread ID reported to the client. GDB reserves 0 and -1, so we count from 1.
+fn gdb_thread_id(thread: ThreadId) -> u64 {
+    u64::from(thread.to_u32()) + 1
+}
+
+/// Parses a thread ID sent by the client. "Any thread" and "all threads" resolve to the active
+/// thread; unknown and terminated threads to `None`.
+fn parse_thread<'tcx>(ecx: &MiriInterpCx<'tcx>, id: &[u8]) -> Option<ThreadId> {
+    if id == b"0" || id == b"-1" {
+        return Some(ecx.active_thread());
+    }
+    let id = parse_hex(id)?.checked_sub(1)?;
+    ecx.machine
+        .threads
+        .live_threads()
+        .map(|(thread, ..)| thread)
+        .find(|thread| u64::from(thread.to_u32()) == id)
+}
+
+fn stop_reply(signal: u8, thread: ThreadId) -> String {
+    format!("T{signal:02x}thread:{:x};", gdb_thread_id(thread))
+}
+
+fn thread_stack<'a, 'tcx>(
+    ecx: &'a MiriInterpCx<'tcx>,
+    thread: ThreadId,
+) -> &'a [Frame<'tcx, Provenance, FrameExtra<'tcx>>] {
+    ecx.machine
+        .threads
+        .all_stacks()
+        .find(|(id, _)| *id == thread)
+        .map_or(&[][..], |(_, stack)| stack)
+}
+
+fn line_key<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+) -> Option<LineKey> {
+    let (file, line, ..) = ecx.tcx.sess.source_map().span_to_location_info(frame.current_span());
+    Some((file?.start_pos, line))
+}
+
+fn describe_frame<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+) -> String {
+    let span = ecx.tcx.sess.source_map().span_to_diagnostic_string(frame.current_span());
+    format!("`{}` at {span}", frame.instance())
+}
+
+/// Lists the locals of `frame` that have a name in the source. Values that live in registers are
+/// printed; for those in memory only the address is given, since reading them could trigger the
+/// machine's access hooks.
+fn describe_locals<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+) -> String {
+    let body = frame.body();
+    let mut names = FxHashMap::default();
+    for info in &body.var_debug_info {
+        if let mir::VarDebugInfoContents::Place(place) = info.value
+            && place.projection.is_empty()
+        {
+            names.entry(place.local).or_insert(info.name);
+        }
+    }
+    let mut out = String::new();
+    for local in body.local_decls.indices() {
+        let Some(name) = names.get(&local) else { continue };
+        let Ok(layout) = ecx.layout_of_local(frame, local, None).report_err() else {
+            writeln!(out, "{name}: <unknown layout>").unwrap();
+            continue;
+        };
+        write!(out, "{name}: {} = ", layout.ty).unwrap();
+        match frame.locals[local].as_mplace_or_imm() {
+            None => out.push_str("<dead>\n"),
+            Some(Either::Left((ptr, _))) =>
+                writeln!(
+                    out,
+                    "<{} bytes in memory at {:#x}>",
+                    layout.size.bytes(),
+                    ptr.addr().bytes()
+                )
+                .unwrap(),
+            Some(Either::Right(Immediate::Uninit)) => out.push_str("<uninit>\n"),
+            Some(Either::Right(imm)) =>
+                writeln!(out, "{}", ImmTy::from_immediate(imm, layout)).unwrap(),
+        }
+    }
+    out
+}
+
+/// Reads up to `len` bytes at `addr`, stopping at the end of the allocation.
+fn read_memory<'tcx>(ecx: &MiriInterpCx<'tcx>, addr: u64, len: u64) -> Option<Vec<u8>> {
+    let (alloc_id, base) = ecx.alloc_containing_addr(addr)?;
+    let alloc = ecx.get_alloc_raw(alloc_id).report_err().ok()?;
+    let start = addr.strict_sub(base).to_usize();
+    let end = start.saturating_add(len.to_usize()).min(alloc.len());
+    Some(alloc.inspect_with_uninit_and_ptr_outside_interpreter(start..end).to_vec())
+}
+
+fn threads_xml<'tcx>(ecx: &MiriInterpCx<'tcx>) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\"?>\n<threads>\n");
+    for (id, ..) in ecx.machine.threads.live_threads() {
+        let name = ecx.machine.threads.get_thread_display_name(id);
+        writeln!(xml, "  <thread id=\"{:x}\" name=\"{}\"/>", gdb_thread_id(id), xml_escape(&name))
+            .unwrap();
+    }
+    xml.push_str("</threads>\n");
+    xml
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Parses the `<start>,<length>` arguments of memory reads and transfers.
+fn parse_range(args: &[u8]) -> Option<(u64, u64)> {
+    let comma = args.iter().position(|&b| b == b',')?;
+    Some((parse_hex(&args[..comma])?, parse_hex(&args[comma + 1..])?))
+}
+
+/// Answers a `qXfer` read of `data`.
+fn xfer(data: &str, range: &[u8]) -> Action {
+    let Some((offset, len)) = parse_range(range) else {
+        return Action::Reply(b"E00".to_vec());
+    };
+    let data = data.as_bytes();
+    let start = offset.to_usize().min(data.len());
+    let end = start.saturating_add(len.to_usize()).min(data.len());
+    let mut reply = vec![if end == data.len() { b'l' } else { b'm' }];
+    reply.extend(escape(&data[start..end]));
+    Action::Reply(reply)
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Gives the debugger a chance to stop the program before the active thread takes its next
+    /// step.
+    fn gdbserver_before_step(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let Some(mut server) = this.machine.gdbserver.take() else {
+            return interp_ok(());
+        };
+        match server.before_step(this) {
+            Ok(Session::Attached) => this.machine.gdbserver = Some(server),
+            Ok(Session::Detached) => {}
+            Ok(Session::Killed) => {
+                this.machine.handle_abnormal_termination();
+                throw_machine_stop!(TerminationInfo::Interrupted);
+            }
+            Err(err) => this.tcx.dcx().warn(format!("lost the connection to the debugger: {err}")),
+        }
+        interp_ok(())
+    }
+
+    /// Lets the debugger check whether the frame that was just pushed hits a breakpoint.
+    fn gdbserver_after_stack_push(&mut self) {
+        let this = self.eval_context_mut();
+        if let Some(mut server) = this.machine.gdbserver.take() {
+            server.after_stack_push(this);
+            this.machine.gdbserver = Some(server);
+        }
+    }
+
+    /// Tells the debugger that the program terminated, and lets it inspect the final state if the
+    /// program was stopped by an error (`exit_code` is `None`).
+    fn gdbserver_report_termination(&mut self, exit_code: Option<i32>) {
+        let this = self.eval_context_mut();
+        let Some(mut server) = this.machine.gdbserver.take() else {
+            return;
+        };
+        if let Err(err) = server.report_termination(this, exit_code) {
+            this.tcx.dcx().warn(format!("lost the connection to the debugger: {err}"));
+        }
+    }
+}
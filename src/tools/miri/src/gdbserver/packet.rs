@@ -0,0 +1,235 @@
+//! Framing for the GDB remote serial protocol.
+//!
+//! Packets look like `$<data>#<checksum>`, where the checksum is the sum of all data bytes modulo
+//! 256 as two hex digits. Each packet is acknowledged by the receiver with `+` (or `-` to request
+//! a retransmission). While the program runs, the client can send a single `0x03` byte to
+//! interrupt it.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+/// The byte sent by the client to interrupt the running program.
+const INTERRUPT: u8 = 0x03;
+
+/// A bidirectional byte stream the client is connected through.
+pub trait Stream: Read + Write {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Stream for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+/// Something the client sent us.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Incoming {
+    /// The client asked to interrupt the program.
+    Interrupt,
+    /// A packet, with its framing and checksum already removed.
+    Packet(Vec<u8>),
+}
+
+/// A connection to a debugger client.
+pub struct Connection {
+    stream: Box<dyn Stream>,
+    /// Bytes received but not yet consumed.
+    buf: Vec<u8>,
+}
+
+impl Connection {
+    pub fn new(stream: Box<dyn Stream>) -> Self {
+        Connection { stream, buf: Vec::new() }
+    }
+
+    /// Reads more bytes into `buf`. Returns `false` if nothing could be read without blocking.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0; 4096];
+        match self.stream.read(&mut chunk) {
+            Ok(0) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the debugger disconnected")),
+            Ok(n) => {
+                self.buf.extend_from_slice(&chunk[..n]);
+                Ok(true)
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Blocks until the client sends a packet or an interrupt.
+    pub fn recv(&mut self) -> io::Result<Incoming> {
+        loop {
+            match parse_incoming(&mut self.buf) {
+                Some(Ok(incoming)) => {
+                    if matches!(incoming, Incoming::Packet(_)) {
+                        self.stream.write_all(b"+")?;
+                    }
+                    return Ok(incoming);
+                }
+                Some(Err(())) => {
+                    // Checksum mismatch, ask for the packet again.
+                    self.stream.write_all(b"-")?;
+                }
+                None => {
+                    self.fill()?;
+                }
+            }
+        }
+    }
+
+    /// Checks without blocking whether the client asked to interrupt the program. Any other
+    /// traffic while the program runs is dropped; the client must not send packets then.
+    pub fn poll_interrupt(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let res = self.fill();
+        self.stream.set_nonblocking(false)?;
+        res?;
+        let interrupted = self.buf.contains(&INTERRUPT);
+        self.buf.clear();
+        Ok(interrupted)
+    }
+
+    /// Sends a packet. We do not wait for the acknowledgment: the stream is reliable, and
+    /// `recv` skips over any acknowledgments when looking for the next packet.
+    pub fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        self.stream.write_all(&frame(data))?;
+        self.stream.flush()
+    }
+}
+
+/// Wraps `data` in packet framing.
+fn frame(data: &[u8]) -> Vec<u8> {
+    let checksum = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+    let mut packet = Vec::with_capacity(data.len().strict_add(4));
+    packet.push(b'$');
+    packet.extend_from_slice(data);
+    packet.push(b'#');
+    packet.extend_from_slice(format!("{checksum:02x}").as_bytes());
+    packet
+}
+
+/// Removes the first complete message from `buf`. Returns `None` if more bytes are needed, and
+/// `Some(Err(()))` if a packet with a bad checksum was dropped.
+fn parse_incoming(buf: &mut Vec<u8>) -> Option<Result<Incoming, ()>> {
+    // Skip acknowledgments and line noise.
+    let start = buf.iter().position(|&b| b == b'$' || b == INTERRUPT);
+    let Some(start) = start else {
+        buf.clear();
+        return None;
+    };
+    buf.drain(..start);
+    if buf[0] == INTERRUPT {
+        buf.remove(0);
+        return Some(Ok(Incoming::Interrupt));
+    }
+    let hash = buf.iter().position(|&b| b == b'#')?;
+    let end = hash.strict_add(3);
+    if buf.len() < end {
+        return None;
+    }
+    let packet: Vec<u8> = buf.drain(..end).collect();
+    let data = &packet[1..hash];
+    let checksum = std::str::from_utf8(&packet[hash.strict_add(1)..])
+        .ok()
+        .and_then(|s| u8::from_str_radix(s, 16).ok());
+    if checksum != Some(data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))) {
+        return Some(Err(()));
+    }
+    Some(Ok(Incoming::Packet(unescape(data))))
+}
+
+/// Undoes the `}` escaping of binary data: the byte after `}` is XORed with `0x20`.
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&b) = bytes.next() {
+        if b == b'}' {
+            if let Some(&next) = bytes.next() {
+                out.push(next ^ 0x20);
+            }
+        } else {
+            out.push(b);
+        }
+    }
+    out
+}
+
+/// Escapes binary data so it can be sent in a packet.
+pub fn escape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for &b in data {
+        if matches!(b, b'#' | b'$' | b'}' | b'*') {
+            out.push(b'}');
+            out.push(b ^ 0x20);
+        } else {
+            out.push(b);
+        }
+    }
+    out
+}
+
+/// Encodes bytes as lowercase hex digits.
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decodes hex digits into bytes.
+pub fn from_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks(2).map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()).collect()
+}
+
+/// Parses a hex number, as used for addresses, lengths and thread IDs.
+pub fn parse_hex(hex: &[u8]) -> Option<u64> {
+    u64::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame() {
+        assert_eq!(frame(b"OK"), b"$OK#9a");
+        assert_eq!(frame(b""), b"$#00");
+    }
+
+    #[test]
+    fn test_parse_incoming() {
+        let mut buf = b"+$qC#b4\x03$m10,4#".to_vec();
+        assert_eq!(parse_incoming(&mut buf), Some(Ok(Incoming::Packet(b"qC".to_vec()))));
+        assert_eq!(parse_incoming(&mut buf), Some(Ok(Incoming::Interrupt)));
+        // The checksum has not arrived yet.
+        assert_eq!(parse_incoming(&mut buf), None);
+        buf.extend_from_slice(b"00");
+        assert_eq!(parse_incoming(&mut buf), Some(Err(())));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_escape() {
+        let data = b"a}b#c$d*e";
+        assert_eq!(escape(data), b"a}]b}\x03c}\x04d}\x0ae");
+        assert_eq!(unescape(&escape(data)), data);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex(b"\x00\xab"), "00ab");
+        assert_eq!(from_hex(b"00ab"), Some(b"\x00\xab".to_vec()));
+        assert_eq!(from_hex(b"0"), None);
+        assert_eq!(parse_hex(b"7fff"), Some(0x7fff));
+    }
+}
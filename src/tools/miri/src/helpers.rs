@@ -422,7 +422,23 @@ fn gen_random(&mut self, ptr: Pointer, len: u64) -> InterpResult<'tcx> {
 
         let mut data = vec![0; usize::try_from(len).unwrap()];
 
-        if this.machine.communicate() {
+        if let Some(nondet) = this.machine.nondet.clone()
+            && this.machine.communicate()
+        {
+            // Fill the buffer using the host's rng, or with the recorded bytes.
+            data = nondet
+                .host("random", || {
+                    getrandom::fill(&mut data)
+                        .map(|()| data)
+                        .map_err(|err| err.raw_os_error().unwrap_or(-1))
+                })
+                .map_err(|code| {
+                    err_unsup_format!("host getrandom failed with error code {code}")
+                })?;
+            if data.len() != usize::try_from(len).unwrap() {
+                throw_unsup_format!("the recorded random bytes do not have the requested length");
+            }
+        } else if this.machine.communicate() {
             // Fill the buffer using the host's rng.
             getrandom::fill(&mut data)
                 .map_err(|err| err_unsup_format!("host getrandom failed: {}", err))?;
@@ -0,0 +1,279 @@
+//! Profiling the interpreted program, see `-Zmiri-profile`.
+//!
+//! Unlike `-Zmiri-measureme`, which measures how long Miri takes, this measures *what the program
+//! makes Miri do*: every basic block the program executes counts as one step, and so does every
+//! retag and every memory access that the borrow tracker has to check. Steps are attributed to the
+//! call stack of the thread that performed them, with borrow tracker steps collected in a
+//! `[borrow tracker]` pseudo-frame on top of the function that caused them. Threads are shown with
+//! the name they have when the profile is written, since programs usually name their threads only
+//! after they started running. Steps are deterministic, so profiles of different runs can be
+//! compared directly.
+//!
+//! The profile is written in one of two formats, depending on the extension of the file:
+//! - `.json`: a Chrome trace (for `chrome://tracing`, Perfetto, or speedscope) with one event per
+//!   function call, using one microsecond per step as the clock.
+//! - anything else: folded stacks (one `frame;frame;frame steps` line per call stack), as consumed
+//!   by `flamegraph.pl` and `inferno-flamegraph`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::ty::Instance;
+use serde_json::json;
+
+use crate::*;
+
+/// A node in the call tree of the profile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct NodeId(usize);
+
+/// What a node in the call tree represents, relative to its parent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum NodeKey<'tcx> {
+    Call(Instance<'tcx>),
+    BorrowTracker,
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    /// The steps spent in this node itself, excluding its children.
+    steps: u64,
+}
+
+/// The profiling state of a stack frame.
+#[derive(Copy, Clone, Debug)]
+pub struct ProfileFrame {
+    node: NodeId,
+    /// The value of the step counter when this frame was pushed.
+    start: u64,
+}
+
+#[derive(Debug)]
+pub struct InterpProfile<'tcx> {
+    path: PathBuf,
+    /// The number of steps so far; this is the clock of the Chrome trace.
+    steps: u64,
+    /// The call tree; the roots are the threads, which are only named when the profile is written.
+    nodes: Vec<Node>,
+    children: FxHashMap<(NodeId, NodeKey<'tcx>), NodeId>,
+    threads: FxHashMap<ThreadId, NodeId>,
+    /// The Chrome trace, if that format was requested. The events are written as the calls
+    /// finish, so the profile of a long execution does not need to be kept in memory.
+    trace: Option<BufWriter<File>>,
+    /// Whether any event has been written to the trace yet.
+    trace_started: bool,
+    /// The first error that occurred while writing the trace; it is reported by `finish`.
+    trace_error: Option<io::Error>,
+    finished: bool,
+}
+
+impl<'tcx> InterpProfile<'tcx> {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let trace = if path.extension().is_some_and(|ext| ext == "json") {
+            Some(BufWriter::new(File::create(path)?))
+        } else {
+            None
+        };
+        Ok(InterpProfile {
+            path: path.to_owned(),
+            steps: 0,
+            nodes: Vec::new(),
+            children: FxHashMap::default(),
+            threads: FxHashMap::default(),
+            trace,
+            trace_started: false,
+            trace_error: None,
+            finished: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records a call of `instance` on `thread`. `caller` is the profiling state of the calling
+    /// frame, if there is one.
+    pub fn push_frame(
+        &mut self,
+        thread: ThreadId,
+        caller: Option<ProfileFrame>,
+        instance: Instance<'tcx>,
+    ) -> ProfileFrame {
+        let parent = match caller {
+            Some(caller) => caller.node,
+            None => self.thread_root(thread),
+        };
+        let node = self.child(parent, NodeKey::Call(instance), || instance.to_string());
+        ProfileFrame { node, start: self.steps }
+    }
+
+    /// Records the end of a call on `thread`.
+    pub fn pop_frame(&mut self, thread: ThreadId, frame: ProfileFrame) {
+        if self.trace.is_none() {
+            return;
+        }
+        let event = json!({
+            "name": self.nodes[frame.node.0].name,
+            "ph": "X",
+            "ts": frame.start,
+            "dur": self.steps.strict_sub(frame.start),
+            "pid": 0,
+            "tid": thread.to_u32(),
+        });
+        self.write_event(event);
+    }
+
+    /// Records that the frame executed a basic block.
+    pub fn step(&mut self, frame: ProfileFrame) {
+        self.nodes[frame.node.0].steps += 1;
+        self.steps += 1;
+    }
+
+    /// Records that the borrow tracker checked a retag or memory access for the frame.
+    pub fn borrow_tracker_step(&mut self, frame: ProfileFrame) {
+        let node = self.child(frame.node, NodeKey::BorrowTracker, || "[borrow tracker]".to_owned());
+        self.nodes[node.0].steps += 1;
+        self.steps += 1;
+    }
+
+    /// Writes out the profile. `open_frames` are the frames that are still on some stack; in the
+    /// Chrome trace, their calls end now. `thread_name` returns the current name of a thread.
+    pub fn finish(
+        &mut self,
+        open_frames: impl Iterator<Item = (ThreadId, ProfileFrame)>,
+        thread_name: impl Fn(ThreadId) -> String,
+    ) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.name_threads(thread_name);
+        if self.trace.is_some() {
+            for (thread, frame) in open_frames {
+                self.pop_frame(thread, frame);
+            }
+            if let Some(err) = self.trace_error.take() {
+                return Err(err);
+            }
+            let trace = self.trace.as_mut().unwrap();
+            if !self.trace_started {
+                write!(trace, "[")?;
+            }
+            writeln!(trace, "\n]")?;
+            trace.flush()
+        } else {
+            let mut out = BufWriter::new(File::create(&self.path)?);
+            out.write_all(self.folded().as_bytes())?;
+            out.flush()
+        }
+    }
+
+    /// Renders the call tree as folded stacks.
+    fn folded(&self) -> String {
+        let mut out = String::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if node.steps == 0 {
+                continue;
+            }
+            let mut stack = Vec::new();
+            let mut current = Some(NodeId(idx));
+            while let Some(id) = current {
+                // `;` separates the frames, so it must not appear in the names (e.g. in array
+                // types).
+                stack.push(self.nodes[id.0].name.replace(';', ","));
+                current = self.nodes[id.0].parent;
+            }
+            stack.reverse();
+            out.push_str(&format!("{} {}\n", stack.join(";"), node.steps));
+        }
+        out
+    }
+
+    fn thread_root(&mut self, thread: ThreadId) -> NodeId {
+        if let Some(&root) = self.threads.get(&thread) {
+            return root;
+        }
+        let root = self.new_node(String::new(), None);
+        self.threads.insert(thread, root);
+        root
+    }
+
+    /// Names the roots of the call tree after their threads, and tells the Chrome trace about the
+    /// names.
+    fn name_threads(&mut self, thread_name: impl Fn(ThreadId) -> String) {
+        let mut threads: Vec<_> =
+            self.threads.iter().map(|(&thread, &root)| (thread, root)).collect();
+        threads.sort_by_key(|&(thread, _)| thread);
+        for (thread, root) in threads {
+            let name = thread_name(thread);
+            if self.trace.is_some() {
+                let event = json!({
+                    "name": "thread_name",
+                    "ph": "M",
+                    "pid": 0,
+                    "tid": thread.to_u32(),
+                    "args": { "name": name },
+                });
+                self.write_event(event);
+            }
+            self.nodes[root.0].name = format!("[thread {name}]");
+        }
+    }
+
+    fn child(
+        &mut self,
+        parent: NodeId,
+        key: NodeKey<'tcx>,
+        name: impl FnOnce() -> String,
+    ) -> NodeId {
+        if let Some(&child) = self.children.get(&(parent, key)) {
+            return child;
+        }
+        let child = self.new_node(name(), Some(parent));
+        self.children.insert((parent, key), child);
+        child
+    }
+
+    fn new_node(&mut self, name: String, parent: Option<NodeId>) -> NodeId {
+        self.nodes.push(Node { name, parent, steps: 0 });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    fn write_event(&mut self, event: serde_json::Value) {
+        let trace = self.trace.as_mut().unwrap();
+        let separator = if self.trace_started { ",\n" } else { "[\n" };
+        self.trace_started = true;
+        if let Err(err) = write!(trace, "{separator}{event}") {
+            self.trace_error.get_or_insert(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folded() {
+        let mut profile = InterpProfile::new(Path::new("profile.folded")).unwrap();
+        let root = profile.thread_root(ThreadId::MAIN_THREAD);
+        let frame = ProfileFrame { node: root, start: 0 };
+        profile.step(frame);
+        profile.borrow_tracker_step(frame);
+        profile.borrow_tracker_step(frame);
+        let array = profile.new_node("<[u8; 4] as Foo>::foo".to_owned(), Some(root));
+        profile.step(ProfileFrame { node: array, start: 1 });
+        profile.name_threads(|_| "main".to_owned());
+        assert_eq!(
+            profile.folded(),
+            "[thread main] 1\n\
+             [thread main];[borrow tracker] 2\n\
+             [thread main];<[u8, 4] as Foo>::foo 1\n"
+        );
+        assert_eq!(profile.steps, 4);
+    }
+}
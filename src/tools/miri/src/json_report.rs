@@ -0,0 +1,264 @@
+//! Machine-readable reports of Miri's findings, see `-Zmiri-report-format=json`.
+//!
+//! Every finding (an error that stopped the program, or leaked memory) is printed to stderr as a
+//! single line containing one JSON object. The format is versioned by the `version` field; fields
+//! may be added, but existing fields keep their meaning.
+
+use rustc_abi::{Align, Size};
+use rustc_span::{Span, SpanData};
+use serde_json::{Value, json};
+
+use crate::*;
+
+/// The version of the report format.
+const VERSION: u32 = 1;
+
+/// The parts of an error report that are already computed for the human-readable rendering.
+pub struct ErrorReport<'a> {
+    pub kind: &'static str,
+    pub class: Option<&'static str>,
+    pub message: String,
+    pub labels: &'a [String],
+    pub notes: &'a [(Option<SpanData>, String)],
+    pub allocations: Vec<AllocId>,
+    pub tag_history: Vec<(Option<SpanData>, String)>,
+}
+
+/// Classifies an error for the `kind` field, and for Undefined Behavior also returns the class of
+/// UB, e.g. `PointerUseAfterFree`.
+pub fn classify(kind: &InterpErrorKind<'_>) -> (&'static str, Option<&'static str>) {
+    use InterpErrorKind::*;
+    match kind {
+        MachineStop(info) => {
+            let info = info.downcast_ref::<TerminationInfo>().expect("invalid MachineStop payload");
+            use TerminationInfo::*;
+            match info {
+                StackedBorrowsUb { .. } => ("ub", Some("StackedBorrows")),
+                TreeBorrowsUb { .. } => ("ub", Some("TreeBorrows")),
+                DataRace { .. } => ("data_race", None),
+                Deadlock => ("deadlock", None),
+                UnsupportedInIsolation(_)
+                | Int2PtrWithStrictProvenance
+                | UnsupportedForeignItem(_) => ("unsupported", None),
+                Abort(_) => ("abort", None),
+                Interrupted => ("interrupted", None),
+                _ => ("error", None),
+            }
+        }
+        UndefinedBehavior(info) => ("ub", Some(ub_class(info))),
+        Unsupported(_) => ("unsupported", None),
+        _ => ("error", None),
+    }
+}
+
+/// The class of UB reported for the `class` field. These names are part of the report format,
+/// so they must not change when the variants of `UndefinedBehaviorInfo` are renamed.
+fn ub_class(info: &UndefinedBehaviorInfo<'_>) -> &'static str {
+    use UndefinedBehaviorInfo::*;
+    match info {
+        Ub(_) => "Ub",
+        Custom(_) => "Custom",
+        ValidationError(_) => "ValidationError",
+        Unreachable => "Unreachable",
+        BoundsCheckFailed { .. } => "BoundsCheckFailed",
+        DivisionByZero => "DivisionByZero",
+        RemainderByZero => "RemainderByZero",
+        DivisionOverflow => "DivisionOverflow",
+        RemainderOverflow => "RemainderOverflow",
+        PointerArithOverflow => "PointerArithOverflow",
+        ArithOverflow { .. } => "ArithOverflow",
+        ShiftOverflow { .. } => "ShiftOverflow",
+        InvalidMeta(_) => "InvalidMeta",
+        UnterminatedCString(_) => "UnterminatedCString",
+        PointerUseAfterFree(..) => "PointerUseAfterFree",
+        PointerOutOfBounds { .. } => "PointerOutOfBounds",
+        DanglingIntPointer { .. } => "DanglingIntPointer",
+        AlignmentCheckFailed(..) => "AlignmentCheckFailed",
+        WriteToReadOnly(_) => "WriteToReadOnly",
+        DerefFunctionPointer(_) => "DerefFunctionPointer",
+        DerefVTablePointer(_) => "DerefVTablePointer",
+        DerefTypeIdPointer(_) => "DerefTypeIdPointer",
+        InvalidBool(_) => "InvalidBool",
+        InvalidChar(_) => "InvalidChar",
+        InvalidTag(_) => "InvalidTag",
+        InvalidFunctionPointer(_) => "InvalidFunctionPointer",
+        InvalidVTablePointer(_) => "InvalidVTablePointer",
+        InvalidVTableTrait { .. } => "InvalidVTableTrait",
+        InvalidStr(_) => "InvalidStr",
+        InvalidUninitBytes(_) => "InvalidUninitBytes",
+        DeadLocal => "DeadLocal",
+        ScalarSizeMismatch(_) => "ScalarSizeMismatch",
+        UninhabitedEnumVariantWritten(_) => "UninhabitedEnumVariantWritten",
+        UninhabitedEnumVariantRead(_) => "UninhabitedEnumVariantRead",
+        InvalidNichedEnumVariantWritten { .. } => "InvalidNichedEnumVariantWritten",
+        AbiMismatchArgument { .. } => "AbiMismatchArgument",
+        AbiMismatchReturn { .. } => "AbiMismatchReturn",
+    }
+}
+
+/// Returns the allocations that the error is about.
+pub fn involved_allocations(kind: &InterpErrorKind<'_>) -> Vec<AllocId> {
+    use InterpErrorKind::*;
+    use UndefinedBehaviorInfo::*;
+    match kind {
+        UndefinedBehavior(PointerUseAfterFree(alloc_id, _))
+        | UndefinedBehavior(PointerOutOfBounds { alloc_id, .. })
+        | UndefinedBehavior(InvalidUninitBytes(Some((alloc_id, _)))) => vec![*alloc_id],
+        MachineStop(info) =>
+            match info.downcast_ref::<TerminationInfo>() {
+                Some(TerminationInfo::DataRace { ptr, .. }) => vec![ptr.provenance],
+                _ => vec![],
+            },
+        _ => vec![],
+    }
+}
+
+/// Returns the history of the borrow tracker tag involved in the error, if any.
+pub fn tag_history(kind: &InterpErrorKind<'_>) -> Vec<(Option<SpanData>, String)> {
+    let InterpErrorKind::MachineStop(info) = kind else {
+        return vec![];
+    };
+    match info.downcast_ref::<TerminationInfo>() {
+        Some(TerminationInfo::StackedBorrowsUb { history: Some(history), .. }) => {
+            let mut events = vec![(Some(history.created.1), history.created.0.clone())];
+            events.extend(history.invalidated.iter().map(|(msg, span)| (Some(*span), msg.clone())));
+            events.extend(history.protected.iter().map(|(msg, span)| (Some(*span), msg.clone())));
+            events
+        }
+        Some(TerminationInfo::TreeBorrowsUb { history, .. }) => history.events.clone(),
+        _ => vec![],
+    }
+}
+
+/// Prints the report of an error that stopped the program.
+pub fn emit_error<'tcx>(ecx: &MiriInterpCx<'tcx>, report: ErrorReport<'_>) {
+    let machine = &ecx.machine;
+    let threads: Vec<Value> = machine
+        .threads
+        .all_stacks()
+        .map(|(thread, stack)| {
+            let stacktrace = Frame::generate_stacktrace_from_stack(stack);
+            json!({
+                "id": thread.to_u32(),
+                "name": machine.threads.get_thread_display_name(thread),
+                "active": thread == ecx.active_thread(),
+                "backtrace": backtrace_json(machine, &stacktrace),
+            })
+        })
+        .collect();
+    let allocations: Vec<Value> = report
+        .allocations
+        .iter()
+        .map(|&alloc_id| {
+            let created_at = machine.allocated_span(alloc_id);
+            let freed_at = machine.deallocated_span(alloc_id);
+            json!({
+                "id": alloc_id.0.get(),
+                "created_at": created_at.map(|span| span_json(machine, span.span())),
+                "freed_at": freed_at.map(|span| span_json(machine, span.span())),
+            })
+        })
+        .collect();
+    emit(json!({
+        "version": VERSION,
+        "kind": report.kind,
+        "class": report.class,
+        "message": report.message,
+        "labels": report.labels,
+        "notes": messages_json(machine, report.notes),
+        "threads": threads,
+        "allocations": allocations,
+        "tag_history": messages_json(machine, &report.tag_history),
+    }));
+}
+
+/// Prints the report of a leaked allocation. `backtrace` is where it was allocated, if known.
+pub fn emit_leak<'tcx>(
+    machine: &MiriMachine<'tcx>,
+    message: String,
+    alloc_id: AllocId,
+    kind: MemoryKind,
+    size: Size,
+    align: Align,
+    backtrace: Option<&[FrameInfo<'tcx>]>,
+) {
+    emit(json!({
+        "version": VERSION,
+        "kind": "leak",
+        "class": null,
+        "message": message,
+        "allocations": [{
+            "id": alloc_id.0.get(),
+            "memory_kind": kind.to_string(),
+            "size": size.bytes(),
+            "align": align.bytes(),
+            "backtrace": backtrace.map(|backtrace| backtrace_json(machine, backtrace)),
+        }],
+    }));
+}
+
+/// Prints the report of threads that were still running when the main thread terminated.
+pub fn emit_thread_leak<'tcx>(ecx: &MiriInterpCx<'tcx>, message: &str) {
+    let machine = &ecx.machine;
+    let threads: Vec<Value> = machine
+        .threads
+        .all_stacks()
+        .filter(|(_thread, stack)| !stack.is_empty())
+        .map(|(thread, stack)| {
+            let stacktrace = Frame::generate_stacktrace_from_stack(stack);
+            json!({
+                "id": thread.to_u32(),
+                "name": machine.threads.get_thread_display_name(thread),
+                "backtrace": backtrace_json(machine, &stacktrace),
+            })
+        })
+        .collect();
+    emit(json!({
+        "version": VERSION,
+        "kind": "leak",
+        "class": "threads",
+        "message": message,
+        "threads": threads,
+    }));
+}
+
+fn emit(report: Value) {
+    eprintln!("{report}");
+}
+
+fn backtrace_json<'tcx>(machine: &MiriMachine<'tcx>, stacktrace: &[FrameInfo<'tcx>]) -> Vec<Value> {
+    stacktrace
+        .iter()
+        .map(|frame| {
+            json!({
+                "function": frame.instance.to_string(),
+                "span": span_json(machine, frame.span),
+            })
+        })
+        .collect()
+}
+
+fn messages_json(machine: &MiriMachine<'_>, messages: &[(Option<SpanData>, String)]) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|(span, message)| {
+            json!({
+                "message": message,
+                "span": span.map(|span| span_json(machine, span.span())),
+            })
+        })
+        .collect()
+}
+
+fn span_json(machine: &MiriMachine<'_>, span: Span) -> Value {
+    let sm = machine.tcx.sess.source_map();
+    let lo = sm.lookup_char_pos(span.lo());
+    let hi = sm.lookup_char_pos(span.hi());
+    json!({
+        "file": sm.filename_for_diagnostics(&lo.file.name).to_string(),
+        "line_start": lo.line,
+        "column_start": lo.col.0 + 1,
+        "line_end": hi.line,
+        "column_end": hi.col.0 + 1,
+    })
+}
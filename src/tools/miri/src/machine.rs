@@ -104,6 +104,14 @@ pub enum BacktraceStyle {
     Off,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Report errors and leaks as rustc diagnostics.
+    Human,
+    /// Report errors and leaks as JSON objects, one per line, for consumption by other tools.
+    Json,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ValidationMode {
     /// Do not perform any kind of validation.
@@ -147,18 +155,28 @@ pub struct FrameExtra<'tcx> {
 
     /// Data race detector per-frame data.
     pub data_race: Option<data_race::FrameState>,
+
+    /// If `-Zmiri-profile` is enabled, where this frame is in the profile.
+    pub profile: Option<ProfileFrame>,
 }
 
 impl<'tcx> std::fmt::Debug for FrameExtra<'tcx> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Omitting `timing`, it does not support `Debug`.
-        let FrameExtra { borrow_tracker, catch_unwind, timing: _, is_user_relevant, data_race } =
-            self;
+        let FrameExtra {
+            borrow_tracker,
+            catch_unwind,
+            timing: _,
+            is_user_relevant,
+            data_race,
+            profile,
+        } = self;
         f.debug_struct("FrameData")
             .field("borrow_tracker", borrow_tracker)
             .field("catch_unwind", catch_unwind)
             .field("is_user_relevant", is_user_relevant)
             .field("data_race", data_race)
+            .field("profile", profile)
             .finish()
     }
 }
@@ -171,6 +189,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             timing: _,
             is_user_relevant: _,
             data_race: _,
+            profile: _,
         } = self;
 
         catch_unwind.visit_provenance(visit);
@@ -409,6 +428,9 @@ pub struct AllocExtra<'tcx> {
     /// freed.
     /// This maps offsets to synchronization-primitive-specific data.
     pub sync: FxHashMap<Size, Box<dyn Any>>,
+    /// Where the uninitialized bytes of this allocation came from, see
+    /// `-Zmiri-track-uninit-origins`.
+    pub uninit_origins: Option<UninitOrigins>,
 }
 
 // We need a `Clone` impl because the machine passes `Allocation` through `Cow`...
@@ -421,7 +443,8 @@ fn clone(&self) -> Self {
 
 impl VisitProvenance for AllocExtra<'_> {
     fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
-        let AllocExtra { borrow_tracker, data_race, backtrace: _, sync: _ } = self;
+        let AllocExtra { borrow_tracker, data_race, backtrace: _, sync: _, uninit_origins: _ } =
+            self;
 
         borrow_tracker.visit_provenance(visit);
         data_race.visit_provenance(visit);
@@ -548,6 +571,25 @@ pub struct MiriMachine<'tcx> {
 
     /// The list of all EpollEventInterest.
     pub(crate) epoll_interests: shims::EpollInterestTable,
+    /// The list of all `timerfd` timers.
+    pub(crate) timerfds: shims::TimerFdTable,
+    /// The signal mask and pending signals, and the `signalfd`s that read them.
+    pub(crate) signals: shims::SignalState,
+    /// The open `inotify` instances.
+    pub(crate) inotify: shims::InotifyTable,
+    /// The shared writable file mappings created by `mmap`.
+    pub(crate) file_mappings: shims::FileMappings,
+
+    /// The emulated loopback network that sockets are bound to.
+    pub(crate) network: shims::LoopbackNetwork,
+    /// The emulated child processes, see `-Zmiri-spawn-self`.
+    pub(crate) child_processes: shims::ChildProcesses,
+
+    /// The debugger connection, see `-Zmiri-gdbserver`.
+    pub(crate) gdbserver: Option<GdbServer>,
+
+    /// The log of nondeterministic events, see `-Zmiri-record` and `-Zmiri-replay`.
+    pub(crate) nondet: Option<Rc<NondetLog>>,
 
     /// This machine's monotone clock.
     pub(crate) monotonic_clock: MonotonicClock,
@@ -555,6 +597,9 @@ pub struct MiriMachine<'tcx> {
     /// The set of threads.
     pub(crate) threads: ThreadManager<'tcx>,
 
+    /// The lock-order graph, see `-Zmiri-lock-order-check`.
+    pub(crate) lock_order: Option<RefCell<LockOrder<'tcx>>>,
+
     /// Stores which thread is eligible to run on which CPUs.
     /// This has no effect at all, it is just tracked to produce the correct result
     /// in `sched_getaffinity`
@@ -572,6 +617,8 @@ pub struct MiriMachine<'tcx> {
     /// Used with `profiler` to cache the `StringId`s for event names
     /// used with `measureme`.
     string_cache: FxHashMap<String, measureme::StringId>,
+    /// The profile of the interpreted program, see `-Zmiri-profile`.
+    pub(crate) interp_profile: Option<RefCell<InterpProfile<'tcx>>>,
 
     /// Cache of `Instance` exported under the given `Symbol` name.
     /// `None` means no `Instance` exported under the given name is found.
@@ -579,6 +626,8 @@ pub struct MiriMachine<'tcx> {
 
     /// Equivalent setting as RUST_BACKTRACE on encountering an error.
     pub(crate) backtrace_style: BacktraceStyle,
+    /// How errors and leaks are reported.
+    pub(crate) report_format: ReportFormat,
 
     /// Crates which are considered local for the purposes of error reporting.
     pub(crate) local_crates: Vec<CrateNum>,
@@ -598,6 +647,8 @@ pub struct MiriMachine<'tcx> {
     tracked_alloc_ids: FxHashSet<AllocId>,
     /// For the tracked alloc ids, also report read/write accesses.
     track_alloc_accesses: bool,
+    /// Whether to record where uninitialized memory came from.
+    track_uninit_origins: bool,
 
     /// Controls whether alignment of memory accesses is being checked.
     pub(crate) check_alignment: AlignmentCheck,
@@ -699,7 +750,22 @@ pub(crate) fn new(
             let path = Path::new(out).join(filename);
             measureme::Profiler::new(path).expect("Couldn't create `measureme` profiler")
         });
-        let rng = StdRng::seed_from_u64(config.seed.unwrap_or(0));
+        let interp_profile = config.profile.as_ref().map(|path| {
+            let profile = InterpProfile::new(path).unwrap_or_else(|err| {
+                tcx.dcx().fatal(format!("failed to create the profile `{}`: {err}", path.display()))
+            });
+            RefCell::new(profile)
+        });
+        let nondet = config.record_replay.as_ref().map(|mode| {
+            let log = NondetLog::open(mode, config.seed.unwrap_or(0)).unwrap_or_else(|err| {
+                let (RecordReplay::Record(path) | RecordReplay::Replay(path)) = mode;
+                tcx.dcx().fatal(format!("failed to open the recording `{}`: {err}", path.display()))
+            });
+            Rc::new(log)
+        });
+        // When replaying, the recorded seed takes precedence.
+        let seed = nondet.as_ref().map_or(config.seed.unwrap_or(0), |log| log.seed());
+        let rng = StdRng::seed_from_u64(seed);
         let borrow_tracker = config.borrow_tracker.map(|bt| bt.instantiate_global_state(config));
         let data_race = if config.genmc_config.is_some() {
             // `genmc_ctx` persists across executions, so we don't create a new one here.
@@ -765,15 +831,27 @@ pub(crate) fn new(
             validation: config.validation,
             fds: shims::FdTable::init(config.mute_stdout_stderr),
             epoll_interests: shims::EpollInterestTable::new(),
+            timerfds: Default::default(),
+            signals: Default::default(),
+            inotify: Default::default(),
+            file_mappings: Default::default(),
+            network: Default::default(),
+            child_processes: Default::default(),
+            // The debugger connects once the program is ready to run, see `eval_entry`.
+            gdbserver: None,
+            nondet: nondet.clone(),
             dirs: Default::default(),
             layouts,
             threads,
+            lock_order: config.lock_order_check.then(Default::default),
             thread_cpu_affinity,
             static_roots: Vec::new(),
             profiler,
             string_cache: Default::default(),
+            interp_profile,
             exported_symbols_cache: FxHashMap::default(),
             backtrace_style: config.backtrace_style,
+            report_format: config.report_format,
             local_crates,
             extern_statics: FxHashMap::default(),
             rng: RefCell::new(rng),
@@ -781,12 +859,16 @@ pub(crate) fn new(
                 .then(|| Rc::new(RefCell::new(crate::alloc::isolated_alloc::IsolatedAlloc::new()))),
             tracked_alloc_ids: config.tracked_alloc_ids.clone(),
             track_alloc_accesses: config.track_alloc_accesses,
+            track_uninit_origins: config.track_uninit_origins,
             check_alignment: config.check_alignment,
             cmpxchg_weak_failure_rate: config.cmpxchg_weak_failure_rate,
             preemption_rate: config.preemption_rate,
             report_progress: config.report_progress,
             basic_block_count: 0,
-            monotonic_clock: MonotonicClock::new(config.isolated_op == IsolatedOp::Allow),
+            monotonic_clock: MonotonicClock::new(
+                config.isolated_op == IsolatedOp::Allow,
+                nondet,
+            ),
             #[cfg(all(unix, feature = "native-lib"))]
             native_lib: config.native_lib.iter().map(|lib_file_path| {
                 let host_triple = rustc_session::config::host_tuple();
@@ -899,6 +981,36 @@ pub(crate) fn handle_abnormal_termination(&mut self) {
         // the profiler, it is not possible to interpret the profile data and all measureme tools
         // will panic when given the file.
         drop(self.profiler.take());
+        // Likewise, a recording is buffered and must be flushed.
+        if let Some(nondet) = &self.nondet {
+            nondet.finish();
+        }
+        self.finish_interp_profile();
+    }
+
+    /// Writes out the profile of the interpreted program, see `-Zmiri-profile`.
+    pub(crate) fn finish_interp_profile(&self) {
+        let Some(profile) = &self.interp_profile else { return };
+        let open_frames = self.threads.all_stacks().flat_map(|(thread, stack)| {
+            stack.iter().filter_map(move |frame| Some((thread, frame.extra.profile?)))
+        });
+        let mut profile = profile.borrow_mut();
+        if let Err(err) =
+            profile.finish(open_frames, |thread| self.threads.get_thread_display_name(thread))
+        {
+            let path = profile.path().display();
+            self.tcx.dcx().warn(format!("failed to write the profile `{path}`: {err}"));
+        }
+    }
+
+    /// Attributes a step of the borrow tracker to the active frame, see `-Zmiri-profile`.
+    fn profile_borrow_tracker_step(&self) {
+        if let Some(profile) = &self.interp_profile
+            && let Some(frame) = self.threads.active_thread_stack().last()
+            && let Some(profile_frame) = frame.extra.profile
+        {
+            profile.borrow_mut().borrow_tracker_step(profile_frame);
+        }
     }
 
     pub(crate) fn page_align(&self) -> Align {
@@ -973,7 +1085,18 @@ fn init_allocation(
                 .insert(id, (ecx.machine.current_user_relevant_span(), None));
         }
 
-        interp_ok(AllocExtra { borrow_tracker, data_race, backtrace, sync: FxHashMap::default() })
+        let uninit_origins = ecx
+            .machine
+            .track_uninit_origins
+            .then(|| UninitOrigins::new(size, ecx.machine.current_user_relevant_span()));
+
+        interp_ok(AllocExtra {
+            borrow_tracker,
+            data_race,
+            backtrace,
+            sync: FxHashMap::default(),
+            uninit_origins,
+        })
     }
 }
 
@@ -982,6 +1105,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
         #[rustfmt::skip]
         let MiriMachine {
             threads,
+            lock_order: _,
             thread_cpu_affinity: _,
             tls,
             env_vars,
@@ -996,6 +1120,14 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             alloc_addresses,
             fds,
             epoll_interests:_,
+            timerfds: _,
+            signals: _,
+            inotify: _,
+            file_mappings: _,
+            network,
+            child_processes: _,
+            gdbserver: _,
+            nondet: _,
             tcx: _,
             isolated_op: _,
             validation: _,
@@ -1004,13 +1136,16 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             static_roots: _,
             profiler: _,
             string_cache: _,
+            interp_profile: _,
             exported_symbols_cache: _,
             backtrace_style: _,
+            report_format: _,
             local_crates: _,
             rng: _,
             allocator: _,
             tracked_alloc_ids: _,
             track_alloc_accesses: _,
+            track_uninit_origins: _,
             check_alignment: _,
             cmpxchg_weak_failure_rate: _,
             preemption_rate: _,
@@ -1043,6 +1178,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
         env_vars.visit_provenance(visit);
         dirs.visit_provenance(visit);
         fds.visit_provenance(visit);
+        network.visit_provenance(visit);
         data_race.visit_provenance(visit);
         borrow_tracker.visit_provenance(visit);
         alloc_addresses.visit_provenance(visit);
@@ -1231,6 +1367,10 @@ fn find_mir_or_eval_fn(
             return interp_ok(None);
         }
 
+        if ecx.machine.lock_order.is_some() {
+            ecx.lock_order_std_call(instance, args)?;
+        }
+
         // Otherwise, load the MIR.
         let _trace = enter_trace_span!("load_mir");
         interp_ok(Some((ecx.load_mir(instance.def, None)?, instance)))
@@ -1521,6 +1661,7 @@ fn before_memory_read(
             }
         }
         if let Some(borrow_tracker) = &alloc_extra.borrow_tracker {
+            machine.profile_borrow_tracker_step();
             borrow_tracker.before_memory_read(alloc_id, prov_extra, range, machine)?;
         }
         interp_ok(())
@@ -1539,6 +1680,10 @@ fn before_memory_write(
             machine
                 .emit_diagnostic(NonHaltingDiagnostic::AccessedAlloc(alloc_id, AccessKind::Write));
         }
+        // Memory mapped without `PROT_WRITE`; on a real system, this write would fault.
+        if machine.file_mappings.is_read_only(alloc_id) {
+            throw_ub!(WriteToReadOnly(alloc_id));
+        }
         match &machine.data_race {
             GlobalDataRaceHandler::None => {}
             GlobalDataRaceHandler::Genmc(genmc_ctx) =>
@@ -1557,8 +1702,12 @@ fn before_memory_write(
             }
         }
         if let Some(borrow_tracker) = &mut alloc_extra.borrow_tracker {
+            machine.profile_borrow_tracker_step();
             borrow_tracker.before_memory_write(alloc_id, prov_extra, range, machine)?;
         }
+        if let Some(uninit_origins) = &mut alloc_extra.uninit_origins {
+            uninit_origins.record_write(range, machine.current_user_relevant_span());
+        }
         interp_ok(())
     }
 
@@ -1593,6 +1742,7 @@ fn before_memory_deallocation(
             }
         }
         if let Some(borrow_tracker) = &mut alloc_extra.borrow_tracker {
+            machine.profile_borrow_tracker_step();
             borrow_tracker.before_memory_deallocation(alloc_id, prove_extra, size, machine)?;
         }
         if let Some((_, deallocated_at)) = machine.allocation_spans.borrow_mut().get_mut(&alloc_id)
@@ -1610,6 +1760,7 @@ fn retag_ptr_value(
         val: &ImmTy<'tcx>,
     ) -> InterpResult<'tcx, ImmTy<'tcx>> {
         if ecx.machine.borrow_tracker.is_some() {
+            ecx.machine.profile_borrow_tracker_step();
             ecx.retag_ptr_value(kind, val)
         } else {
             interp_ok(val.clone())
@@ -1623,6 +1774,7 @@ fn retag_place_contents(
         place: &PlaceTy<'tcx>,
     ) -> InterpResult<'tcx> {
         if ecx.machine.borrow_tracker.is_some() {
+            ecx.machine.profile_borrow_tracker_step();
             ecx.retag_place_contents(kind, place)?;
         }
         interp_ok(())
@@ -1669,6 +1821,14 @@ fn init_frame(
             None
         };
 
+        let profile = if let Some(interp_profile) = &ecx.machine.interp_profile {
+            let thread = ecx.active_thread();
+            let caller = ecx.active_thread_stack().last().and_then(|caller| caller.extra.profile);
+            Some(interp_profile.borrow_mut().push_frame(thread, caller, frame.instance()))
+        } else {
+            None
+        };
+
         let borrow_tracker = ecx.machine.borrow_tracker.as_ref();
 
         let extra = FrameExtra {
@@ -1681,6 +1841,7 @@ fn init_frame(
                 .data_race
                 .as_vclocks_ref()
                 .map(|_| data_race::FrameState::default()),
+            profile,
         };
 
         interp_ok(frame.with_extra(extra))
@@ -1701,6 +1862,11 @@ fn stack_mut<'a>(
     fn before_terminator(ecx: &mut InterpCx<'tcx, Self>) -> InterpResult<'tcx> {
         ecx.machine.basic_block_count += 1u64; // a u64 that is only incremented by 1 will "never" overflow
         ecx.machine.since_gc += 1;
+        if let Some(interp_profile) = &ecx.machine.interp_profile
+            && let Some(frame) = ecx.frame().extra.profile
+        {
+            interp_profile.borrow_mut().step(frame);
+        }
         // Possibly report our progress. This will point at the terminator we are about to execute.
         if let Some(report_progress) = ecx.machine.report_progress {
             if ecx.machine.basic_block_count.is_multiple_of(u64::from(report_progress)) {
@@ -1737,6 +1903,7 @@ fn after_stack_push(ecx: &mut InterpCx<'tcx, Self>) -> InterpResult<'tcx> {
             let stack_len = ecx.active_thread_stack().len();
             ecx.active_thread_mut().set_top_user_relevant_frame(stack_len - 1);
         }
+        ecx.gdbserver_after_stack_push();
         interp_ok(())
     }
 
@@ -1755,6 +1922,11 @@ fn before_stack_pop(ecx: &mut InterpCx<'tcx, Self>) -> InterpResult<'tcx> {
             // We have to skip the frame that is just being popped.
             ecx.active_thread_mut().recompute_top_user_relevant_frame(/* skip */ 1);
         }
+        if let Some(interp_profile) = &ecx.machine.interp_profile
+            && let Some(profile_frame) = ecx.frame().extra.profile
+        {
+            interp_profile.borrow_mut().pop_frame(ecx.active_thread(), profile_frame);
+        }
         // tracing-tree can autoamtically annotate scope changes, but it gets very confused by our
         // concurrency and what it prints is just plain wrong. So we print our own information
         // instead. (Cc https://github.com/rust-lang/miri/issues/2266)
@@ -0,0 +1,317 @@
+//! Recording and replaying the nondeterminism of an execution, see `-Zmiri-record` and
+//! `-Zmiri-replay`.
+//!
+//! There are two sources of nondeterminism in Miri:
+//! - Everything Miri chooses randomly (scheduling, preemption, weak memory loads, addresses, ...)
+//!   is drawn from a RNG seeded with `-Zmiri-seed`. Given the seed and the same inputs, these
+//!   choices are deterministic, so we only record the seed. The scheduling, weak memory and address
+//!   reuse choices are logged nevertheless, but they are only *checked* on replay: if they differ,
+//!   the replay has diverged (e.g. because a different version of Miri or different flags are used),
+//!   and we stop instead of silently exploring a different execution.
+//! - With isolation disabled, the program can observe the host. The values it gets from there
+//!   (clock readings, host randomness, the environment, data read from files and stdin) are logged,
+//!   and *substituted* on replay without consulting the host.
+//!
+//! The log is a text file with one event per line, `<kind> <value>`, in execution order.
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use rustc_data_structures::fx::FxHashMap;
+
+/// The first line of every recording.
+const HEADER: &str = "miri-record v1";
+
+/// Whether to record or replay an execution, and which file to use.
+#[derive(Clone, Debug)]
+pub enum RecordReplay {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// A value that can be stored in the log.
+pub trait LogValue: Sized {
+    fn encode(&self) -> String;
+    fn decode(s: &str) -> Option<Self>;
+}
+
+macro_rules! impl_log_value_for_int {
+    ($($ty:ty),*) => {$(
+        impl LogValue for $ty {
+            fn encode(&self) -> String {
+                self.to_string()
+            }
+            fn decode(s: &str) -> Option<Self> {
+                s.parse().ok()
+            }
+        }
+    )*};
+}
+impl_log_value_for_int!(u32, u64, u128, usize, i128);
+
+impl LogValue for Vec<u8> {
+    fn encode(&self) -> String {
+        self.iter().map(|b| format!("{b:02x}")).collect()
+    }
+    fn decode(s: &str) -> Option<Self> {
+        if !s.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+    }
+}
+
+/// The result of a host read: the bytes read, or the raw OS error code (`-1` if there was none).
+impl LogValue for Result<Vec<u8>, i32> {
+    fn encode(&self) -> String {
+        match self {
+            Ok(bytes) => format!("+{}", bytes.encode()),
+            Err(code) => format!("!{code}"),
+        }
+    }
+    fn decode(s: &str) -> Option<Self> {
+        if let Some(bytes) = s.strip_prefix('+') {
+            Some(Ok(Vec::decode(bytes)?))
+        } else {
+            Some(Err(s.strip_prefix('!')?.parse().ok()?))
+        }
+    }
+}
+
+/// Environment variables, as `name=value` pairs separated by NUL bytes.
+impl LogValue for FxHashMap<OsString, OsString> {
+    fn encode(&self) -> String {
+        let mut vars: Vec<_> = self.iter().collect();
+        vars.sort();
+        let mut bytes = Vec::new();
+        for (name, value) in vars {
+            bytes.extend_from_slice(name.as_encoded_bytes());
+            bytes.push(b'=');
+            bytes.extend_from_slice(value.as_encoded_bytes());
+            bytes.push(0);
+        }
+        bytes.encode()
+    }
+    fn decode(s: &str) -> Option<Self> {
+        let bytes = Vec::decode(s)?;
+        let os_string = |bytes: &[u8]| -> OsString {
+            #[cfg(unix)]
+            {
+                std::os::unix::ffi::OsStringExt::from_vec(bytes.to_vec())
+            }
+            #[cfg(not(unix))]
+            {
+                OsString::from(String::from_utf8_lossy(bytes).into_owned())
+            }
+        };
+        bytes
+            .split(|&b| b == 0)
+            .filter(|var| !var.is_empty())
+            .map(|var| {
+                let eq = var.iter().position(|&b| b == b'=')?;
+                Some((os_string(&var[..eq]), os_string(&var[eq + 1..])))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+enum LogState {
+    Record(BufWriter<File>),
+    Replay {
+        /// The remaining events, in reverse order so that we can pop them.
+        events: Vec<(usize, String)>,
+    },
+    /// The replay diverged from the recording; we continue with live values until the divergence
+    /// is reported.
+    Diverged,
+}
+
+/// The log of nondeterministic events of this execution.
+#[derive(Debug)]
+pub struct NondetLog {
+    path: PathBuf,
+    seed: u64,
+    state: RefCell<LogState>,
+    /// Set when the replay diverged, until the interpreter loop picks it up and stops.
+    divergence: RefCell<Option<String>>,
+}
+
+impl NondetLog {
+    /// Starts recording to or replaying from the given file. `seed` is the seed of Miri's RNG; when
+    /// replaying, the recorded seed is used instead.
+    pub fn open(mode: &RecordReplay, seed: u64) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_owned());
+        match mode {
+            RecordReplay::Record(path) => {
+                let mut out = BufWriter::new(File::create(path)?);
+                writeln!(out, "{HEADER}")?;
+                writeln!(out, "seed {seed}")?;
+                Ok(NondetLog {
+                    path: path.clone(),
+                    seed,
+                    state: RefCell::new(LogState::Record(out)),
+                    divergence: RefCell::new(None),
+                })
+            }
+            RecordReplay::Replay(path) => {
+                let mut lines = BufReader::new(File::open(path)?).lines();
+                if lines.next().transpose()?.as_deref() != Some(HEADER) {
+                    return Err(invalid("this is not a Miri recording"));
+                }
+                let seed = lines
+                    .next()
+                    .transpose()?
+                    .as_deref()
+                    .and_then(|line| line.strip_prefix("seed "))
+                    .and_then(|seed| seed.parse().ok())
+                    .ok_or_else(|| invalid("the recording does not start with a seed"))?;
+                // Line numbers are 1-based, and we already consumed two lines.
+                let mut events = (3..)
+                    .zip(lines)
+                    .map(|(n, line)| Ok((n, line?)))
+                    .collect::<io::Result<Vec<_>>>()?;
+                events.reverse();
+                Ok(NondetLog {
+                    path: path.clone(),
+                    seed,
+                    state: RefCell::new(LogState::Replay { events }),
+                    divergence: RefCell::new(None),
+                })
+            }
+        }
+    }
+
+    /// The seed to initialize Miri's RNG with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Logs a value the program obtained from the host. When replaying, the recorded value is
+    /// returned instead, and `live` is not called at all.
+    pub fn host<T: LogValue>(&self, kind: &str, live: impl FnOnce() -> T) -> T {
+        let mut state = self.state.borrow_mut();
+        match &mut *state {
+            LogState::Record(out) => {
+                let value = live();
+                Self::write(out, kind, &value);
+                value
+            }
+            LogState::Replay { events } => {
+                let recorded = Self::pop(events, kind).and_then(|(line, value)| {
+                    T::decode(&value).ok_or(Some((line, format!("{kind} {value}"))))
+                });
+                match recorded {
+                    Ok(value) => value,
+                    Err(recorded) => {
+                        self.diverge(&mut state, recorded, &format!("a `{kind}` value"));
+                        live()
+                    }
+                }
+            }
+            LogState::Diverged => live(),
+        }
+    }
+
+    /// Logs a choice Miri made. When replaying, the choice is checked against the recording, and
+    /// the replay is stopped if they disagree.
+    pub fn check<T: LogValue>(&self, kind: &str, value: T) {
+        let mut state = self.state.borrow_mut();
+        match &mut *state {
+            LogState::Record(out) => Self::write(out, kind, &value),
+            LogState::Replay { events } => {
+                let value = value.encode();
+                let recorded = Self::pop(events, kind).and_then(|(line, recorded)| {
+                    if recorded == value {
+                        Ok(())
+                    } else {
+                        Err(Some((line, format!("{kind} {recorded}"))))
+                    }
+                });
+                if let Err(recorded) = recorded {
+                    self.diverge(&mut state, recorded, &format!("`{kind} {value}`"));
+                }
+            }
+            LogState::Diverged => {}
+        }
+    }
+
+    /// Returns the divergence from the recording that happened since the last call, if any.
+    pub fn take_divergence(&self) -> Option<String> {
+        self.divergence.borrow_mut().take()
+    }
+
+    /// Returns a warning if the execution ended before the end of the recording.
+    pub fn finish(&self) -> Option<String> {
+        match &mut *self.state.borrow_mut() {
+            LogState::Record(out) => {
+                out.flush().expect("failed to write the recording");
+                None
+            }
+            LogState::Replay { events } if !events.is_empty() =>
+                Some(format!(
+                    "the execution ended before the end of the recording `{}`, at line {}",
+                    self.path.display(),
+                    events.last().unwrap().0,
+                )),
+            LogState::Replay { .. } | LogState::Diverged => None,
+        }
+    }
+
+    fn write<T: LogValue>(out: &mut BufWriter<File>, kind: &str, value: &T) {
+        writeln!(out, "{kind} {}", value.encode()).expect("failed to write the recording");
+    }
+
+    /// Pops the next event, which must be of the given kind, and returns its line number and
+    /// value. Otherwise, returns the event that was found instead (`None` at the end of the
+    /// recording).
+    fn pop(
+        events: &mut Vec<(usize, String)>,
+        kind: &str,
+    ) -> Result<(usize, String), Option<(usize, String)>> {
+        let (line, event) = events.pop().ok_or(None)?;
+        match event.split_once(' ') {
+            Some((recorded_kind, value)) if recorded_kind == kind => Ok((line, value.to_owned())),
+            _ => Err(Some((line, event))),
+        }
+    }
+
+    /// Remembers that the replay diverged, and stops replaying.
+    fn diverge(&self, state: &mut LogState, recorded: Option<(usize, String)>, got: &str) {
+        let recorded = match recorded {
+            Some((line, event)) => format!("line {line} has `{event}`"),
+            None => "it ended".to_owned(),
+        };
+        *self.divergence.borrow_mut() = Some(format!(
+            "the execution diverged from the recording `{}`: {recorded}, but the execution produced {got}",
+            self.path.display(),
+        ));
+        *state = LogState::Diverged;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: LogValue + PartialEq + std::fmt::Debug>(value: T) {
+        assert_eq!(T::decode(&value.encode()), Some(value));
+    }
+
+    #[test]
+    fn test_log_values() {
+        roundtrip(42u32);
+        roundtrip(-7i128);
+        roundtrip(vec![0u8, 0xab, 0xff]);
+        roundtrip(Result::<Vec<u8>, i32>::Ok(b"hello".to_vec()));
+        roundtrip(Result::<Vec<u8>, i32>::Err(11));
+        let mut env = FxHashMap::default();
+        env.insert(OsString::from("HOME"), OsString::from("/root"));
+        env.insert(OsString::from("EMPTY"), OsString::new());
+        roundtrip(env);
+        assert_eq!(Vec::<u8>::decode("abc"), None);
+    }
+}
@@ -31,16 +31,24 @@ pub(crate) fn init(
     ) -> InterpResult<'tcx> {
         // Initialize the `env_vars` map.
         // Skip the loop entirely if we don't want to forward anything.
-        let mut env_vars = FxHashMap::default();
-        if ecx.machine.communicate() || !config.forwarded_env_vars.is_empty() {
-            for (name, value) in &config.env {
-                let forward = ecx.machine.communicate()
-                    || config.forwarded_env_vars.iter().any(|v| **v == *name);
-                if forward {
-                    env_vars.insert(OsString::from(name), OsString::from(value));
+        let forwarded_env_vars = || {
+            let mut env_vars = FxHashMap::default();
+            if ecx.machine.communicate() || !config.forwarded_env_vars.is_empty() {
+                for (name, value) in &config.env {
+                    let forward = ecx.machine.communicate()
+                        || config.forwarded_env_vars.iter().any(|v| **v == *name);
+                    if forward {
+                        env_vars.insert(OsString::from(name), OsString::from(value));
+                    }
                 }
             }
-        }
+            env_vars
+        };
+        // The host environment is recorded, and replayed instead of the current one.
+        let mut env_vars: FxHashMap<OsString, OsString> = match &ecx.machine.nondet {
+            Some(nondet) => nondet.host("env", forwarded_env_vars),
+            None => forwarded_env_vars(),
+        };
 
         for (name, value) in &config.set_env_vars {
             env_vars.insert(OsString::from(name), OsString::from(value));
@@ -1,15 +1,17 @@
 use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::collections::BTreeMap;
 use std::fs::{File, Metadata};
 use std::io::{ErrorKind, IsTerminal, Seek, SeekFrom, Write};
 use std::marker::CoercePointee;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::rc::{Rc, Weak};
 use std::{fs, io};
 
 use rustc_abi::Size;
 
-use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::*;
 use crate::*;
 
 /// A unique id for file descriptions. While we could use the address, considering that
@@ -242,7 +244,7 @@ fn read<'tcx>(
             helpers::isolation_abort_error("`read` from stdin")?;
         }
 
-        let result = ecx.read_from_host(&*self, len, ptr)?;
+        let result = ecx.read_from_host_nondet(&*self, len, ptr)?;
         finish.call(ecx, result)
     }
 
@@ -309,6 +311,8 @@ fn is_tty(&self, communicate_allowed: bool) -> bool {
 pub struct FileHandle {
     pub(crate) file: File,
     pub(crate) writable: bool,
+    /// The absolute path the file was opened with, for reporting changes to inotify.
+    pub(crate) path: PathBuf,
 }
 
 impl FileDescription for FileHandle {
@@ -326,7 +330,7 @@ fn read<'tcx>(
     ) -> InterpResult<'tcx> {
         assert!(communicate_allowed, "isolation should have prevented even opening a file");
 
-        let result = ecx.read_from_host(&self.file, len, ptr)?;
+        let result = ecx.read_from_host_nondet(&self.file, len, ptr)?;
         finish.call(ecx, result)
     }
 
@@ -350,6 +354,9 @@ fn write<'tcx>(
             return finish.call(ecx, Err(ErrorKind::PermissionDenied.into()));
         }
         let result = ecx.write_to_host(&self.file, len, ptr)?;
+        if matches!(result, Ok(written) if written > 0) {
+            ecx.inotify_notify(&self.path, FsChange::Modified)?;
+        }
         finish.call(ecx, result)
     }
 
@@ -365,9 +372,10 @@ fn seek<'tcx>(
     fn close<'tcx>(
         self,
         communicate_allowed: bool,
-        _ecx: &mut MiriInterpCx<'tcx>,
+        ecx: &mut MiriInterpCx<'tcx>,
     ) -> InterpResult<'tcx, io::Result<()>> {
         assert!(communicate_allowed, "isolation should have prevented even opening a file");
+        ecx.inotify_notify(&self.path, FsChange::Closed { written: self.writable })?;
         // We sync the file if it was opened in a mode different than read-only.
         if self.writable {
             // `File::sync_all` does the checks that are done when closing a file. We do this to
@@ -412,6 +420,113 @@ fn as_unix<'tcx>(&self, ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
     }
 }
 
+/// An anonymous file that only exists in memory, created by `memfd_create`. It does not touch the
+/// host, so it can be used with isolation enabled.
+#[derive(Debug, Default)]
+pub struct MemFile {
+    contents: RefCell<Vec<u8>>,
+    /// The file position, shared by all file descriptors that refer to this file description.
+    pos: Cell<u64>,
+}
+
+impl MemFile {
+    pub(crate) fn len(&self) -> u64 {
+        self.contents.borrow().len().to_u64()
+    }
+
+    pub(crate) fn set_len(&self, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).map_err(|_| io::Error::from(ErrorKind::FileTooLarge))?;
+        self.contents.borrow_mut().resize(len, 0);
+        Ok(())
+    }
+
+    /// Returns up to `len` bytes starting at `offset`; fewer at the end of the file.
+    pub(crate) fn read_bytes_at(&self, offset: u64, len: usize) -> Vec<u8> {
+        let contents = self.contents.borrow();
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(contents.len());
+        let end = start.saturating_add(len).min(contents.len());
+        contents[start..end].to_vec()
+    }
+
+    /// Writes `bytes` starting at `offset`, growing the file as needed.
+    pub(crate) fn write_bytes_at(&self, offset: u64, bytes: &[u8]) -> io::Result<()> {
+        let too_large = || io::Error::from(ErrorKind::FileTooLarge);
+        let start = usize::try_from(offset).map_err(|_| too_large())?;
+        let end = start.checked_add(bytes.len()).ok_or_else(too_large)?;
+        let mut contents = self.contents.borrow_mut();
+        if contents.len() < end {
+            contents.resize(end, 0);
+        }
+        contents[start..end].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl FileDescription for MemFile {
+    fn name(&self) -> &'static str {
+        "memfd"
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let bytes = self.read_bytes_at(self.pos.get(), len);
+        ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+        self.pos.set(self.pos.get().strict_add(bytes.len().to_u64()));
+        finish.call(ecx, Ok(bytes.len()))
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let result = self.write_bytes_at(self.pos.get(), bytes).map(|()| {
+            self.pos.set(self.pos.get().strict_add(len.to_u64()));
+            len
+        });
+        finish.call(ecx, result.map_err(IoError::HostError))
+    }
+
+    fn seek<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: SeekFrom,
+    ) -> InterpResult<'tcx, io::Result<u64>> {
+        let pos = match offset {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.pos.get().checked_add_signed(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+        };
+        let Some(pos) = pos else {
+            return interp_ok(Err(ErrorKind::InvalidInput.into()));
+        };
+        self.pos.set(pos);
+        interp_ok(Ok(pos))
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(Ok(()))
+    }
+
+    fn as_unix<'tcx>(&self, _ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
 /// Like /dev/null
 #[derive(Debug)]
 pub struct NullOutput;
@@ -556,6 +671,54 @@ fn read_from_host(
         }
     }
 
+    /// Like `read_from_host`, but for reads from the host's files and standard input, which are
+    /// recorded and replayed with `-Zmiri-record`/`-Zmiri-replay`. Reads that only touch state
+    /// inside the interpreter (pipes, sockets, ...) must use `read_from_host`: replaying those
+    /// would not drain the buffer they read from.
+    fn read_from_host_nondet(
+        &mut self,
+        mut file: impl io::Read,
+        len: usize,
+        ptr: Pointer,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        let this = self.eval_context_mut();
+        if this.machine.nondet.is_none() {
+            return this.read_from_host(file, len, ptr);
+        }
+
+        let result = this.host_read_nondet(|| {
+            let mut bytes = vec![0; len];
+            let read_size = file.read(&mut bytes)?;
+            bytes.truncate(read_size);
+            Ok(bytes)
+        });
+        match result {
+            Ok(bytes) => {
+                if bytes.len() > len {
+                    throw_unsup_format!("the recorded read returned more bytes than requested");
+                }
+                this.write_bytes_ptr(ptr, bytes.iter().copied())?;
+                interp_ok(Ok(bytes.len()))
+            }
+            Err(e) => interp_ok(Err(IoError::HostError(e))),
+        }
+    }
+
+    /// Performs a read from the host's files or standard input. The data read from the host is
+    /// recorded, and replayed without touching the host.
+    fn host_read_nondet(&self, read: impl FnOnce() -> io::Result<Vec<u8>>) -> io::Result<Vec<u8>> {
+        let this = self.eval_context_ref();
+        let Some(nondet) = &this.machine.nondet else {
+            return read();
+        };
+        nondet.host("read", || read().map_err(|e| e.raw_os_error().unwrap_or(-1))).map_err(|code| {
+            match code {
+                -1 => io::Error::other("the host returned an error without an error code"),
+                code => io::Error::from_raw_os_error(code),
+            }
+        })
+    }
+
     /// Write data to a host `Write` type, withthe bytes taken from machine memory.
     fn write_to_host(
         &mut self,
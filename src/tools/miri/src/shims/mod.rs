@@ -27,7 +27,10 @@
 pub use self::files::FdTable;
 #[cfg(all(unix, feature = "native-lib"))]
 pub use self::native_lib::trace::{init_sv, register_retcode_sv};
-pub use self::unix::{DirTable, EpollInterestTable};
+pub use self::unix::{
+    ChildProcesses, DirTable, EpollInterestTable, FileMappings, InotifyTable, LoopbackNetwork,
+    SignalState, TimerFdTable,
+};
 
 /// What needs to be done after emulating an item (a shim or an intrinsic) is done.
 pub enum EmulateItemResult {
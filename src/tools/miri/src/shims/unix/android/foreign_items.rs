@@ -7,7 +7,11 @@
 use crate::shims::unix::env::EvalContextExt as _;
 use crate::shims::unix::linux_like::epoll::EvalContextExt as _;
 use crate::shims::unix::linux_like::eventfd::EvalContextExt as _;
+use crate::shims::unix::linux_like::inotify::EvalContextExt as _;
+use crate::shims::unix::linux_like::memfd::EvalContextExt as _;
+use crate::shims::unix::linux_like::signalfd::EvalContextExt as _;
 use crate::shims::unix::linux_like::syscall::syscall;
+use crate::shims::unix::linux_like::timerfd::EvalContextExt as _;
 use crate::*;
 
 pub fn is_dyn_sym(name: &str) -> bool {
@@ -25,7 +29,7 @@ fn emulate_foreign_item_inner(
     ) -> InterpResult<'tcx, EmulateItemResult> {
         let this = self.eval_context_mut();
         match link_name.as_str() {
-            // epoll, eventfd
+            // epoll, eventfd, timerfd, signalfd
             "epoll_create1" => {
                 let [flag] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
                 let result = this.epoll_create1(flag)?;
@@ -47,6 +51,70 @@ fn emulate_foreign_item_inner(
                 let result = this.eventfd(val, flag)?;
                 this.write_scalar(result, dest)?;
             }
+            "timerfd_create" => {
+                let [clockid, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_create(clockid, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "timerfd_settime" => {
+                let [fd, flags, new_value, old_value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_settime(fd, flags, new_value, old_value)?;
+                this.write_scalar(result, dest)?;
+            }
+            "timerfd_gettime" => {
+                let [fd, curr_value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_gettime(fd, curr_value)?;
+                this.write_scalar(result, dest)?;
+            }
+            "signalfd" => {
+                let [fd, mask, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.signalfd(fd, mask, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigprocmask" => {
+                let [how, set, oldset] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigprocmask(how, set, oldset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pthread_sigmask" => {
+                let [how, set, oldset] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.pthread_sigmask(how, set, oldset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_init" => {
+                let [] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_init1(0)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_init1" => {
+                let [flags] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                let result = this.inotify_init1(flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_add_watch" => {
+                let [fd, pathname, mask] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_add_watch(fd, pathname, mask)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_rm_watch" => {
+                let [fd, wd] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_rm_watch(fd, wd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "memfd_create" => {
+                let [name, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.memfd_create(name, flags)?;
+                this.write_scalar(result, dest)?;
+            }
 
             // Miscellaneous
             "__errno" => {
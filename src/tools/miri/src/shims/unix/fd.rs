@@ -3,13 +3,15 @@
 
 use std::io;
 use std::io::ErrorKind;
+use std::time::Duration;
 
 use rand::Rng;
 use rustc_abi::Size;
 
 use crate::shims::files::FileDescription;
 use crate::shims::sig::check_min_vararg_count;
-use crate::shims::unix::linux_like::epoll::EpollReadyEvents;
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::shims::unix::linux_like::timerfd::EvalContextExt as _;
 use crate::shims::unix::*;
 use crate::*;
 
@@ -53,6 +55,28 @@ fn pwrite<'tcx>(
         throw_unsup_format!("cannot pwrite to {}", self.name());
     }
 
+    /// Reads up to `len` bytes starting at `offset`, without moving the file position; fewer
+    /// bytes are returned only at the end of the file. This is used to map the file into memory.
+    fn read_at<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        _offset: u64,
+        _len: usize,
+    ) -> InterpResult<'tcx, io::Result<Vec<u8>>> {
+        throw_unsup_format!("cannot map {} into memory", self.name());
+    }
+
+    /// Writes all of `bytes` starting at `offset`, without moving the file position. This is used
+    /// to write back memory that has been mapped from the file.
+    fn write_at<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        _offset: u64,
+        _bytes: &[u8],
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        throw_unsup_format!("cannot map {} into memory", self.name());
+    }
+
     fn flock<'tcx>(
         &self,
         _communicate_allowed: bool,
@@ -214,6 +238,67 @@ fn fcntl(
         }
     }
 
+    fn ioctl(
+        &mut self,
+        fd_num: &OpTy<'tcx>,
+        cmd: &OpTy<'tcx>,
+        varargs: &[OpTy<'tcx>],
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(fd_num)?.to_i32()?;
+        // The type of `cmd` differs between targets, so we compare it as a scalar.
+        let cmd = this.read_scalar(cmd)?;
+
+        if cmd == this.eval_libc("FIOCLEX") {
+            // Since we don't support `exec`, this is a NOP. However, we want to
+            // return EBADF if the FD is invalid.
+            if this.machine.fds.is_fd_num(fd_num) {
+                interp_ok(Scalar::from_i32(0))
+            } else {
+                this.set_last_error_and_return_i32(LibcError("EBADF"))
+            }
+        } else if cmd == this.eval_libc("FIONBIO") {
+            // This is equivalent to setting or clearing `O_NONBLOCK` with `fcntl`.
+            let Some(fd) = this.machine.fds.get(fd_num) else {
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            };
+            let [arg] = check_min_vararg_count("ioctl(fd, FIONBIO, ...)", varargs)?;
+            let nonblocking = this.deref_pointer_as(arg, this.machine.layouts.i32)?;
+            let nonblocking = this.read_scalar(&nonblocking)?.to_i32()? != 0;
+
+            let o_nonblock = this.eval_libc_i32("O_NONBLOCK");
+            let flags = fd.get_flags(this)?.to_i32()?;
+            let flags = if nonblocking { flags | o_nonblock } else { flags & !o_nonblock };
+            fd.set_flags(flags, this)
+        } else {
+            throw_unsup_format!("ioctl: unsupported command {cmd:#x}");
+        }
+    }
+
+    fn poll(
+        &mut self,
+        fds_op: &OpTy<'tcx>,
+        nfds_op: &OpTy<'tcx>,
+        timeout_op: &OpTy<'tcx>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let nfds = this.read_scalar(nfds_op)?.to_uint(nfds_op.layout.size)?;
+        let timeout = this.read_scalar(timeout_op)?.to_i32()?;
+        let fds = this.deref_pointer_as(
+            fds_op,
+            this.libc_array_ty_layout("pollfd", nfds.try_into().unwrap()),
+        )?;
+
+        // A negative timeout means to wait forever.
+        let deadline = u64::try_from(timeout).ok().map(|timeout| {
+            this.machine.monotonic_clock.elapsed().saturating_add(Duration::from_millis(timeout))
+        });
+        poll_fds(fds, deadline, dest.clone(), this)
+    }
+
     fn close(&mut self, fd_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
@@ -309,6 +394,8 @@ fn read(
                 }}
             )
         };
+        // Shared mappings of the file are written back first, so that the read sees them.
+        this.file_mappings_to_file(fd.id())?;
         match offset {
             None => fd.read(communicate, buf, count, this, finish)?,
             Some(offset) => {
@@ -393,6 +480,11 @@ fn write(
                 }}
             )
         };
+        // Shared mappings of the file are written back before the write and reloaded after it, so
+        // that they see the written data and the write does not get lost when they are written back
+        // later. Writes to files complete immediately, so the reload happens after the write.
+        let fd_id = fd.id();
+        this.file_mappings_to_file(fd_id)?;
         match offset {
             None => fd.write(communicate, buf, count, this, finish)?,
             Some(offset) => {
@@ -402,6 +494,99 @@ fn write(
                 fd.as_unix(this).pwrite(communicate, buf, count, offset, this, finish)?
             }
         };
+        this.file_mappings_from_file(fd_id)?;
         interp_ok(())
     }
 }
+
+/// Reports which of the `pollfd`s in `fds` are ready, blocking until at least one of them is or
+/// until `deadline` passes. The deadline is measured from the epoch of the monotonic clock.
+fn poll_fds<'tcx>(
+    fds: MPlaceTy<'tcx>,
+    deadline: Option<Duration>,
+    dest: MPlaceTy<'tcx>,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx> {
+    // Timers that expired since we last looked may have made file descriptors ready.
+    ecx.fire_expired_timers()?;
+
+    let pollin = ecx.eval_libc("POLLIN").to_i16()?;
+    let pollout = ecx.eval_libc("POLLOUT").to_i16()?;
+    let pollhup = ecx.eval_libc("POLLHUP").to_i16()?;
+    let pollerr = ecx.eval_libc("POLLERR").to_i16()?;
+    let pollnval = ecx.eval_libc("POLLNVAL").to_i16()?;
+
+    let mut num_ready: i32 = 0;
+    // The file descriptions to wait for if none is ready, with their FD numbers and the events
+    // that were requested.
+    let mut waiting_for = Vec::new();
+    let mut array_iter = ecx.project_array_fields(&fds)?;
+    while let Some((_, pollfd)) = array_iter.next(ecx)? {
+        let fd_num = ecx.project_field_named(&pollfd, "fd")?;
+        let fd_num = ecx.read_scalar(&fd_num)?.to_i32()?;
+        let events = ecx.project_field_named(&pollfd, "events")?;
+        let events = ecx.read_scalar(&events)?.to_i16()?;
+
+        let revents = if fd_num < 0 {
+            // Negative file descriptors are ignored.
+            0
+        } else if let Some(fd) = ecx.machine.fds.get(fd_num) {
+            // We can reuse the readiness that epoll works with.
+            let ready = fd.as_unix(ecx).get_epoll_ready_events()?;
+            let mut revents = 0;
+            if ready.epollin {
+                revents |= pollin;
+            }
+            if ready.epollout {
+                revents |= pollout;
+            }
+            revents &= events;
+            // These are reported even if they were not requested.
+            if ready.epollhup {
+                revents |= pollhup;
+            }
+            if ready.epollerr {
+                revents |= pollerr;
+            }
+            let mut interest = EpollReadyEvents::new();
+            interest.epollin = events & pollin != 0;
+            interest.epollout = events & pollout != 0;
+            waiting_for.push((fd_num, fd, interest));
+            revents
+        } else {
+            pollnval
+        };
+
+        let revents_place = ecx.project_field_named(&pollfd, "revents")?;
+        ecx.write_scalar(Scalar::from_i16(revents), &revents_place)?;
+        if revents != 0 {
+            num_ready = num_ready.strict_add(1);
+        }
+    }
+
+    if num_ready != 0 || deadline.is_some_and(|d| d <= ecx.machine.monotonic_clock.elapsed()) {
+        return ecx.write_scalar(Scalar::from_i32(num_ready), &dest);
+    }
+
+    // Waiting uses the notifications of epoll, which only exists on some targets.
+    if !matches!(&*ecx.tcx.sess.target.os, "linux" | "android" | "illumos") {
+        throw_unsup_format!(
+            "poll: blocking until a file descriptor becomes ready is not supported on this target"
+        );
+    }
+    ecx.block_until_ready(
+        waiting_for,
+        deadline,
+        callback!(
+            @capture<'tcx> {
+                fds: MPlaceTy<'tcx>,
+                deadline: Option<Duration>,
+                dest: MPlaceTy<'tcx>,
+            }
+            |this, _unblock: UnblockKind| {
+                // Look again; if nothing is ready yet, this keeps waiting.
+                poll_fds(fds, deadline, dest, this)
+            }
+        ),
+    )
+}
@@ -13,6 +13,7 @@
 use self::shims::unix::solarish::foreign_items as solarish;
 use crate::concurrency::cpu_affinity::CpuAffinityMask;
 use crate::shims::alloc::EvalContextExt as _;
+use crate::shims::unix::linux_like::signalfd::EvalContextExt as _;
 use crate::shims::unix::*;
 use crate::{shim_sig, *};
 
@@ -314,6 +315,11 @@ fn emulate_foreign_item_inner(
                 let result = this.flock(fd, op)?;
                 this.write_scalar(result, dest)?;
             }
+            "poll" => {
+                let [fds, nfds, timeout] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.poll(fds, nfds, timeout, dest)?;
+            }
 
             // File and file system access
             "open" | "open64" => {
@@ -541,6 +547,250 @@ fn emulate_foreign_item_inner(
                 this.write_scalar(result, dest)?;
             }
 
+            // Sockets on the emulated loopback network
+            "socket" => {
+                let [domain, type_, protocol] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, i32, i32) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.socket(domain, type_, protocol)?;
+                this.write_scalar(result, dest)?;
+            }
+            "bind" => {
+                let [socket, address, address_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *const _, libc::socklen_t) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.bind(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "listen" => {
+                let [socket, backlog] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, i32) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.listen(socket, backlog)?;
+                this.write_scalar(result, dest)?;
+            }
+            "connect" => {
+                let [socket, address, address_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *const _, libc::socklen_t) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.connect(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "accept" => {
+                let [socket, address, address_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *mut _, *mut _) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                this.accept4(socket, address, address_len, /* flags */ None, dest)?;
+            }
+            "accept4" => {
+                // Currently this function does not exist on all Unixes, e.g. on macOS.
+                this.check_target_os(
+                    &["linux", "android", "freebsd", "solaris", "illumos"],
+                    link_name,
+                )?;
+                let [socket, address, address_len, flags] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *mut _, *mut _, i32) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                this.accept4(socket, address, address_len, Some(flags), dest)?;
+            }
+            "send" => {
+                let [socket, buf, len, flags] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *const _, usize, i32) -> isize),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                this.send_to(socket, buf, len, flags, /* address */ None, dest)?;
+            }
+            "sendto" => {
+                let [socket, buf, len, flags, address, address_len] = this.check_shim_sig(
+                    shim_sig!(
+                        extern "C" fn(
+                            i32,
+                            *const _,
+                            usize,
+                            i32,
+                            *const _,
+                            libc::socklen_t,
+                        ) -> isize
+                    ),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                this.send_to(socket, buf, len, flags, Some((address, address_len)), dest)?;
+            }
+            "recv" => {
+                let [socket, buf, len, flags] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *mut _, usize, i32) -> isize),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                this.recv_from(socket, buf, len, flags, /* address */ None, dest)?;
+            }
+            "recvfrom" => {
+                let [socket, buf, len, flags, address, address_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *mut _, usize, i32, *mut _, *mut _) -> isize),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                this.recv_from(socket, buf, len, flags, Some((address, address_len)), dest)?;
+            }
+            "getsockname" => {
+                let [socket, address, address_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *mut _, *mut _) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.getsockname(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getpeername" => {
+                let [socket, address, address_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, *mut _, *mut _) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.getpeername(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "shutdown" => {
+                let [socket, how] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, i32) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result = this.shutdown(socket, how)?;
+                this.write_scalar(result, dest)?;
+            }
+            "setsockopt" => {
+                let [socket, level, option_name, option_value, option_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, i32, i32, *const _, libc::socklen_t) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result =
+                    this.setsockopt(socket, level, option_name, option_value, option_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getsockopt" => {
+                let [socket, level, option_name, option_value, option_len] = this.check_shim_sig(
+                    shim_sig!(extern "C" fn(i32, i32, i32, *mut _, *mut _) -> i32),
+                    link_name,
+                    abi,
+                    args,
+                )?;
+                let result =
+                    this.getsockopt(socket, level, option_name, option_value, option_len)?;
+                this.write_scalar(result, dest)?;
+            }
+
+            // Child processes
+            "posix_spawn" | "posix_spawnp" => {
+                let [pid, path, file_actions, attrp, argv, envp] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn(
+                    link_name.as_str(),
+                    pid,
+                    path,
+                    file_actions,
+                    attrp,
+                    argv,
+                    envp,
+                )?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_init" => {
+                let [file_actions] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_init(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_destroy" => {
+                let [file_actions] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_destroy(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_adddup2" => {
+                let [file_actions, fd, newfd] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_adddup2(file_actions, fd, newfd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_init" | "posix_spawnattr_destroy" => {
+                let [_attr] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.write_null(dest)?;
+            }
+            "posix_spawnattr_setflags"
+            | "posix_spawnattr_setsigdefault"
+            | "posix_spawnattr_setsigmask"
+            | "posix_spawnattr_setpgroup" => {
+                // The child runs to completion inside `posix_spawn`, so its signal dispositions
+                // and process group do not matter.
+                let [_attr, _value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                this.write_null(dest)?;
+            }
+            "waitpid" => {
+                let [pid, wstatus, options] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.waitpid(pid, wstatus, options)?;
+                this.write_scalar(result, dest)?;
+            }
+            "kill" => {
+                let [pid, sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.kill(pid, sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "raise" => {
+                let [sig] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let sig = this.read_scalar(sig)?.to_i32()?;
+                let result = this.raise_signal(sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigemptyset" => {
+                let [set] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigemptyset(set)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigaddset" => {
+                let [set, signum] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigaddset(set, signum)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigismember" => {
+                let [set, signum] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigismember(set, signum)?;
+                this.write_scalar(result, dest)?;
+            }
+
             // Time
             "gettimeofday" => {
                 let [tv, tz] = this.check_shim_sig(
@@ -593,6 +843,12 @@ fn emulate_foreign_item_inner(
                 let result = this.munmap(addr, length)?;
                 this.write_scalar(result, dest)?;
             }
+            "msync" => {
+                let [addr, length, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.msync(addr, length, flags)?;
+                this.write_scalar(result, dest)?;
+            }
 
             "reallocarray" => {
                 // Currently this function does not exist on all Unixes, e.g. on macOS.
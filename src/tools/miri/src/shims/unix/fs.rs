@@ -13,10 +13,12 @@
 use rustc_data_structures::fx::FxHashMap;
 
 use self::shims::time::system_time_to_duration;
-use crate::shims::files::FileHandle;
+use crate::shims::files::{FileHandle, MemFile};
 use crate::shims::os_str::bytes_to_os_str;
 use crate::shims::sig::check_min_vararg_count;
 use crate::shims::unix::fd::{FlockOp, UnixFileDescription};
+use crate::shims::unix::linux_like::inotify::EvalContextExt as _;
+use crate::shims::unix::{FsChange, UID};
 use crate::*;
 
 impl UnixFileDescription for FileHandle {
@@ -82,9 +84,57 @@ fn pwrite<'tcx>(
             res
         };
         let result = f();
+        if matches!(result, Ok(written) if written > 0) {
+            ecx.inotify_notify(&self.path, FsChange::Modified)?;
+        }
         finish.call(ecx, result.map_err(IoError::HostError))
     }
 
+    fn read_at<'tcx>(
+        &self,
+        communicate_allowed: bool,
+        offset: u64,
+        len: usize,
+    ) -> InterpResult<'tcx, io::Result<Vec<u8>>> {
+        assert!(communicate_allowed, "isolation should have prevented even opening a file");
+        let mut bytes = Vec::new();
+        // Like `pread`, but read until `len` bytes or the end of the file.
+        let file = &mut &self.file;
+        let mut f = || {
+            let cursor_pos = file.stream_position()?;
+            file.seek(SeekFrom::Start(offset))?;
+            let res = file.by_ref().take(len.to_u64()).read_to_end(&mut bytes);
+            file.seek(SeekFrom::Start(cursor_pos))
+                .expect("failed to restore file position, this shouldn't be possible");
+            res
+        };
+        let result = f();
+        interp_ok(result.map(|_| bytes))
+    }
+
+    fn write_at<'tcx>(
+        &self,
+        communicate_allowed: bool,
+        offset: u64,
+        bytes: &[u8],
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        assert!(communicate_allowed, "isolation should have prevented even opening a file");
+        if !self.writable {
+            return interp_ok(Err(ErrorKind::PermissionDenied.into()));
+        }
+        // Like `pwrite`, but write all of `bytes`.
+        let file = &mut &self.file;
+        let mut f = || {
+            let cursor_pos = file.stream_position()?;
+            file.seek(SeekFrom::Start(offset))?;
+            let res = file.write_all(bytes);
+            file.seek(SeekFrom::Start(cursor_pos))
+                .expect("failed to restore file position, this shouldn't be possible");
+            res
+        };
+        interp_ok(f())
+    }
+
     fn flock<'tcx>(
         &self,
         communicate_allowed: bool,
@@ -115,6 +165,54 @@ fn flock<'tcx>(
     }
 }
 
+impl UnixFileDescription for MemFile {
+    fn pread<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: u64,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let bytes = self.read_bytes_at(offset, len);
+        ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+        finish.call(ecx, Ok(bytes.len()))
+    }
+
+    fn pwrite<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        offset: u64,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let result = self.write_bytes_at(offset, bytes).map(|()| len);
+        finish.call(ecx, result.map_err(IoError::HostError))
+    }
+
+    fn read_at<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: u64,
+        len: usize,
+    ) -> InterpResult<'tcx, io::Result<Vec<u8>>> {
+        interp_ok(Ok(self.read_bytes_at(offset, len)))
+    }
+
+    fn write_at<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: u64,
+        bytes: &[u8],
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(self.write_bytes_at(offset, bytes))
+    }
+}
+
 impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
 trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
     fn macos_fbsd_solarish_write_stat_buf(
@@ -431,9 +529,14 @@ fn open(
             return this.set_last_error_and_return_i32(ErrorKind::PermissionDenied);
         }
 
-        let fd = options
-            .open(path)
-            .map(|file| this.machine.fds.insert_new(FileHandle { file, writable }));
+        let path = std::path::absolute(&path).unwrap_or_else(|_| path.into_owned());
+        let created = flag & o_creat == o_creat && !path.exists();
+        let fd = options.open(&path).map(|file| {
+            this.machine.fds.insert_new(FileHandle { file, writable, path: path.clone() })
+        });
+        if created && fd.is_ok() {
+            this.inotify_notify(&path, FsChange::Created { dir: false })?;
+        }
 
         interp_ok(Scalar::from_i32(this.try_unwrap_io_result(fd)?))
     }
@@ -480,7 +583,7 @@ fn lseek64(
     fn unlink(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?.into_owned();
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -488,7 +591,10 @@ fn unlink(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
             return this.set_last_error_and_return_i32(ErrorKind::PermissionDenied);
         }
 
-        let result = remove_file(path).map(|_| 0);
+        let result = remove_file(&path).map(|_| 0);
+        if result.is_ok() {
+            this.inotify_notify(&path, FsChange::Deleted { dir: false })?;
+        }
         interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
     }
 
@@ -599,8 +705,12 @@ fn macos_fbsd_solarish_fstat(
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled, unless this queries an in-memory file.
+        let is_mem_file =
+            this.machine.fds.get(fd).is_some_and(|fd| fd.downcast::<MemFile>().is_some());
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && !is_mem_file
+        {
             this.reject_in_isolation("`fstat`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return this.set_last_error_and_return_i32(LibcError("EBADF"));
@@ -660,8 +770,13 @@ fn linux_statx(
             )
         }
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled, unless this queries an in-memory file.
+        let is_mem_file = path.as_os_str().is_empty()
+            && empty_path_flag
+            && this.machine.fds.get(dirfd).is_some_and(|fd| fd.downcast::<MemFile>().is_some());
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            && !is_mem_file
+        {
             this.reject_in_isolation("`statx`", reject_with)?;
             let ecode = if path.is_absolute() || dirfd == this.eval_libc_i32("AT_FDCWD") {
                 // since `path` is provided, either absolute or
@@ -806,8 +921,8 @@ fn rename(
             return this.set_last_error_and_return_i32(LibcError("EFAULT"));
         }
 
-        let oldpath = this.read_path_from_c_str(oldpath_ptr)?;
-        let newpath = this.read_path_from_c_str(newpath_ptr)?;
+        let oldpath = this.read_path_from_c_str(oldpath_ptr)?.into_owned();
+        let newpath = this.read_path_from_c_str(newpath_ptr)?.into_owned();
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -815,7 +930,12 @@ fn rename(
             return this.set_last_error_and_return_i32(ErrorKind::PermissionDenied);
         }
 
-        let result = rename(oldpath, newpath).map(|_| 0);
+        let dir = oldpath.is_dir();
+        let result = rename(&oldpath, &newpath).map(|_| 0);
+        if result.is_ok() {
+            let to = std::path::absolute(&newpath).unwrap_or(newpath);
+            this.inotify_notify(&oldpath, FsChange::Renamed { to, dir })?;
+        }
 
         interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
     }
@@ -830,7 +950,7 @@ fn mkdir(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<
             this.read_scalar(mode_op)?.to_u32()?
         };
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?.into_owned();
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -849,7 +969,10 @@ fn mkdir(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<
             builder.mode(mode);
         }
 
-        let result = builder.create(path).map(|_| 0i32);
+        let result = builder.create(&path).map(|_| 0i32);
+        if result.is_ok() {
+            this.inotify_notify(&path, FsChange::Created { dir: true })?;
+        }
 
         interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
     }
@@ -857,7 +980,7 @@ fn mkdir(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<
     fn rmdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
+        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?.into_owned();
 
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
@@ -865,7 +988,10 @@ fn rmdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
             return this.set_last_error_and_return_i32(ErrorKind::PermissionDenied);
         }
 
-        let result = remove_dir(path).map(|_| 0i32);
+        let result = remove_dir(&path).map(|_| 0i32);
+        if result.is_ok() {
+            this.inotify_notify(&path, FsChange::Deleted { dir: true })?;
+        }
 
         interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
     }
@@ -1167,6 +1293,15 @@ fn closedir(&mut self, dirp_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
     fn ftruncate64(&mut self, fd_num: i32, length: i128) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
+        // In-memory files do not touch the host, so they work with isolation.
+        if let Some(file) = this.machine.fds.get(fd_num).and_then(|fd| fd.downcast::<MemFile>()) {
+            let Ok(length) = length.try_into() else {
+                return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+            };
+            let result = file.set_len(length).map(|_| 0i32);
+            return interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?));
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`ftruncate64`", reject_with)?;
@@ -1186,6 +1321,9 @@ fn ftruncate64(&mut self, fd_num: i32, length: i128) -> InterpResult<'tcx, Scala
         if file.writable {
             if let Ok(length) = length.try_into() {
                 let result = file.file.set_len(length);
+                if result.is_ok() {
+                    this.inotify_notify(&file.path, FsChange::Modified)?;
+                }
                 let result = this.try_unwrap_io_result(result.map(|_| 0i32))?;
                 interp_ok(Scalar::from_i32(result))
             } else {
@@ -1511,11 +1649,15 @@ fn mkstemp(&mut self, template_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
             let possibly_unique = std::env::temp_dir().join::<PathBuf>(p.into());
 
-            let file = fopts.open(possibly_unique);
+            let file = fopts.open(&possibly_unique);
 
             match file {
                 Ok(f) => {
-                    let fd = this.machine.fds.insert_new(FileHandle { file: f, writable: true });
+                    let fd = this.machine.fds.insert_new(FileHandle {
+                        file: f,
+                        writable: true,
+                        path: possibly_unique,
+                    });
                     return interp_ok(Scalar::from_i32(fd));
                 }
                 Err(e) =>
@@ -1585,6 +1727,19 @@ fn from_fd_num<'tcx>(
             return interp_ok(Err(LibcError("EBADF")));
         };
 
+        if let Some(file) = fd.downcast::<MemFile>() {
+            return interp_ok(Ok(FileMetadata {
+                mode: ecx.eval_libc("S_IFREG"),
+                size: file.len(),
+                created: None,
+                accessed: None,
+                modified: None,
+                dev: 0,
+                uid: UID,
+                gid: UID,
+            }));
+        }
+
         let metadata = fd.metadata()?;
         drop(fd);
         FileMetadata::from_meta(ecx, metadata)
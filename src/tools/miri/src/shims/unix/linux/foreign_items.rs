@@ -6,7 +6,10 @@
 use self::shims::unix::linux::mem::EvalContextExt as _;
 use self::shims::unix::linux_like::epoll::EvalContextExt as _;
 use self::shims::unix::linux_like::eventfd::EvalContextExt as _;
+use self::shims::unix::linux_like::memfd::EvalContextExt as _;
+use self::shims::unix::linux_like::signalfd::EvalContextExt as _;
 use self::shims::unix::linux_like::syscall::syscall;
+use self::shims::unix::linux_like::timerfd::EvalContextExt as _;
 use crate::machine::{SIGRTMAX, SIGRTMIN};
 use crate::shims::unix::foreign_items::EvalContextExt as _;
 use crate::shims::unix::*;
@@ -53,8 +56,14 @@ fn emulate_foreign_item_inner(
                 let result = this.linux_statx(dirfd, pathname, flags, mask, statxbuf)?;
                 this.write_scalar(result, dest)?;
             }
+            "ioctl" => {
+                let ([fd_num, cmd], varargs) =
+                    this.check_shim_sig_variadic_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.ioctl(fd_num, cmd, varargs)?;
+                this.write_scalar(result, dest)?;
+            }
 
-            // epoll, eventfd
+            // epoll, eventfd, timerfd, signalfd
             "epoll_create1" => {
                 let [flag] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
                 let result = this.epoll_create1(flag)?;
@@ -76,6 +85,70 @@ fn emulate_foreign_item_inner(
                 let result = this.eventfd(val, flag)?;
                 this.write_scalar(result, dest)?;
             }
+            "timerfd_create" => {
+                let [clockid, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_create(clockid, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "timerfd_settime" => {
+                let [fd, flags, new_value, old_value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_settime(fd, flags, new_value, old_value)?;
+                this.write_scalar(result, dest)?;
+            }
+            "timerfd_gettime" => {
+                let [fd, curr_value] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.timerfd_gettime(fd, curr_value)?;
+                this.write_scalar(result, dest)?;
+            }
+            "signalfd" => {
+                let [fd, mask, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.signalfd(fd, mask, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigprocmask" => {
+                let [how, set, oldset] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.sigprocmask(how, set, oldset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "pthread_sigmask" => {
+                let [how, set, oldset] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.pthread_sigmask(how, set, oldset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_init" => {
+                let [] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_init1(0)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_init1" => {
+                let [flags] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let flags = this.read_scalar(flags)?.to_i32()?;
+                let result = this.inotify_init1(flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_add_watch" => {
+                let [fd, pathname, mask] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_add_watch(fd, pathname, mask)?;
+                this.write_scalar(result, dest)?;
+            }
+            "inotify_rm_watch" => {
+                let [fd, wd] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.inotify_rm_watch(fd, wd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "memfd_create" => {
+                let [name, flags] =
+                    this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                let result = this.memfd_create(name, flags)?;
+                this.write_scalar(result, dest)?;
+            }
 
             // Threading
             "pthread_setname_np" => {
@@ -149,6 +222,17 @@ fn emulate_foreign_item_inner(
                 let result = this.strerror_r(errnum, buf, buflen)?;
                 this.write_scalar(result, dest)?;
             }
+            "gnu_get_libc_version" if this.tcx.sess.target.env == "gnu" => {
+                let [] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
+                // There is no real glibc, but `std` probes the version before using
+                // `posix_spawn`: it falls back to `fork`/`exec` before glibc 2.24, whose
+                // `posix_spawn` can't report a failed `exec`. Our `posix_spawn` reports errors
+                // directly, so we claim a version that passes that check. The only other use in
+                // `std` is the `res_init` workaround for glibc before 2.26, which is not needed
+                // either. Any version from 2.26 on thus behaves the same.
+                let version = this.allocate_bytes_dedup(b"2.40\0")?;
+                this.write_pointer(version, dest)?;
+            }
             "__errno_location" => {
                 let [] = this.check_shim_sig_lenient(abi, CanonAbi::C, link_name, args)?;
                 let errno_place = this.last_error_place()?;
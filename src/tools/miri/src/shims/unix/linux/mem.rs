@@ -41,6 +41,12 @@ fn mremap(
             return interp_ok(this.eval_libc("MAP_FAILED"));
         }
 
+        if let Ok((alloc_id, _offset, _extra)) = this.ptr_try_get_alloc_id(old_address, 0)
+            && this.machine.file_mappings.contains(alloc_id)
+        {
+            throw_unsup_format!("Miri does not support mremap of shared file mappings");
+        }
+
         let align = this.machine.page_align();
         let ptr = this.reallocate_ptr(
             old_address,
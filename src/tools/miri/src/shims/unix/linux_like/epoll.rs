@@ -8,9 +8,11 @@
 
 use crate::concurrency::VClock;
 use crate::shims::files::{
-    DynFileDescriptionRef, FdId, FileDescription, FileDescriptionRef, WeakFileDescriptionRef,
+    DynFileDescriptionRef, FdId, FileDescription, FileDescriptionRef, FileHandle, MemFile,
+    WeakFileDescriptionRef,
 };
 use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::linux_like::timerfd::EvalContextExt as _;
 use crate::*;
 
 /// An `Epoll` file descriptor connects file handles and epoll events
@@ -284,6 +286,13 @@ fn epoll_ctl(
         };
         let id = fd_ref.id();
 
+        // Regular files are always ready, so they cannot be watched with epoll.
+        if fd_ref.clone().downcast::<FileHandle>().is_some()
+            || fd_ref.clone().downcast::<MemFile>().is_some()
+        {
+            return this.set_last_error_and_return_i32(LibcError("EPERM"));
+        }
+
         if op == epoll_ctl_add || op == epoll_ctl_mod {
             // Read event bitmask and data from epoll_event passed by caller.
             let mut events =
@@ -461,6 +470,9 @@ fn epoll_wait(
             return this.set_last_error_and_return(LibcError("EBADF"), dest);
         };
 
+        // Timers that expired since we last looked may have produced new events.
+        this.fire_expired_timers()?;
+
         // We just need to know if the ready list is empty and borrow the thread_ids out.
         let ready_list_empty = epfd.ready_list.mapping.borrow().is_empty();
         if timeout == 0 || !ready_list_empty {
@@ -468,10 +480,10 @@ fn epoll_wait(
             return_ready_list(&epfd, dest, &event, this)?;
         } else {
             // Blocking
-            let timeout = match timeout {
+            let deadline = match timeout {
                 0.. => {
                     let duration = Duration::from_millis(timeout.try_into().unwrap());
-                    Some((TimeoutClock::Monotonic, TimeoutAnchor::Relative, duration))
+                    Some(this.machine.monotonic_clock.elapsed().saturating_add(duration))
                 }
                 -1 => None,
                 ..-1 => {
@@ -480,40 +492,82 @@ fn epoll_wait(
                     );
                 }
             };
-            // Record this thread as blocked.
-            epfd.blocked_tid.borrow_mut().push(this.active_thread());
-            // And block it.
-            let dest = dest.clone();
-            // We keep a strong ref to the underlying `Epoll` to make sure it sticks around.
-            // This means there'll be a leak if we never wake up, but that anyway would imply
-            // a thread is permanently blocked so this is fine.
-            this.block_thread(
-                BlockReason::Epoll,
-                timeout,
-                callback!(
-                    @capture<'tcx> {
-                        epfd: FileDescriptionRef<Epoll>,
-                        dest: MPlaceTy<'tcx>,
-                        event: MPlaceTy<'tcx>,
-                    }
-                    |this, unblock: UnblockKind| {
-                        match unblock {
-                            UnblockKind::Ready => {
-                                return_ready_list(&epfd, &dest, &event, this)?;
-                                interp_ok(())
-                            },
-                            UnblockKind::TimedOut => {
-                                // Remove the current active thread_id from the blocked thread_id list.
-                                epfd
-                                    .blocked_tid.borrow_mut()
-                                    .retain(|&id| id != this.active_thread());
-                                this.write_int(0, &dest)?;
-                                interp_ok(())
-                            },
-                        }
-                    }
-                ),
-            );
+            blocking_epoll_wait(epfd, dest.clone(), event, deadline, this);
+        }
+        interp_ok(())
+    }
+
+    /// Blocks the active thread until one of the given file descriptions reports one of the
+    /// given events, or until `deadline` passes, and then calls `callback`. The file
+    /// descriptions are watched by an epoll instance that only exists for the duration of the
+    /// wait, so that `poll` can wait for them the same way `epoll_wait` does. The deadline is
+    /// measured from the epoch of the monotonic clock.
+    fn block_until_ready(
+        &mut self,
+        interests: Vec<(i32, DynFileDescriptionRef, EpollReadyEvents)>,
+        deadline: Option<Duration>,
+        callback: DynUnblockCallback<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        // Like with `epoll_ctl`, hangups and errors are always reported.
+        let hangup_or_error = this.eval_libc_u32("EPOLLHUP") | this.eval_libc_u32("EPOLLERR");
+
+        let epfd = this.machine.fds.new_ref(Epoll::default());
+        for (fd_num, fd_ref, events) in interests {
+            let id = fd_ref.id();
+            let interest = Rc::new(RefCell::new(EpollEventInterest {
+                fd_num,
+                events: events.get_event_bitmask(this) | hangup_or_error,
+                data: 0,
+                weak_epfd: FileDescriptionRef::downgrade(&epfd),
+            }));
+            this.machine.epoll_interests.insert_epoll_interest(id, Rc::downgrade(&interest));
+            epfd.interest_list.borrow_mut().insert((id, fd_num), interest);
+        }
+
+        // Armed timers shorten the wait so that their expirations get noticed.
+        let wake_up = match (deadline, this.next_timer_deadline()) {
+            (Some(deadline), Some(timer)) => Some(deadline.min(timer)),
+            (deadline, timer) => deadline.or(timer),
+        };
+        epfd.blocked_tid.borrow_mut().push(this.active_thread());
+        this.block_thread(
+            BlockReason::Epoll,
+            wake_up.map(|wake_up| (TimeoutClock::Monotonic, TimeoutAnchor::Absolute, wake_up)),
+            callback!(
+                @capture<'tcx> {
+                    epfd: FileDescriptionRef<Epoll>,
+                    callback: DynUnblockCallback<'tcx>,
+                }
+                |this, unblock: UnblockKind| {
+                    // Dropping the epoll instance unregisters its interests.
+                    drop(epfd);
+                    callback.call(this, unblock)
+                }
+            ),
+        );
+        interp_ok(())
+    }
+
+    /// Wakes up all threads that are blocked on an epoll instance interested in the given file
+    /// description, without reporting an event. This is needed when the time at which that file
+    /// description becomes ready changes, e.g. because a timer was re-armed. Threads that find
+    /// nothing to report go back to waiting.
+    fn wake_epoll_waiters(&mut self, id: FdId) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let mut waiter = Vec::new();
+        if let Some(epoll_interests) = this.machine.epoll_interests.get_epoll_interest(id) {
+            for weak_epoll_interest in epoll_interests {
+                if let Some(epoll_interest) = weak_epoll_interest.upgrade() {
+                    let epfd = epoll_interest.borrow().weak_epfd.upgrade().unwrap();
+                    waiter.append(&mut epfd.blocked_tid.borrow_mut());
+                }
+            }
+        }
+        waiter.sort();
+        waiter.dedup();
+        for thread_id in waiter {
+            this.unblock_thread(thread_id, BlockReason::Epoll)?;
         }
         interp_ok(())
     }
@@ -564,6 +618,59 @@ fn check_and_update_readiness(
     }
 }
 
+/// Blocks the active thread on `epfd` until an event is reported or `deadline` passes.
+/// The deadline is measured from the epoch of the monotonic clock. Armed timers shorten the wait
+/// so that their expirations get noticed even when no other thread is running.
+fn blocking_epoll_wait<'tcx>(
+    epfd: FileDescriptionRef<Epoll>,
+    dest: MPlaceTy<'tcx>,
+    event: MPlaceTy<'tcx>,
+    deadline: Option<Duration>,
+    ecx: &mut MiriInterpCx<'tcx>,
+) {
+    let wake_up = match (deadline, ecx.next_timer_deadline()) {
+        (Some(deadline), Some(timer)) => Some(deadline.min(timer)),
+        (deadline, timer) => deadline.or(timer),
+    };
+    // Record this thread as blocked.
+    epfd.blocked_tid.borrow_mut().push(ecx.active_thread());
+    // And block it.
+    // We keep a strong ref to the underlying `Epoll` to make sure it sticks around.
+    // This means there'll be a leak if we never wake up, but that anyway would imply
+    // a thread is permanently blocked so this is fine.
+    ecx.block_thread(
+        BlockReason::Epoll,
+        wake_up.map(|wake_up| (TimeoutClock::Monotonic, TimeoutAnchor::Absolute, wake_up)),
+        callback!(
+            @capture<'tcx> {
+                epfd: FileDescriptionRef<Epoll>,
+                dest: MPlaceTy<'tcx>,
+                event: MPlaceTy<'tcx>,
+                deadline: Option<Duration>,
+            }
+            |this, unblock: UnblockKind| {
+                if unblock == UnblockKind::TimedOut {
+                    // Remove the current active thread_id from the blocked thread_id list.
+                    epfd
+                        .blocked_tid.borrow_mut()
+                        .retain(|&id| id != this.active_thread());
+                    // We may have woken up early to let a timer expire.
+                    this.fire_expired_timers()?;
+                }
+                if !epfd.ready_list.mapping.borrow().is_empty() {
+                    return_ready_list(&epfd, &dest, &event, this)
+                } else if deadline.is_some_and(|d| d <= this.machine.monotonic_clock.elapsed()) {
+                    this.write_int(0, &dest)
+                } else {
+                    // Nothing to report yet, keep waiting.
+                    blocking_epoll_wait(epfd, dest, event, deadline, this);
+                    interp_ok(())
+                }
+            }
+        ),
+    );
+}
+
 /// This function takes in ready list and returns EpollEventInstance with file description
 /// that is not closed.
 fn ready_list_next(
@@ -0,0 +1,555 @@
+//! Linux `inotify` implementation.
+//!
+//! Miri does not watch the host file system. Instead, the file system shims report the changes
+//! that the interpreted program itself makes (see `FsChange`), and those are turned into events for
+//! the matching watches. Changes made by other processes are not reported.
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use rustc_abi::Size;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{FileDescription, FileDescriptionRef, WeakFileDescriptionRef};
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::*;
+
+/// A change the program made to the file system, reported by the file system shims.
+#[derive(Debug)]
+pub enum FsChange {
+    /// A file was written to or truncated.
+    Modified,
+    /// A file was closed.
+    Closed { written: bool },
+    /// A file or directory was created.
+    Created { dir: bool },
+    /// A file or directory was removed.
+    Deleted { dir: bool },
+    /// A file or directory was renamed to `to`.
+    Renamed { to: PathBuf, dir: bool },
+}
+
+/// A watch on a path, created by `inotify_add_watch`.
+#[derive(Debug)]
+struct Watch {
+    wd: i32,
+    /// The canonical path of the watched file or directory.
+    path: PathBuf,
+    mask: u32,
+}
+
+/// An event that has not been read yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QueuedEvent {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    /// For events on a file in a watched directory, the name of that file.
+    name: Vec<u8>,
+}
+
+/// A kind of file descriptor created by `inotify_init`.
+///
+/// <https://man7.org/linux/man-pages/man7/inotify.7.html>
+#[derive(Debug)]
+struct Inotify {
+    watches: RefCell<Vec<Watch>>,
+    next_wd: Cell<i32>,
+    queue: RefCell<VecDeque<QueuedEvent>>,
+    is_nonblock: bool,
+    clock: RefCell<VClock>,
+    /// A list of thread ids blocked on inotify::read.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+}
+
+impl Inotify {
+    fn push_event(&self, event: QueuedEvent) {
+        let mut queue = self.queue.borrow_mut();
+        // Like Linux, we merge an event into an identical one that has not been read yet.
+        if queue.back() != Some(&event) {
+            queue.push_back(event);
+        }
+    }
+}
+
+impl FileDescription for Inotify {
+    fn name(&self) -> &'static str {
+        "inotify"
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(Ok(()))
+    }
+
+    /// Read as many queued events as fit into the buffer, blocking until there is at least one.
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        inotify_read(ptr, len, self, ecx, finish)
+    }
+
+    fn as_unix<'tcx>(&self, _ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for Inotify {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        // An inotify instance is readable while it has queued events; it is never writable.
+        interp_ok(EpollReadyEvents {
+            epollin: !self.queue.borrow().is_empty(),
+            ..EpollReadyEvents::new()
+        })
+    }
+}
+
+/// The list of all inotify instances, so that file system changes can be reported to them.
+#[derive(Debug, Default)]
+pub struct InotifyTable {
+    instances: Vec<WeakFileDescriptionRef<Inotify>>,
+    /// The cookie of the last rename, which connects its `IN_MOVED_FROM` and `IN_MOVED_TO` events.
+    last_cookie: u32,
+}
+
+/// Returns the canonical path of `path`, which may not exist (any more) if its parent does.
+fn canonical_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+    let name = path.file_name()?;
+    let parent = path.parent()?;
+    let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+    Some(parent.canonicalize().ok()?.join(name))
+}
+
+/// Splits a canonical path into its parent directory and its name.
+fn split_path(path: &Path) -> Option<(&Path, &[u8])> {
+    Some((path.parent()?, path.file_name()?.as_encoded_bytes()))
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Returns all inotify instances that are still open.
+    fn inotify_instances(&mut self) -> Vec<FileDescriptionRef<Inotify>> {
+        let this = self.eval_context_mut();
+        this.machine.inotify.instances.retain(|inotify| inotify.upgrade().is_some());
+        this.machine.inotify.instances.iter().filter_map(|inotify| inotify.upgrade()).collect()
+    }
+
+    /// Queues an event for all watches of `watched` that are interested in it.
+    fn inotify_deliver(
+        &mut self,
+        watched: &Path,
+        name: &[u8],
+        mask: u32,
+        cookie: u32,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let in_all_events = this.eval_libc_u32("IN_ALL_EVENTS");
+        let in_oneshot = this.eval_libc_u32("IN_ONESHOT");
+        let in_ignored = this.eval_libc_u32("IN_IGNORED");
+
+        for inotify in this.inotify_instances() {
+            let mut queued = false;
+            inotify.watches.borrow_mut().retain(|watch| {
+                if watch.path != watched || watch.mask & mask & in_all_events == 0 {
+                    return true;
+                }
+                inotify.push_event(QueuedEvent { wd: watch.wd, mask, cookie, name: name.to_vec() });
+                queued = true;
+                if watch.mask & in_oneshot != 0 {
+                    // A one-shot watch is removed after its first event.
+                    inotify.push_event(QueuedEvent {
+                        wd: watch.wd,
+                        mask: in_ignored,
+                        cookie: 0,
+                        name: Vec::new(),
+                    });
+                    return false;
+                }
+                true
+            });
+            if queued {
+                this.inotify_wake(inotify)?;
+            }
+        }
+        interp_ok(())
+    }
+
+    /// Removes all watches of `path`, which was deleted.
+    fn inotify_remove_watches(&mut self, path: &Path) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let in_ignored = this.eval_libc_u32("IN_IGNORED");
+
+        for inotify in this.inotify_instances() {
+            let mut removed = false;
+            inotify.watches.borrow_mut().retain(|watch| {
+                if watch.path != path {
+                    return true;
+                }
+                inotify.push_event(QueuedEvent {
+                    wd: watch.wd,
+                    mask: in_ignored,
+                    cookie: 0,
+                    name: Vec::new(),
+                });
+                removed = true;
+                false
+            });
+            if removed {
+                this.inotify_wake(inotify)?;
+            }
+        }
+        interp_ok(())
+    }
+
+    /// Wakes up the readers of an inotify instance that has new events.
+    fn inotify_wake(&mut self, inotify: FileDescriptionRef<Inotify>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        // Reading the events synchronizes with the change that caused them.
+        this.release_clock(|clock| {
+            inotify.clock.borrow_mut().join(clock);
+        })?;
+
+        // Unblock *all* threads previously blocked on `read`.
+        // We need to take out the blocked thread ids and unblock them together,
+        // because `unblock_threads` may block them again and end up re-adding the
+        // thread to the blocked list.
+        let waiting_threads = std::mem::take(&mut *inotify.blocked_read_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            this.unblock_thread(thread_id, BlockReason::Inotify)?;
+        }
+
+        // The state changed; we check and update the status of all supported event
+        // types for current file description.
+        this.check_and_update_readiness(inotify)
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// This function creates a new inotify instance and returns a file descriptor that refers to
+    /// it. `inotify_init` is the same as `inotify_init1` with `flags` set to 0.
+    ///
+    /// The `flags` may be bitwise ORed to change the behavior of `inotify_init1`:
+    /// `IN_CLOEXEC` - Set the close-on-exec (`FD_CLOEXEC`) flag on the new file descriptor.
+    /// `IN_NONBLOCK` - Set the `O_NONBLOCK` file status flag on the new open file description.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/inotify_init.2.html>
+    fn inotify_init1(&mut self, flags: i32) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let mut flags = flags;
+        let in_cloexec = this.eval_libc_i32("IN_CLOEXEC");
+        let in_nonblock = this.eval_libc_i32("IN_NONBLOCK");
+
+        let mut is_nonblock = false;
+        // Unset the flag that we support.
+        // After unloading, flags != 0 means other flags are used.
+        if flags & in_cloexec == in_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !in_cloexec;
+        }
+        if flags & in_nonblock == in_nonblock {
+            flags &= !in_nonblock;
+            is_nonblock = true;
+        }
+        if flags != 0 {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+
+        let inotify = this.machine.fds.new_ref(Inotify {
+            watches: RefCell::new(Vec::new()),
+            next_wd: Cell::new(1),
+            queue: RefCell::new(VecDeque::new()),
+            is_nonblock,
+            clock: RefCell::new(VClock::default()),
+            blocked_read_tid: RefCell::new(Vec::new()),
+        });
+        this.machine.inotify.instances.push(FileDescriptionRef::downgrade(&inotify));
+        let fd_value = this.machine.fds.insert(inotify);
+
+        interp_ok(Scalar::from_i32(fd_value))
+    }
+
+    /// This function adds a watch for the file or directory at `pathname` to the inotify
+    /// instance `fd`, or changes the mask of an existing watch for it, and returns the watch
+    /// descriptor.
+    ///
+    /// Besides the event types, the `mask` may contain `IN_ONLYDIR`, `IN_MASK_ADD`, `IN_ONESHOT`
+    /// and `IN_EXCL_UNLINK`. The last one has no effect since Miri does not report events for
+    /// unlinked files anyway.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/inotify_add_watch.2.html>
+    fn inotify_add_watch(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        pathname: &OpTy<'tcx>,
+        mask: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let path = this.read_path_from_c_str(this.read_pointer(pathname)?)?;
+        let mut mask = this.read_scalar(mask)?.to_u32()?;
+
+        let in_all_events = this.eval_libc_u32("IN_ALL_EVENTS");
+        let in_onlydir = this.eval_libc_u32("IN_ONLYDIR");
+        let in_mask_add = this.eval_libc_u32("IN_MASK_ADD");
+        let in_oneshot = this.eval_libc_u32("IN_ONESHOT");
+        let in_excl_unlink = this.eval_libc_u32("IN_EXCL_UNLINK");
+
+        let unsupported =
+            mask & !(in_all_events | in_onlydir | in_mask_add | in_oneshot | in_excl_unlink);
+        if unsupported != 0 {
+            throw_unsup_format!(
+                "inotify_add_watch: encountered unknown unsupported flags {:#x}",
+                unsupported
+            );
+        }
+        if mask & in_all_events == 0 {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+
+        let Some(inotify) = this.machine.fds.get(fd) else {
+            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        };
+        let Some(inotify) = inotify.downcast::<Inotify>() else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+
+        // Reject if isolation is enabled.
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation("`inotify_add_watch`", reject_with)?;
+            return this.set_last_error_and_return_i32(ErrorKind::PermissionDenied);
+        }
+
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        if mask & in_onlydir != 0 && !path.is_dir() {
+            return this.set_last_error_and_return_i32(LibcError("ENOTDIR"));
+        }
+        mask &= !in_onlydir;
+
+        let mut watches = inotify.watches.borrow_mut();
+        let wd = if let Some(watch) = watches.iter_mut().find(|watch| watch.path == path) {
+            if mask & in_mask_add != 0 {
+                watch.mask |= mask & !in_mask_add;
+            } else {
+                watch.mask = mask;
+            }
+            watch.wd
+        } else {
+            let wd = inotify.next_wd.get();
+            inotify.next_wd.set(wd.strict_add(1));
+            watches.push(Watch { wd, path, mask: mask & !in_mask_add });
+            wd
+        };
+
+        interp_ok(Scalar::from_i32(wd))
+    }
+
+    /// This function removes the watch `wd` from the inotify instance `fd`. An `IN_IGNORED` event
+    /// is queued for it.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/inotify_rm_watch.2.html>
+    fn inotify_rm_watch(&mut self, fd: &OpTy<'tcx>, wd: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let wd = this.read_scalar(wd)?.to_i32()?;
+
+        let Some(inotify) = this.machine.fds.get(fd) else {
+            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        };
+        let Some(inotify) = inotify.downcast::<Inotify>() else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+
+        let mut watches = inotify.watches.borrow_mut();
+        let Some(idx) = watches.iter().position(|watch| watch.wd == wd) else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+        watches.remove(idx);
+        drop(watches);
+
+        let in_ignored = this.eval_libc_u32("IN_IGNORED");
+        inotify.push_event(QueuedEvent { wd, mask: in_ignored, cookie: 0, name: Vec::new() });
+        this.inotify_wake(inotify)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Reports a change that the program made to the file system at `path` to all interested
+    /// inotify watches.
+    fn inotify_notify(&mut self, path: &Path, change: FsChange) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        // Avoid touching the host file system (and looking up constants that only exist on Linux)
+        // if there is nobody to notify.
+        if this.inotify_instances().is_empty() {
+            return interp_ok(());
+        }
+        let Some(path) = canonical_path(path) else {
+            return interp_ok(());
+        };
+        let Some((parent, name)) = split_path(&path) else {
+            return interp_ok(());
+        };
+        let in_isdir = this.eval_libc_u32("IN_ISDIR");
+        let is_dir = |dir: bool| if dir { in_isdir } else { 0 };
+
+        match change {
+            FsChange::Modified => {
+                let in_modify = this.eval_libc_u32("IN_MODIFY");
+                this.inotify_deliver(parent, name, in_modify, 0)?;
+                this.inotify_deliver(&path, &[], in_modify, 0)?;
+            }
+            FsChange::Closed { written } => {
+                let in_close = if written {
+                    this.eval_libc_u32("IN_CLOSE_WRITE")
+                } else {
+                    this.eval_libc_u32("IN_CLOSE_NOWRITE")
+                };
+                this.inotify_deliver(parent, name, in_close, 0)?;
+                this.inotify_deliver(&path, &[], in_close, 0)?;
+            }
+            FsChange::Created { dir } => {
+                let mask = this.eval_libc_u32("IN_CREATE") | is_dir(dir);
+                this.inotify_deliver(parent, name, mask, 0)?;
+            }
+            FsChange::Deleted { dir } => {
+                let mask = this.eval_libc_u32("IN_DELETE") | is_dir(dir);
+                this.inotify_deliver(parent, name, mask, 0)?;
+                let in_delete_self = this.eval_libc_u32("IN_DELETE_SELF");
+                this.inotify_deliver(&path, &[], in_delete_self, 0)?;
+                this.inotify_remove_watches(&path)?;
+            }
+            FsChange::Renamed { to, dir } => {
+                let Some(to) = canonical_path(&to) else {
+                    return interp_ok(());
+                };
+                let Some((to_parent, to_name)) = split_path(&to) else {
+                    return interp_ok(());
+                };
+                // Cookies are never zero, that means "no cookie".
+                let cookie = this.machine.inotify.last_cookie.wrapping_add(1).max(1);
+                this.machine.inotify.last_cookie = cookie;
+                let in_moved_from = this.eval_libc_u32("IN_MOVED_FROM") | is_dir(dir);
+                let in_moved_to = this.eval_libc_u32("IN_MOVED_TO") | is_dir(dir);
+                this.inotify_deliver(parent, name, in_moved_from, cookie)?;
+                this.inotify_deliver(to_parent, to_name, in_moved_to, cookie)?;
+                let in_move_self = this.eval_libc_u32("IN_MOVE_SELF");
+                this.inotify_deliver(&path, &[], in_move_self, 0)?;
+                // Watches follow the file to its new name.
+                for inotify in this.inotify_instances() {
+                    for watch in inotify.watches.borrow_mut().iter_mut() {
+                        if watch.path == path {
+                            watch.path = to.clone();
+                        }
+                    }
+                }
+            }
+        }
+        interp_ok(())
+    }
+}
+
+/// Block thread if there are no queued events,
+/// else read as many events as fit into the buffer.
+fn inotify_read<'tcx>(
+    ptr: Pointer,
+    len: usize,
+    inotify: FileDescriptionRef<Inotify>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    if inotify.queue.borrow().is_empty() {
+        if inotify.is_nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+
+        inotify.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+
+        let weak_inotify = FileDescriptionRef::downgrade(&inotify);
+        ecx.block_thread(
+            BlockReason::Inotify,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    ptr: Pointer,
+                    len: usize,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                    weak_inotify: WeakFileDescriptionRef<Inotify>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    // When we get unblocked, try again. We know the ref is still valid,
+                    // otherwise there couldn't be an event that unblocks us.
+                    let inotify_ref = weak_inotify.upgrade().unwrap();
+                    inotify_read(ptr, len, inotify_ref, this, finish)
+                }
+            ),
+        );
+        return interp_ok(());
+    }
+
+    // Synchronize with all changes that caused the queued events.
+    ecx.acquire_clock(&inotify.clock.borrow())?;
+
+    let header = ecx.libc_ty_layout("inotify_event");
+    let header_size = header.size.bytes();
+    let len = len.to_u64();
+    let mut written: u64 = 0;
+    let mut queue = inotify.queue.borrow_mut();
+    while let Some(event) = queue.front() {
+        // The name is NUL-terminated and padded so that the next event is aligned.
+        let name_len = if event.name.is_empty() {
+            0
+        } else {
+            event.name.len().to_u64().strict_add(1).next_multiple_of(header_size)
+        };
+        let event_size = header_size.strict_add(name_len);
+        if written.strict_add(event_size) > len {
+            break;
+        }
+        let event_ptr = ptr.wrapping_offset(Size::from_bytes(written), ecx);
+        let event_place = ecx.ptr_to_mplace_unaligned(event_ptr, header);
+        ecx.write_int_fields_named(
+            &[
+                ("wd", event.wd.into()),
+                ("mask", event.mask.into()),
+                ("cookie", event.cookie.into()),
+                ("len", name_len.into()),
+            ],
+            &event_place,
+        )?;
+        let mut name = event.name.clone();
+        name.resize(name_len.try_into().unwrap(), 0);
+        ecx.write_bytes_ptr(event_ptr.wrapping_offset(header.size, ecx), name)?;
+        written = written.strict_add(event_size);
+        queue.pop_front();
+    }
+    drop(queue);
+
+    if written == 0 {
+        // The buffer is too small for the next event.
+        return finish.call(ecx, Err(ErrorKind::InvalidInput.into()));
+    }
+    finish.call(ecx, Ok(written.try_into().unwrap()))
+}
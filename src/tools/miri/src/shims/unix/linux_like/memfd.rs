@@ -0,0 +1,60 @@
+//! Linux `memfd_create` implementation.
+use crate::shims::files::MemFile;
+use crate::*;
+
+/// The maximum length of the name of a memfd, excluding the null terminator.
+const MFD_NAME_MAX_LEN: usize = 249;
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// This function creates an anonymous file that lives in memory and returns a file
+    /// descriptor that refers to it. The `name` is only used for debugging on Linux, so it is
+    /// ignored. The file never touches the host file system, so this works with isolation.
+    ///
+    /// The `flags` may be bitwise ORed to change the behavior of `memfd_create`:
+    /// `MFD_CLOEXEC` - Set the close-on-exec (`FD_CLOEXEC`) flag on the new file descriptor.
+    /// `MFD_ALLOW_SEALING` - Allow sealing operations on the file. Miri accepts this flag, but
+    /// does not support sealing.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/memfd_create.2.html>
+    fn memfd_create(
+        &mut self,
+        name: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let name = this.read_pointer(name)?;
+        let mut flags = this.read_scalar(flags)?.to_u32()?;
+
+        let name = this.read_c_str(name)?;
+        if name.len() > MFD_NAME_MAX_LEN {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+
+        let mfd_cloexec = this.eval_libc_u32("MFD_CLOEXEC");
+        let mfd_allow_sealing = this.eval_libc_u32("MFD_ALLOW_SEALING");
+        let mfd_hugetlb = this.eval_libc_u32("MFD_HUGETLB");
+
+        if flags & mfd_hugetlb == mfd_hugetlb {
+            throw_unsup_format!("memfd_create: MFD_HUGETLB is unsupported");
+        }
+
+        // Unset the flags that we support.
+        // After unloading, flags != 0 means other flags are used.
+        if flags & mfd_cloexec == mfd_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !mfd_cloexec;
+        }
+        if flags & mfd_allow_sealing == mfd_allow_sealing {
+            // There is no way to add seals yet, so the file simply never gets sealed.
+            flags &= !mfd_allow_sealing;
+        }
+        if flags != 0 {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+
+        let fd = this.machine.fds.insert_new(MemFile::default());
+        interp_ok(Scalar::from_i32(fd))
+    }
+}
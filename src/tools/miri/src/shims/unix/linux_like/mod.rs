@@ -1,4 +1,8 @@
 pub mod epoll;
 pub mod eventfd;
+pub mod inotify;
+pub mod memfd;
+pub mod signalfd;
 pub mod sync;
 pub mod syscall;
+pub mod timerfd;
@@ -0,0 +1,446 @@
+//! Linux `signalfd` implementation, along with the signal mask that it relies on.
+//!
+//! Miri does not deliver signals to signal handlers. A program can still send itself signals with
+//! `kill` or `raise` as long as it blocks them first, and then read them from a `signalfd`.
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::io::ErrorKind;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{FileDescription, FileDescriptionRef, WeakFileDescriptionRef};
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::shims::unix::{UID, UnixFileDescription};
+use crate::*;
+
+/// The value of `ssi_code` for signals sent by `kill` or `raise`.
+const SI_USER: i32 = 0;
+
+/// A kind of file descriptor created by `signalfd`.
+///
+/// <https://man7.org/linux/man-pages/man2/signalfd.2.html>
+#[derive(Debug)]
+struct SignalFd {
+    /// The signals that can be read from this file descriptor, with signal `n` being the bit
+    /// `n - 1`.
+    mask: Cell<u64>,
+    /// Whether one of the signals in `mask` is pending. Kept up to date by `update_signalfds`.
+    readable: Cell<bool>,
+    is_nonblock: bool,
+    /// A list of thread ids blocked on signalfd::read.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+}
+
+impl FileDescription for SignalFd {
+    fn name(&self) -> &'static str {
+        "signal"
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(Ok(()))
+    }
+
+    /// Read as many pending signals as fit in the buffer, blocking until there is at least one.
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        // The buffer has to fit at least one `signalfd_siginfo`.
+        let siginfo = ecx.libc_ty_layout("signalfd_siginfo");
+        if len < siginfo.size.bytes_usize() {
+            return finish.call(ecx, Err(ErrorKind::InvalidInput.into()));
+        }
+
+        signalfd_read(ptr, len, self, ecx, finish)
+    }
+
+    fn as_unix<'tcx>(&self, _ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for SignalFd {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        // A signalfd is readable while one of its signals is pending; it is never writable.
+        interp_ok(EpollReadyEvents { epollin: self.readable.get(), ..EpollReadyEvents::new() })
+    }
+}
+
+/// The signal mask of the process and the signals that are pending because they were blocked
+/// when they got sent.
+///
+/// Linux has a signal mask per thread. Miri keeps a single one for the whole process, so
+/// `pthread_sigmask` behaves like `sigprocmask`.
+#[derive(Debug, Default)]
+pub struct SignalState {
+    /// The blocked signals, with signal `n` being the bit `n - 1`.
+    blocked: u64,
+    /// The signals that have been sent but not been read yet. Like standard signals on Linux,
+    /// a signal that is sent again while it is pending is only read once.
+    pending: u64,
+    /// Reading a signal synchronizes with the threads that sent the pending signals.
+    clock: RefCell<VClock>,
+    /// The list of all `signalfd`s, so that their readiness can be updated when signals get sent.
+    signalfds: Vec<WeakFileDescriptionRef<SignalFd>>,
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// This function creates a new file descriptor to read the signals in `mask`, or, if `fd` is
+    /// not `-1`, replaces the mask of the existing signalfd that it refers to.
+    ///
+    /// The `flags` may be bitwise ORed to change the behavior of `signalfd`:
+    /// `SFD_CLOEXEC` - Set the close-on-exec (`FD_CLOEXEC`) flag on the new file descriptor.
+    /// `SFD_NONBLOCK` - Set the `O_NONBLOCK` file status flag on the new open file description.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/signalfd.2.html>
+    fn signalfd(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        mask: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let mask = this.deref_pointer_as(mask, this.libc_ty_layout("sigset_t"))?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+
+        // `SIGKILL` and `SIGSTOP` are silently ignored, they can't be read from a signalfd.
+        let mask = this.read_sigset(&mask)? & !this.unblockable_signals();
+
+        if fd != -1 {
+            let Some(fd_ref) = this.machine.fds.get(fd) else {
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            };
+            let Some(signalfd) = fd_ref.downcast::<SignalFd>() else {
+                return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+            };
+            // The flags only apply when creating a new signalfd.
+            signalfd.mask.set(mask);
+            this.update_signalfds()?;
+            return interp_ok(Scalar::from_i32(fd));
+        }
+
+        let sfd_cloexec = this.eval_libc_i32("SFD_CLOEXEC");
+        let sfd_nonblock = this.eval_libc_i32("SFD_NONBLOCK");
+
+        let mut is_nonblock = false;
+        // Unset the flag that we support.
+        // After unloading, flags != 0 means other flags are used.
+        if flags & sfd_cloexec == sfd_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !sfd_cloexec;
+        }
+        if flags & sfd_nonblock == sfd_nonblock {
+            flags &= !sfd_nonblock;
+            is_nonblock = true;
+        }
+        if flags != 0 {
+            throw_unsup_format!("signalfd: encountered unknown unsupported flags {:#x}", flags);
+        }
+
+        let signalfd = this.machine.fds.new_ref(SignalFd {
+            mask: Cell::new(mask),
+            readable: Cell::new(false),
+            is_nonblock,
+            blocked_read_tid: RefCell::new(Vec::new()),
+        });
+        this.machine.signals.signalfds.push(FileDescriptionRef::downgrade(&signalfd));
+        let fd_value = this.machine.fds.insert(signalfd);
+        // One of the signals may already be pending.
+        this.update_signalfds()?;
+
+        interp_ok(Scalar::from_i32(fd_value))
+    }
+
+    /// Examines and changes the signal mask, setting `errno` on failure.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/sigprocmask.2.html>
+    fn sigprocmask(
+        &mut self,
+        how: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+        oldset: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        match this.change_signal_mask(how, set, oldset)? {
+            Ok(()) => interp_ok(Scalar::from_i32(0)),
+            Err(e) => this.set_last_error_and_return_i32(LibcError(e)),
+        }
+    }
+
+    /// Examines and changes the signal mask, returning the error number on failure.
+    ///
+    /// <https://man7.org/linux/man-pages/man3/pthread_sigmask.3.html>
+    fn pthread_sigmask(
+        &mut self,
+        how: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+        oldset: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        match this.change_signal_mask(how, set, oldset)? {
+            Ok(()) => interp_ok(Scalar::from_i32(0)),
+            Err(e) => interp_ok(this.eval_libc(e)),
+        }
+    }
+
+    /// Sends the signal `sig` to the process itself. Signals can only be sent while they are
+    /// blocked, because Miri can't run signal handlers; they then stay pending until they are
+    /// read from a signalfd.
+    fn raise_signal(&mut self, sig: i32) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        // Signal 0 only checks that the process exists.
+        if sig == 0 {
+            return interp_ok(Scalar::from_i32(0));
+        }
+        let Some(bit) = signal_bit(sig) else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+        if this.machine.signals.blocked & bit == 0 {
+            throw_unsup_format!(
+                "delivering signal {sig} is not supported; only blocked signals can be sent, \
+                 to be read with `signalfd`"
+            );
+        }
+
+        // Reading the signal will synchronize with this thread.
+        this.release_clock(|clock| {
+            this.machine.signals.clock.borrow_mut().join(clock);
+        })?;
+        this.machine.signals.pending |= bit;
+        this.update_signalfds()?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+}
+
+impl<'tcx> EvalContextExtPriv<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPriv<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Implements `sigprocmask` and `pthread_sigmask`, returning the name of the error on failure.
+    fn change_signal_mask(
+        &mut self,
+        how: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+        oldset: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Result<(), &'static str>> {
+        let this = self.eval_context_mut();
+
+        let how = this.read_scalar(how)?.to_i32()?;
+        let set = this.read_pointer(set)?;
+        let oldset = this.read_pointer(oldset)?;
+        let sigset = this.libc_ty_layout("sigset_t");
+
+        let old = this.machine.signals.blocked;
+        let mut new = old;
+        if !this.ptr_is_null(set)? {
+            let set = this.ptr_to_mplace(set, sigset);
+            let set = this.read_sigset(&set)?;
+            new = if how == this.eval_libc_i32("SIG_BLOCK") {
+                old | set
+            } else if how == this.eval_libc_i32("SIG_UNBLOCK") {
+                old & !set
+            } else if how == this.eval_libc_i32("SIG_SETMASK") {
+                set
+            } else {
+                return interp_ok(Err("EINVAL"));
+            };
+        }
+        // `SIGKILL` and `SIGSTOP` can't be blocked, attempts to do so are silently ignored.
+        new &= !this.unblockable_signals();
+
+        if !this.ptr_is_null(oldset)? {
+            let oldset = this.ptr_to_mplace(oldset, sigset);
+            this.write_sigset(&oldset, old)?;
+        }
+
+        let delivered = this.machine.signals.pending & !new;
+        if delivered != 0 {
+            throw_unsup_format!(
+                "delivering signal {} is not supported; pending signals can't be unblocked",
+                delivered.trailing_zeros().strict_add(1)
+            );
+        }
+        this.machine.signals.blocked = new;
+
+        interp_ok(Ok(()))
+    }
+
+    /// Reads the signals 1 to 64 from a `sigset_t`, with signal `n` being the bit `n - 1`.
+    fn read_sigset(&self, set: &MPlaceTy<'tcx>) -> InterpResult<'tcx, u64> {
+        let this = self.eval_context_ref();
+
+        // The set is an array of words, like in `sigaddset`.
+        let word_size = set.layout.size.min(this.pointer_size());
+        let word_bits = word_size.bits();
+        let mut mask = 0;
+        for index in 0..set.layout.size.bits().min(64).strict_div(word_bits) {
+            let word =
+                set.offset(word_size * index, this.machine.layouts.uint(word_size).unwrap(), this)?;
+            let word = u64::try_from(this.read_scalar(&word)?.to_uint(word_size)?).unwrap();
+            mask |= word.strict_shl(u32::try_from(index.strict_mul(word_bits)).unwrap());
+        }
+        interp_ok(mask)
+    }
+
+    /// Writes the signals of `mask` to a `sigset_t`, the inverse of `read_sigset`.
+    fn write_sigset(&mut self, set: &MPlaceTy<'tcx>, mask: u64) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        this.write_bytes_ptr(set.ptr(), std::iter::repeat_n(0u8, set.layout.size.bytes_usize()))?;
+        let word_size = set.layout.size.min(this.pointer_size());
+        let word_bits = word_size.bits();
+        for index in 0..set.layout.size.bits().min(64).strict_div(word_bits) {
+            let word =
+                set.offset(word_size * index, this.machine.layouts.uint(word_size).unwrap(), this)?;
+            let bits = mask.strict_shr(u32::try_from(index.strict_mul(word_bits)).unwrap());
+            this.write_scalar(
+                Scalar::from_uint(word_size.truncate(bits.into()), word_size),
+                &word,
+            )?;
+        }
+        interp_ok(())
+    }
+
+    /// The signals that can neither be blocked nor read from a signalfd.
+    fn unblockable_signals(&self) -> u64 {
+        let this = self.eval_context_ref();
+        [this.eval_libc_i32("SIGKILL"), this.eval_libc_i32("SIGSTOP")]
+            .into_iter()
+            .filter_map(signal_bit)
+            .fold(0, |mask, bit| mask | bit)
+    }
+
+    /// Updates the readiness of all signalfds after the pending signals or their masks changed,
+    /// waking up the threads that wait for them.
+    fn update_signalfds(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let pending = this.machine.signals.pending;
+
+        // Forget about signalfds that have been closed.
+        this.machine.signals.signalfds.retain(|signalfd| signalfd.upgrade().is_some());
+        let changed: Vec<_> = this
+            .machine
+            .signals
+            .signalfds
+            .iter()
+            .filter_map(|signalfd| signalfd.upgrade())
+            .filter(|signalfd| {
+                let readable = signalfd.mask.get() & pending != 0;
+                signalfd.readable.replace(readable) != readable
+            })
+            .collect();
+
+        for signalfd in changed {
+            if signalfd.readable.get() {
+                // Unblock *all* threads previously blocked on `read`.
+                // We need to take out the blocked thread ids and unblock them together,
+                // because `unblock_threads` may block them again and end up re-adding the
+                // thread to the blocked list.
+                let waiting_threads = std::mem::take(&mut *signalfd.blocked_read_tid.borrow_mut());
+                for thread_id in waiting_threads {
+                    this.unblock_thread(thread_id, BlockReason::Signalfd)?;
+                }
+            }
+
+            // The state changed; we check and update the status of all supported event
+            // types for current file description.
+            this.check_and_update_readiness(signalfd)?;
+        }
+        interp_ok(())
+    }
+}
+
+/// The bit of signal `sig` in a signal mask, if it is a valid signal number.
+fn signal_bit(sig: i32) -> Option<u64> {
+    let bit = u32::try_from(sig).ok()?.checked_sub(1)?;
+    1u64.checked_shl(bit)
+}
+
+/// Block thread if none of the signals of the signalfd is pending,
+/// else write them to the buffer and remove them from the pending signals.
+fn signalfd_read<'tcx>(
+    ptr: Pointer,
+    len: usize,
+    signalfd: FileDescriptionRef<SignalFd>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    let mut available = ecx.machine.signals.pending & signalfd.mask.get();
+
+    if available == 0 {
+        if signalfd.is_nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+
+        signalfd.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+
+        let weak_signalfd = FileDescriptionRef::downgrade(&signalfd);
+        ecx.block_thread(
+            BlockReason::Signalfd,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    ptr: Pointer,
+                    len: usize,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                    weak_signalfd: WeakFileDescriptionRef<SignalFd>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    let Some(signalfd) = weak_signalfd.upgrade() else {
+                        // The signalfd got closed while we were waiting.
+                        return finish.call(this, Err(LibcError("EBADF")));
+                    };
+                    // Try again; another thread may have read the signal in the meantime.
+                    signalfd_read(ptr, len, signalfd, this, finish)
+                }
+            ),
+        );
+        return interp_ok(());
+    }
+
+    // Synchronize with the threads that sent the signals.
+    ecx.acquire_clock(&ecx.machine.signals.clock.borrow())?;
+
+    // Lower-numbered signals are read first, one `signalfd_siginfo` each.
+    let siginfo = ecx.libc_ty_layout("signalfd_siginfo");
+    let pid = ecx.get_pid();
+    let mut read = 0;
+    while available != 0 && (read + 1) * siginfo.size.bytes_usize() <= len {
+        let bit = available.trailing_zeros();
+        available &= !(1 << bit);
+        ecx.machine.signals.pending &= !(1 << bit);
+
+        let place = ecx.ptr_to_mplace_unaligned(
+            ptr.wrapping_offset(siginfo.size * u64::try_from(read).unwrap(), ecx),
+            siginfo,
+        );
+        ecx.write_bytes_ptr(place.ptr(), std::iter::repeat_n(0u8, siginfo.size.bytes_usize()))?;
+        ecx.write_int_fields_named(
+            &[
+                ("ssi_signo", bit.strict_add(1).into()),
+                ("ssi_code", SI_USER.into()),
+                ("ssi_pid", pid.into()),
+                ("ssi_uid", UID.into()),
+            ],
+            &place,
+        )?;
+        read += 1;
+    }
+
+    // The signals are no longer pending.
+    ecx.update_signalfds()?;
+
+    // Tell userspace how many bytes we put into the buffer.
+    finish.call(ecx, Ok(read * siginfo.size.bytes_usize()))
+}
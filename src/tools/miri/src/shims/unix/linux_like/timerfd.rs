@@ -0,0 +1,422 @@
+//! Linux `timerfd` implementation.
+//!
+//! Timers are driven by Miri's monotonic clock, so with isolation enabled they follow the
+//! deterministic virtual clock.
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{FileDescription, FileDescriptionRef, WeakFileDescriptionRef};
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::*;
+
+/// A kind of file descriptor created by `timerfd_create`.
+///
+/// <https://man7.org/linux/man-pages/man2/timerfd_create.2.html>
+#[derive(Debug)]
+struct TimerFd {
+    /// The next time the timer expires, measured from the epoch of the monotonic clock.
+    /// `None` if the timer is disarmed.
+    deadline: Cell<Option<Duration>>,
+    /// The period of the timer after its first expiration. Zero for a one-shot timer.
+    interval: Cell<Duration>,
+    /// The number of expirations that have not been read yet.
+    expirations: Cell<u64>,
+    is_nonblock: bool,
+    clock: RefCell<VClock>,
+    /// A list of thread ids blocked on timerfd::read.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+}
+
+impl TimerFd {
+    /// Accounts for all expirations up to `now` and returns whether there were any.
+    fn expire(&self, now: Duration) -> bool {
+        let Some(deadline) = self.deadline.get() else {
+            return false;
+        };
+        if now < deadline {
+            return false;
+        }
+        let interval = self.interval.get();
+        let count = if interval.is_zero() {
+            self.deadline.set(None);
+            1
+        } else {
+            // The timer may have expired several times since we last looked at it.
+            let overruns = now.saturating_sub(deadline).as_nanos().strict_div(interval.as_nanos());
+            let count = u64::try_from(overruns).unwrap_or(u64::MAX).saturating_add(1);
+            let periods = u32::try_from(count).unwrap_or(u32::MAX);
+            self.deadline.set(Some(deadline.saturating_add(interval.saturating_mul(periods))));
+            count
+        };
+        self.expirations.set(self.expirations.get().saturating_add(count));
+        true
+    }
+
+    /// The time until the next expiration, or zero if the timer is disarmed.
+    fn remaining(&self, now: Duration) -> Duration {
+        self.deadline.get().map_or(Duration::ZERO, |deadline| deadline.saturating_sub(now))
+    }
+}
+
+impl FileDescription for TimerFd {
+    fn name(&self) -> &'static str {
+        "timer"
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(Ok(()))
+    }
+
+    /// Read the number of expirations since the last read, blocking until there is at least one.
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        // We're treating the buffer as a `u64`.
+        let ty = ecx.machine.layouts.u64;
+        // Check the size of slice, and return error only if the size of the slice < 8.
+        if len < ty.size.bytes_usize() {
+            return finish.call(ecx, Err(ErrorKind::InvalidInput.into()));
+        }
+
+        // Turn the pointer into a place at the right type.
+        let buf_place = ecx.ptr_to_mplace_unaligned(ptr, ty);
+
+        timerfd_read(buf_place, self, ecx, finish)
+    }
+
+    fn as_unix<'tcx>(&self, _ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for TimerFd {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        // A timer is readable once it has expired; it is never writable.
+        interp_ok(EpollReadyEvents {
+            epollin: self.expirations.get() != 0,
+            ..EpollReadyEvents::new()
+        })
+    }
+}
+
+/// The list of all `timerfd` timers, so that their expirations can be noticed even when no thread
+/// is currently reading from them.
+#[derive(Debug, Default)]
+pub struct TimerFdTable(Vec<WeakFileDescriptionRef<TimerFd>>);
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// This function creates a new timer object and returns a file descriptor that refers to it.
+    /// Only `CLOCK_MONOTONIC` and `CLOCK_BOOTTIME` are supported; both are backed by Miri's
+    /// monotonic clock.
+    ///
+    /// The `flags` may be bitwise ORed to change the behavior of `timerfd_create`:
+    /// `TFD_CLOEXEC` - Set the close-on-exec (`FD_CLOEXEC`) flag on the new file descriptor.
+    /// `TFD_NONBLOCK` - Set the `O_NONBLOCK` file status flag on the new open file description.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/timerfd_create.2.html>
+    fn timerfd_create(
+        &mut self,
+        clockid: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let clockid = this.read_scalar(clockid)?.to_i32()?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+
+        if clockid == this.eval_libc_i32("CLOCK_REALTIME") {
+            throw_unsup_format!("timerfd_create: CLOCK_REALTIME is unsupported");
+        } else if clockid != this.eval_libc_i32("CLOCK_MONOTONIC")
+            && clockid != this.eval_libc_i32("CLOCK_BOOTTIME")
+        {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+
+        let tfd_cloexec = this.eval_libc_i32("TFD_CLOEXEC");
+        let tfd_nonblock = this.eval_libc_i32("TFD_NONBLOCK");
+
+        let mut is_nonblock = false;
+        // Unset the flag that we support.
+        // After unloading, flags != 0 means other flags are used.
+        if flags & tfd_cloexec == tfd_cloexec {
+            // cloexec is ignored because Miri does not support exec.
+            flags &= !tfd_cloexec;
+        }
+        if flags & tfd_nonblock == tfd_nonblock {
+            flags &= !tfd_nonblock;
+            is_nonblock = true;
+        }
+        if flags != 0 {
+            throw_unsup_format!(
+                "timerfd_create: encountered unknown unsupported flags {:#x}",
+                flags
+            );
+        }
+
+        let timer = this.machine.fds.new_ref(TimerFd {
+            deadline: Cell::new(None),
+            interval: Cell::new(Duration::ZERO),
+            expirations: Cell::new(0),
+            is_nonblock,
+            clock: RefCell::new(VClock::default()),
+            blocked_read_tid: RefCell::new(Vec::new()),
+        });
+        this.machine.timerfds.0.push(FileDescriptionRef::downgrade(&timer));
+        let fd_value = this.machine.fds.insert(timer);
+
+        interp_ok(Scalar::from_i32(fd_value))
+    }
+
+    /// This function arms or disarms the timer referred to by `fd`, and optionally returns its
+    /// previous setting in `old_value`. With `TFD_TIMER_ABSTIME`, the initial expiration in
+    /// `new_value` is an absolute time on the timer's clock; otherwise it is relative to now.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/timerfd_settime.2.html>
+    fn timerfd_settime(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        new_value: &OpTy<'tcx>,
+        old_value: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+        let new_value = this.deref_pointer_as(new_value, this.libc_ty_layout("itimerspec"))?;
+        let old_value_ptr = this.read_pointer(old_value)?;
+
+        let timer = match this.timerfd(fd) {
+            Ok(timer) => timer,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+
+        let tfd_timer_abstime = this.eval_libc_i32("TFD_TIMER_ABSTIME");
+        if flags & !tfd_timer_abstime != 0 {
+            throw_unsup_format!(
+                "timerfd_settime: encountered unknown unsupported flags {:#x}",
+                flags
+            );
+        }
+
+        let interval = this.project_field_named(&new_value, "it_interval")?;
+        let value = this.project_field_named(&new_value, "it_value")?;
+        let (Some(interval), Some(value)) =
+            (this.read_timespec(&interval)?, this.read_timespec(&value)?)
+        else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+
+        // Make sure the old setting we report is up to date.
+        this.fire_expired_timers()?;
+        let now = this.machine.monotonic_clock.elapsed();
+
+        if !this.ptr_is_null(old_value_ptr)? {
+            let old_value = this.ptr_to_mplace(old_value_ptr, new_value.layout);
+            write_itimerspec(&old_value, timer.remaining(now), timer.interval.get(), this)?;
+        }
+
+        // Future `read` calls will synchronize with this update, so update the FD clock.
+        this.release_clock(|clock| {
+            timer.clock.borrow_mut().join(clock);
+        })?;
+
+        // Setting the timer discards expirations that have not been read yet.
+        timer.expirations.set(0);
+        timer.interval.set(interval);
+        timer.deadline.set(if value.is_zero() {
+            None
+        } else if flags & tfd_timer_abstime != 0 {
+            Some(value)
+        } else {
+            Some(now.saturating_add(value))
+        });
+
+        // A deadline in the past expires immediately.
+        this.fire_expired_timers()?;
+
+        // Everyone who is waiting for this timer has to re-evaluate how long to wait.
+        // We need to take out the blocked thread ids and unblock them together,
+        // because `unblock_threads` may block them again and end up re-adding the
+        // thread to the blocked list.
+        let waiting_threads = std::mem::take(&mut *timer.blocked_read_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            this.unblock_thread(thread_id, BlockReason::Timerfd)?;
+        }
+        this.wake_epoll_waiters(timer.id())?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// This function returns the time until the next expiration of the timer referred to by `fd`
+    /// and its interval.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/timerfd_gettime.2.html>
+    fn timerfd_gettime(
+        &mut self,
+        fd: &OpTy<'tcx>,
+        curr_value: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let curr_value = this.deref_pointer_as(curr_value, this.libc_ty_layout("itimerspec"))?;
+
+        let timer = match this.timerfd(fd) {
+            Ok(timer) => timer,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+
+        this.fire_expired_timers()?;
+        let now = this.machine.monotonic_clock.elapsed();
+        write_itimerspec(&curr_value, timer.remaining(now), timer.interval.get(), this)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Records the expirations of all timers whose deadline has passed, waking up the threads that
+    /// wait for them. Time only passes in between calls into the shims, so this has to be called
+    /// by every operation whose outcome depends on whether a timer has expired.
+    fn fire_expired_timers(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let now = this.machine.monotonic_clock.elapsed();
+
+        // Forget about timers that have been closed.
+        this.machine.timerfds.0.retain(|timer| timer.upgrade().is_some());
+        let expired: Vec<_> = this
+            .machine
+            .timerfds
+            .0
+            .iter()
+            .filter_map(|timer| timer.upgrade())
+            .filter(|timer| timer.expire(now))
+            .collect();
+
+        for timer in expired {
+            // Unblock *all* threads previously blocked on `read`.
+            // We need to take out the blocked thread ids and unblock them together,
+            // because `unblock_threads` may block them again and end up re-adding the
+            // thread to the blocked list.
+            let waiting_threads = std::mem::take(&mut *timer.blocked_read_tid.borrow_mut());
+            for thread_id in waiting_threads {
+                this.unblock_thread(thread_id, BlockReason::Timerfd)?;
+            }
+
+            // The state changed; we check and update the status of all supported event
+            // types for current file description.
+            this.check_and_update_readiness(timer)?;
+        }
+        interp_ok(())
+    }
+
+    /// Returns the earliest deadline of all armed timers, measured from the epoch of the
+    /// monotonic clock. Threads that wait for timers have to wake up no later than this.
+    fn next_timer_deadline(&self) -> Option<Duration> {
+        let this = self.eval_context_ref();
+        this.machine.timerfds.0.iter().filter_map(|timer| timer.upgrade()?.deadline.get()).min()
+    }
+}
+
+impl<'tcx> EvalContextExtPriv<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPriv<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Looks up the timer that `fd` refers to.
+    fn timerfd(&self, fd: i32) -> Result<FileDescriptionRef<TimerFd>, IoError> {
+        let this = self.eval_context_ref();
+        let Some(fd) = this.machine.fds.get(fd) else {
+            return Err(LibcError("EBADF"));
+        };
+        fd.downcast::<TimerFd>().ok_or(LibcError("EINVAL"))
+    }
+}
+
+/// Writes an `itimerspec` with the given time until the next expiration and interval.
+fn write_itimerspec<'tcx>(
+    place: &MPlaceTy<'tcx>,
+    value: Duration,
+    interval: Duration,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx> {
+    for (field, duration) in [("it_interval", interval), ("it_value", value)] {
+        let timespec = ecx.project_field_named(place, field)?;
+        ecx.write_int_fields(
+            &[duration.as_secs().into(), duration.subsec_nanos().into()],
+            &timespec,
+        )?;
+    }
+    interp_ok(())
+}
+
+/// Block thread if the timer has not expired since the last read,
+/// else return the number of expirations to the caller and reset it to 0.
+fn timerfd_read<'tcx>(
+    buf_place: MPlaceTy<'tcx>,
+    timer: FileDescriptionRef<TimerFd>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    ecx.fire_expired_timers()?;
+
+    // Reset the expiration count, get old value.
+    let expirations = timer.expirations.replace(0);
+
+    if expirations == 0 {
+        if timer.is_nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+
+        timer.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+
+        // Wake up when the timer expires. If it is not armed, we have to wait for someone to
+        // arm it.
+        let timeout =
+            timer.deadline.get().map(|d| (TimeoutClock::Monotonic, TimeoutAnchor::Absolute, d));
+        let weak_timer = FileDescriptionRef::downgrade(&timer);
+        ecx.block_thread(
+            BlockReason::Timerfd,
+            timeout,
+            callback!(
+                @capture<'tcx> {
+                    buf_place: MPlaceTy<'tcx>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                    weak_timer: WeakFileDescriptionRef<TimerFd>,
+                }
+                |this, unblock: UnblockKind| {
+                    let Some(timer) = weak_timer.upgrade() else {
+                        // The timer got closed while we were waiting.
+                        return finish.call(this, Err(LibcError("EBADF")));
+                    };
+                    if unblock == UnblockKind::TimedOut {
+                        // Remove the current active thread_id from the blocked thread_id list.
+                        timer.blocked_read_tid.borrow_mut().retain(|&id| id != this.active_thread());
+                    }
+                    // Try again; the timer should have expired by now, unless it was re-armed.
+                    timerfd_read(buf_place, timer, this, finish)
+                }
+            ),
+        );
+    } else {
+        // Synchronize with all prior `timerfd_settime` calls on this FD.
+        ecx.acquire_clock(&timer.clock.borrow())?;
+
+        // Return the number of expirations into user-space buffer.
+        ecx.write_int(expirations, &buf_place)?;
+
+        // Tell userspace how many bytes we put into the buffer.
+        return finish.call(ecx, Ok(buf_place.layout.size.bytes_usize()));
+    }
+    interp_ok(())
+}
@@ -320,30 +320,4 @@ fn emulate_foreign_item_inner(
 
         interp_ok(EmulateItemResult::NeedsReturn)
     }
-
-    fn ioctl(
-        &mut self,
-        fd_num: &OpTy<'tcx>,
-        cmd: &OpTy<'tcx>,
-        _varargs: &[OpTy<'tcx>],
-    ) -> InterpResult<'tcx, Scalar> {
-        let this = self.eval_context_mut();
-
-        let fioclex = this.eval_libc_u64("FIOCLEX");
-
-        let fd_num = this.read_scalar(fd_num)?.to_i32()?;
-        let cmd = this.read_scalar(cmd)?.to_u64()?;
-
-        if cmd == fioclex {
-            // Since we don't support `exec`, this is a NOP. However, we want to
-            // return EBADF if the FD is invalid.
-            if this.machine.fds.is_fd_num(fd_num) {
-                interp_ok(Scalar::from_i32(0))
-            } else {
-                this.set_last_error_and_return_i32(LibcError("EBADF"))
-            }
-        } else {
-            throw_unsup_format!("ioctl: unsupported command {cmd:#x}");
-        }
-    }
 }
@@ -1,11 +1,20 @@
 //! This is an incomplete implementation of mmap/munmap which is restricted in order to be
 //! implementable on top of the existing memory system. The point of these function as-written is
-//! to allow memory allocators written entirely in Rust to be executed by Miri. This implementation
-//! does not support other uses of mmap such as file mappings.
+//! to allow memory allocators written entirely in Rust to be executed by Miri.
 //!
 //! mmap/munmap behave a lot like alloc/dealloc, and for simple use they are exactly
-//! equivalent. That is the only part we support: no MAP_FIXED or MAP_SHARED or anything
-//! else that goes beyond a basic allocation API.
+//! equivalent. That is the main part we support: no MAP_FIXED or anything else that goes beyond a
+//! basic allocation API.
+//!
+//! File mappings are supported by copying: `mmap` copies the contents of the file into a fresh
+//! allocation, and for shared writable mappings, `msync` and `munmap` copy the memory back into
+//! the file. Shared mappings are kept coherent with `read` and `write` (and `pread`/`pwrite`) on
+//! the file description they were created from, or a `dup` of it: before such an access, the
+//! mappings are written back to the file, and after a write, they are reloaded from it. These
+//! copies act like the kernel and are not checked by the borrow tracker or the data race detector.
+//! The mappings are *not* coherent with accesses through other file descriptions of the same file,
+//! e.g. when it is opened a second time, and mappings that are still alive when the program exits
+//! are not written back. Writing to a mapping without `PROT_WRITE` is reported as an error.
 //!
 //! Note that in addition to only supporting malloc-like calls to mmap, we only support free-like
 //! calls to munmap, but for a very different reason. In principle, according to the man pages, it
@@ -14,12 +23,216 @@
 //! munmap shim which would partially unmap a region of address space previously mapped by mmap will
 //! report UB.
 
+use std::io;
+
 use rustc_abi::Size;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 
+use crate::shims::files::{DynFileDescriptionRef, FdId};
 use crate::*;
 
+/// A shared mapping of a file, which is kept coherent with the file description it was created
+/// from. If it is writable, its memory also gets written back by `msync` and `munmap`.
+#[derive(Debug)]
+struct FileMapping {
+    fd: DynFileDescriptionRef,
+    /// The offset in the file where the mapping starts.
+    offset: u64,
+    /// The number of bytes that were mapped from the file. Memory beyond that (up to the end of
+    /// the page) does not correspond to the file and is never written back.
+    len: u64,
+    writable: bool,
+}
+
+/// The file mappings that need special treatment, indexed by their allocation.
+#[derive(Debug, Default)]
+pub struct FileMappings {
+    /// The shared mappings.
+    mappings: FxHashMap<AllocId, FileMapping>,
+    /// The mappings without `PROT_WRITE`, shared or not.
+    read_only: FxHashSet<AllocId>,
+}
+
+impl FileMappings {
+    pub fn contains(&self, alloc_id: AllocId) -> bool {
+        self.mappings.contains_key(&alloc_id) || self.read_only.contains(&alloc_id)
+    }
+
+    /// Whether the program must not write to this allocation.
+    pub fn is_read_only(&self, alloc_id: AllocId) -> bool {
+        self.read_only.contains(&alloc_id)
+    }
+
+    /// The shared mappings created from the file description `fd`, in a deterministic order.
+    fn of_fd(&self, fd: FdId) -> Vec<AllocId> {
+        let mut mappings: Vec<AllocId> = self
+            .mappings
+            .iter()
+            .filter(|(_, mapping)| mapping.fd.id() == fd)
+            .map(|(&alloc_id, _)| alloc_id)
+            .collect();
+        mappings.sort();
+        mappings
+    }
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// The part of `mmap` that maps the file `fd`. The arguments have already been read and
+    /// `length` is known to be non-zero.
+    fn mmap_file(
+        &mut self,
+        length: u64,
+        prot: i32,
+        flags: i32,
+        fd: i32,
+        offset: i128,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let map_private = this.eval_libc_i32("MAP_PRIVATE");
+        let map_shared = this.eval_libc_i32("MAP_SHARED");
+        let prot_read = this.eval_libc_i32("PROT_READ");
+        let prot_write = this.eval_libc_i32("PROT_WRITE");
+
+        if flags != map_private && flags != map_shared {
+            throw_unsup_format!(
+                "Miri only supports calls to mmap of a file which set the flags argument to \
+                 MAP_PRIVATE or MAP_SHARED",
+            );
+        }
+        if prot != prot_read && prot != prot_read | prot_write {
+            throw_unsup_format!(
+                "Miri only supports calls to mmap of a file with protections PROT_READ or \
+                 PROT_READ|PROT_WRITE",
+            );
+        }
+
+        // The offset must be a multiple of the page size.
+        let Ok(offset) = u64::try_from(offset) else {
+            this.set_last_error(LibcError("EINVAL"))?;
+            return interp_ok(this.eval_libc("MAP_FAILED"));
+        };
+        if !offset.is_multiple_of(this.machine.page_size) {
+            this.set_last_error(LibcError("EINVAL"))?;
+            return interp_ok(this.eval_libc("MAP_FAILED"));
+        }
+
+        let align = this.machine.page_align();
+        let Some(map_length) = length.checked_next_multiple_of(this.machine.page_size) else {
+            this.set_last_error(LibcError("EINVAL"))?;
+            return interp_ok(this.eval_libc("MAP_FAILED"));
+        };
+        if map_length > this.target_usize_max() {
+            this.set_last_error(LibcError("EINVAL"))?;
+            return interp_ok(this.eval_libc("MAP_FAILED"));
+        }
+
+        let Some(fd) = this.machine.fds.get(fd) else {
+            this.set_last_error(LibcError("EBADF"))?;
+            return interp_ok(this.eval_libc("MAP_FAILED"));
+        };
+        // `length` fits in the address space, so it fits in a `usize` on the host.
+        let len = usize::try_from(length).unwrap();
+        let bytes = match fd.as_unix(this).read_at(this.machine.communicate(), offset, len)? {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                this.set_last_error(err)?;
+                return interp_ok(this.eval_libc("MAP_FAILED"));
+            }
+        };
+
+        let ptr = this.allocate_ptr(
+            Size::from_bytes(map_length),
+            align,
+            MiriMemoryKind::Mmap.into(),
+            // The part of the mapping beyond the end of the file is zero-init.
+            AllocInit::Zero,
+        )?;
+        this.write_bytes_ptr(ptr.into(), bytes.iter().copied())?;
+
+        let (alloc_id, _offset, _extra) = this.ptr_get_alloc_id(ptr.into(), 0)?;
+        let writable = prot & prot_write != 0;
+        if !writable {
+            this.machine.file_mappings.read_only.insert(alloc_id);
+        }
+        if flags == map_shared {
+            let mapping = FileMapping { fd, offset, len: bytes.len().to_u64(), writable };
+            this.machine.file_mappings.mappings.insert(alloc_id, mapping);
+        }
+
+        interp_ok(Scalar::from_pointer(ptr, this))
+    }
+
+    /// Writes `len` bytes of memory at `ptr`, which is `start` bytes into `mapping`, back to the
+    /// file. Bytes beyond the part of the mapping that corresponds to the file are ignored.
+    fn write_back_file_mapping(
+        &mut self,
+        mapping: &FileMapping,
+        ptr: Pointer,
+        start: u64,
+        len: u64,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        let this = self.eval_context_mut();
+        if !mapping.writable {
+            return interp_ok(Ok(()));
+        }
+        let end = start.saturating_add(len).min(mapping.len);
+        if end <= start {
+            return interp_ok(Ok(()));
+        }
+        let bytes =
+            this.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(end.strict_sub(start)))?;
+        let bytes = bytes.to_vec();
+        mapping.fd.as_unix(this).write_at(
+            this.machine.communicate(),
+            mapping.offset.strict_add(start),
+            &bytes,
+        )
+    }
+}
+
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
 pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Writes the writable shared mappings of the file description `fd` back to the file, before
+    /// the program reads from or writes to `fd`. Like on a real system, errors while doing so are
+    /// not reported.
+    fn file_mappings_to_file(&mut self, fd: FdId) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        for alloc_id in this.machine.file_mappings.of_fd(fd) {
+            let mapping = &this.machine.file_mappings.mappings[&alloc_id];
+            if !mapping.writable {
+                continue;
+            }
+            let (fd, offset) = (mapping.fd.clone(), mapping.offset);
+            let range = alloc_range(Size::ZERO, Size::from_bytes(mapping.len));
+            let bytes = this.get_alloc_raw(alloc_id)?.get_bytes_unchecked(range).to_vec();
+            let _ = fd.as_unix(this).write_at(this.machine.communicate(), offset, &bytes)?;
+        }
+        interp_ok(())
+    }
+
+    /// Reloads the shared mappings of the file description `fd` from the file, after the program
+    /// wrote to `fd`. Like on a real system, errors while doing so are not reported.
+    fn file_mappings_from_file(&mut self, fd: FdId) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        for alloc_id in this.machine.file_mappings.of_fd(fd) {
+            let mapping = &this.machine.file_mappings.mappings[&alloc_id];
+            let (fd, offset) = (mapping.fd.clone(), mapping.offset);
+            // `len` was mapped, so it fits in a `usize`.
+            let len = usize::try_from(mapping.len).unwrap();
+            let Ok(bytes) = fd.as_unix(this).read_at(this.machine.communicate(), offset, len)?
+            else {
+                continue;
+            };
+            let tcx = *this.tcx;
+            let (alloc, _machine) = this.get_alloc_raw_mut(alloc_id)?;
+            let range = alloc_range(Size::ZERO, Size::from_bytes(bytes.len()));
+            alloc.get_bytes_unchecked_for_overwrite(&tcx, range).copy_from_slice(&bytes);
+        }
+        interp_ok(())
+    }
+
     fn mmap(
         &mut self,
         addr: &OpTy<'tcx>,
@@ -65,13 +278,6 @@ fn mmap(
             return interp_ok(this.eval_libc("MAP_FAILED"));
         }
 
-        // If a user tries to map a file, we want to loudly inform them that this is not going
-        // to work. It is possible that POSIX gives us enough leeway to return an error, but the
-        // outcome for the user (I need to add cfg(miri)) is the same, just more frustrating.
-        if fd != -1 {
-            throw_unsup_format!("Miri does not support file-backed memory mappings");
-        }
-
         // Miri doesn't support MAP_FIXED.
         if flags & map_fixed != 0 {
             throw_unsup_format!(
@@ -79,6 +285,10 @@ fn mmap(
             );
         }
 
+        if fd != -1 {
+            return this.mmap_file(length, prot, flags, fd, offset);
+        }
+
         // Miri doesn't support protections other than PROT_READ|PROT_WRITE.
         if prot != prot_read | prot_write {
             throw_unsup_format!(
@@ -96,7 +306,7 @@ fn mmap(
             );
         }
 
-        // This is only used for file mappings, which we don't support anyway.
+        // The offset is only used for file mappings.
         if offset != 0 {
             throw_unsup_format!("Miri does not support non-zero offsets to mmap");
         }
@@ -142,6 +352,15 @@ fn munmap(&mut self, addr: &OpTy<'tcx>, length: &OpTy<'tcx>) -> InterpResult<'tc
             return interp_ok(this.eval_libc("MAP_FAILED"));
         }
 
+        // Shared file mappings get written back to the file first. Like on a real system, errors
+        // while doing so are not reported.
+        if let Ok((alloc_id, _offset, _extra)) = this.ptr_try_get_alloc_id(addr, 0) {
+            if let Some(mapping) = this.machine.file_mappings.mappings.remove(&alloc_id) {
+                let _ = this.write_back_file_mapping(&mapping, addr, 0, length)?;
+            }
+            this.machine.file_mappings.read_only.remove(&alloc_id);
+        }
+
         let length = Size::from_bytes(length);
         this.deallocate_ptr(
             addr,
@@ -151,4 +370,51 @@ fn munmap(&mut self, addr: &OpTy<'tcx>, length: &OpTy<'tcx>) -> InterpResult<'tc
 
         interp_ok(Scalar::from_i32(0))
     }
+
+    fn msync(
+        &mut self,
+        addr: &OpTy<'tcx>,
+        length: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(addr)?;
+        let length = this.read_target_usize(length)?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+
+        let ms_async = this.eval_libc_i32("MS_ASYNC");
+        let ms_sync = this.eval_libc_i32("MS_SYNC");
+        let ms_invalidate = this.eval_libc_i32("MS_INVALIDATE");
+
+        // addr must be a multiple of the page size, exactly one of MS_ASYNC and MS_SYNC may be
+        // set, and there must be no other flags.
+        if !addr.addr().bytes().is_multiple_of(this.machine.page_size)
+            || (flags & ms_async != 0 && flags & ms_sync != 0)
+            || flags & !(ms_async | ms_sync | ms_invalidate) != 0
+        {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+
+        // The range must be mapped.
+        let Ok((alloc_id, start, _extra)) = this.ptr_try_get_alloc_id(addr, 0) else {
+            return this.set_last_error_and_return_i32(LibcError("ENOMEM"));
+        };
+        let alloc_size = this.get_alloc_info(alloc_id).size;
+        if start.bytes().checked_add(length).is_none_or(|end| end > alloc_size.bytes()) {
+            return this.set_last_error_and_return_i32(LibcError("ENOMEM"));
+        }
+
+        // Only shared file mappings need to be written back; for everything else, the memory is
+        // all there is. We write back synchronously even for MS_ASYNC.
+        if let Some(mapping) = this.machine.file_mappings.mappings.remove(&alloc_id) {
+            let result = this.write_back_file_mapping(&mapping, addr, start.bytes(), length)?;
+            this.machine.file_mappings.mappings.insert(alloc_id, mapping);
+            if let Err(err) = result {
+                return this.set_last_error_and_return_i32(err);
+            }
+        }
+
+        interp_ok(Scalar::from_i32(0))
+    }
 }
@@ -4,6 +4,8 @@
 mod fd;
 mod fs;
 mod mem;
+mod process;
+mod socket;
 mod sync;
 mod thread;
 mod unnamed_socket;
@@ -20,7 +22,12 @@
 pub use self::fd::{EvalContextExt as _, UnixFileDescription};
 pub use self::fs::{DirTable, EvalContextExt as _};
 pub use self::linux_like::epoll::EpollInterestTable;
-pub use self::mem::EvalContextExt as _;
+pub use self::linux_like::inotify::{EvalContextExt as _, FsChange, InotifyTable};
+pub use self::linux_like::signalfd::SignalState;
+pub use self::linux_like::timerfd::TimerFdTable;
+pub use self::mem::{EvalContextExt as _, FileMappings};
+pub use self::process::{ChildProcesses, EvalContextExt as _};
+pub use self::socket::{EvalContextExt as _, LoopbackNetwork};
 pub use self::sync::EvalContextExt as _;
 pub use self::thread::{EvalContextExt as _, ThreadNameResult};
 pub use self::unnamed_socket::EvalContextExt as _;
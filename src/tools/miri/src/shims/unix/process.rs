@@ -0,0 +1,533 @@
+//! This implements spawning child processes, for the one case we can support: the program spawning
+//! itself (see `-Zmiri-spawn-self`). The child runs in a nested interpreter, to completion, before
+//! `posix_spawn` returns. Redirected standard streams are forwarded through the parent's file
+//! descriptors: the child sees the input that was available when it got spawned, and the parent
+//! sees the child's output once it terminated.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+
+use crate::eval::{ChildTermination, eval_child};
+use crate::shims::files::{
+    DynFileDescriptionRef, EvalContextExt as _, FileDescription, FileDescriptionRef, FileHandle,
+    NullOutput,
+};
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::linux_like::epoll::EpollReadyEvents;
+use crate::shims::unix::linux_like::signalfd::EvalContextExt as _;
+use crate::shims::unix::unnamed_socket::{anonsocket_deliver, anonsocket_drain};
+use crate::*;
+
+/// What is needed to start the interpreted program again.
+#[derive(Clone)]
+struct SpawnSelf {
+    entry_id: DefId,
+    entry_type: MiriEntryFnType,
+    config: MiriConfig,
+}
+
+/// The emulated child processes of the interpreted program.
+#[derive(Default)]
+pub struct ChildProcesses {
+    /// Set if the program may spawn itself.
+    spawn_self: Option<SpawnSelf>,
+    /// The wait status of each child that was not waited for yet, indexed by PID.
+    /// Children always terminate before `posix_spawn` returns, so this is all there is to track.
+    unwaited: BTreeMap<i32, i32>,
+    /// The number of children spawned so far.
+    num_spawned: i32,
+}
+
+impl ChildProcesses {
+    pub fn enable_spawn_self(
+        &mut self,
+        entry_id: DefId,
+        entry_type: MiriEntryFnType,
+        config: MiriConfig,
+    ) {
+        self.spawn_self = Some(SpawnSelf { entry_id, entry_type, config });
+    }
+}
+
+/// The actions recorded in a `posix_spawn_file_actions_t`.
+#[derive(Default)]
+struct FileActions {
+    /// The `(fd, newfd)` pairs added with `posix_spawn_file_actions_adddup2`.
+    dup2: RefCell<Vec<(i32, i32)>>,
+}
+
+/// A standard stream of a child process that was redirected by the parent.
+/// This is just a buffer: it is filled with the input before the child runs,
+/// or with the output while the child runs.
+#[derive(Debug)]
+struct ChildStdio {
+    buf: Rc<RefCell<VecDeque<u8>>>,
+}
+
+impl FileDescription for ChildStdio {
+    fn name(&self) -> &'static str {
+        "redirected standard stream"
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        // Once the buffer is empty, this reads 0 bytes, i.e. EOF.
+        let result = ecx.read_from_host(&mut *self.buf.borrow_mut(), len, ptr)?;
+        finish.call(ecx, result)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let result = ecx.write_to_host(&mut *self.buf.borrow_mut(), len, ptr)?;
+        finish.call(ecx, result)
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(Ok(()))
+    }
+
+    fn as_unix<'tcx>(&self, _ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for ChildStdio {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        // Neither reading nor writing ever blocks.
+        let mut ready_events = EpollReadyEvents::new();
+        ready_events.epollin = true;
+        ready_events.epollout = true;
+        interp_ok(ready_events)
+    }
+}
+
+/// Whether `path` refers to the program that is being interpreted.
+fn is_interpreted_program(path: &Path, config: &MiriConfig) -> bool {
+    // The program can learn its own path via `argv[0]`, or via `std::env::current_exe` if
+    // isolation is disabled. The latter is the path of the Miri binary on the host.
+    config.args.first().is_some_and(|arg0| path == Path::new(arg0))
+        || std::env::current_exe().is_ok_and(|exe| path == exe)
+}
+
+impl<'tcx> EvalContextExtPriv<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPriv<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Reads a null-terminated array of pointers to C strings, like `argv` and `envp`.
+    fn read_c_str_array(&self, ptr: Pointer) -> InterpResult<'tcx, Vec<Vec<u8>>> {
+        let this = self.eval_context_ref();
+        let ptr_layout = this.machine.layouts.mut_raw_ptr;
+
+        let mut strings = Vec::new();
+        if this.ptr_is_null(ptr)? {
+            return interp_ok(strings);
+        }
+        for idx in 0u64.. {
+            let entry =
+                this.ptr_to_mplace(ptr.wrapping_offset(ptr_layout.size * idx, this), ptr_layout);
+            let str_ptr = this.read_pointer(&entry)?;
+            if this.ptr_is_null(str_ptr)? {
+                break;
+            }
+            strings.push(this.read_c_str(str_ptr)?.to_owned());
+        }
+        interp_ok(strings)
+    }
+
+    /// Takes the input for a child whose standard input was redirected to `fd`.
+    fn take_child_input(
+        &mut self,
+        link_name: &str,
+        fd: DynFileDescriptionRef,
+    ) -> InterpResult<'tcx, io::Result<Vec<u8>>> {
+        let this = self.eval_context_mut();
+
+        // For pipes and sockets, the child gets what has been written so far.
+        if let Some(bytes) = anonsocket_drain(fd.clone(), this)? {
+            return interp_ok(Ok(bytes));
+        }
+        // For files, the child gets the rest of the file. This reads from the host, so it is
+        // recorded and replayed like the other reads from files.
+        if let Some(file) = fd.clone().downcast::<FileHandle>() {
+            return interp_ok(this.host_read_nondet(|| {
+                let mut bytes = Vec::new();
+                (&file.file).read_to_end(&mut bytes)?;
+                Ok(bytes)
+            }));
+        }
+        throw_unsup_format!(
+            "{link_name}: cannot redirect the standard input of the child to a {}",
+            fd.name()
+        );
+    }
+
+    /// Forwards the output of a child whose standard output or error was redirected to `fd`.
+    fn forward_child_output(
+        &mut self,
+        link_name: &str,
+        fd: DynFileDescriptionRef,
+        bytes: &[u8],
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        let this = self.eval_context_mut();
+
+        if anonsocket_deliver(fd.clone(), bytes, this)? {
+            return interp_ok(Ok(()));
+        }
+        if let Some(file) = fd.clone().downcast::<FileHandle>() {
+            return interp_ok((&file.file).write_all(bytes));
+        }
+        if fd.clone().downcast::<io::Stdout>().is_some() {
+            return interp_ok(io::stdout().write_all(bytes));
+        }
+        if fd.clone().downcast::<io::Stderr>().is_some() {
+            return interp_ok(io::stderr().write_all(bytes));
+        }
+        if fd.clone().downcast::<NullOutput>().is_some() {
+            return interp_ok(Ok(()));
+        }
+        throw_unsup_format!(
+            "{link_name}: cannot redirect the output of the child to a {}",
+            fd.name()
+        );
+    }
+
+    /// Returns the actions recorded for the given `posix_spawn_file_actions_t`.
+    fn file_actions<'a>(&'a mut self, ptr: Pointer) -> InterpResult<'tcx, &'a FileActions>
+    where
+        'tcx: 'a,
+    {
+        let this = self.eval_context_mut();
+        let Some(actions) = this.get_sync_or_init(ptr, |_| FileActions::default()) else {
+            throw_ub_format!("`posix_spawn_file_actions_t` must point to writable memory");
+        };
+        interp_ok(actions)
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn posix_spawn_file_actions_init(
+        &mut self,
+        file_actions_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let file_actions = this.read_pointer(file_actions_op)?;
+        // The memory may be reused from an earlier set of actions.
+        this.file_actions(file_actions)?.dup2.borrow_mut().clear();
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_destroy(
+        &mut self,
+        file_actions_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let file_actions = this.read_pointer(file_actions_op)?;
+        this.file_actions(file_actions)?.dup2.borrow_mut().clear();
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_adddup2(
+        &mut self,
+        file_actions_op: &OpTy<'tcx>,
+        fd_op: &OpTy<'tcx>,
+        newfd_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let file_actions = this.read_pointer(file_actions_op)?;
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let newfd = this.read_scalar(newfd_op)?.to_i32()?;
+
+        if fd < 0 || newfd < 0 {
+            return interp_ok(this.eval_libc("EBADF"));
+        }
+        this.file_actions(file_actions)?.dup2.borrow_mut().push((fd, newfd));
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Implements `posix_spawn` and `posix_spawnp`. Like those functions, this returns an error
+    /// number instead of setting `errno`.
+    fn posix_spawn(
+        &mut self,
+        link_name: &str,
+        pid_op: &OpTy<'tcx>,
+        path_op: &OpTy<'tcx>,
+        file_actions_op: &OpTy<'tcx>,
+        _attrp_op: &OpTy<'tcx>,
+        argv_op: &OpTy<'tcx>,
+        envp_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let Some(spawn_self) = this.machine.child_processes.spawn_self.clone() else {
+            throw_unsup_format!(
+                "{link_name}: spawning processes is only supported with `-Zmiri-spawn-self`"
+            );
+        };
+
+        let pid_ptr = this.read_pointer(pid_op)?;
+        let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?.into_owned();
+        let file_actions = this.read_pointer(file_actions_op)?;
+        let argv = this.read_pointer(argv_op)?;
+        let envp = this.read_pointer(envp_op)?;
+
+        if !is_interpreted_program(&path, &spawn_self.config) {
+            throw_unsup_format!(
+                "{link_name}: can only spawn the interpreted program itself, not `{}`",
+                path.display()
+            );
+        }
+
+        let args: Vec<String> = this
+            .read_c_str_array(argv)?
+            .into_iter()
+            .map(|arg| String::from_utf8_lossy(&arg).into_owned())
+            .collect();
+        let env: FxHashMap<String, String> = this
+            .read_c_str_array(envp)?
+            .into_iter()
+            .filter_map(|var| {
+                let var = String::from_utf8_lossy(&var).into_owned();
+                let (name, value) = var.split_once('=')?;
+                Some((name.to_owned(), value.to_owned()))
+            })
+            .collect();
+
+        // Figure out where the standard streams of the child go. Streams that are not redirected
+        // are inherited, and the child already gets those by default.
+        let dup2 = if this.ptr_is_null(file_actions)? {
+            Vec::new()
+        } else {
+            this.file_actions(file_actions)?.dup2.borrow().clone()
+        };
+        let mut redirected: [Option<DynFileDescriptionRef>; 3] = Default::default();
+        for (fd, newfd) in dup2 {
+            let Some(fd) = this.machine.fds.get(fd) else {
+                return interp_ok(this.eval_libc("EBADF"));
+            };
+            let Some(slot) = usize::try_from(newfd).ok().and_then(|n| redirected.get_mut(n)) else {
+                throw_unsup_format!(
+                    "{link_name}: only the standard streams of the child can be redirected"
+                );
+            };
+            *slot = Some(fd);
+        }
+        let mut buffers: [Option<Rc<RefCell<VecDeque<u8>>>>; 3] = Default::default();
+        for (idx, fd) in redirected.iter().enumerate() {
+            let Some(fd) = fd else { continue };
+            let contents = if idx == 0 {
+                match this.take_child_input(link_name, fd.clone())? {
+                    Ok(bytes) => VecDeque::from(bytes),
+                    Err(err) => return this.io_error_to_errnum(err),
+                }
+            } else {
+                VecDeque::new()
+            };
+            buffers[idx] = Some(Rc::new(RefCell::new(contents)));
+        }
+
+        let parent_pid = i32::try_from(this.get_pid()).unwrap();
+        let child_processes = &mut this.machine.child_processes;
+        child_processes.num_spawned = child_processes.num_spawned.strict_add(1);
+        let pid = parent_pid.strict_add(child_processes.num_spawned);
+
+        let mut config = spawn_self.config;
+        config.args = args;
+        config.env = Vec::new();
+        config.forwarded_env_vars = Vec::new();
+        config.set_env_vars = env;
+        // Only the parent records profiles, talks to the debugger, and records or replays its
+        // execution.
+        config.measureme_out = None;
+        config.profile = None;
+        config.gdbserver = None;
+        config.record_replay = None;
+
+        let termination =
+            eval_child(*this.tcx, spawn_self.entry_id, spawn_self.entry_type, &config, |child| {
+                for (fd_num, buf) in (0..).zip(&buffers) {
+                    let Some(buf) = buf else { continue };
+                    child.machine.fds.remove(fd_num);
+                    let fd = child.machine.fds.new_ref(ChildStdio { buf: buf.clone() });
+                    assert_eq!(child.machine.fds.insert_with_min_num(fd, fd_num), fd_num);
+                }
+                interp_ok(())
+            });
+        let Some(termination) = termination else {
+            throw_machine_stop!(TerminationInfo::ChildProcessError { pid });
+        };
+
+        // Hand the output of the child to the parent.
+        for (fd, buf) in redirected.into_iter().zip(buffers).skip(1) {
+            let (Some(fd), Some(buf)) = (fd, buf) else { continue };
+            let bytes = Vec::from(buf.take());
+            if let Err(err) = this.forward_child_output(link_name, fd, &bytes)? {
+                return this.io_error_to_errnum(err);
+            }
+        }
+
+        let status = match termination {
+            ChildTermination::Exited(code) => (code & 0xff).strict_shl(8),
+            ChildTermination::Aborted => this.eval_libc_i32("SIGABRT"),
+        };
+        this.machine.child_processes.unwaited.insert(pid, status);
+
+        if !this.ptr_is_null(pid_ptr)? {
+            let pid_place = this.ptr_to_mplace(pid_ptr, this.libc_ty_layout("pid_t"));
+            this.write_scalar(Scalar::from_i32(pid), &pid_place)?;
+        }
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn waitpid(
+        &mut self,
+        pid_op: &OpTy<'tcx>,
+        wstatus_op: &OpTy<'tcx>,
+        options_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid_op)?.to_i32()?;
+        let wstatus = this.read_pointer(wstatus_op)?;
+        let options = this.read_scalar(options_op)?.to_i32()?;
+
+        // All children terminate right away, so there is never a need to block and `WNOHANG`
+        // makes no difference.
+        let wnohang = this.eval_libc_i32("WNOHANG");
+        if options & !wnohang != 0 {
+            throw_unsup_format!("waitpid: unsupported flags {options:#x}");
+        }
+
+        let unwaited = &mut this.machine.child_processes.unwaited;
+        let child = if pid == -1 {
+            unwaited.pop_first()
+        } else if pid > 0 {
+            unwaited.remove_entry(&pid)
+        } else {
+            throw_unsup_format!("waitpid: waiting for process groups is not supported");
+        };
+        let Some((pid, status)) = child else {
+            return this.set_last_error_and_return_i32(LibcError("ECHILD"));
+        };
+
+        if !this.ptr_is_null(wstatus)? {
+            let wstatus = this.ptr_to_mplace(wstatus, this.machine.layouts.i32);
+            this.write_scalar(Scalar::from_i32(status), &wstatus)?;
+        }
+        interp_ok(Scalar::from_i32(pid))
+    }
+
+    fn kill(&mut self, pid_op: &OpTy<'tcx>, sig_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid_op)?.to_i32()?;
+        let sig = this.read_scalar(sig_op)?.to_i32()?;
+
+        if u32::try_from(pid).is_ok_and(|pid| pid == this.get_pid()) {
+            return this.raise_signal(sig);
+        }
+        if !this.machine.child_processes.unwaited.contains_key(&pid) {
+            throw_unsup_format!("kill: can only send signals to child processes");
+        }
+        // The child already terminated, so the signal has no effect.
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigemptyset(&mut self, set_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set_op, this.libc_ty_layout("sigset_t"))?;
+        this.write_bytes_ptr(set.ptr(), std::iter::repeat_n(0u8, set.layout.size.bytes_usize()))?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigaddset(
+        &mut self,
+        set_op: &OpTy<'tcx>,
+        signum_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set_op, this.libc_ty_layout("sigset_t"))?;
+        let signum = this.read_scalar(signum_op)?.to_i32()?;
+
+        // The set is an array of words, with signal `n` being the bit `n - 1`.
+        let Some(bit) = signum
+            .checked_sub(1)
+            .and_then(|bit| u64::try_from(bit).ok())
+            .filter(|&bit| bit < set.layout.size.bits())
+        else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+        let word_size = set.layout.size.min(this.pointer_size());
+        let word_bits = word_size.bits();
+        let word = set.offset(
+            word_size * bit.strict_div(word_bits),
+            this.machine.layouts.uint(word_size).unwrap(),
+            this,
+        )?;
+        let old = this.read_scalar(&word)?.to_uint(word_size)?;
+        let mask = 1u128.strict_shl(u32::try_from(bit.strict_rem(word_bits)).unwrap());
+        this.write_scalar(Scalar::from_uint(old | mask, word_size), &word)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigismember(
+        &mut self,
+        set_op: &OpTy<'tcx>,
+        signum_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set_op, this.libc_ty_layout("sigset_t"))?;
+        let signum = this.read_scalar(signum_op)?.to_i32()?;
+
+        // Same layout as in `sigaddset`.
+        let Some(bit) = signum
+            .checked_sub(1)
+            .and_then(|bit| u64::try_from(bit).ok())
+            .filter(|&bit| bit < set.layout.size.bits())
+        else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+        let word_size = set.layout.size.min(this.pointer_size());
+        let word_bits = word_size.bits();
+        let word = set.offset(
+            word_size * bit.strict_div(word_bits),
+            this.machine.layouts.uint(word_size).unwrap(),
+            this,
+        )?;
+        let word = this.read_scalar(&word)?.to_uint(word_size)?;
+        let is_member = word.strict_shr(u32::try_from(bit.strict_rem(word_bits)).unwrap()) & 1;
+
+        interp_ok(Scalar::from_i32(i32::try_from(is_member).unwrap()))
+    }
+}
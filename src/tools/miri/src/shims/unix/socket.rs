@@ -0,0 +1,1482 @@
+//! This implements TCP and UDP sockets on an emulated loopback network that lives entirely inside
+//! Miri. Sockets can only communicate with other sockets of the same program; nothing is ever
+//! sent to the host, so this also works with isolation enabled.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use rand::Rng;
+use rustc_abi::Size;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{
+    EvalContextExt as _, FileDescription, FileDescriptionExt, FileDescriptionRef,
+    WeakFileDescriptionRef,
+};
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::*;
+
+/// The maximum number of bytes that can be queued for reading on a socket.
+/// Like for socketpairs, this number is arbitrary as the value can always
+/// be configured in the real system.
+const MAX_SOCKET_BUFFER_CAPACITY: usize = 212992;
+
+/// The largest payload of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65507;
+
+/// The first port handed out when binding to port 0. We use the range suggested by IANA
+/// for dynamic ports.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+const NUM_EPHEMERAL_PORTS: u16 = 16384;
+
+/// The callback invoked when a receive completes, with the number of bytes received and, for
+/// datagram sockets, the address the data was sent from.
+type RecvCallback<'tcx> = DynMachineCallback<'tcx, Result<(usize, Option<SocketAddr>), IoError>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SocketType {
+    /// A `SOCK_STREAM` (TCP) socket.
+    Stream,
+    /// A `SOCK_DGRAM` (UDP) socket.
+    Datagram,
+}
+
+/// The ports of the emulated loopback network.
+///
+/// There is only one host, so every socket is reachable through 127.0.0.1, ::1 and the
+/// unspecified address alike. Ports are shared between IPv4 and IPv6.
+#[derive(Debug, Default)]
+pub struct LoopbackNetwork {
+    /// The sockets bound to each port. Closed sockets are only removed lazily, so the weak
+    /// reference may be dangling.
+    ports: BTreeMap<(SocketType, u16), WeakFileDescriptionRef<InetSocket>>,
+    /// The offset from `FIRST_EPHEMERAL_PORT` of the next ephemeral port to try.
+    next_ephemeral_port: u16,
+}
+
+impl VisitProvenance for LoopbackNetwork {
+    fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {
+        // Sockets do not contain any provenance.
+    }
+}
+
+impl LoopbackNetwork {
+    /// Returns the open socket bound to the given port, if any.
+    fn lookup(&self, socket_type: SocketType, port: u16) -> Option<FileDescriptionRef<InetSocket>> {
+        self.ports.get(&(socket_type, port))?.upgrade()
+    }
+
+    /// Binds `socket` to `port`, or to a free ephemeral port if `port` is 0.
+    /// Returns the port, or `None` if the port is in use (or no ephemeral port is free).
+    fn bind(&mut self, port: u16, socket: &FileDescriptionRef<InetSocket>) -> Option<u16> {
+        let socket_type = socket.socket_type;
+        let port = if port == 0 {
+            (0..NUM_EPHEMERAL_PORTS)
+                .map(|i| {
+                    let offset = self.next_ephemeral_port.strict_add(i) % NUM_EPHEMERAL_PORTS;
+                    FIRST_EPHEMERAL_PORT.strict_add(offset)
+                })
+                .find(|&port| self.lookup(socket_type, port).is_none())?
+        } else if self.lookup(socket_type, port).is_some() {
+            return None;
+        } else {
+            port
+        };
+        if port >= FIRST_EPHEMERAL_PORT {
+            self.next_ephemeral_port =
+                port.strict_sub(FIRST_EPHEMERAL_PORT).strict_add(1) % NUM_EPHEMERAL_PORTS;
+        }
+        self.ports.insert((socket_type, port), FileDescriptionRef::downgrade(socket));
+        Some(port)
+    }
+
+    /// Forgets about all ports whose socket has been closed.
+    fn remove_closed(&mut self) {
+        self.ports.retain(|_, socket| socket.upgrade().is_some());
+    }
+}
+
+/// Returns the address under which `ip` is reached on the loopback network, or `None` if it
+/// is neither a loopback nor the unspecified address.
+fn resolve_loopback(ip: IpAddr) -> Option<IpAddr> {
+    match ip {
+        IpAddr::V4(ip) if ip.is_unspecified() => Some(Ipv4Addr::LOCALHOST.into()),
+        IpAddr::V6(ip) if ip.is_unspecified() => Some(Ipv6Addr::LOCALHOST.into()),
+        ip if ip.is_loopback() => Some(ip),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+enum SocketState {
+    /// The socket has not been bound to an address yet.
+    Unbound,
+    /// The socket is bound to a local address. A datagram socket may additionally have a
+    /// default peer that was set with `connect`.
+    Bound { local: SocketAddr, peer: Option<SocketAddr> },
+    /// A stream socket that is listening for connections. Connections that have not been
+    /// accepted yet are queued together with the clock of the thread that connected.
+    Listening { local: SocketAddr, pending: VecDeque<(FileDescriptionRef<InetSocket>, VClock)> },
+    /// A connected stream socket.
+    Connected { local: SocketAddr, peer_addr: SocketAddr, peer: WeakFileDescriptionRef<InetSocket> },
+}
+
+#[derive(Debug)]
+struct Buffer {
+    buf: VecDeque<u8>,
+    clock: VClock,
+}
+
+#[derive(Debug)]
+struct Datagram {
+    data: Vec<u8>,
+    source: SocketAddr,
+    /// The clock of the sending thread, so that receiving synchronizes with exactly this send.
+    clock: VClock,
+}
+
+/// An `AF_INET` or `AF_INET6` socket on the loopback network.
+#[derive(Debug)]
+struct InetSocket {
+    is_ipv6: bool,
+    socket_type: SocketType,
+    state: RefCell<SocketState>,
+    /// The bytes received on a connected stream socket.
+    readbuf: RefCell<Buffer>,
+    /// The datagrams received on a datagram socket.
+    datagrams: RefCell<VecDeque<Datagram>>,
+    /// Whether no more data will be received, because either the peer shut down writing or we
+    /// shut down reading. Reads then return EOF once the buffer is drained.
+    read_closed: Cell<bool>,
+    /// Whether we shut down writing.
+    write_closed: Cell<bool>,
+    /// A list of thread ids blocked in `read`, `recv` or `accept` because there was nothing to
+    /// receive. Once something arrives, these threads will be unblocked.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+    /// A list of thread ids blocked in `write` or `send` because the peer's buffer was full.
+    /// Once the peer reads some bytes, these threads will be unblocked.
+    blocked_write_tid: RefCell<Vec<ThreadId>>,
+    /// Whether this fd is non-blocking or not.
+    is_nonblock: Cell<bool>,
+    /// The integer-valued options set with `setsockopt`, keyed by level and option name.
+    options: RefCell<BTreeMap<(i32, i32), i32>>,
+}
+
+impl InetSocket {
+    fn new(is_ipv6: bool, socket_type: SocketType, is_nonblock: bool) -> Self {
+        InetSocket {
+            is_ipv6,
+            socket_type,
+            state: RefCell::new(SocketState::Unbound),
+            readbuf: RefCell::new(Buffer { buf: VecDeque::new(), clock: VClock::default() }),
+            datagrams: RefCell::new(VecDeque::new()),
+            read_closed: Cell::new(false),
+            write_closed: Cell::new(false),
+            blocked_read_tid: RefCell::new(Vec::new()),
+            blocked_write_tid: RefCell::new(Vec::new()),
+            is_nonblock: Cell::new(is_nonblock),
+            options: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// The unspecified address of this socket's family.
+    fn unspecified_ip(&self) -> IpAddr {
+        if self.is_ipv6 { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() }
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        match *self.state.borrow() {
+            SocketState::Unbound => None,
+            SocketState::Bound { local, .. }
+            | SocketState::Listening { local, .. }
+            | SocketState::Connected { local, .. } => Some(local),
+        }
+    }
+
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        match *self.state.borrow() {
+            SocketState::Bound { peer, .. } => peer,
+            SocketState::Connected { peer_addr, .. } => Some(peer_addr),
+            SocketState::Unbound | SocketState::Listening { .. } => None,
+        }
+    }
+
+    /// The other end of a connected stream socket, if it is still open.
+    fn peer(&self) -> Option<FileDescriptionRef<InetSocket>> {
+        match &*self.state.borrow() {
+            SocketState::Connected { peer, .. } => peer.upgrade(),
+            _ => None,
+        }
+    }
+
+    fn is_connected_stream(&self) -> bool {
+        matches!(*self.state.borrow(), SocketState::Connected { .. })
+    }
+
+    /// The address under which datagrams sent from this socket arrive.
+    fn source_addr(&self) -> Option<SocketAddr> {
+        let local = self.local_addr()?;
+        Some(SocketAddr::new(resolve_loopback(local.ip())?, local.port()))
+    }
+}
+
+impl FileDescription for InetSocket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn close<'tcx>(
+        self,
+        communicate_allowed: bool,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        // Our port is free again.
+        ecx.machine.network.remove_closed();
+
+        // Threads blocked on this socket will find it closed when they retry.
+        let waiting_threads = std::mem::take(&mut *self.blocked_read_tid.borrow_mut())
+            .into_iter()
+            .chain(std::mem::take(&mut *self.blocked_write_tid.borrow_mut()));
+        for thread_id in waiting_threads {
+            ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+        }
+
+        match self.state.into_inner() {
+            SocketState::Listening { pending, .. } => {
+                // Connections that were never accepted are closed, which their peers observe
+                // as EOF.
+                for (socket, _clock) in pending {
+                    socket.close_ref(communicate_allowed, ecx)?.ok();
+                }
+            }
+            SocketState::Connected { peer, .. } =>
+                if let Some(peer) = peer.upgrade() {
+                    // Reads on the peer now return EOF once its buffer is drained, and writes fail.
+                    peer.read_closed.set(true);
+                    let waiting_threads = std::mem::take(&mut *peer.blocked_read_tid.borrow_mut())
+                        .into_iter()
+                        .chain(std::mem::take(&mut *peer.blocked_write_tid.borrow_mut()));
+                    for thread_id in waiting_threads {
+                        ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+                    }
+                    ecx.check_and_update_readiness(peer)?;
+                },
+            SocketState::Unbound | SocketState::Bound { .. } => {}
+        }
+        interp_ok(Ok(()))
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let nonblock = self.is_nonblock.get();
+        let finish = callback!(
+            @capture<'tcx> {
+                finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+            }
+            |this, result: Result<(usize, Option<SocketAddr>), IoError>| {
+                finish.call(this, result.map(|(read_size, _source)| read_size))
+            }
+        );
+        socket_recv(self, ptr, len, /* peek */ false, nonblock, ecx, finish)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let nonblock = self.is_nonblock.get();
+        socket_send(self, ptr, len, /* dest_addr */ None, nonblock, ecx, finish)
+    }
+
+    fn short_fd_operations(&self) -> bool {
+        // Datagrams are never split up, but stream sockets can always return short reads
+        // and writes.
+        self.socket_type == SocketType::Stream
+    }
+
+    fn as_unix<'tcx>(&self, _ecx: &MiriInterpCx<'tcx>) -> &dyn UnixFileDescription {
+        self
+    }
+
+    fn get_flags<'tcx>(&self, ecx: &mut MiriInterpCx<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let mut flags = ecx.eval_libc_i32("O_RDWR");
+        if self.is_nonblock.get() {
+            flags |= ecx.eval_libc_i32("O_NONBLOCK");
+        }
+        interp_ok(Scalar::from_i32(flags))
+    }
+
+    fn set_flags<'tcx>(
+        &self,
+        mut flag: i32,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let o_nonblock = ecx.eval_libc_i32("O_NONBLOCK");
+        let o_rdwr = ecx.eval_libc_i32("O_RDWR");
+
+        // O_NONBLOCK flag can be set / unset by user.
+        if flag & o_nonblock == o_nonblock {
+            self.is_nonblock.set(true);
+            flag &= !o_nonblock;
+        } else {
+            self.is_nonblock.set(false);
+        }
+
+        // Ignore the file access mode.
+        flag &= !o_rdwr;
+
+        if flag != 0 {
+            throw_unsup_format!("fcntl: only O_NONBLOCK is supported for F_SETFL on sockets")
+        }
+
+        interp_ok(Scalar::from_i32(0))
+    }
+}
+
+impl UnixFileDescription for InetSocket {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        let mut epoll_ready_events = EpollReadyEvents::new();
+
+        match &*self.state.borrow() {
+            SocketState::Listening { pending, .. } => {
+                // A listening socket is readable when a connection can be accepted.
+                epoll_ready_events.epollin = !pending.is_empty();
+            }
+            SocketState::Connected { peer, .. } => {
+                if !self.readbuf.borrow().buf.is_empty() || self.read_closed.get() {
+                    epoll_ready_events.epollin = true;
+                }
+                if self.read_closed.get() {
+                    epoll_ready_events.epollrdhup = true;
+                }
+                match peer.upgrade() {
+                    Some(peer) => {
+                        let data_size = peer.readbuf.borrow().buf.len();
+                        if data_size < MAX_SOCKET_BUFFER_CAPACITY || self.write_closed.get() {
+                            epoll_ready_events.epollout = true;
+                        }
+                    }
+                    None => {
+                        // The connection is gone: reads return EOF and writes fail, so neither
+                        // blocks.
+                        epoll_ready_events.epollin = true;
+                        epoll_ready_events.epollout = true;
+                        epoll_ready_events.epollrdhup = true;
+                        epoll_ready_events.epollhup = true;
+                    }
+                }
+            }
+            SocketState::Unbound | SocketState::Bound { .. } =>
+                match self.socket_type {
+                    SocketType::Datagram => {
+                        // Sending a datagram never blocks.
+                        epoll_ready_events.epollout = true;
+                        if !self.datagrams.borrow().is_empty() {
+                            epoll_ready_events.epollin = true;
+                        }
+                    }
+                    SocketType::Stream => {
+                        // Like Linux, report a stream socket that is not connected as hung up.
+                        epoll_ready_events.epollout = true;
+                        epoll_ready_events.epollhup = true;
+                    }
+                },
+        }
+        interp_ok(epoll_ready_events)
+    }
+}
+
+/// Receive from a socket.
+fn socket_recv<'tcx>(
+    self_ref: FileDescriptionRef<InetSocket>,
+    ptr: Pointer,
+    len: usize,
+    peek: bool,
+    nonblock: bool,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: RecvCallback<'tcx>,
+) -> InterpResult<'tcx> {
+    let has_data = match self_ref.socket_type {
+        SocketType::Stream => {
+            if !self_ref.is_connected_stream() {
+                return finish.call(ecx, Err(IoError::LibcError("ENOTCONN")));
+            }
+            !self_ref.readbuf.borrow().buf.is_empty()
+        }
+        SocketType::Datagram => !self_ref.datagrams.borrow().is_empty(),
+    };
+
+    if !has_data {
+        if self_ref.read_closed.get()
+            || (self_ref.socket_type == SocketType::Stream && self_ref.peer().is_none())
+        {
+            // Nothing more will arrive: 0 bytes successfully read indicates end-of-file.
+            return finish.call(ecx, Ok((0, None)));
+        } else if nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        } else {
+            self_ref.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+            // Block the current thread; only keep a weak ref for this.
+            let weak_self_ref = FileDescriptionRef::downgrade(&self_ref);
+            ecx.block_thread(
+                BlockReason::Socket,
+                None,
+                callback!(
+                    @capture<'tcx> {
+                        weak_self_ref: WeakFileDescriptionRef<InetSocket>,
+                        ptr: Pointer,
+                        len: usize,
+                        peek: bool,
+                        finish: RecvCallback<'tcx>,
+                    }
+                    |this, unblock: UnblockKind| {
+                        assert_eq!(unblock, UnblockKind::Ready);
+                        let Some(self_ref) = weak_self_ref.upgrade() else {
+                            // The socket was closed while we were waiting.
+                            return finish.call(this, Err(IoError::LibcError("EBADF")));
+                        };
+                        socket_recv(self_ref, ptr, len, peek, /* nonblock */ false, this, finish)
+                    }
+                ),
+            );
+            return interp_ok(());
+        }
+    }
+
+    match self_ref.socket_type {
+        SocketType::Stream => {
+            let mut readbuf = self_ref.readbuf.borrow_mut();
+            // Synchronize with all previous writes to this buffer.
+            // FIXME: this over-synchronizes; a more precise approach would be to
+            // only sync with the writes whose data we will read.
+            ecx.acquire_clock(&readbuf.clock)?;
+
+            let read_size = if peek {
+                let read_size = len.min(readbuf.buf.len());
+                ecx.write_bytes_ptr(ptr, readbuf.buf.iter().take(read_size).copied())?;
+                read_size
+            } else {
+                // Conveniently, `read` exists on `VecDeque` and has exactly the desired behavior.
+                ecx.read_from_host(&mut readbuf.buf, len, ptr)?.unwrap()
+            };
+
+            // Need to drop before others can access the readbuf again.
+            drop(readbuf);
+
+            if !peek && let Some(peer) = self_ref.peer() {
+                // There is space in our buffer again, so the peer can write.
+                let waiting_threads = std::mem::take(&mut *peer.blocked_write_tid.borrow_mut());
+                for thread_id in waiting_threads {
+                    ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+                }
+                ecx.check_and_update_readiness(peer)?;
+            }
+
+            finish.call(ecx, Ok((read_size, None)))
+        }
+        SocketType::Datagram => {
+            let mut datagrams = self_ref.datagrams.borrow_mut();
+            let datagram = datagrams.front().unwrap();
+            // Synchronize with exactly the send that produced this datagram.
+            ecx.acquire_clock(&datagram.clock)?;
+            // Like on real systems, the part of the datagram that does not fit is discarded.
+            let read_size = len.min(datagram.data.len());
+            ecx.write_bytes_ptr(ptr, datagram.data[..read_size].iter().copied())?;
+            let source = datagram.source;
+            if !peek {
+                datagrams.pop_front();
+            }
+            drop(datagrams);
+
+            finish.call(ecx, Ok((read_size, Some(source))))
+        }
+    }
+}
+
+/// Send on a socket. For datagram sockets, `dest_addr` overrides the peer set with `connect`.
+fn socket_send<'tcx>(
+    self_ref: FileDescriptionRef<InetSocket>,
+    ptr: Pointer,
+    len: usize,
+    dest_addr: Option<SocketAddr>,
+    nonblock: bool,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    if self_ref.write_closed.get() {
+        return finish.call(ecx, Err(ErrorKind::BrokenPipe.into()));
+    }
+
+    match self_ref.socket_type {
+        SocketType::Stream => {
+            if !self_ref.is_connected_stream() {
+                return finish.call(ecx, Err(IoError::LibcError("ENOTCONN")));
+            }
+            // Always succeed on write size 0.
+            if len == 0 {
+                return finish.call(ecx, Ok(0));
+            }
+            // We are writing to our peer's readbuf.
+            let Some(peer) = self_ref.peer() else {
+                // The connection was closed by the peer.
+                return finish.call(ecx, Err(ErrorKind::BrokenPipe.into()));
+            };
+
+            let available_space =
+                MAX_SOCKET_BUFFER_CAPACITY.strict_sub(peer.readbuf.borrow().buf.len());
+            if available_space == 0 {
+                if nonblock {
+                    return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+                }
+                self_ref.blocked_write_tid.borrow_mut().push(ecx.active_thread());
+                // Block the current thread; only keep a weak ref for this.
+                let weak_self_ref = FileDescriptionRef::downgrade(&self_ref);
+                ecx.block_thread(
+                    BlockReason::Socket,
+                    None,
+                    callback!(
+                        @capture<'tcx> {
+                            weak_self_ref: WeakFileDescriptionRef<InetSocket>,
+                            ptr: Pointer,
+                            len: usize,
+                            finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                        }
+                        |this, unblock: UnblockKind| {
+                            assert_eq!(unblock, UnblockKind::Ready);
+                            let Some(self_ref) = weak_self_ref.upgrade() else {
+                                // The socket was closed while we were waiting.
+                                return finish.call(this, Err(IoError::LibcError("EBADF")));
+                            };
+                            socket_send(
+                                self_ref,
+                                ptr,
+                                len,
+                                /* dest_addr */ None,
+                                /* nonblock */ false,
+                                this,
+                                finish,
+                            )
+                        }
+                    ),
+                );
+                return interp_ok(());
+            }
+
+            let mut writebuf = peer.readbuf.borrow_mut();
+            // Remember this clock so `read` can synchronize with us.
+            ecx.release_clock(|clock| {
+                writebuf.clock.join(clock);
+            })?;
+            // Do full write / partial write based on the space available.
+            let write_size = len.min(available_space);
+            let actual_write_size = ecx.write_to_host(&mut writebuf.buf, write_size, ptr)?.unwrap();
+            assert_eq!(actual_write_size, write_size);
+            drop(writebuf);
+
+            let waiting_threads = std::mem::take(&mut *peer.blocked_read_tid.borrow_mut());
+            for thread_id in waiting_threads {
+                ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+            }
+            ecx.check_and_update_readiness(peer)?;
+
+            finish.call(ecx, Ok(write_size))
+        }
+        SocketType::Datagram => {
+            let Some(dest_addr) = dest_addr.or_else(|| self_ref.peer_addr()) else {
+                return finish.call(ecx, Err(IoError::LibcError("EDESTADDRREQ")));
+            };
+            if resolve_loopback(dest_addr.ip()).is_none() {
+                throw_unsup_format!(
+                    "sending to {dest_addr} is not supported, \
+                     only loopback addresses can be reached"
+                );
+            }
+            if len > MAX_DATAGRAM_SIZE {
+                return finish.call(ecx, Err(IoError::LibcError("EMSGSIZE")));
+            }
+            // Sending from an unbound socket implicitly binds it to an ephemeral port.
+            if self_ref.local_addr().is_none() {
+                let ip = self_ref.unspecified_ip();
+                if let Err(e) = autobind(&self_ref, ip, ecx) {
+                    return finish.call(ecx, Err(e));
+                }
+            }
+            let source = self_ref.source_addr().unwrap();
+
+            // UDP is unreliable: datagrams sent to a port nobody listens on, or to a socket whose
+            // buffer is full, are silently dropped.
+            let receiver = ecx.machine.network.lookup(SocketType::Datagram, dest_addr.port());
+            // A connected datagram socket only receives from its peer.
+            if let Some(receiver) = receiver
+                && receiver.peer_addr().is_none_or(|peer| peer == source)
+            {
+                let queued: usize = receiver.datagrams.borrow().iter().map(|d| d.data.len()).sum();
+                if queued.strict_add(len) <= MAX_SOCKET_BUFFER_CAPACITY {
+                    let data =
+                        ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?.to_vec();
+                    let mut clock = VClock::default();
+                    ecx.release_clock(|release_clock| clock.join(release_clock))?;
+                    receiver.datagrams.borrow_mut().push_back(Datagram { data, source, clock });
+
+                    let waiting_threads =
+                        std::mem::take(&mut *receiver.blocked_read_tid.borrow_mut());
+                    for thread_id in waiting_threads {
+                        ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+                    }
+                    ecx.check_and_update_readiness(receiver)?;
+                }
+            }
+
+            finish.call(ecx, Ok(len))
+        }
+    }
+}
+
+/// Binds an unbound socket to an ephemeral port on `ip`. This is what happens implicitly when
+/// an unbound socket is used to connect or send.
+fn autobind<'tcx>(
+    socket: &FileDescriptionRef<InetSocket>,
+    ip: IpAddr,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> Result<SocketAddr, IoError> {
+    let Some(port) = ecx.machine.network.bind(0, socket) else {
+        return Err(IoError::LibcError("EADDRNOTAVAIL"));
+    };
+    let local = SocketAddr::new(ip, port);
+    *socket.state.borrow_mut() = SocketState::Bound { local, peer: None };
+    Ok(local)
+}
+
+/// Accept a connection on a listening socket, blocking if there is none.
+fn socket_accept<'tcx>(
+    self_ref: FileDescriptionRef<InetSocket>,
+    addr: Pointer,
+    addrlen: Pointer,
+    accepted_nonblock: bool,
+    dest: MPlaceTy<'tcx>,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx> {
+    let connection = match &mut *self_ref.state.borrow_mut() {
+        SocketState::Listening { pending, .. } => pending.pop_front(),
+        _ => return ecx.set_last_error_and_return(LibcError("EINVAL"), &dest),
+    };
+
+    let Some((socket, clock)) = connection else {
+        if self_ref.is_nonblock.get() {
+            return ecx.set_last_error_and_return(ErrorKind::WouldBlock, &dest);
+        }
+        self_ref.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+        // Block the current thread; only keep a weak ref for this.
+        let weak_self_ref = FileDescriptionRef::downgrade(&self_ref);
+        ecx.block_thread(
+            BlockReason::Socket,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    weak_self_ref: WeakFileDescriptionRef<InetSocket>,
+                    addr: Pointer,
+                    addrlen: Pointer,
+                    accepted_nonblock: bool,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    let Some(self_ref) = weak_self_ref.upgrade() else {
+                        // The socket was closed while we were waiting.
+                        return this.set_last_error_and_return(LibcError("EBADF"), &dest);
+                    };
+                    socket_accept(self_ref, addr, addrlen, accepted_nonblock, dest, this)
+                }
+            ),
+        );
+        return interp_ok(());
+    };
+
+    // Synchronize with the thread that connected.
+    ecx.acquire_clock(&clock)?;
+    ecx.check_and_update_readiness(self_ref)?;
+
+    socket.is_nonblock.set(accepted_nonblock);
+    let peer_addr = socket.peer_addr().unwrap();
+    let fd = ecx.machine.fds.insert(socket);
+    if !ecx.ptr_is_null(addr)? {
+        ecx.write_socket_addr(peer_addr, addr, addrlen)?;
+    }
+    ecx.write_int(fd, &dest)
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// For more information on the arguments see the socket manpage:
+    /// <https://man7.org/linux/man-pages/man2/socket.2.html>
+    fn socket(
+        &mut self,
+        domain: &OpTy<'tcx>,
+        type_: &OpTy<'tcx>,
+        protocol: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let mut flags = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
+
+        let mut is_sock_nonblock = false;
+
+        // Interpret the flag. Every flag we recognize is "subtracted" from `flags`, so
+        // if there is anything left at the end, that's an unsupported flag.
+        if matches!(
+            &*this.tcx.sess.target.os,
+            "linux" | "android" | "freebsd" | "illumos" | "solaris"
+        ) {
+            let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+            let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+            if flags & sock_nonblock == sock_nonblock {
+                is_sock_nonblock = true;
+                flags &= !sock_nonblock;
+            }
+            if flags & sock_cloexec == sock_cloexec {
+                flags &= !sock_cloexec;
+            }
+        }
+
+        let is_ipv6 = if domain == this.eval_libc_i32("AF_INET") {
+            false
+        } else if domain == this.eval_libc_i32("AF_INET6") {
+            true
+        } else {
+            throw_unsup_format!(
+                "socket: domain {domain:#x} is unsupported, only AF_INET and AF_INET6 are allowed \
+                 (use `socketpair` for AF_UNIX sockets)"
+            );
+        };
+        let (socket_type, default_protocol) = if flags == this.eval_libc_i32("SOCK_STREAM") {
+            (SocketType::Stream, this.eval_libc_i32("IPPROTO_TCP"))
+        } else if flags == this.eval_libc_i32("SOCK_DGRAM") {
+            (SocketType::Datagram, this.eval_libc_i32("IPPROTO_UDP"))
+        } else {
+            throw_unsup_format!(
+                "socket: type {flags:#x} is unsupported, only SOCK_STREAM, SOCK_DGRAM, \
+                 SOCK_CLOEXEC and SOCK_NONBLOCK are allowed"
+            );
+        };
+        if protocol != 0 && protocol != default_protocol {
+            throw_unsup_format!(
+                "socket: socket protocol {protocol} is unsupported, only 0, IPPROTO_TCP for \
+                 stream sockets and IPPROTO_UDP for datagram sockets are allowed"
+            );
+        }
+
+        let fd =
+            this.machine.fds.insert_new(InetSocket::new(is_ipv6, socket_type, is_sock_nonblock));
+        interp_ok(Scalar::from_i32(fd))
+    }
+
+    fn bind(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let addr = match this.read_socket_addr(address, address_len, &socket)? {
+            Ok(addr) => addr,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+
+        if !matches!(*socket.state.borrow(), SocketState::Unbound) {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+        if resolve_loopback(addr.ip()).is_none() {
+            // There are no interfaces besides loopback.
+            return this.set_last_error_and_return_i32(LibcError("EADDRNOTAVAIL"));
+        }
+        let Some(port) = this.machine.network.bind(addr.port(), &socket) else {
+            return this.set_last_error_and_return_i32(LibcError("EADDRINUSE"));
+        };
+        let local = SocketAddr::new(addr.ip(), port);
+        *socket.state.borrow_mut() = SocketState::Bound { local, peer: None };
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn listen(&mut self, socket: &OpTy<'tcx>, backlog: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        // We never refuse connections, so the backlog does not matter.
+        let _backlog = this.read_scalar(backlog)?.to_i32()?;
+
+        if socket.socket_type != SocketType::Stream {
+            return this.set_last_error_and_return_i32(LibcError("EOPNOTSUPP"));
+        }
+        // Listening on an unbound socket implicitly binds it to an ephemeral port.
+        if socket.local_addr().is_none() {
+            let ip = socket.unspecified_ip();
+            if let Err(e) = autobind(&socket, ip, this) {
+                return this.set_last_error_and_return_i32(e);
+            }
+        }
+        let local = match *socket.state.borrow() {
+            SocketState::Bound { local, .. } => local,
+            // Listening again only changes the backlog.
+            SocketState::Listening { .. } => return interp_ok(Scalar::from_i32(0)),
+            SocketState::Unbound | SocketState::Connected { .. } =>
+                return this.set_last_error_and_return_i32(LibcError("EINVAL")),
+        };
+        *socket.state.borrow_mut() = SocketState::Listening { local, pending: VecDeque::new() };
+        this.check_and_update_readiness(socket)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// For stream sockets, connecting always completes immediately (even for non-blocking
+    /// sockets) and the connection is queued on the listening socket until it is accepted.
+    fn connect(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let addr = match this.read_socket_addr(address, address_len, &socket)? {
+            Ok(addr) => addr,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let Some(peer_ip) = resolve_loopback(addr.ip()) else {
+            throw_unsup_format!(
+                "connecting to {addr} is not supported, only loopback addresses can be reached"
+            );
+        };
+        let peer_addr = SocketAddr::new(peer_ip, addr.port());
+
+        match socket.socket_type {
+            SocketType::Datagram => {
+                // This only sets the default destination and filters incoming datagrams.
+                if socket.local_addr().is_none() {
+                    let ip = socket.unspecified_ip();
+                    if let Err(e) = autobind(&socket, ip, this) {
+                        return this.set_last_error_and_return_i32(e);
+                    }
+                }
+                if let SocketState::Bound { peer, .. } = &mut *socket.state.borrow_mut() {
+                    *peer = Some(peer_addr);
+                }
+            }
+            SocketType::Stream => {
+                match *socket.state.borrow() {
+                    SocketState::Unbound | SocketState::Bound { .. } => {}
+                    SocketState::Listening { .. } =>
+                        return this.set_last_error_and_return_i32(LibcError("EINVAL")),
+                    SocketState::Connected { .. } =>
+                        return this.set_last_error_and_return_i32(LibcError("EISCONN")),
+                }
+                let listener = this.machine.network.lookup(SocketType::Stream, addr.port());
+                let Some(listener) = listener.filter(|listener| {
+                    matches!(*listener.state.borrow(), SocketState::Listening { .. })
+                }) else {
+                    return this.set_last_error_and_return_i32(LibcError("ECONNREFUSED"));
+                };
+
+                let local = match socket.local_addr() {
+                    Some(local) => local,
+                    None =>
+                        match autobind(&socket, peer_ip, this) {
+                            Ok(local) => local,
+                            Err(e) => return this.set_last_error_and_return_i32(e),
+                        },
+                };
+                let local = SocketAddr::new(resolve_loopback(local.ip()).unwrap(), local.port());
+
+                // Create the socket that `accept` will return, and connect the two.
+                let accepted = this.machine.fds.new_ref(InetSocket::new(
+                    listener.is_ipv6,
+                    SocketType::Stream,
+                    /* is_nonblock */ false,
+                ));
+                *accepted.state.borrow_mut() = SocketState::Connected {
+                    local: peer_addr,
+                    peer_addr: local,
+                    peer: FileDescriptionRef::downgrade(&socket),
+                };
+                *socket.state.borrow_mut() = SocketState::Connected {
+                    local,
+                    peer_addr,
+                    peer: FileDescriptionRef::downgrade(&accepted),
+                };
+
+                // `accept` synchronizes with us.
+                let mut clock = VClock::default();
+                this.release_clock(|release_clock| clock.join(release_clock))?;
+                if let SocketState::Listening { pending, .. } = &mut *listener.state.borrow_mut() {
+                    pending.push_back((accepted, clock));
+                }
+
+                let waiting_threads = std::mem::take(&mut *listener.blocked_read_tid.borrow_mut());
+                for thread_id in waiting_threads {
+                    this.unblock_thread(thread_id, BlockReason::Socket)?;
+                }
+                this.check_and_update_readiness(listener)?;
+            }
+        }
+        this.check_and_update_readiness(socket)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn accept4(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+        flags: Option<&OpTy<'tcx>>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return(e, dest),
+        };
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+        let mut flags = match flags {
+            Some(flags) => this.read_scalar(flags)?.to_i32()?,
+            None => 0,
+        };
+
+        let mut is_sock_nonblock = false;
+        if flags != 0 {
+            let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+            let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+            if flags & sock_nonblock == sock_nonblock {
+                is_sock_nonblock = true;
+                flags &= !sock_nonblock;
+            }
+            if flags & sock_cloexec == sock_cloexec {
+                flags &= !sock_cloexec;
+            }
+            if flags != 0 {
+                throw_unsup_format!(
+                    "accept4: flag {flags:#x} is unsupported, only SOCK_CLOEXEC and \
+                     SOCK_NONBLOCK are allowed"
+                );
+            }
+        }
+
+        socket_accept(socket, address, address_len, is_sock_nonblock, dest.clone(), this)
+    }
+
+    /// Implements `send` and `sendto`. `address` is `None` for `send`.
+    fn send_to(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        buf: &OpTy<'tcx>,
+        len: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        address: Option<(&OpTy<'tcx>, &OpTy<'tcx>)>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let buf = this.read_pointer(buf)?;
+        let len = this.read_target_usize(len)?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buf, Size::from_bytes(len), CheckInAllocMsg::MemoryAccess)?;
+
+        // We never raise SIGPIPE, so MSG_NOSIGNAL does not change anything.
+        let msg_nosignal = this.eval_libc_i32("MSG_NOSIGNAL");
+        flags &= !msg_nosignal;
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        let dontwait = flags & msg_dontwait == msg_dontwait;
+        flags &= !msg_dontwait;
+        if flags != 0 {
+            throw_unsup_format!(
+                "send: flag {flags:#x} is unsupported, \
+                 only MSG_NOSIGNAL and MSG_DONTWAIT are allowed"
+            );
+        }
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return(e, dest),
+        };
+        let dest_addr = match address {
+            Some((address, address_len)) if !this.ptr_is_null(this.read_pointer(address)?)? =>
+                match this.read_socket_addr(address, address_len, &socket)? {
+                    Ok(addr) => Some(addr),
+                    Err(e) => return this.set_last_error_and_return(e, dest),
+                },
+            _ => None,
+        };
+
+        // Cap the length like `write` does, and possibly do a short write.
+        let len = len
+            .min(u64::try_from(this.target_isize_max()).unwrap())
+            .min(u64::try_from(isize::MAX).unwrap());
+        let mut len = usize::try_from(len).unwrap();
+        if this.machine.short_fd_operations
+            && socket.short_fd_operations()
+            && len >= 2
+            && this.machine.rng.get_mut().random()
+        {
+            len /= 2;
+        }
+
+        let finish = {
+            let dest = dest.clone();
+            callback!(
+                @capture<'tcx> {
+                    len: usize,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, result: Result<usize, IoError>| {
+                    match result {
+                        Ok(write_size) => {
+                            assert!(write_size <= len);
+                            // This must fit since `len` fits.
+                            this.write_int(u64::try_from(write_size).unwrap(), &dest)
+                        }
+                        Err(e) => this.set_last_error_and_return(e, &dest),
+                    }
+                }
+            )
+        };
+        let nonblock = dontwait || socket.is_nonblock.get();
+        socket_send(socket, buf, len, dest_addr, nonblock, this, finish)
+    }
+
+    /// Implements `recv` and `recvfrom`. `address` is `None` for `recv`.
+    fn recv_from(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        buf: &OpTy<'tcx>,
+        len: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        address: Option<(&OpTy<'tcx>, &OpTy<'tcx>)>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let buf = this.read_pointer(buf)?;
+        let len = this.read_target_usize(len)?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buf, Size::from_bytes(len), CheckInAllocMsg::MemoryAccess)?;
+        let (address, address_len) = match address {
+            Some((address, address_len)) =>
+                (this.read_pointer(address)?, this.read_pointer(address_len)?),
+            None => (Pointer::null(), Pointer::null()),
+        };
+
+        let msg_peek = this.eval_libc_i32("MSG_PEEK");
+        let peek = flags & msg_peek == msg_peek;
+        flags &= !msg_peek;
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        let dontwait = flags & msg_dontwait == msg_dontwait;
+        flags &= !msg_dontwait;
+        if flags != 0 {
+            throw_unsup_format!(
+                "recv: flag {flags:#x} is unsupported, only MSG_PEEK and MSG_DONTWAIT are allowed"
+            );
+        }
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return(e, dest),
+        };
+
+        // Cap the length like `read` does, and possibly do a short read (but never to 0, that
+        // would indicate EOF).
+        let len = len
+            .min(u64::try_from(this.target_isize_max()).unwrap())
+            .min(u64::try_from(isize::MAX).unwrap());
+        let mut len = usize::try_from(len).unwrap();
+        if this.machine.short_fd_operations
+            && socket.short_fd_operations()
+            && len >= 2
+            && this.machine.rng.get_mut().random()
+        {
+            len /= 2;
+        }
+
+        let finish = {
+            let dest = dest.clone();
+            callback!(
+                @capture<'tcx> {
+                    len: usize,
+                    address: Pointer,
+                    address_len: Pointer,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, result: Result<(usize, Option<SocketAddr>), IoError>| {
+                    match result {
+                        Ok((read_size, source)) => {
+                            assert!(read_size <= len);
+                            if let Some(source) = source && !this.ptr_is_null(address)? {
+                                this.write_socket_addr(source, address, address_len)?;
+                            }
+                            // This must fit since `len` fits.
+                            this.write_int(u64::try_from(read_size).unwrap(), &dest)
+                        }
+                        Err(e) => this.set_last_error_and_return(e, &dest),
+                    }
+                }
+            )
+        };
+        let nonblock = dontwait || socket.is_nonblock.get();
+        socket_recv(socket, buf, len, peek, nonblock, this, finish)
+    }
+
+    fn getsockname(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        // An unbound socket reports the unspecified address with port 0.
+        let local = socket.local_addr().unwrap_or(SocketAddr::new(socket.unspecified_ip(), 0));
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+        this.write_socket_addr(local, address, address_len)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn getpeername(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let Some(peer_addr) = socket.peer_addr() else {
+            return this.set_last_error_and_return_i32(LibcError("ENOTCONN"));
+        };
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+        this.write_socket_addr(peer_addr, address, address_len)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn shutdown(&mut self, socket: &OpTy<'tcx>, how: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let how = this.read_scalar(how)?.to_i32()?;
+
+        let (shut_read, shut_write) = if how == this.eval_libc_i32("SHUT_RD") {
+            (true, false)
+        } else if how == this.eval_libc_i32("SHUT_WR") {
+            (false, true)
+        } else if how == this.eval_libc_i32("SHUT_RDWR") {
+            (true, true)
+        } else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+        if socket.socket_type != SocketType::Stream {
+            throw_unsup_format!("shutdown: only stream sockets can be shut down");
+        }
+        if !socket.is_connected_stream() {
+            return this.set_last_error_and_return_i32(LibcError("ENOTCONN"));
+        }
+
+        if shut_read {
+            socket.read_closed.set(true);
+            let waiting_threads = std::mem::take(&mut *socket.blocked_read_tid.borrow_mut());
+            for thread_id in waiting_threads {
+                this.unblock_thread(thread_id, BlockReason::Socket)?;
+            }
+        }
+        if shut_write {
+            socket.write_closed.set(true);
+            let waiting_threads = std::mem::take(&mut *socket.blocked_write_tid.borrow_mut());
+            for thread_id in waiting_threads {
+                this.unblock_thread(thread_id, BlockReason::Socket)?;
+            }
+            // The peer reads EOF once it has drained its buffer.
+            if let Some(peer) = socket.peer() {
+                peer.read_closed.set(true);
+                let waiting_threads = std::mem::take(&mut *peer.blocked_read_tid.borrow_mut());
+                for thread_id in waiting_threads {
+                    this.unblock_thread(thread_id, BlockReason::Socket)?;
+                }
+                this.check_and_update_readiness(peer)?;
+            }
+        }
+        this.check_and_update_readiness(socket)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn setsockopt(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        level: &OpTy<'tcx>,
+        option_name: &OpTy<'tcx>,
+        option_value: &OpTy<'tcx>,
+        option_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let level = this.read_scalar(level)?.to_i32()?;
+        let option_name = this.read_scalar(option_name)?.to_i32()?;
+        let option_len = this.read_scalar(option_len)?.to_u32()?;
+
+        if this.socket_option_default(level, option_name).is_none() {
+            throw_unsup_format!(
+                "setsockopt: option {option_name:#x} at level {level:#x} is unsupported"
+            );
+        }
+        if u64::from(option_len) < this.machine.layouts.i32.size.bytes() {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+        let value = this.deref_pointer_as(option_value, this.machine.layouts.i32)?;
+        let value = this.read_scalar(&value)?.to_i32()?;
+        socket.options.borrow_mut().insert((level, option_name), value);
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn getsockopt(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        level: &OpTy<'tcx>,
+        option_name: &OpTy<'tcx>,
+        option_value: &OpTy<'tcx>,
+        option_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let socket = match this.inet_socket(socket)? {
+            Ok(socket) => socket,
+            Err(e) => return this.set_last_error_and_return_i32(e),
+        };
+        let level = this.read_scalar(level)?.to_i32()?;
+        let option_name = this.read_scalar(option_name)?.to_i32()?;
+        let option_len = this.deref_pointer_as(option_len, this.libc_ty_layout("socklen_t"))?;
+
+        let value = if level == this.eval_libc_i32("SOL_SOCKET")
+            && option_name == this.eval_libc_i32("SO_ERROR")
+        {
+            // Errors are always reported directly, so there is never a pending error.
+            0
+        } else if level == this.eval_libc_i32("SOL_SOCKET")
+            && option_name == this.eval_libc_i32("SO_TYPE")
+        {
+            match socket.socket_type {
+                SocketType::Stream => this.eval_libc_i32("SOCK_STREAM"),
+                SocketType::Datagram => this.eval_libc_i32("SOCK_DGRAM"),
+            }
+        } else if let Some(default) = this.socket_option_default(level, option_name) {
+            socket.options.borrow().get(&(level, option_name)).copied().unwrap_or(default)
+        } else {
+            throw_unsup_format!(
+                "getsockopt: option {option_name:#x} at level {level:#x} is unsupported"
+            );
+        };
+
+        let size = this.machine.layouts.i32.size;
+        if u64::from(this.read_scalar(&option_len)?.to_u32()?) < size.bytes() {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+        let option_value = this.deref_pointer_as(option_value, this.machine.layouts.i32)?;
+        this.write_scalar(Scalar::from_i32(value), &option_value)?;
+        this.write_int(size.bytes(), &option_len)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+}
+
+impl<'tcx> EvalContextExtPriv<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPriv<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Looks up the socket referred to by the file descriptor `fd`.
+    fn inet_socket(
+        &self,
+        fd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Result<FileDescriptionRef<InetSocket>, IoError>> {
+        let this = self.eval_context_ref();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let Some(fd) = this.machine.fds.get(fd) else {
+            return interp_ok(Err(LibcError("EBADF")));
+        };
+        let Some(socket) = fd.downcast::<InetSocket>() else {
+            return interp_ok(Err(LibcError("ENOTSOCK")));
+        };
+        interp_ok(Ok(socket))
+    }
+
+    /// The integer-valued socket options that `setsockopt` and `getsockopt` support, with
+    /// their default value. None of them affect the emulated network.
+    fn socket_option_default(&self, level: i32, option_name: i32) -> Option<i32> {
+        let this = self.eval_context_ref();
+        let mut options = vec![
+            ("SOL_SOCKET", "SO_REUSEADDR", 0),
+            ("SOL_SOCKET", "SO_KEEPALIVE", 0),
+            ("SOL_SOCKET", "SO_BROADCAST", 0),
+            ("IPPROTO_TCP", "TCP_NODELAY", 0),
+            ("IPPROTO_IP", "IP_TTL", 64),
+            ("IPPROTO_IPV6", "IPV6_V6ONLY", 0),
+        ];
+        if this.tcx.sess.target.is_like_darwin {
+            // The standard library sets this on every socket.
+            options.push(("SOL_SOCKET", "SO_NOSIGPIPE", 0));
+        }
+        options.into_iter().find_map(|(option_level, name, default)| {
+            (level == this.eval_libc_i32(option_level) && option_name == this.eval_libc_i32(name))
+                .then_some(default)
+        })
+    }
+
+    /// Reads a `sockaddr_in` or `sockaddr_in6` of the family of `socket`.
+    fn read_socket_addr(
+        &self,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+        socket: &InetSocket,
+    ) -> InterpResult<'tcx, Result<SocketAddr, IoError>> {
+        let this = self.eval_context_ref();
+
+        let address_len = u64::from(this.read_scalar(address_len)?.to_u32()?);
+        let (family, layout) = if socket.is_ipv6 {
+            ("AF_INET6", this.libc_ty_layout("sockaddr_in6"))
+        } else {
+            ("AF_INET", this.libc_ty_layout("sockaddr_in"))
+        };
+        if address_len < layout.size.bytes() {
+            return interp_ok(Err(LibcError("EINVAL")));
+        }
+        let address = this.deref_pointer_as(address, layout)?;
+
+        let (family_field, port_field, ip_field) = if socket.is_ipv6 {
+            ("sin6_family", "sin6_port", "sin6_addr")
+        } else {
+            ("sin_family", "sin_port", "sin_addr")
+        };
+        let family_field = this.project_field_named(&address, family_field)?;
+        let actual_family = this.read_scalar(&family_field)?.to_int(family_field.layout.size)?;
+        if actual_family != i128::from(this.eval_libc_i32(family)) {
+            return interp_ok(Err(LibcError("EAFNOSUPPORT")));
+        }
+
+        // The port and address are stored in network byte order.
+        let port = this.project_field_named(&address, port_field)?;
+        let port = this.read_bytes_ptr_strip_provenance(port.ptr(), Size::from_bytes(2))?;
+        let port = u16::from_be_bytes(port.try_into().unwrap());
+        let ip = this.project_field_named(&address, ip_field)?;
+        let ip = this.read_bytes_ptr_strip_provenance(ip.ptr(), ip.layout.size)?;
+        let ip: IpAddr = if socket.is_ipv6 {
+            <[u8; 16]>::try_from(ip).unwrap().into()
+        } else {
+            <[u8; 4]>::try_from(ip).unwrap().into()
+        };
+
+        interp_ok(Ok(SocketAddr::new(ip, port)))
+    }
+
+    /// Writes `addr` as a `sockaddr_in` or `sockaddr_in6` to `address`, truncating it to the
+    /// buffer size stored in `address_len`, and stores the actual size in `address_len`.
+    fn write_socket_addr(
+        &mut self,
+        addr: SocketAddr,
+        address: Pointer,
+        address_len: Pointer,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let address_len = this.ptr_to_mplace(address_len, this.libc_ty_layout("socklen_t"));
+        let buffer_len = u64::from(this.read_scalar(&address_len)?.to_u32()?);
+
+        let (family, layout, len_field, family_field, port_field, ip_field, ip) = match addr {
+            SocketAddr::V4(addr) =>
+                (
+                    "AF_INET",
+                    this.libc_ty_layout("sockaddr_in"),
+                    "sin_len",
+                    "sin_family",
+                    "sin_port",
+                    "sin_addr",
+                    addr.ip().octets().to_vec(),
+                ),
+            SocketAddr::V6(addr) =>
+                (
+                    "AF_INET6",
+                    this.libc_ty_layout("sockaddr_in6"),
+                    "sin6_len",
+                    "sin6_family",
+                    "sin6_port",
+                    "sin6_addr",
+                    addr.ip().octets().to_vec(),
+                ),
+        };
+
+        // Build the whole address in a temporary allocation, so that we can copy the
+        // part that fits into the user's buffer.
+        let tmp = this.allocate(layout, MiriMemoryKind::Machine.into())?;
+        this.write_bytes_ptr(tmp.ptr(), std::iter::repeat_n(0u8, layout.size.bytes_usize()))?;
+        // BSDs store the length of the address in the address.
+        if let Some(len_field) = this.try_project_field_named(&tmp, len_field)? {
+            this.write_int(layout.size.bytes(), &len_field)?;
+        }
+        let family_field = this.project_field_named(&tmp, family_field)?;
+        this.write_int(this.eval_libc_i32(family), &family_field)?;
+        // The port and address are stored in network byte order.
+        let port_field = this.project_field_named(&tmp, port_field)?;
+        this.write_bytes_ptr(port_field.ptr(), addr.port().to_be_bytes())?;
+        let ip_field = this.project_field_named(&tmp, ip_field)?;
+        this.write_bytes_ptr(ip_field.ptr(), ip)?;
+
+        let copy_len = buffer_len.min(layout.size.bytes());
+        this.mem_copy(tmp.ptr(), address, Size::from_bytes(copy_len), true)?;
+        this.deallocate_ptr(tmp.ptr(), None, MiriMemoryKind::Machine.into())?;
+
+        this.write_int(layout.size.bytes(), &address_len)
+    }
+}
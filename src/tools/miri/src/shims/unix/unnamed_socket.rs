@@ -9,7 +9,8 @@
 
 use crate::concurrency::VClock;
 use crate::shims::files::{
-    EvalContextExt as _, FileDescription, FileDescriptionRef, WeakFileDescriptionRef,
+    DynFileDescriptionRef, EvalContextExt as _, FileDescription, FileDescriptionRef,
+    WeakFileDescriptionRef,
 };
 use crate::shims::unix::UnixFileDescription;
 use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
@@ -52,6 +53,9 @@ struct AnonSocket {
     blocked_write_tid: RefCell<Vec<ThreadId>>,
     /// Whether this fd is non-blocking or not.
     is_nonblock: Cell<bool>,
+    /// Whether our peer was the standard input of an emulated child process. Children run to
+    /// completion when they are spawned, so they never see what gets written afterwards.
+    peer_was_child_stdin: Cell<bool>,
     // Differentiate between different AnonSocket fd types.
     fd_type: AnonSocketType,
 }
@@ -210,6 +214,13 @@ fn anonsocket_write<'tcx>(
         return finish.call(ecx, Ok(0));
     }
 
+    if self_ref.peer_was_child_stdin.get() {
+        throw_unsup_format!(
+            "writing to the standard input of a child process after spawning it is not supported, \
+             because the child already ran to completion"
+        );
+    }
+
     // We are writing to our peer's readbuf.
     let Some(peer_fd) = self_ref.peer_fd().upgrade() else {
         // If the upgrade from Weak to Rc fails, it indicates that all read ends have been
@@ -377,6 +388,70 @@ fn anonsocket_read<'tcx>(
     interp_ok(())
 }
 
+/// Hands `bytes` to whoever reads from the other end of `fd`, as if they had been written to `fd`.
+/// Unlike a regular write, this never blocks and ignores the buffer capacity. This is used to
+/// forward the output of an emulated child process.
+/// Returns `false` if `fd` is not the writing end of an unnamed socket or pipe.
+pub(crate) fn anonsocket_deliver<'tcx>(
+    fd: DynFileDescriptionRef,
+    bytes: &[u8],
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx, bool> {
+    let Some(self_ref) = fd.downcast::<AnonSocket>() else {
+        return interp_ok(false);
+    };
+    if self_ref.fd_type == AnonSocketType::PipeRead {
+        return interp_ok(false);
+    }
+    let Some(peer_fd) = self_ref.peer_fd().upgrade() else {
+        // All read ends have been closed, so nobody will ever see this data.
+        return interp_ok(true);
+    };
+    let mut writebuf = peer_fd.readbuf.as_ref().unwrap().borrow_mut();
+    ecx.release_clock(|clock| {
+        writebuf.clock.join(clock);
+    })?;
+    writebuf.buf.extend(bytes);
+    drop(writebuf);
+
+    let waiting_threads = std::mem::take(&mut *peer_fd.blocked_read_tid.borrow_mut());
+    for thread_id in waiting_threads {
+        ecx.unblock_thread(thread_id, BlockReason::UnnamedSocket)?;
+    }
+    ecx.check_and_update_readiness(peer_fd)?;
+    interp_ok(true)
+}
+
+/// Takes everything that can currently be read from `fd` without blocking. This is used to
+/// forward the input of an emulated child process; writing to the other end afterwards is
+/// reported as unsupported, since the child will never read it.
+/// Returns `None` if `fd` is not the reading end of an unnamed socket or pipe.
+pub(crate) fn anonsocket_drain<'tcx>(
+    fd: DynFileDescriptionRef,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx, Option<Vec<u8>>> {
+    let Some(self_ref) = fd.downcast::<AnonSocket>() else {
+        return interp_ok(None);
+    };
+    let Some(readbuf) = &self_ref.readbuf else {
+        return interp_ok(None);
+    };
+    let mut readbuf = readbuf.borrow_mut();
+    ecx.acquire_clock(&readbuf.clock)?;
+    let bytes = readbuf.buf.drain(..).collect();
+    drop(readbuf);
+
+    if let Some(peer_fd) = self_ref.peer_fd().upgrade() {
+        peer_fd.peer_was_child_stdin.set(true);
+        let waiting_threads = std::mem::take(&mut *peer_fd.blocked_write_tid.borrow_mut());
+        for thread_id in waiting_threads {
+            ecx.unblock_thread(thread_id, BlockReason::UnnamedSocket)?;
+        }
+        ecx.check_and_update_readiness(peer_fd)?;
+    }
+    interp_ok(Some(bytes))
+}
+
 impl UnixFileDescription for AnonSocket {
     fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
         // We only check the status of EPOLLIN, EPOLLOUT, EPOLLHUP and EPOLLRDHUP flags.
@@ -492,6 +567,7 @@ fn socketpair(
             blocked_read_tid: RefCell::new(Vec::new()),
             blocked_write_tid: RefCell::new(Vec::new()),
             is_nonblock: Cell::new(is_sock_nonblock),
+            peer_was_child_stdin: Cell::new(false),
             fd_type: AnonSocketType::Socketpair,
         });
         let fd1 = fds.new_ref(AnonSocket {
@@ -501,6 +577,7 @@ fn socketpair(
             blocked_read_tid: RefCell::new(Vec::new()),
             blocked_write_tid: RefCell::new(Vec::new()),
             is_nonblock: Cell::new(is_sock_nonblock),
+            peer_was_child_stdin: Cell::new(false),
             fd_type: AnonSocketType::Socketpair,
         });
 
@@ -562,6 +639,7 @@ fn pipe2(
             blocked_read_tid: RefCell::new(Vec::new()),
             blocked_write_tid: RefCell::new(Vec::new()),
             is_nonblock: Cell::new(is_nonblock),
+            peer_was_child_stdin: Cell::new(false),
             fd_type: AnonSocketType::PipeRead,
         });
         let fd1 = fds.new_ref(AnonSocket {
@@ -571,6 +649,7 @@ fn pipe2(
             blocked_read_tid: RefCell::new(Vec::new()),
             blocked_write_tid: RefCell::new(Vec::new()),
             is_nonblock: Cell::new(is_nonblock),
+            peer_was_child_stdin: Cell::new(false),
             fd_type: AnonSocketType::PipeWrite,
         });
 
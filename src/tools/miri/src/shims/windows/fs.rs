@@ -286,9 +286,10 @@ fn CreateFileW(
                 }
             }
 
-            options.open(file_name).map(|file| {
+            options.open(&file_name).map(|file| {
+                let path = std::path::absolute(&file_name).unwrap_or(file_name);
                 let fd_num =
-                    this.machine.fds.insert_new(FileHandle { file, writable: desired_write });
+                    this.machine.fds.insert_new(FileHandle { file, writable: desired_write, path });
                 Handle::File(fd_num)
             })
         };
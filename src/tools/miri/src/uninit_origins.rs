@@ -0,0 +1,85 @@
+//! Tracking where uninitialized memory came from, see `-Zmiri-track-uninit-origins`.
+//!
+//! The init mask of an allocation only says *whether* a byte is initialized. To explain an
+//! uninitialized read, we remember for every byte of the allocation the last write to it: if a
+//! byte is uninitialized now, that write is what de-initialized it (e.g. a copy of
+//! `MaybeUninit::uninit()`, of a moved-out value, or of padding). Bytes that were never written
+//! have been uninitialized since the allocation was created.
+
+use std::ops;
+
+use rustc_span::{Span, SpanData};
+
+use crate::*;
+
+/// Where the current contents of each byte of an allocation came from.
+#[derive(Debug)]
+pub struct UninitOrigins {
+    /// Where this allocation was created.
+    allocated: Span,
+    /// The last write to each byte, if any.
+    last_write: DedupRangeMap<Option<Span>>,
+}
+
+/// Why a range of bytes is uninitialized.
+#[derive(Debug)]
+pub enum UninitOrigin {
+    /// The bytes were never written since the allocation was created here.
+    Allocated(SpanData),
+    /// The bytes were last written here, which de-initialized them.
+    Written(SpanData),
+}
+
+impl UninitOrigins {
+    pub fn new(size: Size, allocated: Span) -> Self {
+        UninitOrigins { allocated, last_write: DedupRangeMap::new(size, None) }
+    }
+
+    /// Records a write to the given range of the allocation.
+    pub fn record_write(&mut self, range: AllocRange, span: Span) {
+        for (_offset, last_write) in self.last_write.iter_mut(range.start, range.size) {
+            *last_write = Some(span);
+        }
+    }
+
+    /// Explains why the bytes in the given range are uninitialized. The range must be entirely
+    /// uninitialized.
+    pub fn explain(&self, range: AllocRange) -> Vec<(ops::Range<u64>, UninitOrigin)> {
+        self.last_write
+            .iter(range.start, range.size)
+            .map(|(offset, last_write)| {
+                // Only report the part of the element that overlaps with `range`.
+                let offset =
+                    offset.start.max(range.start.bytes())..offset.end.min(range.end().bytes());
+                let origin = match last_write {
+                    Some(span) => UninitOrigin::Written(span.data()),
+                    None => UninitOrigin::Allocated(self.allocated.data()),
+                };
+                (offset, origin)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_span::DUMMY_SP;
+
+    use super::*;
+
+    fn range(start: u64, size: u64) -> AllocRange {
+        alloc_range(Size::from_bytes(start), Size::from_bytes(size))
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut origins = UninitOrigins::new(Size::from_bytes(8), DUMMY_SP);
+        origins.record_write(range(2, 2), DUMMY_SP);
+        let explained = origins.explain(range(1, 4));
+        let explained: Vec<_> = explained
+            .iter()
+            .map(|(offset, origin)| (offset.clone(), matches!(origin, UninitOrigin::Written(_))))
+            .collect();
+        assert_eq!(explained, vec![(1..2, false), (2..4, true), (4..5, false)]);
+    }
+}
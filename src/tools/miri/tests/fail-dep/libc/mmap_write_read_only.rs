@@ -0,0 +1,12 @@
+//@only-target: linux # memfd_create
+
+fn main() {
+    unsafe {
+        let fd = libc::memfd_create(c"miri".as_ptr(), libc::MFD_CLOEXEC);
+        assert!(fd >= 0);
+        assert_eq!(libc::ftruncate(fd, 16), 0);
+        let ptr = libc::mmap(std::ptr::null_mut(), 16, libc::PROT_READ, libc::MAP_SHARED, fd, 0);
+        assert_ne!(ptr, libc::MAP_FAILED);
+        *ptr.cast::<u8>() = 1; //~ ERROR: read-only
+    }
+}
@@ -0,0 +1,41 @@
+//@ignore-target: windows # No process support on Windows
+//@compile-flags: -Zmiri-spawn-self
+
+// The child runs to completion when it is spawned, so it never sees input that is written later.
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::{env, ptr};
+
+fn main() {
+    if env::args().nth(1).as_deref() == Some("child") {
+        return;
+    }
+
+    let mut fds = [-1, -1];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let [read_end, write_end] = fds;
+
+    let path = CString::new(env::args().next().unwrap()).unwrap();
+    let arg = CString::new("child").unwrap();
+    let argv = [path.as_ptr().cast_mut(), arg.as_ptr().cast_mut(), ptr::null_mut()];
+    let envp = [ptr::null_mut()];
+    unsafe {
+        let mut actions = MaybeUninit::uninit();
+        assert_eq!(libc::posix_spawn_file_actions_init(actions.as_mut_ptr()), 0);
+        assert_eq!(libc::posix_spawn_file_actions_adddup2(actions.as_mut_ptr(), read_end, 0), 0);
+        let mut pid = 0;
+        let res = libc::posix_spawn(
+            &mut pid,
+            path.as_ptr(),
+            actions.as_ptr(),
+            ptr::null(),
+            argv.as_ptr(),
+            envp.as_ptr(),
+        );
+        assert_eq!(res, 0);
+        assert_eq!(libc::close(read_end), 0);
+
+        libc::write(write_end, b"late".as_ptr().cast(), 4);
+        //~^ERROR: writing to the standard input of a child process after spawning it is not supported
+    }
+}
@@ -0,0 +1,8 @@
+//@only-target: linux android
+
+// Miri can't run signal handlers, so it only supports sending blocked signals.
+fn main() {
+    let res = unsafe { libc::kill(libc::getpid(), libc::SIGUSR1) };
+    //~^ERROR: delivering signal 10 is not supported
+    assert_eq!(res, 0);
+}
@@ -0,0 +1,17 @@
+//@compile-flags: -Zmiri-track-uninit-origins
+use std::mem;
+
+#[repr(C)]
+struct Pair(u8, u16);
+
+fn main() {
+    unsafe {
+        let mut buf = [0u16; 2];
+        let p = &raw mut buf as *mut Pair;
+        // Storing a `Pair` de-initializes its padding byte.
+        p.write(mem::transmute(0u32));
+        let c = p as *const u8;
+        let _val = *c.add(1);
+        //~^ERROR: uninitialized
+    }
+}
@@ -0,0 +1,18 @@
+//! Run by `gdbserver` in `tests/ui.rs`, which sets a breakpoint on `answer`. The breakpoint must be
+//! hit once per call, no matter how the loop inside `answer` jumps around.
+
+#[inline(never)]
+fn answer() -> i32 {
+    let mut n = 0;
+    loop {
+        n += 1;
+        if n == 42 {
+            return n;
+        }
+    }
+}
+
+fn main() {
+    assert_eq!(answer(), 42);
+    assert_eq!(answer(), 42);
+}
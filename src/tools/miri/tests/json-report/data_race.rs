@@ -0,0 +1,35 @@
+//! A data race, reported as JSON.
+//@ignore-target: windows # No pthreads on Windows
+//@compile-flags: -Zmiri-deterministic-concurrency
+//@normalize-stderr-test: "(.freed_at.:null,.id.:)[0-9]+" -> "${1}ID"
+#![no_main]
+
+use std::ptr;
+
+extern "C" {
+    fn pthread_create(
+        thread: *mut usize,
+        attr: *const u8,
+        start: extern "C" fn(*mut u8) -> *mut u8,
+        arg: *mut u8,
+    ) -> i32;
+    fn pthread_join(thread: usize, retval: *mut *mut u8) -> i32;
+}
+
+static mut X: u32 = 0;
+
+extern "C" fn thread_start(_arg: *mut u8) -> *mut u8 {
+    unsafe { X = 2 };
+    ptr::null_mut()
+}
+
+#[unsafe(no_mangle)]
+fn miri_start(_argc: isize, _argv: *const *const u8) -> isize {
+    unsafe {
+        let mut thread = 0;
+        pthread_create(&mut thread, ptr::null(), thread_start, ptr::null_mut());
+        X = 1;
+        pthread_join(thread, ptr::null_mut());
+    }
+    0
+}
@@ -0,0 +1,13 @@
+//! Leaked memory, reported as JSON.
+//@normalize-stderr-test: "(.id.:)[0-9]+(,.memory_kind)" -> "${1}ID$2"
+#![no_main]
+
+extern "Rust" {
+    fn miri_alloc(size: usize, align: usize) -> *mut u8;
+}
+
+#[unsafe(no_mangle)]
+fn miri_start(_argc: isize, _argv: *const *const u8) -> isize {
+    unsafe { miri_alloc(4, 4) };
+    0
+}
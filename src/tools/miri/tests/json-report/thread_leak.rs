@@ -0,0 +1,36 @@
+//! A thread that is still running when the main thread terminates, reported as JSON.
+//@ignore-target: windows # No pthreads on Windows
+//@compile-flags: -Zmiri-deterministic-concurrency
+#![no_main]
+
+use std::ptr;
+
+extern "C" {
+    fn pthread_create(
+        thread: *mut usize,
+        attr: *const u8,
+        start: extern "C" fn(*mut u8) -> *mut u8,
+        arg: *mut u8,
+    ) -> i32;
+    fn pthread_join(thread: usize, retval: *mut *mut u8) -> i32;
+    fn pthread_self() -> usize;
+    fn sched_yield() -> i32;
+}
+
+/// Waits for the main thread, which does not wait for this thread.
+extern "C" fn thread_start(main: *mut u8) -> *mut u8 {
+    unsafe { pthread_join(main.addr(), ptr::null_mut()) };
+    ptr::null_mut()
+}
+
+#[unsafe(no_mangle)]
+fn miri_start(_argc: isize, _argv: *const *const u8) -> isize {
+    unsafe {
+        let main = ptr::without_provenance_mut(pthread_self());
+        let mut thread = 0;
+        pthread_create(&mut thread, ptr::null(), thread_start, main);
+        // Let the thread start waiting.
+        sched_yield();
+    }
+    0
+}
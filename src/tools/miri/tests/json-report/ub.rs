@@ -0,0 +1,8 @@
+//! Undefined Behavior, reported as JSON.
+#![feature(core_intrinsics)]
+#![no_main]
+
+#[unsafe(no_mangle)]
+fn miri_start(_argc: isize, _argv: *const *const u8) -> isize {
+    unsafe { std::intrinsics::unchecked_div(1, 0) }
+}
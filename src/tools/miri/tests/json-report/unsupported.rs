@@ -0,0 +1,13 @@
+//! An unsupported operation, reported as JSON.
+//@normalize-stderr-test: "OS `[a-z]+`" -> "$$OS"
+#![no_main]
+
+extern "Rust" {
+    fn foo();
+}
+
+#[unsafe(no_mangle)]
+fn miri_start(_argc: isize, _argv: *const *const u8) -> isize {
+    unsafe { foo() };
+    0
+}
@@ -0,0 +1,233 @@
+//@only-target: linux
+//@compile-flags: -Zmiri-disable-isolation
+
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Write};
+use std::mem::size_of;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+#[path = "../../utils/mod.rs"]
+mod utils;
+
+fn main() {
+    let dir = utils::tmp().join("miri_test_libc_inotify");
+    fs::remove_dir(&dir).ok();
+    fs::create_dir(&dir).unwrap();
+
+    test_dir_events(&dir);
+    test_file_events(&dir);
+    test_rename(&dir);
+    test_oneshot(&dir);
+    test_rm_watch(&dir);
+    test_epoll(&dir);
+
+    fs::remove_dir(&dir).unwrap();
+}
+
+// Using `as` cast since `EPOLLET` wraps around
+const EPOLL_IN_ET: u32 = (libc::EPOLLIN | libc::EPOLLET) as _;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Event {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    name: String,
+}
+
+fn event(wd: i32, mask: u32, name: &str) -> Event {
+    Event { wd, mask, cookie: 0, name: name.to_owned() }
+}
+
+fn add_watch(fd: i32, path: &Path, mask: u32) -> i32 {
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let wd = unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) };
+    assert!(wd > 0, "{}", Error::last_os_error());
+    wd
+}
+
+/// Reads all queued events; the inotify instance must be non-blocking.
+fn read_events(fd: i32) -> Vec<Event> {
+    let mut buf = [0u8; 4096];
+    let len = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    if len == -1 {
+        assert_eq!(Error::last_os_error().kind(), ErrorKind::WouldBlock);
+        return Vec::new();
+    }
+    let mut events = Vec::new();
+    let mut buf = &buf[..len as usize];
+    while !buf.is_empty() {
+        let header = unsafe { buf.as_ptr().cast::<libc::inotify_event>().read_unaligned() };
+        let header_len = size_of::<libc::inotify_event>();
+        let name = &buf[header_len..header_len + header.len as usize];
+        // The name is padded with null bytes.
+        let name = name.split(|&b| b == 0).next().unwrap();
+        events.push(Event {
+            wd: header.wd,
+            mask: header.mask,
+            cookie: header.cookie,
+            name: String::from_utf8(name.to_vec()).unwrap(),
+        });
+        buf = &buf[header_len + header.len as usize..];
+    }
+    events
+}
+
+fn test_dir_events(dir: &Path) {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+    assert!(fd >= 0);
+    let wd =
+        add_watch(fd, dir, libc::IN_CREATE | libc::IN_MODIFY | libc::IN_CLOSE | libc::IN_DELETE);
+
+    // Nothing happened yet.
+    assert_eq!(read_events(fd), []);
+
+    let path = dir.join("file");
+    let mut file = File::create(&path).unwrap();
+    file.write_all(b"hello").unwrap();
+    drop(file);
+    fs::create_dir(dir.join("subdir")).unwrap();
+    fs::remove_file(&path).unwrap();
+    fs::remove_dir(dir.join("subdir")).unwrap();
+
+    assert_eq!(
+        read_events(fd),
+        [
+            event(wd, libc::IN_CREATE, "file"),
+            event(wd, libc::IN_MODIFY, "file"),
+            event(wd, libc::IN_CLOSE_WRITE, "file"),
+            event(wd, libc::IN_CREATE | libc::IN_ISDIR, "subdir"),
+            event(wd, libc::IN_DELETE, "file"),
+            event(wd, libc::IN_DELETE | libc::IN_ISDIR, "subdir"),
+        ]
+    );
+
+    unsafe { libc::close(fd) };
+}
+
+fn test_file_events(dir: &Path) {
+    let path = dir.join("watched");
+    fs::write(&path, b"hello").unwrap();
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK) };
+    assert!(fd >= 0);
+    let wd = add_watch(fd, &path, libc::IN_MODIFY | libc::IN_CLOSE_NOWRITE | libc::IN_DELETE_SELF);
+
+    fs::read(&path).unwrap();
+    fs::write(&path, b"world").unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // Removing the file also removes the watch.
+    assert_eq!(
+        read_events(fd),
+        [
+            event(wd, libc::IN_CLOSE_NOWRITE, ""),
+            event(wd, libc::IN_MODIFY, ""),
+            event(wd, libc::IN_DELETE_SELF, ""),
+            event(wd, libc::IN_IGNORED, ""),
+        ]
+    );
+
+    // Watching a file that does not exist fails.
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let res = unsafe { libc::inotify_add_watch(fd, path.as_ptr(), libc::IN_MODIFY) };
+    assert_eq!(res, -1);
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::NotFound);
+
+    unsafe { libc::close(fd) };
+}
+
+fn test_rename(dir: &Path) {
+    let from = dir.join("from");
+    let to = dir.join("to");
+    fs::write(&from, b"").unwrap();
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK) };
+    assert!(fd >= 0);
+    let wd = add_watch(fd, dir, libc::IN_MOVE);
+    fs::rename(&from, &to).unwrap();
+
+    let events = read_events(fd);
+    assert_eq!(events.len(), 2);
+    let cookie = events[0].cookie;
+    assert_ne!(cookie, 0);
+    assert_eq!(
+        events,
+        [
+            Event { wd, mask: libc::IN_MOVED_FROM, cookie, name: "from".to_owned() },
+            Event { wd, mask: libc::IN_MOVED_TO, cookie, name: "to".to_owned() },
+        ]
+    );
+
+    fs::remove_file(&to).unwrap();
+    unsafe { libc::close(fd) };
+}
+
+fn test_oneshot(dir: &Path) {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK) };
+    assert!(fd >= 0);
+    let wd = add_watch(fd, dir, libc::IN_CREATE | libc::IN_DELETE | libc::IN_ONESHOT);
+
+    let path = dir.join("oneshot");
+    fs::write(&path, b"").unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // Only the first event is reported, then the watch is removed.
+    assert_eq!(
+        read_events(fd),
+        [event(wd, libc::IN_CREATE, "oneshot"), event(wd, libc::IN_IGNORED, "")]
+    );
+
+    unsafe { libc::close(fd) };
+}
+
+fn test_rm_watch(dir: &Path) {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK) };
+    assert!(fd >= 0);
+    let wd = add_watch(fd, dir, libc::IN_CREATE);
+
+    let res = unsafe { libc::inotify_rm_watch(fd, wd) };
+    assert_eq!(res, 0);
+    fs::write(dir.join("unwatched"), b"").unwrap();
+    assert_eq!(read_events(fd), [event(wd, libc::IN_IGNORED, "")]);
+
+    // The watch is gone now.
+    let res = unsafe { libc::inotify_rm_watch(fd, wd) };
+    assert_eq!(res, -1);
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::InvalidInput);
+
+    fs::remove_file(dir.join("unwatched")).unwrap();
+    unsafe { libc::close(fd) };
+}
+
+fn test_epoll(dir: &Path) {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK) };
+    assert!(fd >= 0);
+    let wd = add_watch(fd, dir, libc::IN_CREATE);
+
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let mut ev = libc::epoll_event { events: EPOLL_IN_ET, u64: fd as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev) };
+    assert_eq!(res, 0);
+
+    // Not ready yet.
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 1];
+    let res = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 1, 0) };
+    assert_eq!(res, 0);
+
+    // Ready once there is an event.
+    fs::write(dir.join("epoll"), b"").unwrap();
+    let res = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 1, 0) };
+    assert_eq!(res, 1);
+    let (ready, data) = (events[0].events, events[0].u64);
+    assert_eq!(ready, libc::EPOLLIN as u32);
+    assert_eq!(data, fd as u64);
+    assert_eq!(read_events(fd), [event(wd, libc::IN_CREATE, "epoll")]);
+
+    fs::remove_file(dir.join("epoll")).unwrap();
+    unsafe { libc::close(epfd) };
+    unsafe { libc::close(fd) };
+}
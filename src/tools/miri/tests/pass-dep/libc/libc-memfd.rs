@@ -0,0 +1,234 @@
+//@only-target: linux
+// memfds do not touch the host, so everything here works with isolation enabled.
+
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::os::fd::FromRawFd;
+use std::{ptr, slice};
+
+fn main() {
+    test_read_write();
+    test_invalid_flags();
+    test_mmap_shared();
+    test_mmap_coherent();
+    test_mmap_private();
+    test_mmap_offset();
+    test_mmap_invalid();
+    test_msync_invalid();
+    test_epoll();
+}
+
+fn memfd() -> File {
+    let fd = unsafe { libc::memfd_create(c"miri".as_ptr(), libc::MFD_CLOEXEC) };
+    assert!(fd >= 0, "{}", Error::last_os_error());
+    unsafe { File::from_raw_fd(fd) }
+}
+
+fn test_read_write() {
+    let mut file = memfd();
+    assert_eq!(file.metadata().unwrap().len(), 0);
+    file.write_all(b"hello, world").unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 12);
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "");
+    file.seek(SeekFrom::Start(7)).unwrap();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "world");
+
+    // Truncating and growing the file.
+    file.set_len(5).unwrap();
+    file.set_len(8).unwrap();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"hello\0\0\0");
+    assert!(file.metadata().unwrap().is_file());
+}
+
+fn test_invalid_flags() {
+    let fd = unsafe { libc::memfd_create(c"miri".as_ptr(), 0x8000) };
+    assert_eq!(fd, -1);
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::InvalidInput);
+
+    let name = c"a very long name that goes on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on and on";
+    assert!(name.count_bytes() > 249);
+    let fd = unsafe { libc::memfd_create(name.as_ptr(), 0) };
+    assert_eq!(fd, -1);
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::InvalidInput);
+}
+
+fn mmap(file: &File, len: usize, prot: libc::c_int, flags: libc::c_int, offset: i64) -> *mut u8 {
+    use std::os::fd::AsRawFd;
+    let ptr = unsafe { libc::mmap64(ptr::null_mut(), len, prot, flags, file.as_raw_fd(), offset) };
+    ptr.cast()
+}
+
+fn test_mmap_shared() {
+    let mut file = memfd();
+    file.write_all(b"hello, world").unwrap();
+
+    let page_size = page_size::get();
+    let ptr = mmap(&file, 12, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, 0);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+    let mapping = unsafe { slice::from_raw_parts_mut(ptr, page_size) };
+    assert_eq!(&mapping[..12], b"hello, world");
+    // Beyond the end of the file, the mapping is zeroed.
+    assert!(mapping[12..].iter().all(|&b| b == 0));
+
+    // Changes become visible in the file after `msync`.
+    mapping[..5].copy_from_slice(b"HELLO");
+    let res = unsafe { libc::msync(ptr.cast(), page_size, libc::MS_SYNC) };
+    assert_eq!(res, 0);
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "HELLO, world");
+
+    // ... and after `munmap`. Memory beyond the end of the file is not written back.
+    mapping[7..12].copy_from_slice(b"WORLD");
+    mapping[100] = 1;
+    let res = unsafe { libc::munmap(ptr.cast(), 12) };
+    assert_eq!(res, 0);
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "HELLO, WORLD");
+}
+
+fn test_mmap_coherent() {
+    let mut file = memfd();
+    file.write_all(b"hello, world").unwrap();
+
+    let ptr = mmap(&file, 12, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, 0);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+    let read_only = mmap(&file, 12, libc::PROT_READ, libc::MAP_SHARED, 0);
+    assert_ne!(read_only, libc::MAP_FAILED.cast());
+
+    // Writes to the mapping are visible to reads from the file without `msync`...
+    unsafe { ptr.copy_from(b"HELLO".as_ptr(), 5) };
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "HELLO, world");
+
+    // ... and writes to the file are visible in all shared mappings.
+    file.seek(SeekFrom::Start(7)).unwrap();
+    file.write_all(b"WORLD").unwrap();
+    assert_eq!(unsafe { slice::from_raw_parts(ptr, 12) }, b"HELLO, WORLD");
+    assert_eq!(unsafe { slice::from_raw_parts(read_only, 12) }, b"HELLO, WORLD");
+
+    let res = unsafe { libc::munmap(ptr.cast(), 12) };
+    assert_eq!(res, 0);
+    let res = unsafe { libc::munmap(read_only.cast(), 12) };
+    assert_eq!(res, 0);
+}
+
+fn test_mmap_private() {
+    let mut file = memfd();
+    file.write_all(b"hello, world").unwrap();
+
+    let ptr = mmap(&file, 12, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_PRIVATE, 0);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+    let mapping = unsafe { slice::from_raw_parts_mut(ptr, 12) };
+    mapping.copy_from_slice(b"goodbye, all");
+    let res = unsafe { libc::msync(ptr.cast(), 12, libc::MS_SYNC) };
+    assert_eq!(res, 0);
+    let res = unsafe { libc::munmap(ptr.cast(), 12) };
+    assert_eq!(res, 0);
+
+    // Private mappings are never written back.
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "hello, world");
+}
+
+fn test_mmap_offset() {
+    let page_size = page_size::get();
+    let file = memfd();
+    file.set_len(2 * page_size as u64).unwrap();
+
+    let ptr = mmap(&file, page_size, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, 0);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+    unsafe { ptr.write_bytes(1, page_size) };
+    let res = unsafe { libc::munmap(ptr.cast(), page_size) };
+    assert_eq!(res, 0);
+
+    let ptr = mmap(&file, 2 * page_size, libc::PROT_READ, libc::MAP_SHARED, 0);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+    let mapping = unsafe { slice::from_raw_parts(ptr, 2 * page_size) };
+    assert!(mapping[..page_size].iter().all(|&b| b == 1));
+    assert!(mapping[page_size..].iter().all(|&b| b == 0));
+    let res = unsafe { libc::munmap(ptr.cast(), 2 * page_size) };
+    assert_eq!(res, 0);
+
+    let offset = page_size as i64;
+    let ptr = mmap(&file, page_size, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, offset);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+    unsafe { ptr.write_bytes(2, page_size) };
+    let res = unsafe { libc::munmap(ptr.cast(), page_size) };
+    assert_eq!(res, 0);
+
+    let mut contents = Vec::new();
+    (&file).read_to_end(&mut contents).unwrap();
+    assert!(contents[..page_size].iter().all(|&b| b == 1));
+    assert!(contents[page_size..].iter().all(|&b| b == 2));
+}
+
+fn test_mmap_invalid() {
+    let file = memfd();
+    file.set_len(16).unwrap();
+
+    // The offset must be a multiple of the page size.
+    let ptr = mmap(&file, 16, libc::PROT_READ, libc::MAP_SHARED, 1);
+    assert_eq!(ptr, libc::MAP_FAILED.cast());
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::InvalidInput);
+
+    // The file descriptor must be valid.
+    let ptr =
+        unsafe { libc::mmap64(ptr::null_mut(), 16, libc::PROT_READ, libc::MAP_SHARED, 1234, 0) };
+    assert_eq!(ptr, libc::MAP_FAILED);
+    assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EBADF));
+}
+
+fn test_msync_invalid() {
+    let page_size = page_size::get();
+    let file = memfd();
+    file.set_len(16).unwrap();
+    let ptr = mmap(&file, 16, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, 0);
+    assert_ne!(ptr, libc::MAP_FAILED.cast());
+
+    // MS_SYNC and MS_ASYNC are mutually exclusive.
+    let res = unsafe { libc::msync(ptr.cast(), 16, libc::MS_SYNC | libc::MS_ASYNC) };
+    assert_eq!(res, -1);
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::InvalidInput);
+
+    // The address must be a multiple of the page size.
+    let res = unsafe { libc::msync(ptr.add(1).cast(), 15, libc::MS_SYNC) };
+    assert_eq!(res, -1);
+    assert_eq!(Error::last_os_error().kind(), ErrorKind::InvalidInput);
+
+    // The range must be mapped.
+    let res = unsafe { libc::msync(ptr.cast(), 2 * page_size, libc::MS_SYNC) };
+    assert_eq!(res, -1);
+    assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::ENOMEM));
+
+    let res = unsafe { libc::msync(ptr.cast(), 16, libc::MS_ASYNC | libc::MS_INVALIDATE) };
+    assert_eq!(res, 0);
+    let res = unsafe { libc::munmap(ptr.cast(), 16) };
+    assert_eq!(res, 0);
+}
+
+fn test_epoll() {
+    use std::os::fd::AsRawFd;
+    let file = memfd();
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    // Regular files cannot be added to an epoll instance.
+    let mut ev = libc::epoll_event { events: (libc::EPOLLIN | libc::EPOLLET) as _, u64: 0 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, file.as_raw_fd(), &mut ev) };
+    assert_eq!(res, -1);
+    assert_eq!(Error::last_os_error().raw_os_error(), Some(libc::EPERM));
+}
@@ -0,0 +1,113 @@
+//@only-target: linux android illumos
+// test_block_until_written depends on a deterministic schedule.
+//@compile-flags: -Zmiri-deterministic-concurrency
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn main() {
+    test_ready();
+    test_invalid_fd();
+    test_timeout();
+    test_block_until_written();
+    test_block_until_closed();
+}
+
+fn pipe() -> [i32; 2] {
+    let mut fds = [-1, -1];
+    let res = unsafe { libc::pipe(fds.as_mut_ptr()) };
+    assert_eq!(res, 0);
+    fds
+}
+
+fn pollfd(fd: i32, events: i16) -> libc::pollfd {
+    libc::pollfd { fd, events, revents: 0 }
+}
+
+fn poll(fds: &mut [libc::pollfd], timeout: i32) -> i32 {
+    let res = unsafe { libc::poll(fds.as_mut_ptr(), fds.len().try_into().unwrap(), timeout) };
+    assert!(res >= 0, "poll failed: {}", std::io::Error::last_os_error());
+    res
+}
+
+fn write(fd: i32, data: &[u8]) {
+    let res = unsafe { libc::write(fd, data.as_ptr().cast(), data.len()) };
+    assert_eq!(res, data.len().try_into().unwrap());
+}
+
+fn test_ready() {
+    let [read_end, write_end] = pipe();
+
+    // An empty pipe is writable but not readable.
+    let mut fds = [pollfd(read_end, libc::POLLIN), pollfd(write_end, libc::POLLOUT)];
+    assert_eq!(poll(&mut fds, 0), 1);
+    assert_eq!(fds[0].revents, 0);
+    assert_eq!(fds[1].revents, libc::POLLOUT);
+
+    write(write_end, b"abc");
+    let mut fds = [pollfd(read_end, libc::POLLIN)];
+    assert_eq!(poll(&mut fds, -1), 1);
+    assert_eq!(fds[0].revents, libc::POLLIN);
+
+    unsafe {
+        assert_eq!(libc::close(read_end), 0);
+        assert_eq!(libc::close(write_end), 0);
+    }
+}
+
+fn test_invalid_fd() {
+    // Negative file descriptors are ignored, closed ones are reported.
+    let mut fds = [pollfd(-1, libc::POLLIN), pollfd(1000, libc::POLLIN)];
+    assert_eq!(poll(&mut fds, -1), 1);
+    assert_eq!(fds[0].revents, 0);
+    assert_eq!(fds[1].revents, libc::POLLNVAL);
+}
+
+fn test_timeout() {
+    let [read_end, write_end] = pipe();
+
+    let start = Instant::now();
+    let mut fds = [pollfd(read_end, libc::POLLIN)];
+    assert_eq!(poll(&mut fds, 100), 0);
+    assert_eq!(fds[0].revents, 0);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    unsafe {
+        assert_eq!(libc::close(read_end), 0);
+        assert_eq!(libc::close(write_end), 0);
+    }
+}
+
+fn test_block_until_written() {
+    let [read_end, write_end] = pipe();
+
+    let writer = thread::spawn(move || {
+        write(write_end, b"abc");
+    });
+    // This blocks until the other thread writes to the pipe.
+    let mut fds = [pollfd(read_end, libc::POLLIN)];
+    assert_eq!(poll(&mut fds, -1), 1);
+    assert_eq!(fds[0].revents, libc::POLLIN);
+    writer.join().unwrap();
+
+    unsafe {
+        assert_eq!(libc::close(read_end), 0);
+        assert_eq!(libc::close(write_end), 0);
+    }
+}
+
+fn test_block_until_closed() {
+    let [read_end, write_end] = pipe();
+
+    let closer = thread::spawn(move || {
+        assert_eq!(unsafe { libc::close(write_end) }, 0);
+    });
+    // Closing the write end makes the read end report a hangup, even though it was not
+    // requested.
+    let mut fds = [pollfd(read_end, 0)];
+    assert_eq!(poll(&mut fds, -1), 1);
+    assert_eq!(fds[0].revents, libc::POLLHUP);
+    closer.join().unwrap();
+
+    assert_eq!(unsafe { libc::close(read_end) }, 0);
+}
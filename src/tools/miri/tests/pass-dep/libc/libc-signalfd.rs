@@ -0,0 +1,210 @@
+//@only-target: linux android
+// test_blocking_read depends on a deterministic schedule.
+//@compile-flags: -Zmiri-deterministic-concurrency
+
+use std::mem::MaybeUninit;
+use std::thread;
+
+fn main() {
+    test_sigprocmask();
+    test_read();
+    test_read_several();
+    test_already_pending();
+    test_update_mask();
+    test_epoll();
+    test_blocking_read();
+}
+
+// Using `as` cast since `EPOLLET` wraps around
+const EPOLL_IN_ET: u32 = (libc::EPOLLIN | libc::EPOLLET) as _;
+
+fn sigset(signals: &[i32]) -> libc::sigset_t {
+    let mut set = MaybeUninit::uninit();
+    unsafe {
+        assert_eq!(libc::sigemptyset(set.as_mut_ptr()), 0);
+        for &sig in signals {
+            assert_eq!(libc::sigaddset(set.as_mut_ptr(), sig), 0);
+        }
+        set.assume_init()
+    }
+}
+
+fn block(signals: &[i32]) {
+    let set = sigset(signals);
+    let res = unsafe { libc::sigprocmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) };
+    assert_eq!(res, 0);
+}
+
+fn read_signals(fd: i32, max: usize) -> Result<Vec<u32>, std::io::Error> {
+    let mut buf = vec![unsafe { std::mem::zeroed::<libc::signalfd_siginfo>() }; max];
+    let len = std::mem::size_of_val(buf.as_slice());
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), len) };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let res = usize::try_from(res).unwrap();
+    assert_eq!(res % size_of::<libc::signalfd_siginfo>(), 0);
+    let pid = unsafe { libc::getpid() };
+    Ok(buf[..res / size_of::<libc::signalfd_siginfo>()]
+        .iter()
+        .map(|info| {
+            assert_eq!(info.ssi_pid, pid as u32);
+            info.ssi_signo
+        })
+        .collect())
+}
+
+fn check_epoll_wait(epfd: i32, expected: &[(u32, u64)]) {
+    let mut array = [libc::epoll_event { events: 0, u64: 0 }; 4];
+    let res = unsafe { libc::epoll_wait(epfd, array.as_mut_ptr(), 4, 0) };
+    assert!(res >= 0, "epoll_wait failed: {}", std::io::Error::last_os_error());
+    let events: Vec<_> =
+        array[..res.try_into().unwrap()].iter().map(|ev| (ev.events, ev.u64)).collect();
+    assert_eq!(events, expected);
+}
+
+fn test_sigprocmask() {
+    let set = sigset(&[libc::SIGUSR1, libc::SIGKILL]);
+    let mut old = sigset(&[libc::SIGUSR2]);
+    let res = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut old) };
+    assert_eq!(res, 0);
+    // Nothing was blocked before.
+    assert_eq!(unsafe { libc::sigismember(&old, libc::SIGUSR2) }, 0);
+
+    // `SIGKILL` can't be blocked.
+    let res = unsafe { libc::sigprocmask(libc::SIG_UNBLOCK, std::ptr::null(), &mut old) };
+    assert_eq!(res, 0);
+    assert_eq!(unsafe { libc::sigismember(&old, libc::SIGUSR1) }, 1);
+    assert_eq!(unsafe { libc::sigismember(&old, libc::SIGKILL) }, 0);
+
+    let res = unsafe { libc::sigprocmask(libc::SIG_UNBLOCK, &set, std::ptr::null_mut()) };
+    assert_eq!(res, 0);
+
+    // An invalid `how` is rejected.
+    let res = unsafe { libc::pthread_sigmask(-1, &set, std::ptr::null_mut()) };
+    assert_eq!(res, libc::EINVAL);
+}
+
+fn test_read() {
+    block(&[libc::SIGUSR1]);
+    let set = sigset(&[libc::SIGUSR1]);
+    let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) };
+    assert!(fd >= 0);
+
+    // Nothing is pending yet.
+    let err = read_signals(fd, 1).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+
+    // A buffer smaller than `signalfd_siginfo` is rejected.
+    let mut buf = [0u8; 8];
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, -1);
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+    // A signal that is sent twice before being read is only read once.
+    assert_eq!(unsafe { libc::kill(libc::getpid(), libc::SIGUSR1) }, 0);
+    assert_eq!(unsafe { libc::raise(libc::SIGUSR1) }, 0);
+    assert_eq!(read_signals(fd, 2).unwrap(), [libc::SIGUSR1 as u32]);
+    let err = read_signals(fd, 1).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_read_several() {
+    block(&[libc::SIGUSR2, libc::SIGTERM, libc::SIGHUP]);
+    let set = sigset(&[libc::SIGUSR2, libc::SIGTERM, libc::SIGHUP]);
+    let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK) };
+    assert!(fd >= 0);
+
+    assert_eq!(unsafe { libc::raise(libc::SIGTERM) }, 0);
+    assert_eq!(unsafe { libc::raise(libc::SIGUSR2) }, 0);
+    assert_eq!(unsafe { libc::raise(libc::SIGHUP) }, 0);
+
+    // Lower-numbered signals are read first, and only as many as fit in the buffer.
+    assert_eq!(read_signals(fd, 2).unwrap(), [libc::SIGHUP as u32, libc::SIGUSR2 as u32]);
+    assert_eq!(read_signals(fd, 2).unwrap(), [libc::SIGTERM as u32]);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_already_pending() {
+    block(&[libc::SIGALRM]);
+    assert_eq!(unsafe { libc::raise(libc::SIGALRM) }, 0);
+
+    // The signal was sent before the signalfd was created.
+    let set = sigset(&[libc::SIGALRM]);
+    let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK) };
+    assert!(fd >= 0);
+    assert_eq!(read_signals(fd, 1).unwrap(), [libc::SIGALRM as u32]);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_update_mask() {
+    block(&[libc::SIGWINCH, libc::SIGURG]);
+    let set = sigset(&[libc::SIGWINCH]);
+    let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK) };
+    assert!(fd >= 0);
+
+    // The signal stays pending until a signalfd that includes it reads it.
+    assert_eq!(unsafe { libc::raise(libc::SIGURG) }, 0);
+    let err = read_signals(fd, 1).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+
+    let set = sigset(&[libc::SIGURG]);
+    let res = unsafe { libc::signalfd(fd, &set, 0) };
+    assert_eq!(res, fd);
+    assert_eq!(read_signals(fd, 1).unwrap(), [libc::SIGURG as u32]);
+
+    // Only signalfds can be updated.
+    let efd = unsafe { libc::eventfd(0, 0) };
+    let res = unsafe { libc::signalfd(efd, &set, 0) };
+    assert_eq!(res, -1);
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+    assert_eq!(unsafe { libc::close(efd) }, 0);
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_epoll() {
+    block(&[libc::SIGCHLD]);
+    let set = sigset(&[libc::SIGCHLD]);
+    let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK) };
+    assert!(fd >= 0);
+
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let mut ev = libc::epoll_event { events: EPOLL_IN_ET, u64: fd.try_into().unwrap() };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev) };
+    assert_eq!(res, 0);
+
+    // Nothing is pending yet.
+    check_epoll_wait(epfd, &[]);
+
+    assert_eq!(unsafe { libc::raise(libc::SIGCHLD) }, 0);
+    check_epoll_wait(epfd, &[(libc::EPOLLIN as u32, fd.try_into().unwrap())]);
+
+    assert_eq!(read_signals(fd, 1).unwrap(), [libc::SIGCHLD as u32]);
+    check_epoll_wait(epfd, &[]);
+
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_blocking_read() {
+    block(&[libc::SIGPIPE]);
+    let set = sigset(&[libc::SIGPIPE]);
+    let fd = unsafe { libc::signalfd(-1, &set, 0) };
+    assert!(fd >= 0);
+
+    let reader = thread::spawn(move || {
+        // This blocks until the main thread sends the signal.
+        assert_eq!(read_signals(fd, 1).unwrap(), [libc::SIGPIPE as u32]);
+    });
+    thread::yield_now();
+    assert_eq!(unsafe { libc::kill(libc::getpid(), libc::SIGPIPE) }, 0);
+    reader.join().unwrap();
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
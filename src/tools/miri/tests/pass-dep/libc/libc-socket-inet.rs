@@ -0,0 +1,317 @@
+//@ignore-target: windows # No libc sockets on Windows
+// test_race depends on a deterministic schedule.
+//@compile-flags: -Zmiri-deterministic-concurrency
+
+// FIXME(static_mut_refs): Do not allow `static_mut_refs` lint
+#![allow(static_mut_refs)]
+
+use std::mem::{MaybeUninit, size_of};
+use std::{io, thread};
+
+#[path = "../../utils/libc.rs"]
+mod libc_utils;
+
+fn main() {
+    test_bind_ephemeral();
+    test_addr_in_use();
+    test_connection_refused();
+    test_accept_nonblocking();
+    test_stream();
+    test_shutdown();
+    test_peek();
+    test_race();
+    test_blocking_accept();
+    test_datagram();
+}
+
+fn socket(ty: libc::c_int) -> libc::c_int {
+    let fd = unsafe { libc::socket(libc::AF_INET, ty, 0) };
+    assert!(fd >= 0);
+    fd
+}
+
+fn loopback(port: u16) -> libc::sockaddr_in {
+    let mut addr: libc::sockaddr_in = unsafe { MaybeUninit::zeroed().assume_init() };
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_port = port.to_be();
+    addr.sin_addr.s_addr = u32::from_ne_bytes([127, 0, 0, 1]);
+    addr
+}
+
+fn bind(fd: libc::c_int, port: u16) -> libc::c_int {
+    let addr = loopback(port);
+    unsafe {
+        libc::bind(fd, (&raw const addr).cast(), size_of::<libc::sockaddr_in>() as libc::socklen_t)
+    }
+}
+
+fn connect(fd: libc::c_int, port: u16) -> libc::c_int {
+    let addr = loopback(port);
+    unsafe {
+        libc::connect(
+            fd,
+            (&raw const addr).cast(),
+            size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    }
+}
+
+fn local_port(fd: libc::c_int) -> u16 {
+    let mut addr = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res = unsafe { libc::getsockname(fd, addr.as_mut_ptr().cast(), &mut len) };
+    assert_eq!(res, 0);
+    assert_eq!(len as usize, size_of::<libc::sockaddr_in>());
+    let addr = unsafe { addr.assume_init() };
+    assert_eq!(addr.sin_family, libc::AF_INET as libc::sa_family_t);
+    assert_eq!(addr.sin_addr.s_addr.to_ne_bytes(), [127, 0, 0, 1]);
+    u16::from_be(addr.sin_port)
+}
+
+/// Returns a listening socket and its port.
+fn listener() -> (libc::c_int, u16) {
+    let fd = socket(libc::SOCK_STREAM);
+    assert_eq!(bind(fd, 0), 0);
+    assert_eq!(unsafe { libc::listen(fd, 16) }, 0);
+    (fd, local_port(fd))
+}
+
+/// Returns a connected pair of stream sockets.
+fn connected_pair() -> (libc::c_int, libc::c_int) {
+    let (listener, port) = listener();
+    let client = socket(libc::SOCK_STREAM);
+    assert_eq!(connect(client, port), 0);
+    let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert!(server >= 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+    (client, server)
+}
+
+fn test_bind_ephemeral() {
+    let fd = socket(libc::SOCK_STREAM);
+    assert_eq!(bind(fd, 0), 0);
+    assert_ne!(local_port(fd), 0);
+    // Binding twice is an error.
+    assert_eq!(bind(fd, 0), -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_addr_in_use() {
+    let (fd, port) = listener();
+    let other = socket(libc::SOCK_STREAM);
+    assert_eq!(bind(other, port), -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EADDRINUSE));
+    // Once the first socket is closed, the port is free again.
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(bind(other, port), 0);
+    assert_eq!(unsafe { libc::close(other) }, 0);
+}
+
+fn test_connection_refused() {
+    let (fd, port) = listener();
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    let client = socket(libc::SOCK_STREAM);
+    assert_eq!(connect(client, port), -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::ECONNREFUSED));
+    assert_eq!(unsafe { libc::close(client) }, 0);
+}
+
+fn test_accept_nonblocking() {
+    let (fd, _port) = listener();
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    assert_eq!(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) }, 0);
+    let res = unsafe { libc::accept(fd, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EAGAIN));
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_stream() {
+    let (listener, port) = listener();
+    let client = socket(libc::SOCK_STREAM);
+    assert_eq!(connect(client, port), 0);
+    let client_port = local_port(client);
+
+    // `accept` reports the address of the client.
+    let mut addr = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let server = unsafe { libc::accept(listener, addr.as_mut_ptr().cast(), &mut len) };
+    assert!(server >= 0);
+    let addr = unsafe { addr.assume_init() };
+    assert_eq!(u16::from_be(addr.sin_port), client_port);
+
+    // And `getpeername` reports the address of the server.
+    let mut addr = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    assert_eq!(unsafe { libc::getpeername(client, addr.as_mut_ptr().cast(), &mut len) }, 0);
+    let addr = unsafe { addr.assume_init() };
+    assert_eq!(u16::from_be(addr.sin_port), port);
+
+    // Data flows both ways, through both `send`/`recv` and `write`/`read`.
+    let data = b"abcde";
+    let res = unsafe { libc_utils::write_all(client, data.as_ptr().cast(), data.len()) };
+    assert_eq!(res, 5);
+    let mut buf = [0u8; 5];
+    let res = unsafe { libc_utils::read_all(server, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, 5);
+    assert_eq!(&buf, data);
+
+    let res = unsafe { libc::send(server, data.as_ptr().cast(), 1, libc::MSG_NOSIGNAL) };
+    assert_eq!(res, 1);
+    let res = unsafe { libc::recv(client, buf.as_mut_ptr().cast(), buf.len(), 0) };
+    assert_eq!(res, 1);
+    assert_eq!(buf[0], b'a');
+
+    // Once the server is closed, the client reads EOF and writes fail.
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    let res = unsafe { libc::read(client, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, 0);
+    let res = unsafe { libc::send(client, data.as_ptr().cast(), 1, libc::MSG_NOSIGNAL) };
+    assert_eq!(res, -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EPIPE));
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
+
+fn test_shutdown() {
+    let (client, server) = connected_pair();
+    let data = b"abc";
+    let res = unsafe { libc_utils::write_all(client, data.as_ptr().cast(), data.len()) };
+    assert_eq!(res, 3);
+    assert_eq!(unsafe { libc::shutdown(client, libc::SHUT_WR) }, 0);
+
+    // The server still gets the data that was sent before the shutdown, then EOF.
+    let mut buf = [0u8; 5];
+    let res = unsafe { libc_utils::read_all(server, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, 3);
+    assert_eq!(&buf[..3], data);
+
+    // Writing after the shutdown fails, but the other direction still works.
+    let res = unsafe { libc::send(client, data.as_ptr().cast(), 1, libc::MSG_NOSIGNAL) };
+    assert_eq!(res, -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EPIPE));
+    let res = unsafe { libc::write(server, data.as_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+    let res = unsafe { libc::read(client, buf.as_mut_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+}
+
+fn test_peek() {
+    let (client, server) = connected_pair();
+    let data = b"abc";
+    let res = unsafe { libc_utils::write_all(client, data.as_ptr().cast(), data.len()) };
+    assert_eq!(res, 3);
+
+    // Peeking does not remove the data.
+    let mut buf = [0u8; 3];
+    let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), 1, libc::MSG_PEEK) };
+    assert_eq!(res, 1);
+    assert_eq!(buf[0], b'a');
+    let res = unsafe { libc_utils::read_all(server, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, 3);
+    assert_eq!(&buf, data);
+
+    // Nothing is left, so a non-blocking receive fails.
+    let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), 1, libc::MSG_DONTWAIT) };
+    assert_eq!(res, -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EAGAIN));
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+}
+
+/// Sending data synchronizes the sender with the receiver, so there is no data race here.
+fn test_race() {
+    static mut VAL: u8 = 0;
+    let (client, server) = connected_pair();
+    let thread = thread::spawn(move || {
+        let mut buf = [0u8; 1];
+        // Blocks until the main thread sends something.
+        let res = unsafe { libc::read(server, buf.as_mut_ptr().cast(), 1) };
+        assert_eq!(res, 1);
+        // This read is after the write in the main thread.
+        assert_eq!(unsafe { VAL }, 1);
+        server
+    });
+    unsafe { VAL = 1 };
+    let data = b"a";
+    let res = unsafe { libc::write(client, data.as_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+    let server = thread.join().unwrap();
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+}
+
+fn test_blocking_accept() {
+    let (listener, port) = listener();
+    let thread = thread::spawn(move || {
+        // Blocks until the main thread connects.
+        let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+        assert!(server >= 0);
+        let mut buf = [0u8; 2];
+        let res = unsafe { libc_utils::read_all(server, buf.as_mut_ptr().cast(), buf.len()) };
+        assert_eq!(res, 2);
+        assert_eq!(&buf, b"hi");
+        assert_eq!(unsafe { libc::close(server) }, 0);
+    });
+    thread::yield_now();
+    let client = socket(libc::SOCK_STREAM);
+    assert_eq!(connect(client, port), 0);
+    let res = unsafe { libc_utils::write_all(client, b"hi".as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    thread.join().unwrap();
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
+
+fn test_datagram() {
+    let a = socket(libc::SOCK_DGRAM);
+    let b = socket(libc::SOCK_DGRAM);
+    assert_eq!(bind(b, 0), 0);
+    let b_port = local_port(b);
+
+    // Sending from an unbound socket binds it.
+    let dest = loopback(b_port);
+    let res = unsafe {
+        libc::sendto(
+            a,
+            b"hello".as_ptr().cast(),
+            5,
+            0,
+            (&raw const dest).cast(),
+            size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    };
+    assert_eq!(res, 5);
+    let a_port = local_port(a);
+    assert_ne!(a_port, 0);
+
+    let mut buf = [0u8; 8];
+    let mut from = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res = unsafe {
+        libc::recvfrom(b, buf.as_mut_ptr().cast(), buf.len(), 0, from.as_mut_ptr().cast(), &mut len)
+    };
+    assert_eq!(res, 5);
+    assert_eq!(&buf[..5], b"hello");
+    let from = unsafe { from.assume_init() };
+    assert_eq!(u16::from_be(from.sin_port), a_port);
+
+    // Without a destination, sending fails.
+    let res = unsafe { libc::send(b, b"x".as_ptr().cast(), 1, 0) };
+    assert_eq!(res, -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EDESTADDRREQ));
+
+    // Nothing left to receive.
+    let res = unsafe { libc::recv(b, buf.as_mut_ptr().cast(), buf.len(), libc::MSG_DONTWAIT) };
+    assert_eq!(res, -1);
+    assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::EAGAIN));
+
+    assert_eq!(unsafe { libc::close(a) }, 0);
+    assert_eq!(unsafe { libc::close(b) }, 0);
+}
@@ -0,0 +1,220 @@
+//@only-target: linux android
+// test_settime_wakes_epoll depends on a deterministic schedule.
+//@compile-flags: -Zmiri-deterministic-concurrency
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn main() {
+    test_disarmed();
+    test_one_shot();
+    test_periodic();
+    test_gettime();
+    test_abstime();
+    test_epoll();
+    test_epoll_timeout_before_timer();
+    test_settime_wakes_epoll();
+}
+
+// Using `as` cast since `EPOLLET` wraps around
+const EPOLL_IN_ET: u32 = (libc::EPOLLIN | libc::EPOLLET) as _;
+
+fn timespec(duration: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: duration.as_secs().try_into().unwrap(),
+        tv_nsec: duration.subsec_nanos().into(),
+    }
+}
+
+fn itimerspec(value: Duration, interval: Duration) -> libc::itimerspec {
+    libc::itimerspec { it_interval: timespec(interval), it_value: timespec(value) }
+}
+
+fn settime(fd: i32, flags: i32, value: Duration, interval: Duration) {
+    let new_value = itimerspec(value, interval);
+    let res = unsafe { libc::timerfd_settime(fd, flags, &new_value, std::ptr::null_mut()) };
+    assert_eq!(res, 0);
+}
+
+fn read_expirations(fd: i32) -> Result<u64, std::io::Error> {
+    let mut buf = [0u8; 8];
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    assert_eq!(res, 8);
+    Ok(u64::from_ne_bytes(buf))
+}
+
+fn check_epoll_wait(epfd: i32, expected: &[(u32, u64)], timeout: i32) {
+    let mut array = [libc::epoll_event { events: 0, u64: 0 }; 4];
+    let res = unsafe { libc::epoll_wait(epfd, array.as_mut_ptr(), 4, timeout) };
+    assert!(res >= 0, "epoll_wait failed: {}", std::io::Error::last_os_error());
+    let events: Vec<_> =
+        array[..res.try_into().unwrap()].iter().map(|ev| (ev.events, ev.u64)).collect();
+    assert_eq!(events, expected);
+}
+
+fn test_disarmed() {
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) };
+    assert!(fd >= 0);
+    // A timer that was never armed has nothing to read.
+    let err = read_expirations(fd).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+    // The buffer must fit a `u64`.
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, -1);
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_one_shot() {
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_CLOEXEC) };
+    assert!(fd >= 0);
+    let before = Instant::now();
+    settime(fd, 0, Duration::from_millis(50), Duration::ZERO);
+    // The read blocks until the timer expires.
+    assert_eq!(read_expirations(fd).unwrap(), 1);
+    assert!(before.elapsed() >= Duration::from_millis(50));
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_periodic() {
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) };
+    assert!(fd >= 0);
+    settime(fd, 0, Duration::from_millis(10), Duration::from_millis(10));
+    thread::sleep(Duration::from_millis(35));
+    // All expirations since the timer was armed are reported at once.
+    assert!(read_expirations(fd).unwrap() >= 3);
+    // And then the count starts over.
+    let err = read_expirations(fd).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+    // Disarm the timer.
+    settime(fd, 0, Duration::ZERO, Duration::ZERO);
+    thread::sleep(Duration::from_millis(20));
+    let err = read_expirations(fd).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_gettime() {
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0) };
+    assert!(fd >= 0);
+    settime(fd, 0, Duration::from_secs(10), Duration::from_secs(1));
+
+    let mut curr = itimerspec(Duration::ZERO, Duration::ZERO);
+    assert_eq!(unsafe { libc::timerfd_gettime(fd, &mut curr) }, 0);
+    assert_eq!(curr.it_interval.tv_sec, 1);
+    assert_eq!(curr.it_interval.tv_nsec, 0);
+    assert!(curr.it_value.tv_sec <= 10);
+    assert!(curr.it_value.tv_sec > 0);
+
+    // Re-arming reports the old setting.
+    let new_value = itimerspec(Duration::ZERO, Duration::ZERO);
+    let mut old_value = itimerspec(Duration::ZERO, Duration::ZERO);
+    assert_eq!(unsafe { libc::timerfd_settime(fd, 0, &new_value, &mut old_value) }, 0);
+    assert_eq!(old_value.it_interval.tv_sec, 1);
+    assert!(old_value.it_value.tv_sec > 0);
+
+    // Now it is disarmed.
+    assert_eq!(unsafe { libc::timerfd_gettime(fd, &mut curr) }, 0);
+    assert_eq!((curr.it_value.tv_sec, curr.it_value.tv_nsec), (0, 0));
+
+    // Invalid timespecs are rejected.
+    let mut bad = itimerspec(Duration::ZERO, Duration::ZERO);
+    bad.it_value.tv_nsec = 1_000_000_000;
+    let res = unsafe { libc::timerfd_settime(fd, 0, &bad, std::ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_abstime() {
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) };
+    assert!(fd >= 0);
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    assert_eq!(unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) }, 0);
+    let now = Duration::new(now.tv_sec.try_into().unwrap(), now.tv_nsec.try_into().unwrap());
+
+    // A deadline in the past expires right away.
+    settime(fd, libc::TFD_TIMER_ABSTIME, Duration::from_nanos(1), Duration::ZERO);
+    assert_eq!(read_expirations(fd).unwrap(), 1);
+
+    // A deadline in the future does not.
+    settime(fd, libc::TFD_TIMER_ABSTIME, now + Duration::from_millis(20), Duration::ZERO);
+    let err = read_expirations(fd).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(read_expirations(fd).unwrap(), 1);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_epoll() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) };
+    assert!(fd >= 0);
+    let mut ev = libc::epoll_event { events: EPOLL_IN_ET, u64: fd as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev) };
+    assert_eq!(res, 0);
+
+    // Nothing is ready before the timer expires.
+    check_epoll_wait(epfd, &[], 0);
+
+    // Blocking without a timeout returns once the timer expires.
+    settime(fd, 0, Duration::from_millis(10), Duration::from_millis(10));
+    let expected = (u32::try_from(libc::EPOLLIN).unwrap(), fd as u64);
+    check_epoll_wait(epfd, &[expected], -1);
+    assert_eq!(read_expirations(fd).unwrap(), 1);
+
+    // The next period produces another event.
+    check_epoll_wait(epfd, &[expected], -1);
+    assert!(read_expirations(fd).unwrap() >= 1);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_epoll_timeout_before_timer() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) };
+    assert!(fd >= 0);
+    let mut ev = libc::epoll_event { events: EPOLL_IN_ET, u64: fd as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev) };
+    assert_eq!(res, 0);
+
+    // The epoll timeout is shorter than the timer, so we get nothing.
+    settime(fd, 0, Duration::from_millis(100), Duration::ZERO);
+    check_epoll_wait(epfd, &[], 10);
+    // With a longer timeout, we see the timer.
+    let expected = (u32::try_from(libc::EPOLLIN).unwrap(), fd as u64);
+    check_epoll_wait(epfd, &[expected], 1000);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_settime_wakes_epoll() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_NONBLOCK) };
+    assert!(fd >= 0);
+    let mut ev = libc::epoll_event { events: EPOLL_IN_ET, u64: fd as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev) };
+    assert_eq!(res, 0);
+
+    // The timer is only armed after the main thread started waiting.
+    let thread = thread::spawn(move || {
+        settime(fd, 0, Duration::from_millis(10), Duration::ZERO);
+    });
+    let expected = (u32::try_from(libc::EPOLLIN).unwrap(), fd as u64);
+    check_epoll_wait(epfd, &[expected], -1);
+    thread.join().unwrap();
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
@@ -0,0 +1,23 @@
+//@only-target: linux # the ids of the locks depend on the locks the runtime creates
+//@compile-flags: -Zmiri-lock-order-check
+
+// Locking two mutexes in both orders is reported, even though this program has a single thread and
+// thus never deadlocks.
+fn main() {
+    let mut a = libc::PTHREAD_MUTEX_INITIALIZER;
+    let mut b = libc::PTHREAD_MUTEX_INITIALIZER;
+    unsafe {
+        lock_both(&raw mut a, &raw mut b);
+        lock_both(&raw mut a, &raw mut b);
+        lock_both(&raw mut b, &raw mut a);
+    }
+}
+
+unsafe fn lock_both(first: *mut libc::pthread_mutex_t, second: *mut libc::pthread_mutex_t) {
+    unsafe {
+        libc::pthread_mutex_lock(first);
+        libc::pthread_mutex_lock(second);
+        libc::pthread_mutex_unlock(second);
+        libc::pthread_mutex_unlock(first);
+    }
+}
@@ -0,0 +1,19 @@
+//@only-target: linux # the ids of the locks depend on the locks the runtime creates
+//@compile-flags: -Zmiri-lock-order-check
+
+use std::sync::{Mutex, RwLock};
+
+// Locking two standard library locks in both orders is reported, even though this program has a
+// single thread and thus never deadlocks.
+fn main() {
+    let a = Mutex::new(0);
+    let b = RwLock::new(0);
+    {
+        let _a = a.lock().unwrap();
+        let _b = b.write().unwrap();
+    }
+    {
+        let _b = b.read().unwrap();
+        let _a = a.lock().unwrap();
+    }
+}
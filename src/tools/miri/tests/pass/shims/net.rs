@@ -0,0 +1,83 @@
+//@ignore-target: windows # No socket support on Windows
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+
+fn main() {
+    test_tcp();
+    test_tcp_refused();
+    test_udp();
+}
+
+fn test_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert!(addr.ip().is_loopback());
+    assert_ne!(addr.port(), 0);
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        stream.set_nodelay(true).unwrap();
+        assert!(stream.nodelay().unwrap());
+        stream.write_all(b"ping").unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "pong");
+    });
+
+    let (mut stream, peer) = listener.accept().unwrap();
+    assert_eq!(stream.local_addr().unwrap(), addr);
+    assert_eq!(stream.peer_addr().unwrap(), peer);
+    let mut buf = [0; 4];
+    let len = stream.peek(&mut buf).unwrap();
+    assert!(len > 0);
+    assert_eq!(buf[..len], b"ping"[..len]);
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"ping");
+    stream.write_all(b"pong").unwrap();
+    drop(stream);
+
+    client.join().unwrap();
+}
+
+fn test_tcp_refused() {
+    // Find a port that nobody listens on.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let err = TcpStream::connect(addr).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+}
+
+fn test_udp() {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let a_addr = a.local_addr().unwrap();
+    let b_addr = b.local_addr().unwrap();
+    assert_ne!(a_addr, b_addr);
+
+    assert_eq!(a.send_to(b"hello", b_addr).unwrap(), 5);
+    let mut buf = [0; 16];
+    let (len, from) = b.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"hello");
+    assert_eq!(from, a_addr);
+
+    // Datagrams that do not fit are truncated.
+    b.send_to(b"world", a_addr).unwrap();
+    let mut small = [0; 3];
+    assert_eq!(a.recv(&mut small).unwrap(), 3);
+    assert_eq!(&small, b"wor");
+
+    // A connected socket only receives datagrams from its peer.
+    let c = UdpSocket::bind("127.0.0.1:0").unwrap();
+    a.connect(b_addr).unwrap();
+    c.send_to(b"ignored", a_addr).unwrap();
+    b.send(b"x").unwrap_err();
+    b.send_to(b"from b", a_addr).unwrap();
+    let len = a.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"from b");
+}
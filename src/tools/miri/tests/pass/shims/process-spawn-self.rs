@@ -0,0 +1,46 @@
+//@ignore-target: windows # No process support on Windows
+//@compile-flags: -Zmiri-spawn-self -Zmiri-disable-isolation
+
+use std::process::{Command, Stdio};
+use std::{env, process};
+
+fn main() {
+    match env::var("MIRI_CHILD").as_deref() {
+        Ok("output") => {
+            println!("hello from the child");
+            eprintln!("{}", env::args().nth(1).unwrap());
+        }
+        Ok("exit") => process::exit(42),
+        Ok("panic") => panic!("child panic"),
+        Ok("inherit") => println!("inherited stdout"),
+        Ok(mode) => unreachable!("unknown child mode {mode}"),
+        Err(_) => parent(),
+    }
+}
+
+fn child(mode: &str) -> Command {
+    let mut cmd = Command::new(env::current_exe().unwrap());
+    cmd.env("MIRI_CHILD", mode);
+    cmd
+}
+
+fn parent() {
+    let output = child("output").arg("an argument").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello from the child\n");
+    assert_eq!(output.stderr, b"an argument\n");
+
+    let output = child("exit").output().unwrap();
+    assert_eq!(output.status.code(), Some(42));
+    assert!(output.stdout.is_empty());
+
+    let output = child("panic").output().unwrap();
+    assert_eq!(output.status.code(), Some(101));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("child panic"));
+
+    let status = child("inherit").stdin(Stdio::null()).status().unwrap();
+    assert!(status.success());
+
+    let mut handle = child("exit").spawn().unwrap();
+    assert_eq!(handle.wait().unwrap().code(), Some(42));
+}
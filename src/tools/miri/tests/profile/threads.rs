@@ -0,0 +1,21 @@
+//! Run by `profile` in `tests/ui.rs` with `-Zmiri-profile`, once for each output format.
+
+use std::thread;
+
+#[inline(never)]
+fn worker_loop() -> u32 {
+    (0..100).sum()
+}
+
+#[inline(never)]
+fn main_loop() -> u32 {
+    (0..100).sum()
+}
+
+fn main() {
+    let worker = thread::Builder::new().name("worker".into()).spawn(worker_loop).unwrap();
+    assert_eq!(worker.join().unwrap(), 4950);
+    assert_eq!(main_loop(), 4950);
+    // Exit while `main` is still running, so its call has to be ended when the profile is written.
+    std::process::exit(0);
+}
@@ -0,0 +1,12 @@
+//! Run by `record_replay` in `tests/ui.rs` with isolation disabled. Prints the contents of the file
+//! named by `MIRI_TEST_FILE`, followed by everything on stdin.
+
+use std::io::Read;
+use std::{env, fs, io};
+
+fn main() {
+    let contents = fs::read_to_string(env::var("MIRI_TEST_FILE").unwrap()).unwrap();
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).unwrap();
+    print!("{contents}|{input}");
+}
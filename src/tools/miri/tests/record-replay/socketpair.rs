@@ -0,0 +1,19 @@
+//! Run by `record_replay` in `tests/ui.rs`, which records an execution and then replays it.
+
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+fn main() {
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+    let reader = thread::spawn(move || {
+        let mut buf = [0; 5];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+        // Everything was read, so there is nothing left, when replaying as well.
+        b.set_nonblocking(true).unwrap();
+        assert_eq!(b.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+    });
+    a.write_all(b"hello").unwrap();
+    reader.join().unwrap();
+}
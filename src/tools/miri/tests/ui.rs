@@ -203,6 +203,13 @@ fn run_tests(
         config.program.args.push(flag);
     }
 
+    // The errors are reported as JSON, which `ui_test` does not recognize as diagnostics, so there
+    // is nothing to annotate; the stderr snapshots cover the reports instead.
+    if path == "tests/json-report" {
+        config.program.args.push("-Zmiri-report-format=json".into());
+        config.comment_defaults.base().require_annotations = Spanned::dummy(false).into();
+    }
+
     eprintln!("   Compiler: {}", config.program.display());
     ui_test::run_tests_generic(
         // Only run one test suite. In the future we can add all test suites to one `Vec` and run
@@ -332,6 +339,7 @@ fn main() -> Result<()> {
     ui(Mode::Panic, "tests/panic", &target, WithDependencies, tmpdir.path())?;
     ui(Mode::Fail, "tests/fail", &target, WithoutDependencies, tmpdir.path())?;
     ui(Mode::Fail, "tests/fail-dep", &target, WithDependencies, tmpdir.path())?;
+    ui(Mode::Fail, "tests/json-report", &target, WithoutDependencies, tmpdir.path())?;
     if cfg!(all(unix, feature = "native-lib")) && target == host {
         ui(Mode::Pass, "tests/native-lib/pass", &target, WithoutDependencies, tmpdir.path())?;
         ui(Mode::Fail, "tests/native-lib/fail", &target, WithoutDependencies, tmpdir.path())?;
@@ -351,9 +359,260 @@ fn main() -> Result<()> {
         ui(Mode::Fail, "tests/genmc/fail", &target, WithDependencies, tmpdir.path())?;
     }
 
+    if target == host {
+        profile(&target, tmpdir.path())?;
+    }
+
+    // These tests use Unix sockets, both in Miri and in the interpreted program.
+    #[cfg(unix)]
+    if target == host {
+        record_replay(&target, tmpdir.path())?;
+        gdbserver::run(&target, tmpdir.path())?;
+    }
+
     Ok(())
 }
 
+/// Returns a command that runs `file` in Miri, outside of `ui_test`.
+fn miri_command(target: &str, file: &str) -> Result<Command> {
+    let mut cmd = Command::new(miri_path());
+    cmd.arg(format!("--sysroot={}", env::var("MIRI_SYSROOT")?));
+    cmd.args(["--target", target, "--edition=2021", file]);
+    Ok(cmd)
+}
+
+/// Profiles a program with `-Zmiri-profile` in both output formats, and checks that the calls of
+/// every thread are attributed to that thread, including the calls still running at exit.
+fn profile(target: &str, tmpdir: &Path) -> Result<()> {
+    use serde_json::Value;
+    use ui_test::color_eyre::eyre::{OptionExt, ensure};
+
+    eprintln!("{}", format!("## Running profile test for {target}").green().bold());
+    let run = |file: &Path| -> Result<String> {
+        let status = miri_command(target, "tests/profile/threads.rs")?
+            .arg(format!("-Zmiri-profile={}", file.display()))
+            .status()?;
+        ensure!(status.success(), "Miri failed: {status}");
+        Ok(std::fs::read_to_string(file)?)
+    };
+
+    // Folded stacks: one `frame;frame;frame steps` line per call stack, rooted at the thread.
+    let folded = run(&tmpdir.join("profile.folded"))?;
+    for line in folded.lines() {
+        let steps = line.rsplit_once(' ').and_then(|(_, steps)| steps.parse::<u64>().ok());
+        ensure!(steps.is_some_and(|steps| steps > 0), "invalid folded stack: {line}");
+    }
+    for (thread, function) in [("main", "main_loop"), ("worker", "worker_loop")] {
+        ensure!(
+            folded.lines().any(|line| {
+                line.starts_with(&format!("[thread {thread}];")) && line.contains(function)
+            }),
+            "`{function}` is not attributed to thread `{thread}`:\n{folded}"
+        );
+    }
+
+    // Chrome trace: the threads are named by metadata events, and every call is an `X` event.
+    let trace = run(&tmpdir.join("profile.json"))?;
+    let events: Vec<Value> = serde_json::from_str(&trace)?;
+    let tid = |thread: &str| {
+        events
+            .iter()
+            .find(|event| event["ph"] == "M" && event["args"]["name"] == thread)
+            .and_then(|event| event["tid"].as_u64())
+            .ok_or_eyre(format!("thread `{thread}` is not named in the trace"))
+    };
+    let call = |name: &str, tid: u64| {
+        events
+            .iter()
+            .find(|event| event["ph"] == "X" && event["name"] == name && event["tid"] == tid)
+            .and_then(|event| Some((event["ts"].as_u64()?, event["dur"].as_u64()?)))
+            .ok_or_eyre(format!("no call of `{name}` on thread {tid} in the trace"))
+    };
+    let (main, worker) = (tid("main")?, tid("worker")?);
+    ensure!(main != worker, "both threads have the same id {main}");
+    call("worker_loop", worker)?;
+    let (loop_start, loop_dur) = call("main_loop", main)?;
+    // `main` was still running when the program exited.
+    let (main_start, main_dur) = call("main", main)?;
+    ensure!(
+        main_start <= loop_start && loop_start + loop_dur <= main_start + main_dur,
+        "the call of `main` does not contain the call of `main_loop`"
+    );
+    Ok(())
+}
+
+/// Records executions with `-Zmiri-record` and checks that they replay without diverging, that
+/// replays reproduce what the program read from the host, and that replaying from a log that does
+/// not match the execution is reported.
+#[cfg(unix)]
+fn record_replay(target: &str, tmpdir: &Path) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Output, Stdio};
+
+    use ui_test::color_eyre::eyre::ensure;
+
+    eprintln!("{}", format!("## Running record-replay test for {target}").green().bold());
+
+    // With isolation, only Miri's own choices are recorded.
+    let log = tmpdir.join("socketpair.record");
+    for flag in ["-Zmiri-record", "-Zmiri-replay"] {
+        let status = miri_command(target, "tests/record-replay/socketpair.rs")?
+            .arg(format!("{flag}={}", log.display()))
+            .status()?;
+        ensure!(status.success(), "Miri failed with {flag}: {status}");
+    }
+
+    // Without isolation, reads from host files and stdin are recorded, and replayed without
+    // looking at the host again.
+    let file = tmpdir.join("read_file.txt");
+    let log = tmpdir.join("read_file.record");
+    let run = |flag: &str, log: &Path, stdin: &str| -> Result<Output> {
+        let mut child = miri_command(target, "tests/record-replay/read_file.rs")?
+            .arg("-Zmiri-disable-isolation")
+            .arg(format!("{flag}={}", log.display()))
+            .env("MIRI_TEST_FILE", &file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(stdin.as_bytes())?;
+        Ok(child.wait_with_output()?)
+    };
+    std::fs::write(&file, "recorded file")?;
+    let output = run("-Zmiri-record", &log, "recorded stdin")?;
+    ensure!(output.status.success(), "Miri failed with -Zmiri-record: {output:?}");
+    ensure!(output.stdout == b"recorded file|recorded stdin", "unexpected output: {output:?}");
+    std::fs::write(&file, "the file changed since the recording")?;
+    let output = run("-Zmiri-replay", &log, "other stdin")?;
+    ensure!(output.status.success(), "Miri failed with -Zmiri-replay: {output:?}");
+    ensure!(
+        output.stdout == b"recorded file|recorded stdin",
+        "the replay did not reproduce the recorded reads: {output:?}"
+    );
+
+    // A log that ends before the first read diverges from the execution.
+    let recording = std::fs::read_to_string(&log)?;
+    let first_read = recording.find("\nread ").map(|i| i + 1);
+    ensure!(first_read.is_some(), "the recording does not contain any reads:\n{recording}");
+    let truncated = tmpdir.join("read_file.truncated.record");
+    std::fs::write(&truncated, &recording[..first_read.unwrap()])?;
+    let output = run("-Zmiri-replay", &truncated, "recorded stdin")?;
+    ensure!(!output.status.success(), "Miri accepted a truncated recording: {output:?}");
+    ensure!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("the execution diverged from the recording"),
+        "Miri did not report the divergence: {output:?}"
+    );
+    Ok(())
+}
+
+/// Drives `-Zmiri-gdbserver` over a Unix socket, the way a debugger would.
+#[cfg(unix)]
+mod gdbserver {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use colored::*;
+    use ui_test::color_eyre::eyre::{Result, bail, ensure};
+
+    use super::miri_command;
+
+    /// A minimal client for the GDB remote serial protocol.
+    struct Client {
+        stream: UnixStream,
+        buf: Vec<u8>,
+    }
+
+    impl Client {
+        fn send(&mut self, data: &str) -> Result<()> {
+            let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+            write!(self.stream, "${data}#{checksum:02x}")?;
+            Ok(())
+        }
+
+        /// Returns the data of the next packet, skipping acknowledgments.
+        fn recv(&mut self) -> Result<String> {
+            loop {
+                if let Some(start) = self.buf.iter().position(|&b| b == b'$')
+                    && let Some(end) = self.buf[start..].iter().position(|&b| b == b'#')
+                    && self.buf.len() >= start + end + 3
+                {
+                    let data = String::from_utf8(self.buf[start + 1..start + end].to_vec())?;
+                    self.buf.drain(..start + end + 3);
+                    self.stream.write_all(b"+")?;
+                    return Ok(data);
+                }
+                let mut chunk = [0; 4096];
+                let n = self.stream.read(&mut chunk)?;
+                ensure!(n > 0, "Miri closed the connection");
+                self.buf.extend_from_slice(&chunk[..n]);
+            }
+        }
+
+        fn request(&mut self, data: &str) -> Result<String> {
+            self.send(data)?;
+            self.recv()
+        }
+    }
+
+    fn hex(s: &str) -> String {
+        s.bytes().map(|b| format!("{b:02x}")).collect()
+    }
+
+    pub fn run(target: &str, tmpdir: &Path) -> Result<()> {
+        eprintln!("{}", format!("## Running gdbserver test for {target}").green().bold());
+        let socket = tmpdir.join("gdbserver.sock");
+        let mut miri = miri_command(target, "tests/gdbserver/breakpoint.rs")?
+            .arg(format!("-Zmiri-gdbserver={}", socket.display()))
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        // Miri only listens once the program is ready to run.
+        let deadline = Instant::now() + Duration::from_secs(120);
+        let stream = loop {
+            match UnixStream::connect(&socket) {
+                Ok(stream) => break stream,
+                Err(_) if Instant::now() < deadline && miri.try_wait()?.is_none() =>
+                    thread::sleep(Duration::from_millis(100)),
+                Err(err) => {
+                    let _ = miri.kill();
+                    bail!("could not connect to Miri's gdb server: {err}");
+                }
+            }
+        };
+        stream.set_read_timeout(Some(Duration::from_secs(120)))?;
+        let mut client = Client { stream, buf: Vec::new() };
+
+        // The program is stopped before its first step.
+        let stop = client.request("?")?;
+        ensure!(stop.starts_with("T05"), "unexpected initial stop: {stop}");
+        let reply = client.request(&format!("qRcmd,{}", hex("break answer")))?;
+        ensure!(reply == hex("Breakpoint 1 on `answer`\n"), "unexpected reply: {reply}");
+
+        // Continuing runs until the breakpoint is hit, once for each call...
+        for _ in 0..2 {
+            let console = client.request("c")?;
+            ensure!(
+                console.starts_with(&format!("O{}", hex("Breakpoint 1, "))),
+                "unexpected console output: {console}"
+            );
+            let stop = client.recv()?;
+            ensure!(stop.starts_with("T05"), "unexpected stop at the breakpoint: {stop}");
+        }
+        // ... and then until the program exits.
+        let exit = client.request("c")?;
+        ensure!(exit == "W00", "unexpected exit: {exit}");
+
+        let status = miri.wait()?;
+        ensure!(status.success(), "Miri failed: {status}");
+        Ok(())
+    }
+}
+
 fn run_dep_mode(target: String, args: impl Iterator<Item = OsString>) -> Result<()> {
     let mut config =
         miri_config(&target, "", Mode::RunDep, Some(WithDependencies { bless: false }));
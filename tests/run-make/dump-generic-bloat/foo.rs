@@ -0,0 +1,14 @@
+use std::ops::Add;
+
+#[inline(never)]
+fn double<T: Copy + Add<Output = T>>(x: T) -> T {
+    x + x
+}
+
+fn quadruple<T: Copy + Add<Output = T>>(x: T) -> T {
+    double(double(x))
+}
+
+pub fn entry(a: u32, b: u64) -> u64 {
+    quadruple(a) as u64 + double(b)
+}
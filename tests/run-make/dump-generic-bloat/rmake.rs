@@ -0,0 +1,51 @@
+//@ needs-target-std
+//
+// `-Zdump-generic-bloat` should report the instantiations of each generic function, with their
+// MIR and LLVM IR sizes and the callers that caused them.
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{cwd, rfs, rustc};
+
+fn main() {
+    rustc()
+        .crate_type("lib")
+        .input("foo.rs")
+        .arg(format!("-Zdump-generic-bloat={}", cwd().display()))
+        .run();
+    let report: Value =
+        serde_json::from_str(&rfs::read_to_string("foo.generic_bloat.json")).unwrap();
+    let definitions = report.as_array().unwrap();
+    let find = |name: &str| {
+        definitions
+            .iter()
+            .find(|def| def["name"] == name)
+            .unwrap_or_else(|| panic!("no definition `{name}` in {report:#}"))
+    };
+
+    let double = find("double");
+    assert_eq!(double["instantiation_count"], 2);
+    let instantiations = double["instantiations"].as_array().unwrap();
+    let find_instantiation = |name: &str| {
+        instantiations
+            .iter()
+            .find(|inst| inst["name"] == name)
+            .unwrap_or_else(|| panic!("no instantiation `{name}` in {double:#}"))
+    };
+    let double_u32 = find_instantiation("fn double::<u32>");
+    assert_eq!(double_u32["callers"], serde_json::json!(["fn quadruple::<u32>", "fn entry"]));
+    let double_u64 = find_instantiation("fn double::<u64>");
+    assert_eq!(double_u64["callers"], serde_json::json!(["fn entry"]));
+
+    let mut total_instructions = 0;
+    for inst in instantiations {
+        assert!(inst["mir_statements"].as_u64().unwrap() > 0, "{inst:#}");
+        let instructions = inst["llvm_instructions"].as_u64().unwrap();
+        assert!(instructions > 0, "{inst:#}");
+        total_instructions += instructions;
+    }
+    assert_eq!(double["llvm_instructions"], total_instructions);
+
+    assert_eq!(find("quadruple")["instantiation_count"], 1);
+    // Non-generic functions are not reported.
+    assert!(definitions.iter().all(|def| def["name"] != "entry"));
+}
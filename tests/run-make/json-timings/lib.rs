@@ -0,0 +1,18 @@
+#[macro_export]
+macro_rules! square {
+    ($x:expr) => {
+        $x * $x
+    };
+}
+
+pub fn area(side: u32) -> u32 {
+    square!(side)
+}
+
+pub fn generic<T: Clone>(value: &T) -> (T, T) {
+    (value.clone(), value.clone())
+}
+
+pub fn instantiate() -> (u64, u64) {
+    generic(&area(3).into())
+}
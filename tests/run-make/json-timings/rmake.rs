@@ -0,0 +1,63 @@
+//@ needs-target-std
+//
+// `--json=timings` should report properly nested sections for every phase of the compiler,
+// including the optimization of each codegen unit by the backend.
+
+extern crate run_make_support;
+
+use run_make_support::rustc;
+use run_make_support::serde_json::{self, Value};
+
+fn main() {
+    let output = rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .codegen_units(2)
+        .json("timings")
+        .error_format("json")
+        .arg("-Zunstable-options")
+        .run();
+
+    let mut stack: Vec<String> = vec![];
+    let mut backend_opt: Vec<String> = vec![];
+    let mut seen = vec![];
+    for line in output.stderr_utf8().lines() {
+        let message: Value = serde_json::from_str(line).unwrap();
+        if message["$message_type"] != "section_timing" {
+            continue;
+        }
+        let name = message["name"].as_str().unwrap().to_owned();
+        let event = message["event"].as_str().unwrap();
+        if name == "backend-opt" {
+            // The codegen units are optimized in parallel, so only check that they happen
+            // during codegen.
+            assert!(stack.iter().any(|section| section == "codegen"), "{line}");
+            let cgu = message["codegen_unit"].as_str().unwrap().to_owned();
+            match event {
+                "start" => backend_opt.push(cgu),
+                _ => backend_opt.retain(|started| *started != cgu),
+            }
+        } else {
+            match event {
+                "start" => stack.push(name.clone()),
+                _ => assert_eq!(stack.pop().as_ref(), Some(&name), "{line}"),
+            }
+        }
+        seen.push(name);
+    }
+    assert!(stack.is_empty() && backend_opt.is_empty(), "unfinished sections: {stack:?}");
+
+    for section in [
+        "parse",
+        "expand",
+        "resolve",
+        "typeck",
+        "borrowck",
+        "mir-opt",
+        "mono-collect",
+        "codegen",
+        "backend-opt",
+    ] {
+        assert!(seen.iter().any(|name| name == section), "missing section `{section}`");
+    }
+}
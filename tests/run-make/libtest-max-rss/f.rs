@@ -0,0 +1,9 @@
+#[test]
+fn big() {
+    // Every byte is written, so that all the pages are resident.
+    let v = vec![1u8; 256 * 1024 * 1024];
+    std::hint::black_box(&v);
+}
+
+#[test]
+fn small() {}
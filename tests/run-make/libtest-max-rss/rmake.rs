@@ -0,0 +1,37 @@
+// Tests that run in a subprocess and use more memory than `--max-rss` allows fail.
+
+//@ ignore-cross-compile
+// Reason: the compiled code is ran
+//@ only-linux
+// Reason: resource usage is only measured on Linux, macOS and the BSDs
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{cmd, rustc};
+
+fn main() {
+    // Tests run in a subprocess with the `abort` panic strategy.
+    rustc().arg("--test").arg("-Cpanic=abort").arg("-Zpanic-abort-tests").input("f.rs").run();
+
+    let output = cmd("./f")
+        .args(&["-Zunstable-options", "--format=json", "--test-threads=1", "--max-rss=64"])
+        .run_fail()
+        .stdout_utf8();
+    let events: Vec<Value> =
+        output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let result = |name: &str| {
+        events
+            .iter()
+            .find(|event| {
+                event["type"] == "test" && event["name"] == name && event["event"] != "started"
+            })
+            .unwrap_or_else(|| panic!("no result for `{name}` in:\n{output}"))
+    };
+
+    let big = result("big");
+    assert_eq!(big["event"], "failed", "{big}");
+    let message = big["message"].as_str().unwrap();
+    assert!(message.contains("exceeded the `--max-rss` limit of 64 MiB"), "{message}");
+    assert!(big["max_rss"].as_u64().unwrap() > 64 * 1024 * 1024, "{big}");
+
+    assert_eq!(result("small")["event"], "ok");
+}
@@ -0,0 +1,10 @@
+//! ```
+//! let x = 12;
+//! ```
+//!
+//! ```
+//! println!("first");
+//! println!();
+//! println!("second");
+//! std::process::exit(1);
+//! ```
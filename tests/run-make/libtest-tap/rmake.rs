@@ -0,0 +1,45 @@
+// Check libtest's TAP output against snapshots.
+
+//@ ignore-cross-compile
+//@ needs-unwind (test file contains #[should_panic] test)
+
+use run_make_support::{cmd, diff, rustc, rustdoc};
+
+fn main() {
+    rustc().arg("--test").input("f.rs").run();
+
+    run_tests(&[], "output-default.tap");
+    run_tests(&["--show-output"], "output-stdout-success.tap");
+
+    // Doctests are grouped into a subtest per file, and every line of the subtest is indented,
+    // including the blank lines of a YAML block.
+    let doctest_out = rustdoc()
+        .input("doc.rs")
+        .edition("2021")
+        .arg("--test")
+        .args(&["--test-args", "-Zunstable-options", "--test-args", "--format=tap"])
+        .args(&["--test-args", "--test-threads=1"])
+        .run_fail();
+    diff()
+        .expected_file("output-doctests.tap")
+        .actual_text("stdout", &doctest_out.stdout_utf8())
+        .normalize(r"finished in [0-9.]+s", "finished in $$TIME")
+        .run();
+}
+
+#[track_caller]
+fn run_tests(extra_args: &[&str], expected_file: &str) {
+    let cmd_out = cmd("./f")
+        .env("RUST_BACKTRACE", "0")
+        .args(&["-Zunstable-options", "--test-threads=1", "--format=tap"])
+        .args(extra_args)
+        .run_fail();
+    let test_stdout = &cmd_out.stdout_utf8();
+
+    diff()
+        .expected_file(expected_file)
+        .actual_text("stdout", test_stdout)
+        .normalize(r"finished in [0-9.]+s", "finished in $$TIME")
+        .normalize(r"thread '(?P<name>.*?)' \(\d+\) panicked", "thread '$name' ($$TID) panicked")
+        .run();
+}
@@ -0,0 +1,69 @@
+#![crate_type = "lib"]
+
+// Other crates can't implement `Shape`, so the candidates of its `dyn` calls are complete.
+trait Shape {
+    fn area(&self) -> u64;
+}
+
+pub struct Small(u8);
+
+pub struct Big([u64; 64]);
+
+impl Shape for Small {
+    fn area(&self) -> u64 {
+        self.0 as u64
+    }
+}
+
+impl Shape for Big {
+    fn area(&self) -> u64 {
+        // Copy the array to give this function a large stack frame.
+        let copy = self.0;
+        std::hint::black_box(&copy).len() as u64
+    }
+}
+
+fn measure(shape: &dyn Shape) -> u64 {
+    shape.area()
+}
+
+#[no_mangle]
+pub fn shapes() -> u64 {
+    measure(&Small(1)) + measure(&Big([2; 64]))
+}
+
+#[no_mangle]
+pub fn factorial(n: u64) -> u64 {
+    if n == 0 { 1 } else { n * factorial(n - 1) }
+}
+
+unsafe extern "C" {
+    safe fn abs(x: i32) -> i32;
+}
+
+#[no_mangle]
+pub fn absolute(x: i32) -> i32 {
+    abs(x)
+}
+
+pub trait Callback {
+    fn call(&self) -> u32;
+}
+
+#[no_mangle]
+pub fn run_callback(callback: &dyn Callback) -> u32 {
+    callback.call()
+}
+
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+fn apply(f: fn(u32) -> u32) -> u32 {
+    f(1)
+}
+
+#[no_mangle]
+pub fn apply_increment() -> u32 {
+    apply(increment)
+}
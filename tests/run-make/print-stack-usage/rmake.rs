@@ -0,0 +1,100 @@
+//@ needs-target-std
+//@ only-elf
+// Reason: LLVM only emits the stack sizes that `--print stack-usage` reads for ELF targets.
+//
+// `--print stack-usage` should report the worst-case stack usage of each entry point, and flag
+// recursion, indirect calls with their candidates, calls of foreign functions and the functions
+// that can't be found in the object files. Indirect calls make the report incomplete unless the
+// candidates are known to be complete.
+
+use run_make_support::rustc;
+
+/// Returns the headline of the report of the entry point, followed by its indented lines.
+fn report(output: &str, name: &str) -> (String, Vec<String>) {
+    let headline = format!("stack-usage: `{name}`: ");
+    let mut lines = output.lines().skip_while(|line| !line.starts_with(&headline));
+    let headline = lines.next().unwrap_or_else(|| panic!("no `{name}` in:\n{output}"));
+    let details = lines.take_while(|line| line.starts_with("    ")).map(str::to_owned).collect();
+    (headline.to_owned(), details)
+}
+
+fn bytes(headline: &str) -> u64 {
+    let size = headline.rsplit(": ").next().unwrap().rsplit("at least ").next().unwrap();
+    size.strip_suffix(" bytes").unwrap().parse().unwrap()
+}
+
+fn main() {
+    let output = rustc()
+        .input("foo.rs")
+        .arg("-Zunstable-options")
+        .arg("-Coverflow-checks=off")
+        .print("stack-usage")
+        .run()
+        .stdout_utf8();
+
+    // The worst case goes through the largest candidate of the `dyn` call. Copying the array of
+    // `Big` may call `memcpy`, which makes the worst case a lower bound.
+    let (headline, details) = report(&output, "shapes");
+    let inserted = details.iter().any(|line| line.contains("inserted by the codegen backend"));
+    assert_eq!(headline.contains("incomplete, at least"), inserted, "{headline}\n{details:#?}");
+    assert!(bytes(&headline) >= 512, "{headline}");
+    assert!(details.iter().any(|line| line.starts_with("    `<Big as Shape>::area`: ")));
+    assert!(details.iter().any(|line| {
+        line.contains("`dyn` call of `Shape::area` in `measure`, candidates: ")
+            && line.contains("`<Small as Shape>::area`")
+            && line.contains("`<Big as Shape>::area`")
+    }));
+
+    let (headline, details) = report(&output, "factorial");
+    assert!(headline.contains("unbounded, at least"), "{headline}");
+    assert!(details.iter().any(|line| line == "    note: recursion through `factorial`"));
+
+    let (headline, details) = report(&output, "absolute");
+    assert!(headline.contains(": incomplete, at least"), "{headline}");
+    assert!(
+        details.iter().any(|line| line == "    note: call of foreign function `abs` in `absolute`")
+    );
+
+    // Other crates may implement `Callback`, and function pointers may come from anywhere.
+    let (headline, details) = report(&output, "run_callback");
+    assert!(headline.contains(": incomplete, at least"), "{headline}");
+    assert!(details.iter().any(|line| {
+        line == "    note: `dyn` call of `Callback::call` in `run_callback`, no known candidates"
+    }));
+
+    let (headline, details) = report(&output, "apply_increment");
+    assert!(headline.contains(": incomplete, at least"), "{headline}");
+    assert!(details.iter().any(|line| line.starts_with("    `increment`: ")), "{details:#?}");
+    assert!(details.iter().any(|line| {
+        line == "    note: call through a `fn(u32) -> u32` pointer in `apply`, \
+                 known candidates: `increment`"
+    }));
+
+    // Functions that are called are not entry points.
+    assert!(!output.contains("stack-usage: `measure`"));
+    assert!(!output.contains("stack-usage: `apply`"));
+
+    // With optimizations, functions are inlined into their callers and ThinLTO renames the
+    // functions that it promotes across codegen units. The frames of the exported functions must
+    // still be found, and the functions that aren't found make the worst case a lower bound.
+    let output = rustc()
+        .input("foo.rs")
+        .arg("-Zunstable-options")
+        .opt()
+        .codegen_units(4)
+        .print("stack-usage")
+        .run()
+        .stdout_utf8();
+
+    for name in ["shapes", "factorial", "absolute", "apply_increment"] {
+        let (headline, details) = report(&output, name);
+        let own_frame = format!("    `{name}`: ");
+        let own_frame = details.iter().find(|line| line.starts_with(&own_frame)).unwrap();
+        assert!(own_frame.ends_with(" bytes"), "{own_frame}");
+        if details.iter().any(|line| line.ends_with(": not found")) {
+            assert!(headline.contains("at least"), "{headline}\n{details:#?}");
+        }
+    }
+    let (headline, _) = report(&output, "factorial");
+    assert!(headline.contains("unbounded, at least"), "{headline}");
+}
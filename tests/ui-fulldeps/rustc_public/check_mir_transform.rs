@@ -0,0 +1,102 @@
+//@ run-pass
+//! Test that users are able to rewrite bodies before they are codegened.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ edition: 2021
+
+#![feature(rustc_private)]
+
+extern crate rustc_middle;
+
+extern crate rustc_driver;
+extern crate rustc_interface;
+#[macro_use]
+extern crate rustc_public;
+
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rustc_public::mir::mono::Instance;
+use rustc_public::mir::transform::register_mono_body_transform;
+use rustc_public::mir::{
+    Body, ConstOperand, Operand, Place, Rvalue, Statement, StatementKind, TerminatorKind,
+};
+use rustc_public::ty::{MirConst, UintTy};
+
+const CRATE_NAME: &str = "input";
+
+static TRANSFORMED: AtomicUsize = AtomicUsize::new(0);
+
+/// Make `answer` return 42 by overwriting the return value before each `return`.
+fn transform(instance: Instance, mut body: Body) -> Option<Body> {
+    if instance.trimmed_name() != "answer" {
+        return None;
+    }
+    let value = MirConst::try_from_uint(42, UintTy::U8).unwrap();
+    for block in &mut body.blocks {
+        if block.terminator.kind == TerminatorKind::Return {
+            let span = block.terminator.span;
+            let operand = ConstOperand { span, user_ty: None, const_: value.clone() };
+            block.statements.push(Statement {
+                kind: StatementKind::Assign(
+                    Place::from(0),
+                    Rvalue::Use(Operand::Constant(operand)),
+                ),
+                span,
+            });
+        }
+    }
+    TRANSFORMED.fetch_add(1, Ordering::Relaxed);
+    Some(body)
+}
+
+/// Only one transformation can be registered.
+fn other_transform(_instance: Instance, _body: Body) -> Option<Body> {
+    None
+}
+
+/// This test will generate and compile a dummy crate with a MIR transformation.
+/// The output binary exits with the value returned by the transformed function.
+fn main() {
+    let path = "mir_transform_input.rs";
+    generate_input(&path).unwrap();
+    let output = "./mir_transform_output";
+    let args = &[
+        "rustc".to_string(),
+        "--crate-type=bin".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        "-o".to_string(),
+        output.to_string(),
+        path.to_string(),
+    ];
+    register_mono_body_transform(transform).unwrap();
+    assert!(register_mono_body_transform(other_transform).is_err());
+    run!(args, || ControlFlow::<()>::Continue(())).unwrap();
+    assert_eq!(TRANSFORMED.load(Ordering::Relaxed), 1);
+
+    let status = Command::new(output).status().unwrap();
+    assert_eq!(status.code(), Some(42));
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    #[inline(never)]
+    fn answer() -> u8 {{
+        1
+    }}
+
+    fn main() {{
+        std::process::exit(answer() as i32);
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//@ run-pass
+//! Test that invalid bodies returned by a MIR transformation are reported as errors.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ edition: 2021
+
+#![feature(rustc_private)]
+
+extern crate rustc_middle;
+
+extern crate rustc_driver;
+extern crate rustc_interface;
+#[macro_use]
+extern crate rustc_public;
+
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rustc_public::CompilerError;
+use rustc_public::mir::mono::Instance;
+use rustc_public::mir::transform::register_mono_body_transform;
+use rustc_public::mir::{
+    BinOp, Body, ConstOperand, Operand, Place, Rvalue, Statement, StatementKind, TerminatorKind,
+};
+use rustc_public::ty::{MirConst, UintTy};
+
+const CRATE_NAME: &str = "input";
+
+/// Checked division cannot be converted back into the compiler's representation.
+const CHECKED_DIV: usize = 0;
+/// Assigning a `bool` to the `u8` return place is rejected by the MIR validator.
+const ILL_TYPED: usize = 1;
+
+static MODE: AtomicUsize = AtomicUsize::new(CHECKED_DIV);
+
+/// Break `answer` according to the current mode.
+fn transform(instance: Instance, mut body: Body) -> Option<Body> {
+    if instance.trimmed_name() != "answer" {
+        return None;
+    }
+    let rvalue = |span| {
+        let constant =
+            |const_| Operand::Constant(ConstOperand { span, user_ty: None, const_ });
+        match MODE.load(Ordering::Relaxed) {
+            CHECKED_DIV => {
+                let one = MirConst::try_from_uint(1, UintTy::U8).unwrap();
+                Rvalue::CheckedBinaryOp(BinOp::Div, constant(one.clone()), constant(one))
+            }
+            _ => Rvalue::Use(constant(MirConst::from_bool(true))),
+        }
+    };
+    for block in &mut body.blocks {
+        if block.terminator.kind == TerminatorKind::Return {
+            let span = block.terminator.span;
+            block.statements.push(Statement {
+                kind: StatementKind::Assign(Place::from(0), rvalue(span)),
+                span,
+            });
+        }
+    }
+    Some(body)
+}
+
+/// This test will compile a dummy crate once per mode and check that each compilation fails.
+fn main() {
+    let path = "mir_transform_invalid_input.rs";
+    generate_input(&path).unwrap();
+    let args = &[
+        "rustc".to_string(),
+        "--crate-type=bin".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        "-o".to_string(),
+        "./mir_transform_invalid_output".to_string(),
+        path.to_string(),
+    ];
+    register_mono_body_transform(transform).unwrap();
+    for mode in [CHECKED_DIV, ILL_TYPED] {
+        MODE.store(mode, Ordering::Relaxed);
+        let result = run!(args, || ControlFlow::<()>::Continue(()));
+        assert!(matches!(result, Err(CompilerError::Failed)), "mode {mode}: {result:?}");
+    }
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    #[inline(never)]
+    fn answer() -> u8 {{
+        1
+    }}
+
+    fn main() {{
+        std::process::exit(answer() as i32);
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//@ run-pass
+//! Test that users are able to retrieve the mono item graph and the codegen units.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ edition: 2021
+
+#![feature(rustc_private)]
+
+extern crate rustc_middle;
+
+extern crate rustc_driver;
+extern crate rustc_interface;
+#[macro_use]
+extern crate rustc_public;
+
+use std::io::Write;
+use std::ops::ControlFlow;
+
+use rustc_public::mir::mono::{InstanceKind, MonoItem, MonoItemEdge, MonoItemUsage};
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_mono_items() -> ControlFlow<()> {
+    let items = rustc_public::all_mono_items();
+    let entry = find_fn(&items, "entry");
+    for name in ["double::<u32>", "apply", "triple", "<Square as Shape>::area"] {
+        find_fn(&items, name);
+    }
+
+    let edges = rustc_public::mono_item_edges();
+    assert!(edges.iter().all(|edge| items.contains(&edge.user) && items.contains(&edge.used)));
+    let uses = |name: &str| -> Vec<MonoItemUsage> {
+        let used = find_fn(&items, name);
+        let edge = edges.iter().find(|edge| edge.user == entry && edge.used == used);
+        edge.unwrap_or_else(|| panic!("Expected `entry` to use `{name}`")).usages.clone()
+    };
+    assert_eq!(uses("double::<u32>"), [MonoItemUsage::Call]);
+    assert_eq!(uses("apply"), [MonoItemUsage::Call]);
+    assert_eq!(uses("triple"), [MonoItemUsage::FnPtr]);
+    assert_eq!(uses("<Square as Shape>::area"), [MonoItemUsage::VTable]);
+    assert!(
+        edges.iter().any(|edge| edge.user == entry && is_drop_glue(edge)),
+        "Expected `entry` to drop a `Guard`"
+    );
+
+    // Every mono item is codegened in some codegen unit.
+    let cgus = rustc_public::codegen_units();
+    assert_eq!(cgus.iter().filter(|cgu| cgu.is_primary).count(), 1);
+    for item in &items {
+        assert!(cgus.iter().any(|cgu| cgu.items.contains(item)), "Item not placed: {item:?}");
+    }
+    ControlFlow::Continue(())
+}
+
+fn find_fn(items: &[MonoItem], name: &str) -> MonoItem {
+    items
+        .iter()
+        .find(|item| matches!(item, MonoItem::Fn(instance) if instance.trimmed_name() == name))
+        .unwrap_or_else(|| panic!("Failed to find `{name}` in {items:#?}"))
+        .clone()
+}
+
+fn is_drop_glue(edge: &MonoItemEdge) -> bool {
+    let MonoItem::Fn(instance) = &edge.used else { return false };
+    instance.kind == InstanceKind::Shim
+        && instance.trimmed_name().contains("Guard")
+        && edge.usages == [MonoItemUsage::Drop]
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `RustcPublic` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "mono_items_input.rs";
+    generate_input(&path).unwrap();
+    let args = &[
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_mono_items).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub trait Shape {{
+        fn area(&self) -> u32;
+    }}
+
+    pub struct Square(pub u32);
+
+    impl Shape for Square {{
+        fn area(&self) -> u32 {{
+            self.0 * self.0
+        }}
+    }}
+
+    pub struct Guard;
+
+    impl Drop for Guard {{
+        fn drop(&mut self) {{}}
+    }}
+
+    fn double<T: Copy + std::ops::Add<Output = T>>(x: T) -> T {{
+        x + x
+    }}
+
+    fn apply(f: fn(u32) -> u32, x: u32) -> u32 {{
+        f(x)
+    }}
+
+    fn triple(x: u32) -> u32 {{
+        x * 3
+    }}
+
+    pub fn entry(square: &Square) -> u32 {{
+        let _guard = Guard;
+        let shape: &dyn Shape = square;
+        double(shape.area()) + apply(triple, 1)
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//@ run-pass
+//! Test that users are able to query trait implementations, selection and normalization.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+
+extern crate rustc_middle;
+
+extern crate rustc_driver;
+extern crate rustc_interface;
+#[macro_use]
+extern crate rustc_public;
+
+use std::assert_matches::assert_matches;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+use rustc_public::ty::{
+    BuiltinImplSource, FnDef, GenericArgKind, GenericArgs, ImplDef, ImplSource, ParamEnv, RigidTy,
+    TraitDef, TraitRef, Ty, TyKind, UintTy,
+};
+use rustc_public::CrateDef;
+
+const CRATE_NAME: &str = "trait_selection";
+
+/// This function uses the Stable MIR APIs to query trait solving in the test crate.
+fn test_trait_selection() -> ControlFlow<()> {
+    let local_crate = rustc_public::local_crate();
+    let max = find_trait("Max");
+    let convert = find_trait("Convert");
+    let max_impl = find_impl("<Positive as Max>");
+    let copy = find_impl("<Positive as std::marker::Copy>").trait_impl().value.def_id;
+    let positive = max_impl.trait_impl().value.self_ty();
+    let no_args = GenericArgs(vec![]);
+    let mono = ParamEnv::FullyMonomorphized;
+
+    // User-written impls.
+    let trait_ref = TraitRef::new(max, positive, &no_args);
+    assert!(trait_ref.is_implemented(&mono));
+    let Ok(Some(ImplSource::UserDefined { impl_def, .. })) = trait_ref.select(&mono) else {
+        panic!("Expected a user-defined impl for `{trait_ref:?}`")
+    };
+    assert_eq!(impl_def, max_impl);
+
+    // Missing impls.
+    let trait_ref = TraitRef::new(max, Ty::bool_ty(), &no_args);
+    assert!(!trait_ref.is_implemented(&mono));
+    assert!(trait_ref.select(&mono).is_err());
+
+    // Builtin impls.
+    let tuple = Ty::new_tuple(&[Ty::usize_ty(), positive]);
+    let trait_ref = TraitRef::new(copy, tuple, &no_args);
+    assert!(trait_ref.is_implemented(&mono));
+    assert_matches!(
+        trait_ref.select(&mono),
+        Ok(Some(ImplSource::Builtin(BuiltinImplSource::Misc)))
+    );
+
+    // Where-clauses of a generic function.
+    let check = find_fn(&local_crate.fn_defs(), "check");
+    let env = ParamEnv::Item(check.def_id());
+    let param = pointee(check.fn_sig().skip_binder().inputs()[0]);
+    let trait_ref = TraitRef::new(max, param, &no_args);
+    assert!(trait_ref.is_implemented(&env));
+    assert_matches!(trait_ref.select(&env), Ok(Some(ImplSource::Param)));
+    assert!(!TraitRef::new(copy, param, &no_args).is_implemented(&env));
+
+    // Normalization of projections.
+    let output = convert.associated_items()[0].def_id;
+    let projection = Ty::new_projection(output, &GenericArgs(vec![GenericArgKind::Type(positive)]));
+    assert_eq!(projection.normalize(&mono).unwrap(), Ty::unsigned_ty(UintTy::U64));
+
+    let generic = find_fn(&local_crate.fn_defs(), "generic");
+    let env = ParamEnv::Item(generic.def_id());
+    let param = generic.fn_sig().skip_binder().inputs()[0];
+    let projection = Ty::new_projection(output, &GenericArgs(vec![GenericArgKind::Type(param)]));
+    assert_eq!(projection.normalize(&env).unwrap(), Ty::bool_ty());
+    ControlFlow::Continue(())
+}
+
+fn find_trait(name: &str) -> TraitDef {
+    let traits = rustc_public::local_crate().trait_decls();
+    traits.into_iter().find(|t| t.name() == name).expect("Failed to find trait")
+}
+
+fn find_impl(name: &str) -> ImplDef {
+    let impls = rustc_public::local_crate().trait_impls();
+    impls.into_iter().find(|i| i.name() == name).expect("Failed to find impl")
+}
+
+fn find_fn(fns: &[FnDef], name: &str) -> FnDef {
+    *fns.iter().find(|f| f.name() == name).expect("Failed to find function")
+}
+
+fn pointee(ty: Ty) -> Ty {
+    let TyKind::RigidTy(RigidTy::Ref(_, pointee, _)) = ty.kind() else {
+        panic!("Expected a reference, but found: {ty:?}")
+    };
+    pointee
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `RustcPublic` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "trait_selection.rs";
+    generate_input(&path).unwrap();
+    let args = &[
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_trait_selection).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+        #[derive(Copy, Clone)]
+        pub struct Positive(u64);
+
+        pub trait Max {{
+            fn is_max(&self) -> bool;
+        }}
+
+        impl Max for Positive {{
+            fn is_max(&self) -> bool {{ self.0 == u64::MAX }}
+        }}
+
+        pub trait Convert {{
+            type Output;
+        }}
+
+        impl Convert for Positive {{
+            type Output = u64;
+        }}
+
+        pub fn check<T: Max>(t: &T) -> bool {{
+            t.is_max()
+        }}
+
+        pub fn generic<T: Convert<Output = bool>>(_t: T) {{}}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,7 @@
+//! Check that diagnostics emitted before the session exists are reported in the same SARIF log
+//! as the others.
+
+//@ check-pass
+//@ compile-flags: --error-format=sarif -Zunstable-options -Zremark-dir=remarks
+
+fn main() {}
@@ -0,0 +1,11 @@
+//! Check that diagnostics are reported as a SARIF log when `--error-format=sarif` is used.
+
+//@ check-pass
+//@ compile-flags: --error-format=sarif -Zunstable-options
+//@ ignore-windows (the file URI uses forward slashes)
+
+#![warn(unused_variables)]
+
+fn main() {
+    let x = 0u32;
+}
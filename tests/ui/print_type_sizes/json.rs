@@ -0,0 +1,23 @@
+//@ compile-flags: -Z print-type-sizes=json --crate-type=lib
+//@ build-pass
+
+// This file illustrates the JSON output: padding before fields and at
+// the end of a type, a directly stored discriminant, and a discriminant
+// stored in the niche of a field.
+
+#![allow(dead_code)]
+
+struct S {
+    a: bool,
+    g: i32,
+}
+
+enum E {
+    A(u8),
+    B(i32),
+}
+
+enum N {
+    A,
+    B(S),
+}